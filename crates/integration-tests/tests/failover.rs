@@ -167,3 +167,65 @@ async fn failover_disabled_returns_primary_error() {
     assert!(resp.status().is_server_error());
     assert_eq!(backup.completion_count(), 0);
 }
+
+#[tokio::test]
+async fn routing_headers_report_the_model_that_served_after_failover() {
+    let primary = MockLlm::start_failing(1).await.unwrap();
+    let backup = MockLlm::start_with_response("backup response").await.unwrap();
+
+    let config = ConfigBuilder::new()
+        .with_openai_provider("primary", &primary.base_url())
+        .with_openai_provider("backup", &backup.base_url())
+        .with_smart_routing("primary")
+        .with_failover(vec![EquivalenceGroup {
+            name: "small".to_owned(),
+            models: vec!["primary/mock-small".to_owned(), "backup/mock-small".to_owned()],
+        }])
+        .build();
+
+    let server = TestServer::start(config).await.unwrap();
+
+    let resp = server
+        .client()
+        .post(server.url("/v1/chat/completions"))
+        .json(&completion_body("auto"))
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(resp.status(), 200);
+    assert_eq!(resp.headers()["x-synapse-routing-model"], "backup/mock-small");
+    assert_eq!(resp.headers()["x-synapse-routing-reason"], "low_complexity");
+    assert_eq!(backup.completion_count(), 1);
+}
+
+#[tokio::test]
+async fn failover_skips_alternatives_with_too_small_a_context_window() {
+    let primary = MockLlm::start_failing(1).await.unwrap();
+
+    let config = ConfigBuilder::new()
+        .with_openai_provider("primary", &primary.base_url())
+        .with_smart_routing("primary")
+        .with_context_window("mock-small", 1_000)
+        .with_failover(vec![EquivalenceGroup {
+            name: "chat".to_owned(),
+            models: vec!["primary/mock-large".to_owned(), "primary/mock-small".to_owned()],
+        }])
+        .build();
+
+    let server = TestServer::start(config).await.unwrap();
+
+    let mut body = completion_body("mock-large");
+    body["max_tokens"] = serde_json::json!(4_000);
+    let resp = server
+        .client()
+        .post(server.url("/v1/chat/completions"))
+        .json(&body)
+        .send()
+        .await
+        .unwrap();
+
+    // The only alternative cannot hold the request, so the primary error surfaces
+    assert!(resp.status().is_server_error());
+    assert_eq!(primary.completion_count(), 1);
+}
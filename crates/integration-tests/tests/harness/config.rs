@@ -5,10 +5,14 @@ use std::net::SocketAddr;
 
 use secrecy::SecretString;
 use synapse_config::{
-    CircuitBreakerConfig, Config, CorsConfig, CsrfConfig, EmbeddingsConfig, EmbeddingsProviderConfig,
-    EmbeddingsProviderType, EquivalenceGroup, FailoverConfig, HealthConfig, ImageGenConfig, ImageGenProviderConfig,
-    ImageGenProviderType, LlmConfig, LlmProviderConfig, LlmProviderType, McpConfig, ModelConfig, RateLimitConfig,
-    ServerConfig, SttConfig, TtsConfig,
+    BillingConfig, CascadeVerifierConfig, CircuitBreakerConfig, ClassifierConfig, ClientIdSource,
+    ClientIdentificationConfig, Config, CorsConfig, CsrfConfig, EmbeddingsConfig, EmbeddingsProviderConfig,
+    EmbeddingsProviderType, EquivalenceGroup, ExperimentArmConfig, ExperimentConfig, FailoverConfig, GroupIdSource,
+    HealthConfig, HttpConfig, ImageGenConfig, ImageGenProviderConfig, ImageGenProviderType, KnnConfig, LlmConfig,
+    LlmProviderConfig, LlmProviderType, McpAccessConfig, McpAdminConfig, McpConfig, McpServerConfig, McpServerType,
+    ModelCapabilities, ModelCatalogConfig, ModelConfig, ModelProfileConfig, RateLimitConfig, RoutingStrategy,
+    ScriptStrategyConfig, ServerConfig, ShadowConfig, ShadowMirrorConfig, SttConfig, TrainingDataConfig,
+    TrainingDataFormat, TtsConfig,
 };
 
 /// Builder for constructing test configurations
@@ -57,6 +61,7 @@ impl ConfigBuilder {
                 headers: Vec::new(),
                 forward_authorization: false,
                 rate_limit: None,
+                catalog_id: None,
             },
         );
         self
@@ -112,6 +117,12 @@ impl ConfigBuilder {
         self
     }
 
+    /// Serve health routes on a dedicated listener
+    pub fn with_health_listener(mut self, addr: SocketAddr) -> Self {
+        self.config.server.health.listen_address = Some(addr);
+        self
+    }
+
     /// Enable failover with the given equivalence groups
     pub fn with_failover(mut self, groups: Vec<EquivalenceGroup>) -> Self {
         self.config.llm.failover = FailoverConfig {
@@ -127,6 +138,232 @@ impl ConfigBuilder {
         self
     }
 
+    /// Record served responses so they can be rated via `/v1/feedback`
+    pub fn with_user_feedback(mut self) -> Self {
+        self.config.llm.routing.user_feedback.enabled = true;
+        self
+    }
+
+    /// Enable smart routing across a cheap and a frontier model on one provider
+    pub fn with_smart_routing(mut self, provider: &str) -> Self {
+        let profile = |model: &str, price: f64, quality: f64| ModelProfileConfig {
+            provider: provider.to_owned(),
+            model: model.to_owned(),
+            context_window: 128_000,
+            input_per_mtok: price,
+            output_per_mtok: price * 4.0,
+            quality,
+            capabilities: ModelCapabilities::default(),
+        };
+
+        self.config.llm.routing.enabled = true;
+        self.config.llm.routing.models = vec![profile("mock-small", 0.15, 0.75), profile("mock-large", 3.0, 0.95)];
+        self
+    }
+
+    /// Set the context window of a smart routing model profile
+    pub fn with_context_window(mut self, model: &str, context_window: u32) -> Self {
+        for profile in &mut self.config.llm.routing.models {
+            if profile.model == model {
+                profile.context_window = context_window;
+            }
+        }
+        self
+    }
+
+    /// Mark every smart routing model profile as supporting tool calls
+    pub fn with_tool_calling_models(mut self) -> Self {
+        for profile in &mut self.config.llm.routing.models {
+            profile.capabilities.tool_calling = Some(true);
+        }
+        self
+    }
+
+    /// Keep conversations on the model routed for their first turn
+    pub fn with_conversation_affinity(mut self) -> Self {
+        self.config.llm.routing.affinity.enabled = true;
+        self
+    }
+
+    /// Upgrade requests that exceed a model's context window within its equivalence group
+    pub fn with_context_upgrade(mut self) -> Self {
+        self.config.llm.routing.context_upgrade = true;
+        self
+    }
+
+    /// Route with the classifier strategy, classifying via a model on the given provider
+    pub fn with_classifier_routing(mut self, provider: &str, timeout_ms: u64) -> Self {
+        self = self.with_smart_routing(provider);
+        self.config.llm.routing.strategy = RoutingStrategy::Classifier;
+        self.config.llm.routing.classifier = ClassifierConfig {
+            model: Some(format!("{provider}/mock-classifier")),
+            timeout_ms,
+            ..ClassifierConfig::default()
+        };
+        self
+    }
+
+    /// Route with the cascade strategy, optionally grading answers with a verifier model
+    pub fn with_cascade_routing(mut self, provider: &str, verifier: bool) -> Self {
+        self = self.with_smart_routing(provider);
+        self.config.llm.routing.strategy = RoutingStrategy::Cascade;
+        self.config.llm.routing.cascade.verifier = verifier.then(|| CascadeVerifierConfig {
+            model: Some(format!("{provider}/mock-verifier")),
+            timeout_ms: 5_000,
+        });
+        self
+    }
+
+    /// Route with the kNN strategy over a JSONL examples file, embedding queries
+    /// with an `[embeddings.providers]` model
+    pub fn with_knn_routing(mut self, provider: &str, embedding_model: &str, examples: &std::path::Path) -> Self {
+        self = self.with_smart_routing(provider);
+        self.config.llm.routing.strategy = RoutingStrategy::Knn;
+        self.config.llm.routing.knn = KnnConfig {
+            examples: Some(examples.to_path_buf()),
+            embedding_model: Some(embedding_model.to_owned()),
+            ..KnnConfig::default()
+        };
+        self
+    }
+
+    /// Route with a Rhai script strategy registered under the given name
+    pub fn with_script_routing(mut self, provider: &str, name: &str, path: &std::path::Path) -> Self {
+        self = self.with_smart_routing(provider);
+        self.config.llm.routing.strategy = RoutingStrategy::Custom(name.to_owned());
+        self.config.llm.routing.scripts.push(ScriptStrategyConfig {
+            name: name.to_owned(),
+            path: path.to_path_buf(),
+            timeout_ms: 50,
+            max_operations: 100_000,
+        });
+        self
+    }
+
+    /// Load model profiles from a catalog file, re-checked every second
+    pub fn with_model_catalog(mut self, path: &std::path::Path) -> Self {
+        self.config.llm.catalog = Some(ModelCatalogConfig {
+            enabled: true,
+            path: path.to_path_buf(),
+            reload_interval_secs: 1,
+            default_quality: 0.0,
+        });
+        self
+    }
+
+    /// Add an A/B experiment splitting a virtual model across weighted arms
+    ///
+    /// Arms are given as (name, "provider/model", weight).
+    pub fn with_experiment(mut self, name: &str, model: &str, arms: &[(&str, &str, u32)]) -> Self {
+        self.config.llm.routing.experiments.push(ExperimentConfig {
+            name: name.to_owned(),
+            model: model.to_owned(),
+            sticky: synapse_config::ExperimentStickiness::default(),
+            arms: arms
+                .iter()
+                .map(|(arm, model, weight)| ExperimentArmConfig {
+                    name: (*arm).to_owned(),
+                    model: (*model).to_owned(),
+                    weight: *weight,
+                })
+                .collect(),
+        });
+        self
+    }
+
+    /// Mirror every request for a model to a shadow model, recording to a JSONL sink
+    pub fn with_shadow(mut self, sink: &std::path::Path, model: &str, shadow_model: &str) -> Self {
+        self.config.llm.shadow = Some(ShadowConfig {
+            enabled: true,
+            sink: sink.to_path_buf(),
+            skip_pii: vec![synapse_guardrails::PiiType::Email],
+            mirrors: vec![ShadowMirrorConfig {
+                model: model.to_owned(),
+                shadow_model: shadow_model.to_owned(),
+                sample_rate: 1.0,
+            }],
+        });
+        self
+    }
+
+    /// Export routing training data as CSV, holding samples for ratings
+    pub fn with_training_data(mut self, path: &std::path::Path, rating_window_secs: u64) -> Self {
+        self.config.llm.training_data = Some(TrainingDataConfig {
+            enabled: true,
+            format: TrainingDataFormat::Csv,
+            path: path.to_path_buf(),
+            rating_window_secs,
+            batch_size: 10_000,
+        });
+        self
+    }
+
+    /// Add a Streamable HTTP MCP server, optionally denying some of its tools
+    pub fn with_mcp_server(mut self, name: &str, url: &str, deny: &[&str]) -> Self {
+        let access = (!deny.is_empty()).then(|| McpAccessConfig {
+            allow: Vec::new(),
+            deny: deny.iter().map(|tool| (*tool).to_owned()).collect(),
+            ..McpAccessConfig::default()
+        });
+        self.config.mcp.servers.insert(
+            name.to_owned(),
+            McpServerConfig {
+                server_type: McpServerType::StreamableHttp(HttpConfig {
+                    url: url.parse().expect("valid MCP server URL"),
+                    auth: None,
+                    tls: None,
+                }),
+                enabled: true,
+                tenant: None,
+                rate_limit: None,
+                access,
+                tool_rate_limits: std::collections::HashMap::new(),
+                headers: Vec::new(),
+                structured_content: false,
+                max_concurrent_requests: None,
+                pool_size: 1,
+            },
+        );
+        self
+    }
+
+    /// Serve the MCP admin API, guarded by `secret`
+    pub fn with_mcp_admin(mut self, secret: &str) -> Self {
+        self.config.mcp.admin = McpAdminConfig {
+            enabled: true,
+            secret: Some(SecretString::from(secret.to_owned())),
+            allow_stdio: false,
+        };
+        self
+    }
+
+    /// Enable billing against an Aether API at `aether_url`
+    pub fn with_billing(mut self, aether_url: &str) -> Self {
+        let billing: BillingConfig = serde_json::from_value(serde_json::json!({
+            "enabled": true,
+            "aether_url": aether_url,
+            "service_api_key": "test-service-key",
+            "app_id": "synapse-test",
+        }))
+        .expect("valid billing config");
+        self.config.billing = Some(billing);
+        self
+    }
+
+    /// Identify clients by a header, optionally grouping them by another
+    pub fn with_client_identification(mut self, client_header: &str, group_header: Option<&str>) -> Self {
+        self.config.server.client_identification = Some(ClientIdentificationConfig {
+            client_id: ClientIdSource::Header {
+                name: client_header.to_owned(),
+            },
+            group_id: group_header.map(|name| GroupIdSource::Header {
+                name: name.to_owned(),
+                allowed: Vec::new(),
+            }),
+        });
+        self
+    }
+
     /// Build the final config
     pub fn build(self) -> Config {
         self.config
@@ -116,7 +116,6 @@ impl Drop for MockLlm {
 #[derive(Debug, Deserialize)]
 struct ChatCompletionRequest {
     model: String,
-    #[allow(dead_code)]
     messages: Vec<ChatMessage>,
     #[serde(default)]
     stream: Option<bool>,
@@ -126,9 +125,7 @@ struct ChatCompletionRequest {
 
 #[derive(Debug, Deserialize)]
 struct ChatMessage {
-    #[allow(dead_code)]
     role: String,
-    #[allow(dead_code)]
     content: Option<serde_json::Value>,
 }
 
@@ -247,7 +244,6 @@ struct StreamFunctionCall {
 
 #[derive(Debug, Deserialize)]
 struct EmbeddingRequest {
-    #[allow(dead_code)]
     input: serde_json::Value,
     model: String,
 }
@@ -327,17 +323,17 @@ async fn handle_chat_completions(
 
     let content = state.response_content.as_deref().unwrap_or("Hello from mock LLM");
 
-    // If tools were provided, simulate a tool call
-    let (content, tool_calls, finish_reason) = if req.tools.is_some() {
+    // Answer tool results; otherwise simulate a call of the first tool provided
+    let (content, tool_calls, finish_reason) = if let Some(reply) = tool_reply(&req) {
+        (reply, None, "stop".to_owned())
+    } else if let Some(ref tools) = req.tools {
+        let (name, arguments) = first_tool_call(tools);
         (
             String::new(),
             Some(vec![ToolCallResponse {
                 id: "call_test_123".to_owned(),
                 tool_type: "function".to_owned(),
-                function: FunctionCallResponse {
-                    name: "get_weather".to_owned(),
-                    arguments: r#"{"location":"San Francisco"}"#.to_owned(),
-                },
+                function: FunctionCallResponse { name, arguments },
             }]),
             "tool_calls".to_owned(),
         )
@@ -369,21 +365,56 @@ async fn handle_chat_completions(
     Json(response).into_response()
 }
 
+/// Reply to a conversation ending in a tool result, quoting the result
+fn tool_reply(req: &ChatCompletionRequest) -> Option<String> {
+    let last = req.messages.last().filter(|message| message.role == "tool")?;
+    let result = last
+        .content
+        .as_ref()
+        .and_then(|content| content.as_str())
+        .unwrap_or_default();
+    Some(format!("Tool said: {result}"))
+}
+
+/// Call of the first tool, with "San Francisco" for every parameter
+fn first_tool_call(tools: &[serde_json::Value]) -> (String, String) {
+    let function = &tools[0]["function"];
+    let arguments: serde_json::Map<String, serde_json::Value> = function["parameters"]["properties"]
+        .as_object()
+        .map(|properties| {
+            properties
+                .keys()
+                .map(|name| (name.clone(), serde_json::Value::from("San Francisco")))
+                .collect()
+        })
+        .unwrap_or_default();
+
+    (
+        function["name"].as_str().unwrap_or_default().to_owned(),
+        serde_json::Value::Object(arguments).to_string(),
+    )
+}
+
 /// Build an SSE streaming response body
 fn build_streaming_response(state: &MockLlmState, req: &ChatCompletionRequest) -> impl IntoResponse {
-    let content = state
-        .response_content
-        .as_deref()
-        .unwrap_or("Hello from mock LLM")
-        .to_owned();
+    let reply = tool_reply(req);
+    let has_tools = req.tools.is_some() && reply.is_none();
+    let content = reply.unwrap_or_else(|| {
+        state
+            .response_content
+            .as_deref()
+            .unwrap_or("Hello from mock LLM")
+            .to_owned()
+    });
     let model = req.model.clone();
-    let has_tools = req.tools.is_some();
 
     let id = "chatcmpl-test-stream";
     let created = 1_700_000_000u64;
     let mut body = String::new();
 
     if has_tools {
+        let (name, arguments) = first_tool_call(req.tools.as_deref().unwrap_or_default());
+
         // Tool call start chunk
         let chunk = StreamChunk {
             id: id.to_owned(),
@@ -400,7 +431,7 @@ fn build_streaming_response(state: &MockLlmState, req: &ChatCompletionRequest) -
                         id: Some("call_test_stream".to_owned()),
                         tool_type: Some("function".to_owned()),
                         function: Some(StreamFunctionCall {
-                            name: Some("get_weather".to_owned()),
+                            name: Some(name),
                             arguments: None,
                         }),
                     }]),
@@ -428,7 +459,7 @@ fn build_streaming_response(state: &MockLlmState, req: &ChatCompletionRequest) -
                         tool_type: None,
                         function: Some(StreamFunctionCall {
                             name: None,
-                            arguments: Some(r#"{"location":"San Francisco"}"#.to_owned()),
+                            arguments: Some(arguments),
                         }),
                     }]),
                 },
@@ -565,13 +596,26 @@ async fn handle_embeddings(
     state.request_count.fetch_add(1, Ordering::Relaxed);
     state.embedding_count.fetch_add(1, Ordering::Relaxed);
 
-    let response = EmbeddingResponse {
-        object: "list".to_owned(),
-        data: vec![EmbeddingData {
+    let data = match req.input {
+        serde_json::Value::Array(inputs) => inputs
+            .iter()
+            .enumerate()
+            .map(|(index, input)| EmbeddingData {
+                object: "embedding".to_owned(),
+                embedding: concept_embedding(input.as_str().unwrap_or_default()),
+                index,
+            })
+            .collect(),
+        _ => vec![EmbeddingData {
             object: "embedding".to_owned(),
             embedding: vec![0.1, 0.2, 0.3, 0.4, 0.5],
             index: 0,
         }],
+    };
+
+    let response = EmbeddingResponse {
+        object: "list".to_owned(),
+        data,
         model: req.model,
         usage: EmbeddingUsage {
             prompt_tokens: 8,
@@ -582,6 +626,26 @@ async fn handle_embeddings(
     Json(response)
 }
 
+/// Embed text by the concepts its words belong to, so synonyms land close
+fn concept_embedding(text: &str) -> Vec<f32> {
+    const CONCEPTS: [&[&str]; 3] = [
+        &["add", "sum", "plus", "numbers", "total"],
+        &["echo", "repeat", "text", "say"],
+        &["delete", "remove", "erase", "everything"],
+    ];
+
+    let words: Vec<String> = text
+        .split(|c: char| !c.is_alphanumeric())
+        .map(str::to_lowercase)
+        .collect();
+    let mut embedding: Vec<f32> = CONCEPTS
+        .iter()
+        .map(|concept| words.iter().filter(|word| concept.contains(&word.as_str())).count() as f32)
+        .collect();
+    embedding.push(0.1);
+    embedding
+}
+
 async fn handle_imagegen(
     State(state): State<Arc<MockLlmState>>,
     Json(_req): Json<ImageGenRequest>,
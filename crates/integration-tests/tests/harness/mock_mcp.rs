@@ -0,0 +1,317 @@
+#![allow(dead_code)]
+//! Mock MCP server for integration tests
+//!
+//! Serves a fixed set of tools, resources, and prompts over the Streamable
+//! HTTP transport
+
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, Mutex};
+
+use axum::Router;
+use axum::http::HeaderMap;
+use rmcp::model::{
+    AnnotateAble as _, CallToolRequestParams, CallToolResult, Content, ErrorCode, ErrorData, GetPromptRequestParams,
+    GetPromptResult, ListPromptsResult, ListResourceTemplatesResult, ListResourcesResult, ListToolsResult,
+    PaginatedRequestParams, Prompt, PromptArgument, PromptMessage, PromptMessageRole, RawResource, RawResourceTemplate,
+    ReadResourceRequestParams, ReadResourceResult, ResourceContents, ServerCapabilities, ServerInfo, Tool,
+};
+use rmcp::service::{NotificationContext, RequestContext, RoleServer};
+use rmcp::transport::streamable_http_server::session::local::LocalSessionManager;
+use rmcp::transport::streamable_http_server::{StreamableHttpServerConfig, StreamableHttpService};
+use rmcp::{Peer, ServerHandler};
+use tokio_util::sync::CancellationToken;
+
+/// Mock MCP server exposing `echo`, `add` and `delete_all` tools, `file:///`
+/// resources, and a `greet` prompt
+pub struct MockMcp {
+    addr: SocketAddr,
+    shutdown: CancellationToken,
+    state: Arc<MockMcpState>,
+}
+
+struct MockMcpState {
+    call_count: AtomicU32,
+    /// Sessions opened by clients
+    session_count: AtomicU32,
+    /// Tool calls currently running, and the most seen at once
+    in_flight: AtomicU32,
+    max_in_flight: AtomicU32,
+    /// Request headers of the most recent tool call
+    last_call_headers: Mutex<HeaderMap>,
+    /// Tools added after startup
+    added_tools: Mutex<Vec<String>>,
+    /// Initialized sessions, told when tools are added
+    peers: Mutex<Vec<Peer<RoleServer>>>,
+}
+
+impl MockMcp {
+    /// Start the mock server, returning immediately
+    pub async fn start() -> anyhow::Result<Self> {
+        Self::start_on("127.0.0.1:0".parse()?).await
+    }
+
+    /// Start the mock server on a given address
+    pub async fn start_on(addr: SocketAddr) -> anyhow::Result<Self> {
+        let state = Arc::new(MockMcpState {
+            call_count: AtomicU32::new(0),
+            session_count: AtomicU32::new(0),
+            in_flight: AtomicU32::new(0),
+            max_in_flight: AtomicU32::new(0),
+            last_call_headers: Mutex::new(HeaderMap::new()),
+            added_tools: Mutex::new(Vec::new()),
+            peers: Mutex::new(Vec::new()),
+        });
+        let shutdown = CancellationToken::new();
+
+        let handler_state = Arc::clone(&state);
+        let service = StreamableHttpService::new(
+            move || {
+                handler_state.session_count.fetch_add(1, Ordering::Relaxed);
+                Ok(MockHandler {
+                    state: Arc::clone(&handler_state),
+                })
+            },
+            Arc::new(LocalSessionManager::default()),
+            StreamableHttpServerConfig {
+                cancellation_token: shutdown.child_token(),
+                ..StreamableHttpServerConfig::default()
+            },
+        );
+        let app = Router::new().route_service("/mcp", service);
+
+        let listener = tokio::net::TcpListener::bind(addr).await?;
+        let addr = listener.local_addr()?;
+        let shutdown_clone = shutdown.clone();
+
+        tokio::spawn(async move {
+            axum::serve(listener, app)
+                .with_graceful_shutdown(async move {
+                    shutdown_clone.cancelled().await;
+                })
+                .await
+                .ok();
+        });
+
+        Ok(Self { addr, shutdown, state })
+    }
+
+    /// URL of the MCP endpoint
+    pub fn url(&self) -> String {
+        format!("http://{}/mcp", self.addr)
+    }
+
+    /// Number of tool calls received
+    pub fn call_count(&self) -> u32 {
+        self.state.call_count.load(Ordering::Relaxed)
+    }
+
+    /// Number of sessions opened
+    pub fn session_count(&self) -> u32 {
+        self.state.session_count.load(Ordering::Relaxed)
+    }
+
+    /// Most tool calls that ran at the same time
+    pub fn max_in_flight(&self) -> u32 {
+        self.state.max_in_flight.load(Ordering::Relaxed)
+    }
+
+    /// Add a tool and send `tools/list_changed` to every session
+    pub async fn add_tool(&self, name: &str) {
+        self.state.added_tools.lock().unwrap().push(name.to_owned());
+        let peers = self.state.peers.lock().unwrap().clone();
+        for peer in peers {
+            peer.notify_tool_list_changed().await.ok();
+        }
+    }
+
+    /// A request header of the most recent tool call
+    pub fn last_call_header(&self, name: &str) -> Option<String> {
+        let headers = self.state.last_call_headers.lock().unwrap();
+        headers.get(name).and_then(|v| v.to_str().ok()).map(ToOwned::to_owned)
+    }
+}
+
+impl Drop for MockMcp {
+    fn drop(&mut self) {
+        self.shutdown.cancel();
+    }
+}
+
+struct MockHandler {
+    state: Arc<MockMcpState>,
+}
+
+impl MockHandler {
+    /// Run a tool; `echo` sleeps for `delay_ms` before answering
+    async fn run_tool(
+        &self,
+        name: &str,
+        arguments: serde_json::Map<String, serde_json::Value>,
+    ) -> Result<CallToolResult, ErrorData> {
+        let text = match name {
+            "echo" => {
+                if let Some(delay) = arguments.get("delay_ms").and_then(serde_json::Value::as_u64) {
+                    tokio::time::sleep(std::time::Duration::from_millis(delay)).await;
+                }
+                arguments.get("text").and_then(|v| v.as_str()).unwrap_or("").to_owned()
+            }
+            "add" => {
+                let a = arguments.get("a").and_then(serde_json::Value::as_f64).unwrap_or(0.0);
+                let b = arguments.get("b").and_then(serde_json::Value::as_f64).unwrap_or(0.0);
+                (a + b).to_string()
+            }
+            "delete_all" => "deleted".to_owned(),
+            other => {
+                return Err(ErrorData::new(
+                    ErrorCode::INVALID_PARAMS,
+                    format!("unknown tool {other}"),
+                    None,
+                ));
+            }
+        };
+
+        Ok(CallToolResult::success(vec![Content::text(text)]))
+    }
+}
+
+impl ServerHandler for MockHandler {
+    fn get_info(&self) -> ServerInfo {
+        ServerInfo::new(
+            ServerCapabilities::builder()
+                .enable_tools()
+                .enable_tool_list_changed()
+                .enable_resources()
+                .enable_prompts()
+                .build(),
+        )
+    }
+
+    async fn on_initialized(&self, context: NotificationContext<RoleServer>) {
+        self.state.peers.lock().unwrap().push(context.peer);
+    }
+
+    async fn list_tools(
+        &self,
+        _request: Option<PaginatedRequestParams>,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<ListToolsResult, ErrorData> {
+        let mut tools = vec![
+            tool("echo", "Echo the given text back", &[("text", "string")]),
+            tool("add", "Add two numbers", &[("a", "number"), ("b", "number")]),
+            tool("delete_all", "Delete everything", &[]),
+        ];
+        let added = self.state.added_tools.lock().unwrap();
+        tools.extend(added.iter().map(|name| tool(name, "Added at runtime", &[])));
+
+        Ok(ListToolsResult::with_all_items(tools))
+    }
+
+    async fn call_tool(
+        &self,
+        request: CallToolRequestParams,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, ErrorData> {
+        self.state.call_count.fetch_add(1, Ordering::Relaxed);
+        if let Some(parts) = context.extensions.get::<axum::http::request::Parts>() {
+            *self.state.last_call_headers.lock().unwrap() = parts.headers.clone();
+        }
+        let arguments = request.arguments.unwrap_or_default();
+
+        let in_flight = self.state.in_flight.fetch_add(1, Ordering::Relaxed) + 1;
+        self.state.max_in_flight.fetch_max(in_flight, Ordering::Relaxed);
+        let result = self.run_tool(&request.name, arguments).await;
+        self.state.in_flight.fetch_sub(1, Ordering::Relaxed);
+        result
+    }
+
+    async fn list_resources(
+        &self,
+        _request: Option<PaginatedRequestParams>,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<ListResourcesResult, ErrorData> {
+        Ok(ListResourcesResult::with_all_items(vec![
+            RawResource::new("file:///readme.md", "readme").no_annotation(),
+            RawResource::new("file:///secrets.env", "secrets").no_annotation(),
+        ]))
+    }
+
+    async fn list_resource_templates(
+        &self,
+        _request: Option<PaginatedRequestParams>,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<ListResourceTemplatesResult, ErrorData> {
+        Ok(ListResourceTemplatesResult::with_all_items(vec![
+            RawResourceTemplate::new("file:///notes/{name}", "notes").no_annotation(),
+        ]))
+    }
+
+    async fn read_resource(
+        &self,
+        request: ReadResourceRequestParams,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<ReadResourceResult, ErrorData> {
+        let text = match request.uri.as_str() {
+            "file:///readme.md" => "# Mock".to_owned(),
+            "file:///secrets.env" => "TOKEN=hunter2".to_owned(),
+            uri => match uri.strip_prefix("file:///notes/") {
+                Some(name) => format!("notes on {name}"),
+                None => return Err(ErrorData::resource_not_found(format!("no resource {uri}"), None)),
+            },
+        };
+
+        Ok(ReadResourceResult::new(vec![ResourceContents::text(text, request.uri)]))
+    }
+
+    async fn list_prompts(
+        &self,
+        _request: Option<PaginatedRequestParams>,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<ListPromptsResult, ErrorData> {
+        let mut name = PromptArgument::new("name");
+        name.required = Some(true);
+
+        Ok(ListPromptsResult::with_all_items(vec![Prompt::new(
+            "greet",
+            Some("Greet someone by name"),
+            Some(vec![name]),
+        )]))
+    }
+
+    async fn get_prompt(
+        &self,
+        request: GetPromptRequestParams,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<GetPromptResult, ErrorData> {
+        if request.name != "greet" {
+            return Err(ErrorData::invalid_params(
+                format!("unknown prompt {}", request.name),
+                None,
+            ));
+        }
+        let name = request
+            .arguments
+            .as_ref()
+            .and_then(|arguments| arguments.get("name"))
+            .and_then(|v| v.as_str())
+            .unwrap_or("stranger");
+
+        Ok(GetPromptResult::new(vec![PromptMessage::new_text(
+            PromptMessageRole::User,
+            format!("Say hello to {name}"),
+        )]))
+    }
+}
+
+fn tool(name: &str, description: &str, properties: &[(&str, &str)]) -> Tool {
+    let properties: serde_json::Map<String, serde_json::Value> = properties
+        .iter()
+        .map(|(property, kind)| ((*property).to_owned(), serde_json::json!({ "type": kind })))
+        .collect();
+    let schema = serde_json::json!({"type": "object", "properties": properties});
+    let serde_json::Value::Object(schema) = schema else {
+        unreachable!()
+    };
+
+    Tool::new(name.to_owned(), description.to_owned(), schema)
+}
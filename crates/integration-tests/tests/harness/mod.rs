@@ -1,3 +1,4 @@
 pub mod config;
 pub mod mock_llm;
+pub mod mock_mcp;
 pub mod server;
@@ -35,3 +35,101 @@ async fn health_endpoint_disabled() {
 
     assert_eq!(resp.status(), 404);
 }
+
+#[tokio::test]
+async fn readiness_reports_subsystems() {
+    let mock = MockLlm::start().await.unwrap();
+    let config = ConfigBuilder::new()
+        .with_openai_provider("mock", &mock.base_url())
+        .build();
+
+    let server = TestServer::start(config).await.unwrap();
+
+    let resp = server.client().get(server.url("/health/ready")).send().await.unwrap();
+
+    assert_eq!(resp.status(), 200);
+
+    let body: serde_json::Value = resp.json().await.unwrap();
+    assert_eq!(body["status"], "ok");
+    assert_eq!(body["checks"]["llm"]["status"], "ok");
+    assert_eq!(body["checks"]["llm"]["providers"][0]["name"], "mock");
+    assert_eq!(body["checks"]["llm"]["providers"][0]["circuit"], "closed");
+    assert_eq!(body["checks"]["mcp"]["status"], "ok");
+    assert!(body["checks"].get("cache").is_none());
+    assert!(body["checks"].get("billing").is_none());
+}
+
+#[tokio::test]
+async fn unreachable_mcp_servers_only_degrade_readiness() {
+    let mock = MockLlm::start().await.unwrap();
+    let config = ConfigBuilder::new()
+        .with_openai_provider("mock", &mock.base_url())
+        .with_mcp_server("offline", "http://127.0.0.1:1/mcp", &[])
+        .build();
+
+    let server = TestServer::start(config).await.unwrap();
+
+    let resp = server.client().get(server.url("/health/ready")).send().await.unwrap();
+
+    assert_eq!(resp.status(), 200);
+
+    let body: serde_json::Value = resp.json().await.unwrap();
+    assert_eq!(body["status"], "degraded");
+    assert_eq!(body["checks"]["mcp"]["status"], "degraded");
+}
+
+#[tokio::test]
+async fn required_mcp_servers_gate_readiness() {
+    let mock = MockLlm::start().await.unwrap();
+    let mut config = ConfigBuilder::new()
+        .with_openai_provider("mock", &mock.base_url())
+        .with_mcp_server("offline", "http://127.0.0.1:1/mcp", &[])
+        .build();
+    config.server.health.mcp_required = true;
+
+    let server = TestServer::start(config).await.unwrap();
+
+    let resp = server.client().get(server.url("/health/ready")).send().await.unwrap();
+
+    assert_eq!(resp.status(), 503);
+
+    let body: serde_json::Value = resp.json().await.unwrap();
+    assert_eq!(body["checks"]["mcp"]["status"], "down");
+}
+
+#[tokio::test]
+async fn dedicated_health_listener_serves_health_routes() {
+    let mock = MockLlm::start().await.unwrap();
+    let config = ConfigBuilder::new()
+        .with_openai_provider("mock", &mock.base_url())
+        .with_health_listener("127.0.0.1:0".parse().unwrap())
+        .build();
+
+    let server = synapse_server::Server::new(config).await.unwrap();
+    let (main_router, health_router) = server.into_parts();
+    let health_router = health_router.expect("dedicated health router");
+
+    let main_listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let main_addr = main_listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(main_listener, main_router).await.ok() });
+
+    let health_listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let health_addr = health_listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(health_listener, health_router).await.ok() });
+
+    let client = reqwest::Client::new();
+
+    let resp = client.get(format!("http://{health_addr}/health")).send().await.unwrap();
+    assert_eq!(resp.status(), 200);
+
+    let resp = client
+        .get(format!("http://{health_addr}/health/ready"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+
+    // Health routes are not mounted on the main listener
+    let resp = client.get(format!("http://{main_addr}/health")).send().await.unwrap();
+    assert_eq!(resp.status(), 404);
+}
@@ -3,6 +3,7 @@ mod harness;
 use harness::config::ConfigBuilder;
 use harness::mock_llm::MockLlm;
 use harness::server::TestServer;
+use synapse_config::EquivalenceGroup;
 
 #[tokio::test]
 async fn openai_chat_completion_returns_response() {
@@ -115,3 +116,868 @@ async fn mock_llm_tracks_completions() {
 
     assert_eq!(mock.completion_count(), 2);
 }
+
+#[tokio::test]
+async fn feedback_is_linked_to_served_response() {
+    let mock = MockLlm::start().await.unwrap();
+    let config = ConfigBuilder::new()
+        .with_openai_provider("mock", &mock.base_url())
+        .with_user_feedback()
+        .build();
+
+    let server = TestServer::start(config).await.unwrap();
+
+    let body = serde_json::json!({
+        "model": "mock-model-1",
+        "messages": [
+            {"role": "user", "content": "Write a Rust function that parses JSON"}
+        ]
+    });
+
+    let resp = server
+        .client()
+        .post(server.url("/v1/chat/completions"))
+        .json(&body)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+    let json: serde_json::Value = resp.json().await.unwrap();
+    let response_id = json["id"].as_str().unwrap().to_owned();
+
+    let feedback = serde_json::json!({ "response_id": response_id, "thumbs": "up" });
+    let resp = server
+        .client()
+        .post(server.url("/v1/feedback"))
+        .json(&feedback)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+
+    let receipt: serde_json::Value = resp.json().await.unwrap();
+    assert_eq!(receipt["response_id"], response_id);
+    assert_eq!(receipt["provider"], "mock");
+    assert_eq!(receipt["model"], "mock-model-1");
+    assert_eq!(receipt["task_type"], "code");
+    assert_eq!(receipt["score"], 1.0);
+
+    // A response can only be rated once
+    let resp = server
+        .client()
+        .post(server.url("/v1/feedback"))
+        .json(&feedback)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 404);
+}
+
+#[tokio::test]
+async fn feedback_rejects_invalid_rating() {
+    let mock = MockLlm::start().await.unwrap();
+    let config = ConfigBuilder::new()
+        .with_openai_provider("mock", &mock.base_url())
+        .with_user_feedback()
+        .build();
+
+    let server = TestServer::start(config).await.unwrap();
+
+    let resp = server
+        .client()
+        .post(server.url("/v1/feedback"))
+        .json(&serde_json::json!({ "response_id": "chatcmpl-unknown", "rating": 9 }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 400);
+}
+
+#[tokio::test]
+async fn routing_explain_reports_decision_without_calling_provider() {
+    let mock = MockLlm::start().await.unwrap();
+    let config = ConfigBuilder::new()
+        .with_openai_provider("mock", &mock.base_url())
+        .with_smart_routing("mock")
+        .build();
+
+    let server = TestServer::start(config).await.unwrap();
+
+    let body = serde_json::json!({
+        "model": "auto",
+        "messages": [
+            {"role": "user", "content": "What is the capital of France?"}
+        ]
+    });
+
+    let resp = server
+        .client()
+        .post(server.url("/v1/routing/explain"))
+        .json(&body)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+
+    let json: serde_json::Value = resp.json().await.unwrap();
+    assert_eq!(json["strategy"], "threshold");
+    assert_eq!(json["profile"]["complexity"], "low");
+    assert_eq!(json["model"], "mock-small");
+    assert_eq!(json["reason"], "low_complexity");
+    assert_eq!(json["candidates"].as_array().unwrap().len(), 2);
+    assert_eq!(mock.completion_count(), 0);
+}
+
+#[tokio::test]
+async fn routed_requests_carry_routing_headers() {
+    let mock = MockLlm::start().await.unwrap();
+    let config = ConfigBuilder::new()
+        .with_openai_provider("mock", &mock.base_url())
+        .with_smart_routing("mock")
+        .build();
+
+    let server = TestServer::start(config).await.unwrap();
+
+    let body = serde_json::json!({
+        "model": "auto",
+        "messages": [
+            {"role": "user", "content": "What is the capital of France?"}
+        ]
+    });
+
+    let resp = server
+        .client()
+        .post(server.url("/v1/chat/completions"))
+        .json(&body)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+
+    let headers = resp.headers();
+    assert_eq!(headers["x-synapse-routing-model"], "mock/mock-small");
+    assert_eq!(headers["x-synapse-routing-strategy"], "threshold");
+    assert_eq!(headers["x-synapse-routing-reason"], "low_complexity");
+    assert_eq!(headers["x-synapse-routing-complexity"], "low");
+    assert!(headers.contains_key("x-synapse-routing-task-type"));
+}
+
+#[tokio::test]
+async fn conversation_affinity_keeps_later_turns_on_the_first_model() {
+    let mock = MockLlm::start().await.unwrap();
+    let config = ConfigBuilder::new()
+        .with_openai_provider("mock", &mock.base_url())
+        .with_smart_routing("mock")
+        .with_conversation_affinity()
+        .build();
+
+    let server = TestServer::start(config).await.unwrap();
+
+    let long_question = "Tell me more about its history and architecture. ".repeat(400);
+    let first = serde_json::json!({
+        "model": "auto",
+        "messages": [
+            {"role": "user", "content": "What is the capital of France?"}
+        ]
+    });
+    // A long follow-up is high complexity, which would route to the large model
+    let follow_up = serde_json::json!({
+        "model": "auto",
+        "messages": [
+            {"role": "user", "content": "What is the capital of France?"},
+            {"role": "assistant", "content": "Paris."},
+            {"role": "user", "content": long_question}
+        ]
+    });
+    let other = serde_json::json!({
+        "model": "auto",
+        "messages": [
+            {"role": "user", "content": long_question}
+        ]
+    });
+
+    let mut routed = Vec::new();
+    for body in [&first, &follow_up, &other] {
+        let resp = server
+            .client()
+            .post(server.url("/v1/chat/completions"))
+            .json(body)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), 200);
+
+        let headers = resp.headers();
+        routed.push((
+            headers["x-synapse-routing-model"].to_str().unwrap().to_owned(),
+            headers["x-synapse-routing-reason"].to_str().unwrap().to_owned(),
+        ));
+    }
+
+    assert_eq!(routed[0], ("mock/mock-small".to_owned(), "low_complexity".to_owned()));
+    assert_eq!(
+        routed[1],
+        ("mock/mock-small".to_owned(), "conversation_affinity".to_owned())
+    );
+    assert_eq!(routed[2], ("mock/mock-large".to_owned(), "high_complexity".to_owned()));
+}
+
+#[tokio::test]
+async fn context_window_is_checked_for_explicit_and_routed_models() {
+    let mock = MockLlm::start().await.unwrap();
+    let config = ConfigBuilder::new()
+        .with_openai_provider("mock", &mock.base_url())
+        .with_smart_routing("mock")
+        .with_context_window("mock-small", 1_000)
+        .build();
+
+    let server = TestServer::start(config).await.unwrap();
+
+    let body = serde_json::json!({
+        "model": "mock/mock-small",
+        "max_tokens": 4_000,
+        "messages": [
+            {"role": "user", "content": "What is the capital of France?"}
+        ]
+    });
+
+    let resp = server
+        .client()
+        .post(server.url("/v1/chat/completions"))
+        .json(&body)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 400);
+    assert_eq!(mock.completion_count(), 0);
+
+    // Routing skips the model whose window is too small
+    let mut routed = body.clone();
+    routed["model"] = serde_json::json!("auto");
+    let resp = server
+        .client()
+        .post(server.url("/v1/routing/explain"))
+        .json(&routed)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+
+    let json: serde_json::Value = resp.json().await.unwrap();
+    assert_eq!(json["profile"]["complexity"], "low");
+    assert_eq!(json["model"], "mock-large");
+}
+
+#[tokio::test]
+async fn context_upgrade_moves_to_larger_equivalent_model() {
+    let mock = MockLlm::start().await.unwrap();
+    let config = ConfigBuilder::new()
+        .with_openai_provider("mock", &mock.base_url())
+        .with_smart_routing("mock")
+        .with_context_window("mock-small", 1_000)
+        .with_failover(vec![EquivalenceGroup {
+            name: "chat".to_owned(),
+            models: vec!["mock/mock-small".to_owned(), "mock/mock-large".to_owned()],
+        }])
+        .with_context_upgrade()
+        .build();
+
+    let server = TestServer::start(config).await.unwrap();
+
+    let body = serde_json::json!({
+        "model": "mock-small",
+        "max_tokens": 4_000,
+        "messages": [
+            {"role": "user", "content": "What is the capital of France?"}
+        ]
+    });
+
+    let resp = server
+        .client()
+        .post(server.url("/v1/chat/completions"))
+        .json(&body)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+
+    let json: serde_json::Value = resp.json().await.unwrap();
+    assert_eq!(json["model"], "mock-large");
+
+    // An explicitly selected model is never swapped
+    let mut explicit = body.clone();
+    explicit["model"] = serde_json::json!("mock/mock-small");
+    let resp = server
+        .client()
+        .post(server.url("/v1/chat/completions"))
+        .json(&explicit)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 400);
+    assert_eq!(mock.completion_count(), 1);
+}
+
+#[tokio::test]
+async fn classifier_routing_overrides_heuristic_complexity() {
+    let mock = MockLlm::start_with_response(r#"{"task_type": "code", "complexity": "high"}"#)
+        .await
+        .unwrap();
+    let config = ConfigBuilder::new()
+        .with_openai_provider("mock", &mock.base_url())
+        .with_classifier_routing("mock", 5_000)
+        .build();
+
+    let server = TestServer::start(config).await.unwrap();
+
+    let body = serde_json::json!({
+        "model": "auto",
+        "messages": [
+            {"role": "user", "content": "What is the capital of France?"}
+        ]
+    });
+
+    let explain = || async {
+        let resp = server
+            .client()
+            .post(server.url("/v1/routing/explain"))
+            .json(&body)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), 200);
+        resp.json::<serde_json::Value>().await.unwrap()
+    };
+
+    // A dry run never calls the classifier, so it starts from the heuristics
+    let json = explain().await;
+    assert_eq!(json["strategy"], "classifier");
+    assert_eq!(json["profile"]["complexity"], "low");
+    assert_eq!(mock.completion_count(), 0);
+
+    let resp = server
+        .client()
+        .post(server.url("/v1/chat/completions"))
+        .json(&body)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+    assert_eq!(resp.headers()["x-synapse-routing-model"], "mock/mock-large");
+
+    // The classification is cached for later dry runs
+    let json = explain().await;
+    assert_eq!(json["profile"]["task_type"], "code");
+    assert_eq!(json["profile"]["complexity"], "high");
+    assert_eq!(json["model"], "mock-large");
+
+    // One classifier call and one completion
+    assert_eq!(mock.completion_count(), 2);
+}
+
+#[tokio::test]
+async fn classifier_routing_falls_back_to_heuristics_on_invalid_answer() {
+    // The default mock answer is prose, not a classification
+    let mock = MockLlm::start().await.unwrap();
+    let config = ConfigBuilder::new()
+        .with_openai_provider("mock", &mock.base_url())
+        .with_classifier_routing("mock", 5_000)
+        .build();
+
+    let server = TestServer::start(config).await.unwrap();
+
+    let body = serde_json::json!({
+        "model": "auto",
+        "messages": [
+            {"role": "user", "content": "What is the capital of France?"}
+        ]
+    });
+
+    let resp = server
+        .client()
+        .post(server.url("/v1/chat/completions"))
+        .json(&body)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+    assert_eq!(resp.headers()["x-synapse-routing-model"], "mock/mock-small");
+}
+
+#[tokio::test]
+async fn cascade_verifier_grade_drives_escalation() {
+    // Every completion, including the verifier's, answers with a failing grade
+    let mock = MockLlm::start_with_response(r#"{"grade": 0.1}"#).await.unwrap();
+    let config = ConfigBuilder::new()
+        .with_openai_provider("mock", &mock.base_url())
+        .with_cascade_routing("mock", true)
+        .build();
+
+    let server = TestServer::start(config).await.unwrap();
+
+    let body = serde_json::json!({
+        "model": "auto",
+        "messages": [
+            {"role": "user", "content": "What is the capital of France?"}
+        ]
+    });
+
+    let resp = server
+        .client()
+        .post(server.url("/v1/chat/completions"))
+        .json(&body)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+
+    let json: serde_json::Value = resp.json().await.unwrap();
+    assert_eq!(json["model"], "mock-large");
+    // Initial answer, verification, escalated answer
+    assert_eq!(mock.completion_count(), 3);
+
+    let mut streaming = body.clone();
+    streaming["stream"] = serde_json::json!(true);
+    let resp = server
+        .client()
+        .post(server.url("/v1/chat/completions"))
+        .json(&streaming)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+    let _ = resp.text().await.unwrap();
+    assert_eq!(mock.completion_count(), 6);
+}
+
+#[tokio::test]
+async fn cascade_streams_tool_calls_without_grading() {
+    let mock = MockLlm::start_with_response(r#"{"grade": 0.1}"#).await.unwrap();
+    let config = ConfigBuilder::new()
+        .with_openai_provider("mock", &mock.base_url())
+        .with_cascade_routing("mock", true)
+        .with_tool_calling_models()
+        .build();
+
+    let server = TestServer::start(config).await.unwrap();
+
+    let body = serde_json::json!({
+        "model": "auto",
+        "stream": true,
+        "messages": [
+            {"role": "user", "content": "What is the weather in San Francisco?"}
+        ],
+        "tools": [{
+            "type": "function",
+            "function": {
+                "name": "get_weather",
+                "parameters": {"type": "object", "properties": {"city": {"type": "string"}}}
+            }
+        }]
+    });
+
+    let resp = server
+        .client()
+        .post(server.url("/v1/chat/completions"))
+        .json(&body)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+    let text = resp.text().await.unwrap();
+    assert!(text.contains("get_weather"));
+    // No verification and no escalation of a tool-call turn
+    assert_eq!(mock.completion_count(), 1);
+}
+
+#[tokio::test]
+async fn cascade_without_verifier_keeps_confident_answers() {
+    let mock = MockLlm::start_with_response(r#"{"grade": 0.1}"#).await.unwrap();
+    let config = ConfigBuilder::new()
+        .with_openai_provider("mock", &mock.base_url())
+        .with_cascade_routing("mock", false)
+        .build();
+
+    let server = TestServer::start(config).await.unwrap();
+
+    let body = serde_json::json!({
+        "model": "auto",
+        "messages": [
+            {"role": "user", "content": "What is the capital of France?"}
+        ]
+    });
+
+    let resp = server
+        .client()
+        .post(server.url("/v1/chat/completions"))
+        .json(&body)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+
+    let json: serde_json::Value = resp.json().await.unwrap();
+    assert_eq!(json["model"], "mock-small");
+    assert_eq!(mock.completion_count(), 1);
+}
+
+#[tokio::test]
+async fn model_catalog_profiles_discovered_models_and_reloads() {
+    let catalog = std::env::temp_dir().join(format!("synapse-catalog-{}.json", std::process::id()));
+    let write_catalog = |context: u32| {
+        let contents = serde_json::json!({
+            "mock": {"models": {"mock-model-1": {
+                "tool_call": true,
+                "cost": {"input": 1.0, "output": 2.0},
+                "limit": {"context": context, "output": 4096}
+            }}}
+        });
+        std::fs::write(&catalog, contents.to_string()).unwrap();
+    };
+    write_catalog(1_000);
+
+    let mock = MockLlm::start().await.unwrap();
+    let config = ConfigBuilder::new()
+        .with_openai_provider("mock", &mock.base_url())
+        .with_model_catalog(&catalog)
+        .build();
+
+    let server = TestServer::start(config).await.unwrap();
+
+    let body = serde_json::json!({
+        "model": "mock/mock-model-1",
+        "max_tokens": 4_000,
+        "messages": [
+            {"role": "user", "content": "What is the capital of France?"}
+        ]
+    });
+    let wait_for_status = |expected: u16| {
+        let server = &server;
+        let body = &body;
+        async move {
+            for _ in 0..20 {
+                let resp = server
+                    .client()
+                    .post(server.url("/v1/chat/completions"))
+                    .json(body)
+                    .send()
+                    .await
+                    .unwrap();
+                if resp.status() == expected {
+                    return true;
+                }
+                tokio::time::sleep(std::time::Duration::from_millis(250)).await;
+            }
+            false
+        }
+    };
+
+    // The discovered model gets the catalog's context window
+    assert!(wait_for_status(400).await);
+
+    // A larger window in the reloaded catalog lets the request through
+    write_catalog(200_000);
+    assert!(wait_for_status(200).await);
+
+    std::fs::remove_file(&catalog).ok();
+}
+
+#[tokio::test]
+async fn knn_routing_starts_with_billing_enabled() {
+    let examples = std::env::temp_dir().join(format!("synapse-knn-{}.jsonl", std::process::id()));
+    std::fs::write(
+        &examples,
+        r#"{"prompt": "hello", "embedding": [1.0, 0.0], "scores": {"mock/mock-small": 0.9}}"#,
+    )
+    .unwrap();
+
+    let mock = MockLlm::start().await.unwrap();
+    let config = ConfigBuilder::new()
+        .with_openai_provider("mock", &mock.base_url())
+        .with_embeddings_provider("mock", &mock.base_url())
+        .with_knn_routing("mock", "mock/text-embedding-3-small", &examples)
+        .with_billing("http://127.0.0.1:9")
+        .build();
+
+    let server = TestServer::start(config).await;
+    std::fs::remove_file(&examples).ok();
+
+    server.expect("server starts with knn routing and billing enabled");
+}
+
+#[tokio::test]
+async fn script_strategy_routes_by_client_group() {
+    let script = std::env::temp_dir().join(format!("synapse-route-{}.rhai", std::process::id()));
+    std::fs::write(
+        &script,
+        r#"
+        fn route(query, models, client) {
+            if client == () || client.group != "pro" {
+                return "mock/mock-small";
+            }
+            #{ model: "mock/mock-large", alternatives: ["mock/mock-small"] }
+        }
+        "#,
+    )
+    .unwrap();
+
+    let mock = MockLlm::start().await.unwrap();
+    let config = ConfigBuilder::new()
+        .with_openai_provider("mock", &mock.base_url())
+        .with_script_routing("mock", "by-group", &script)
+        .build();
+
+    let server = TestServer::start(config).await.unwrap();
+
+    let body = serde_json::json!({
+        "model": "auto",
+        "messages": [
+            {"role": "user", "content": "What is the capital of France?"}
+        ]
+    });
+
+    let resp = server
+        .client()
+        .post(server.url("/v1/chat/completions"))
+        .json(&body)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+
+    let headers = resp.headers();
+    assert_eq!(headers["x-synapse-routing-model"], "mock/mock-small");
+    assert_eq!(headers["x-synapse-routing-strategy"], "by-group");
+    assert_eq!(headers["x-synapse-routing-reason"], "scripted");
+
+    let _ = std::fs::remove_file(&script);
+}
+
+#[tokio::test]
+async fn experiment_routes_to_arm_and_reports_outcomes() {
+    let mock = MockLlm::start().await.unwrap();
+    let config = ConfigBuilder::new()
+        .with_openai_provider("mock", &mock.base_url())
+        .with_experiment(
+            "rollout",
+            "chat",
+            &[
+                ("control", "mock/mock-model-1", 1),
+                ("candidate", "mock/mock-model-2", 0),
+            ],
+        )
+        .build();
+
+    let server = TestServer::start(config).await.unwrap();
+
+    let body = serde_json::json!({
+        "model": "chat",
+        "messages": [
+            {"role": "user", "content": "Hello"}
+        ]
+    });
+
+    let resp = server
+        .client()
+        .post(server.url("/v1/chat/completions"))
+        .json(&body)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+    assert_eq!(resp.headers()["x-synapse-experiment"], "rollout");
+    assert_eq!(resp.headers()["x-synapse-experiment-arm"], "control");
+    assert_eq!(mock.completion_count(), 1);
+
+    let resp = server
+        .client()
+        .get(server.url("/v1/routing/experiments"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+
+    let json: serde_json::Value = resp.json().await.unwrap();
+    let arms = json[0]["arms"].as_array().unwrap();
+    assert_eq!(json[0]["name"], "rollout");
+    assert_eq!(arms[0]["name"], "control");
+    assert_eq!(arms[0]["requests"], 1);
+    assert_eq!(arms[0]["error_rate"], 0.0);
+    assert_eq!(arms[1]["requests"], 0);
+}
+
+/// Wait for the shadow sink to contain a record
+async fn read_shadow_record(path: &std::path::Path) -> serde_json::Value {
+    for _ in 0..50 {
+        if let Ok(contents) = tokio::fs::read_to_string(path).await
+            && let Some(line) = contents.lines().next()
+        {
+            return serde_json::from_str(line).unwrap();
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    }
+    panic!("no shadow record written to {}", path.display());
+}
+
+#[tokio::test]
+async fn shadow_mirror_records_both_outcomes() {
+    let sink = std::env::temp_dir().join(format!("synapse-shadow-{}.jsonl", std::process::id()));
+    let _ = std::fs::remove_file(&sink);
+
+    let mock = MockLlm::start().await.unwrap();
+    let config = ConfigBuilder::new()
+        .with_openai_provider("mock", &mock.base_url())
+        .with_shadow(&sink, "mock/mock-model-1", "mock/mock-model-2")
+        .build();
+
+    let server = TestServer::start(config).await.unwrap();
+
+    let body = serde_json::json!({
+        "model": "mock/mock-model-1",
+        "messages": [
+            {"role": "user", "content": "Hello"}
+        ]
+    });
+
+    let resp = server
+        .client()
+        .post(server.url("/v1/chat/completions"))
+        .json(&body)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+
+    let record = read_shadow_record(&sink).await;
+    assert_eq!(record["requested_model"], "mock/mock-model-1");
+    assert_eq!(record["primary"]["model"], "mock-model-1");
+    assert_eq!(record["shadow"]["provider"], "mock");
+    assert_eq!(record["shadow"]["model"], "mock-model-2");
+    assert!(record["shadow"]["output"].is_string());
+    assert!(record["shadow"]["error"].is_null());
+    assert_eq!(mock.completion_count(), 2);
+
+    let _ = std::fs::remove_file(&sink);
+}
+
+#[tokio::test]
+async fn training_data_records_routed_requests_with_ratings() {
+    let path = std::env::temp_dir().join(format!("synapse-training-{}.csv", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+
+    let mock = MockLlm::start().await.unwrap();
+    let config = ConfigBuilder::new()
+        .with_openai_provider("mock", &mock.base_url())
+        .with_smart_routing("mock")
+        .with_user_feedback()
+        .with_training_data(&path, 1)
+        .build();
+
+    let server = TestServer::start(config).await.unwrap();
+
+    let body = serde_json::json!({
+        "model": "auto",
+        "messages": [
+            {"role": "user", "content": "What is the capital of France?"}
+        ]
+    });
+    let resp = server
+        .client()
+        .post(server.url("/v1/chat/completions"))
+        .json(&body)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+    let json: serde_json::Value = resp.json().await.unwrap();
+    let response_id = json["id"].as_str().unwrap().to_owned();
+
+    let feedback = serde_json::json!({ "response_id": response_id, "thumbs": "up" });
+    let resp = server
+        .client()
+        .post(server.url("/v1/feedback"))
+        .json(&feedback)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+
+    // Explicit models are not smart-routed and produce no sample
+    let body = serde_json::json!({
+        "model": "mock/mock-model-1",
+        "messages": [
+            {"role": "user", "content": "Hello"}
+        ]
+    });
+    let resp = server
+        .client()
+        .post(server.url("/v1/chat/completions"))
+        .json(&body)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+
+    let mut lines = Vec::new();
+    for _ in 0..50 {
+        let contents = std::fs::read_to_string(&path).unwrap_or_default();
+        lines = contents.lines().map(str::to_owned).collect();
+        if lines.len() > 1 {
+            break;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    }
+
+    assert_eq!(lines.len(), 2, "expected a header and one sample, got {lines:?}");
+    let header: Vec<&str> = lines[0].split(',').collect();
+    let row: Vec<&str> = lines[1].split(',').collect();
+    let field = |name: &str| row[header.iter().position(|column| *column == name).unwrap()];
+
+    assert_eq!(
+        header[..9].join(","),
+        "input_tokens,task_type,complexity,tool_use,vision,long_context,message_count,system_prompt,selected_model"
+    );
+    assert_eq!(field("selected_model"), "0");
+    assert_eq!(field("model"), "mock-small");
+    assert_eq!(field("reason"), "low_complexity");
+    assert_eq!(field("rating"), "1");
+    assert_eq!(field("response_id"), response_id);
+
+    let _ = std::fs::remove_file(&path);
+}
+
+#[tokio::test]
+async fn shadow_mirror_skips_requests_with_pii() {
+    let sink = std::env::temp_dir().join(format!("synapse-shadow-pii-{}.jsonl", std::process::id()));
+    let _ = std::fs::remove_file(&sink);
+
+    let mock = MockLlm::start().await.unwrap();
+    let config = ConfigBuilder::new()
+        .with_openai_provider("mock", &mock.base_url())
+        .with_shadow(&sink, "mock/mock-model-1", "mock/mock-model-2")
+        .build();
+
+    let server = TestServer::start(config).await.unwrap();
+
+    let body = serde_json::json!({
+        "model": "mock/mock-model-1",
+        "messages": [
+            {"role": "user", "content": "Write to jane@example.com"}
+        ]
+    });
+
+    let resp = server
+        .client()
+        .post(server.url("/v1/chat/completions"))
+        .json(&body)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+
+    tokio::time::sleep(std::time::Duration::from_millis(300)).await;
+    assert_eq!(mock.completion_count(), 1);
+    assert!(std::fs::read_to_string(&sink).unwrap_or_default().is_empty());
+
+    let _ = std::fs::remove_file(&sink);
+}
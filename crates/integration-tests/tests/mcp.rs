@@ -0,0 +1,1064 @@
+//! MCP gateway integration tests
+
+mod harness;
+
+use std::collections::HashMap;
+
+use harness::config::ConfigBuilder;
+use harness::mock_llm::MockLlm;
+use harness::mock_mcp::MockMcp;
+use harness::server::TestServer;
+use reqwest::header::{HeaderName, HeaderValue};
+use rmcp::ServiceExt as _;
+use rmcp::model::{CallToolRequestParams, ErrorCode, GetPromptRequestParams, ReadResourceRequestParams};
+use rmcp::transport::StreamableHttpClientTransport;
+use rmcp::transport::streamable_http_client::StreamableHttpClientTransportConfig;
+use synapse_client::{ContentBlock, SynapseClient};
+use synapse_config::{
+    Config, McpAccessConfig, McpAccessRules, McpArgumentConstraint, McpAuthConfig, McpHeaderInsert, McpPolicyConfig,
+    McpPolicySubject, McpRateLimit, McpServerType,
+};
+
+/// Config with the mock server's secrets resource denied
+fn config_hiding_secrets(mock: &MockMcp) -> Config {
+    let mut config = ConfigBuilder::new().with_mcp_server("mock", &mock.url(), &[]).build();
+    config.mcp.servers["mock"].access = Some(McpAccessConfig {
+        resources: McpAccessRules {
+            allow: Vec::new(),
+            deny: vec!["file:///secrets.env".to_owned()],
+        },
+        ..McpAccessConfig::default()
+    });
+    config
+}
+
+#[tokio::test]
+async fn endpoint_serves_aggregated_tools() {
+    let mock = MockMcp::start().await.unwrap();
+    let config = ConfigBuilder::new()
+        .with_mcp_server("mock", &mock.url(), &["delete_all"])
+        .build();
+    let server = TestServer::start(config).await.unwrap();
+
+    let transport = StreamableHttpClientTransport::from_uri(server.url("/mcp"));
+    let client = ().serve(transport).await.unwrap();
+
+    // Tools keep their qualified names and denied tools are hidden
+    let mut names: Vec<String> = client
+        .list_all_tools()
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|tool| tool.name.to_string())
+        .collect();
+    names.sort();
+    assert_eq!(names, ["mock__add", "mock__echo"]);
+
+    let arguments = serde_json::json!({"text": "hello"});
+    let result = client
+        .call_tool(CallToolRequestParams::new("mock__echo").with_arguments(arguments.as_object().unwrap().clone()))
+        .await
+        .unwrap();
+    assert_eq!(result.content[0].as_text().unwrap().text, "hello");
+
+    // Denied tools are rejected before reaching the downstream server
+    let error = client
+        .call_tool(CallToolRequestParams::new("mock__delete_all"))
+        .await
+        .unwrap_err();
+    let rmcp::ServiceError::McpError(error) = error else {
+        panic!("expected an MCP error, got {error:?}");
+    };
+    assert_eq!(error.code, ErrorCode::INVALID_REQUEST);
+    assert_eq!(error.data.unwrap()["type"], "access_denied");
+    assert_eq!(mock.call_count(), 1);
+
+    client.cancel().await.unwrap();
+}
+
+#[tokio::test]
+async fn endpoint_can_be_disabled() {
+    let mock = MockMcp::start().await.unwrap();
+    let mut config = ConfigBuilder::new().with_mcp_server("mock", &mock.url(), &[]).build();
+    config.mcp.endpoint.enabled = false;
+    let server = TestServer::start(config).await.unwrap();
+
+    let response = server
+        .client()
+        .post(server.url("/mcp"))
+        .json(&serde_json::json!({"jsonrpc": "2.0", "id": 1, "method": "ping"}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 404);
+
+    // The REST routes are unaffected
+    let response = server
+        .client()
+        .post(server.url("/mcp/tools/list"))
+        .json(&serde_json::json!({}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+}
+
+#[tokio::test]
+async fn rest_routes_serve_resources_and_prompts() {
+    let mock = MockMcp::start().await.unwrap();
+    let server = TestServer::start(config_hiding_secrets(&mock)).await.unwrap();
+    let client = SynapseClient::new(&server.url("")).unwrap();
+
+    // Denied resources are hidden and unreadable
+    let resources = client.list_resources(None).await.unwrap();
+    let uris: Vec<&str> = resources.iter().map(|r| r.uri.as_str()).collect();
+    assert_eq!(uris, ["mock__file:///readme.md"]);
+    assert_eq!(resources[0].name, "mock__readme");
+    assert!(client.read_resource("mock__file:///secrets.env").await.is_err());
+
+    let contents = client.read_resource("mock__file:///readme.md").await.unwrap();
+    assert_eq!(contents[0].uri, "mock__file:///readme.md");
+    assert_eq!(contents[0].text.as_deref(), Some("# Mock"));
+
+    // Templates expand to URIs under the same qualification
+    let templates = client.list_resource_templates(Some("mock")).await.unwrap();
+    assert_eq!(templates[0].uri_template, "mock__file:///notes/{name}");
+    let contents = client.read_resource("mock__file:///notes/today").await.unwrap();
+    assert_eq!(contents[0].text.as_deref(), Some("notes on today"));
+
+    let prompts = client.list_prompts(None).await.unwrap();
+    assert_eq!(prompts[0].name, "mock__greet");
+    assert!(prompts[0].arguments[0].required);
+
+    let prompt = client
+        .get_prompt("mock__greet", serde_json::json!({"name": "Ada"}))
+        .await
+        .unwrap();
+    assert_eq!(prompt.messages[0].role, "user");
+    let ContentBlock::Text { ref text } = prompt.messages[0].content else {
+        panic!("expected text content");
+    };
+    assert_eq!(text, "Say hello to Ada");
+
+    assert!(client.get_prompt("mock__missing", serde_json::json!({})).await.is_err());
+}
+
+#[tokio::test]
+async fn endpoint_serves_resources_and_prompts() {
+    let mock = MockMcp::start().await.unwrap();
+    let server = TestServer::start(config_hiding_secrets(&mock)).await.unwrap();
+
+    let transport = StreamableHttpClientTransport::from_uri(server.url("/mcp"));
+    let client = ().serve(transport).await.unwrap();
+
+    let resources = client.list_all_resources().await.unwrap();
+    assert_eq!(resources.len(), 1);
+    assert_eq!(resources[0].uri, "mock__file:///readme.md");
+
+    let result = client
+        .read_resource(ReadResourceRequestParams::new("mock__file:///readme.md"))
+        .await
+        .unwrap();
+    assert_eq!(result.contents.len(), 1);
+
+    let error = client
+        .read_resource(ReadResourceRequestParams::new("mock__file:///secrets.env"))
+        .await
+        .unwrap_err();
+    let rmcp::ServiceError::McpError(error) = error else {
+        panic!("expected an MCP error, got {error:?}");
+    };
+    assert_eq!(error.data.unwrap()["type"], "access_denied");
+
+    let templates = client.list_all_resource_templates().await.unwrap();
+    assert_eq!(templates[0].uri_template, "mock__file:///notes/{name}");
+
+    let prompts = client.list_all_prompts().await.unwrap();
+    assert_eq!(prompts[0].name, "mock__greet");
+
+    let arguments = serde_json::json!({"name": "Ada"});
+    let prompt = client
+        .get_prompt(GetPromptRequestParams::new("mock__greet").with_arguments(arguments.as_object().unwrap().clone()))
+        .await
+        .unwrap();
+    assert_eq!(prompt.messages.len(), 1);
+
+    client.cancel().await.unwrap();
+}
+
+#[tokio::test]
+async fn tool_calls_are_rate_limited_per_client() {
+    let mock = MockMcp::start().await.unwrap();
+    let mut config = ConfigBuilder::new()
+        .with_mcp_server("mock", &mock.url(), &[])
+        .with_client_identification("x-client-id", None)
+        .build();
+    let server_config = &mut config.mcp.servers["mock"];
+    server_config.rate_limit = Some(McpRateLimit {
+        requests: 2,
+        window: "1m".to_owned(),
+    });
+    server_config.tool_rate_limits.insert(
+        "echo".to_owned(),
+        McpRateLimit {
+            requests: 1,
+            window: "1m".to_owned(),
+        },
+    );
+    let server = TestServer::start(config).await.unwrap();
+
+    let call = |client: &'static str, tool: &'static str| {
+        server
+            .client()
+            .post(server.url("/mcp/tools/call"))
+            .header("x-client-id", client)
+            .json(&serde_json::json!({"name": tool, "arguments": {"a": 1, "b": 2, "text": "hi"}}))
+            .send()
+    };
+
+    // A tool's own limit applies on top of the server limit
+    assert_eq!(call("alice", "mock__echo").await.unwrap().status(), 200);
+    assert_eq!(call("alice", "mock__echo").await.unwrap().status(), 429);
+
+    // The echo call counts against the server limit too
+    assert_eq!(call("alice", "mock__add").await.unwrap().status(), 200);
+    let response = call("alice", "mock__add").await.unwrap();
+    assert_eq!(response.status(), 429);
+    assert!(response.headers().contains_key("retry-after"));
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["error"]["type"], "rate_limited");
+
+    // Clients are counted apart
+    assert_eq!(call("bob", "mock__echo").await.unwrap().status(), 200);
+    assert_eq!(call("bob", "mock__add").await.unwrap().status(), 200);
+
+    assert_eq!(mock.call_count(), 4);
+}
+
+#[tokio::test]
+async fn anonymous_tool_calls_are_rate_limited_per_ip() {
+    let mock = MockMcp::start().await.unwrap();
+    let mut config = ConfigBuilder::new().with_mcp_server("mock", &mock.url(), &[]).build();
+    config.mcp.servers["mock"].rate_limit = Some(McpRateLimit {
+        requests: 1,
+        window: "1m".to_owned(),
+    });
+    let server = TestServer::start(config).await.unwrap();
+
+    let call = |ip: &'static str| {
+        server
+            .client()
+            .post(server.url("/mcp/tools/call"))
+            .header("x-forwarded-for", ip)
+            .json(&serde_json::json!({"name": "mock__echo", "arguments": {"text": "hi"}}))
+            .send()
+    };
+
+    assert_eq!(call("203.0.113.1").await.unwrap().status(), 200);
+    assert_eq!(call("203.0.113.1").await.unwrap().status(), 429);
+    assert_eq!(call("203.0.113.2").await.unwrap().status(), 200);
+}
+
+#[tokio::test]
+async fn endpoint_rate_limit_errors_carry_retry_after() {
+    let mock = MockMcp::start().await.unwrap();
+    let mut config = ConfigBuilder::new().with_mcp_server("mock", &mock.url(), &[]).build();
+    config.mcp.servers["mock"].rate_limit = Some(McpRateLimit {
+        requests: 1,
+        window: "1h".to_owned(),
+    });
+    let server = TestServer::start(config).await.unwrap();
+
+    let transport = StreamableHttpClientTransport::from_uri(server.url("/mcp"));
+    let client = ().serve(transport).await.unwrap();
+
+    client
+        .call_tool(CallToolRequestParams::new("mock__delete_all"))
+        .await
+        .unwrap();
+    let error = client
+        .call_tool(CallToolRequestParams::new("mock__delete_all"))
+        .await
+        .unwrap_err();
+    let rmcp::ServiceError::McpError(error) = error else {
+        panic!("expected an MCP error, got {error:?}");
+    };
+    let data = error.data.unwrap();
+    assert_eq!(data["type"], "rate_limited");
+    assert!(data["retry_after"].as_u64().unwrap() > 0);
+
+    client.cancel().await.unwrap();
+}
+
+/// Point the mock server's auth at `auth` and insert a tenant header
+fn config_with_auth(mock: &MockMcp, auth: McpAuthConfig) -> Config {
+    let mut config = ConfigBuilder::new().with_mcp_server("mock", &mock.url(), &[]).build();
+    let server_config = &mut config.mcp.servers["mock"];
+    if let McpServerType::StreamableHttp(ref mut http) = server_config.server_type {
+        http.auth = Some(auth);
+    }
+    server_config.headers.push(McpHeaderInsert {
+        name: "x-tenant".to_owned(),
+        value: "acme".to_owned(),
+    });
+    config
+}
+
+#[tokio::test]
+async fn tool_calls_carry_configured_headers_and_token() {
+    let mock = MockMcp::start().await.unwrap();
+    let config = config_with_auth(
+        &mock,
+        McpAuthConfig::Token {
+            token: "downstream-secret".to_owned().into(),
+        },
+    );
+    let server = TestServer::start(config).await.unwrap();
+
+    let response = server
+        .client()
+        .post(server.url("/mcp/tools/call"))
+        .json(&serde_json::json!({"name": "mock__echo", "arguments": {"text": "hi"}}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+
+    assert_eq!(mock.last_call_header("x-tenant").as_deref(), Some("acme"));
+    assert_eq!(
+        mock.last_call_header("authorization").as_deref(),
+        Some("Bearer downstream-secret")
+    );
+}
+
+#[tokio::test]
+async fn forwarded_auth_uses_each_callers_credential() {
+    let mock = MockMcp::start().await.unwrap();
+    let server = TestServer::start(config_with_auth(&mock, McpAuthConfig::Forward))
+        .await
+        .unwrap();
+
+    let call = |authorization: Option<&'static str>| {
+        let mut request = server
+            .client()
+            .post(server.url("/mcp/tools/call"))
+            .json(&serde_json::json!({"name": "mock__echo", "arguments": {"text": "hi"}}));
+        if let Some(authorization) = authorization {
+            request = request.header("authorization", authorization);
+        }
+        request.send()
+    };
+
+    assert_eq!(call(Some("Bearer alice")).await.unwrap().status(), 200);
+    assert_eq!(mock.last_call_header("authorization").as_deref(), Some("Bearer alice"));
+    assert_eq!(mock.last_call_header("x-tenant").as_deref(), Some("acme"));
+
+    assert_eq!(call(Some("Bearer bob")).await.unwrap().status(), 200);
+    assert_eq!(mock.last_call_header("authorization").as_deref(), Some("Bearer bob"));
+
+    // Callers without a credential share the unauthenticated connection
+    assert_eq!(call(None).await.unwrap().status(), 200);
+    assert_eq!(mock.last_call_header("authorization"), None);
+}
+
+/// Fire `count` concurrent slow echo calls through the REST route
+async fn slow_calls(server: &TestServer, count: usize) {
+    let calls = (0..count).map(|_| {
+        server
+            .client()
+            .post(server.url("/mcp/tools/call"))
+            .json(&serde_json::json!({"name": "mock__echo", "arguments": {"text": "hi", "delay_ms": 300}}))
+            .send()
+    });
+    for response in futures_util::future::join_all(calls).await {
+        assert_eq!(response.unwrap().status(), 200);
+    }
+}
+
+#[tokio::test]
+async fn tool_calls_to_one_server_run_concurrently() {
+    let mock = MockMcp::start().await.unwrap();
+    let config = ConfigBuilder::new().with_mcp_server("mock", &mock.url(), &[]).build();
+    let server = TestServer::start(config).await.unwrap();
+
+    slow_calls(&server, 4).await;
+
+    assert_eq!(mock.session_count(), 1);
+    assert_eq!(mock.max_in_flight(), 4);
+}
+
+#[tokio::test]
+async fn concurrency_limit_and_pool_apply_per_server() {
+    let mock = MockMcp::start().await.unwrap();
+    let mut config = ConfigBuilder::new().with_mcp_server("mock", &mock.url(), &[]).build();
+    let server_config = &mut config.mcp.servers["mock"];
+    server_config.max_concurrent_requests = Some(2);
+    server_config.pool_size = 3;
+    let server = TestServer::start(config).await.unwrap();
+
+    slow_calls(&server, 4).await;
+
+    assert_eq!(mock.session_count(), 3);
+    assert_eq!(mock.max_in_flight(), 2);
+}
+
+/// Send an admin request with the test secret
+fn admin(server: &TestServer, method: reqwest::Method, path: &str) -> reqwest::RequestBuilder {
+    server
+        .client()
+        .request(method, server.url(path))
+        .header("x-admin-secret", "admin-secret")
+}
+
+/// Qualified names of the tools visible to a caller in `group`
+async fn tool_names(server: &TestServer, group: Option<&str>) -> Vec<String> {
+    let mut request = server
+        .client()
+        .post(server.url("/mcp/tools/list"))
+        .header("x-client-id", "alice")
+        .json(&serde_json::json!({}));
+    if let Some(group) = group {
+        request = request.header("x-group", group);
+    }
+
+    let body: serde_json::Value = request.send().await.unwrap().json().await.unwrap();
+    let mut names: Vec<String> = body["tools"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|tool| tool["name"].as_str().unwrap().to_owned())
+        .collect();
+    names.sort();
+    names
+}
+
+#[tokio::test]
+async fn servers_are_managed_at_runtime() {
+    let mock = MockMcp::start().await.unwrap();
+    let config = ConfigBuilder::new()
+        .with_mcp_admin("admin-secret")
+        .with_client_identification("x-client-id", Some("x-group"))
+        .build();
+    let server = TestServer::start(config).await.unwrap();
+    let registration = serde_json::json!({"type": {"transport": "streamable_http", "url": mock.url()}});
+
+    // The secret is required
+    let response = server
+        .client()
+        .put(server.url("/mcp/admin/servers/mock"))
+        .json(&registration)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 401);
+
+    // Registered servers are connected, listed, and callable
+    let response = admin(&server, reqwest::Method::PUT, "/mcp/admin/servers/mock")
+        .json(&registration)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+    let info: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(info["connected"], true);
+    assert!(info["tool_count"].as_u64().unwrap() > 0);
+    assert!(tool_names(&server, None).await.contains(&"mock__echo".to_owned()));
+
+    let search: serde_json::Value = server
+        .client()
+        .get(server.url("/mcp/search?q=echo"))
+        .header("x-client-id", "alice")
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(search["results"][0]["qualified_name"], "mock__echo");
+
+    // Calls in flight finish on a server disabled meanwhile
+    let call = server
+        .client()
+        .post(server.url("/mcp/tools/call"))
+        .header("x-client-id", "alice")
+        .json(&serde_json::json!({"name": "mock__echo", "arguments": {"text": "hi", "delay_ms": 300}}))
+        .send();
+    let disable = async {
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+        admin(&server, reqwest::Method::POST, "/mcp/admin/servers/mock/disable")
+            .send()
+            .await
+            .unwrap()
+    };
+    let (call, disabled) = tokio::join!(call, disable);
+    assert_eq!(call.unwrap().status(), 200);
+    let info: serde_json::Value = disabled.json().await.unwrap();
+    assert_eq!(info["enabled"], false);
+    assert!(tool_names(&server, None).await.is_empty());
+
+    let response = admin(&server, reqwest::Method::POST, "/mcp/admin/servers/mock/enable")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+    assert!(!tool_names(&server, None).await.is_empty());
+
+    // Removed servers are gone
+    let response = admin(&server, reqwest::Method::DELETE, "/mcp/admin/servers/mock")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 204);
+    assert!(tool_names(&server, None).await.is_empty());
+    let list: serde_json::Value = admin(&server, reqwest::Method::GET, "/mcp/admin/servers")
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(list["servers"], serde_json::json!([]));
+
+    let response = admin(&server, reqwest::Method::DELETE, "/mcp/admin/servers/mock")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 404);
+}
+
+#[tokio::test]
+async fn invalid_registrations_are_rejected() {
+    let config = ConfigBuilder::new().with_mcp_admin("admin-secret").build();
+    let server = TestServer::start(config).await.unwrap();
+
+    let response = admin(&server, reqwest::Method::PUT, "/mcp/admin/servers/bad__name")
+        .json(&serde_json::json!({"type": {"transport": "stdio", "command": "true"}}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 400);
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["error"]["type"], "invalid_request_error");
+}
+
+#[tokio::test]
+async fn tenant_servers_are_visible_only_to_their_tenant() {
+    let shared = MockMcp::start().await.unwrap();
+    let private = MockMcp::start().await.unwrap();
+    let config = ConfigBuilder::new()
+        .with_mcp_server("shared", &shared.url(), &[])
+        .with_mcp_admin("admin-secret")
+        .with_client_identification("x-client-id", Some("x-group"))
+        .build();
+    let server = TestServer::start(config).await.unwrap();
+
+    let response = admin(&server, reqwest::Method::PUT, "/mcp/admin/servers/acme")
+        .json(&serde_json::json!({
+            "type": {"transport": "streamable_http", "url": private.url()},
+            "tenant": "acme",
+        }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+
+    let acme = tool_names(&server, Some("acme")).await;
+    assert!(acme.contains(&"acme__echo".to_owned()));
+    assert!(acme.contains(&"shared__echo".to_owned()));
+    assert!(
+        tool_names(&server, None)
+            .await
+            .iter()
+            .all(|name| name.starts_with("shared__"))
+    );
+
+    // Other tenants cannot call the server either
+    let call = |group: &'static str| {
+        server
+            .client()
+            .post(server.url("/mcp/tools/call"))
+            .header("x-client-id", "alice")
+            .header("x-group", group)
+            .json(&serde_json::json!({"name": "acme__echo", "arguments": {"text": "hi"}}))
+            .send()
+    };
+    assert_eq!(call("globex").await.unwrap().status(), 404);
+    assert_eq!(call("acme").await.unwrap().status(), 200);
+}
+
+#[tokio::test]
+async fn policies_apply_to_matching_callers_in_listing_search_and_calls() {
+    let mock = MockMcp::start().await.unwrap();
+    let mut config = ConfigBuilder::new()
+        .with_mcp_server("mock", &mock.url(), &[])
+        .with_client_identification("x-client-id", Some("x-group"))
+        .build();
+    config.mcp.policies = vec![McpPolicyConfig {
+        subject: McpPolicySubject {
+            group: Some("free".to_owned()),
+            ..McpPolicySubject::default()
+        },
+        allow: Vec::new(),
+        deny: vec!["mock__delete*".to_owned()],
+        arguments: vec![McpArgumentConstraint {
+            tool: "mock__echo".to_owned(),
+            argument: "text".to_owned(),
+            allow: vec!["hello*".to_owned()],
+        }],
+        resources: McpAccessRules::default(),
+        prompts: McpAccessRules::default(),
+    }];
+    let server = TestServer::start(config).await.unwrap();
+
+    let free = tool_names(&server, Some("free")).await;
+    assert!(free.contains(&"mock__echo".to_owned()));
+    assert!(!free.contains(&"mock__delete_all".to_owned()));
+    assert!(
+        tool_names(&server, Some("pro"))
+            .await
+            .contains(&"mock__delete_all".to_owned())
+    );
+
+    // Search hides denied tools too
+    let search = |group: &'static str| {
+        server
+            .client()
+            .get(server.url("/mcp/search?q=delete"))
+            .header("x-client-id", "alice")
+            .header("x-group", group)
+            .send()
+    };
+    let results: serde_json::Value = search("free").await.unwrap().json().await.unwrap();
+    assert!(results["results"].as_array().unwrap().is_empty());
+    let results: serde_json::Value = search("pro").await.unwrap().json().await.unwrap();
+    assert_eq!(results["results"][0]["qualified_name"], "mock__delete_all");
+
+    let call = |group: &'static str, text: &'static str| {
+        server
+            .client()
+            .post(server.url("/mcp/tools/call"))
+            .header("x-client-id", "alice")
+            .header("x-group", group)
+            .json(&serde_json::json!({"name": "mock__echo", "arguments": {"text": text}}))
+            .send()
+    };
+    assert_eq!(call("free", "hello there").await.unwrap().status(), 200);
+    let response = call("free", "goodbye").await.unwrap();
+    assert_eq!(response.status(), 403);
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["error"]["type"], "access_denied");
+    assert_eq!(call("pro", "goodbye").await.unwrap().status(), 200);
+}
+
+#[tokio::test]
+async fn policies_limit_resources_and_prompts_in_both_front_ends() {
+    let mock = MockMcp::start().await.unwrap();
+    let mut config = ConfigBuilder::new()
+        .with_mcp_server("mock", &mock.url(), &[])
+        .with_client_identification("x-client-id", Some("x-group"))
+        .build();
+    config.mcp.policies = vec![McpPolicyConfig {
+        subject: McpPolicySubject {
+            group: Some("free".to_owned()),
+            ..McpPolicySubject::default()
+        },
+        allow: Vec::new(),
+        deny: Vec::new(),
+        arguments: Vec::new(),
+        resources: McpAccessRules {
+            allow: Vec::new(),
+            deny: vec!["mock__*".to_owned()],
+        },
+        prompts: McpAccessRules {
+            allow: Vec::new(),
+            deny: vec!["mock__greet".to_owned()],
+        },
+    }];
+    let server = TestServer::start(config).await.unwrap();
+
+    let rest = |path: &'static str, group: &'static str, body: serde_json::Value| {
+        server
+            .client()
+            .post(server.url(path))
+            .header("x-client-id", "alice")
+            .header("x-group", group)
+            .json(&body)
+            .send()
+    };
+    let read = serde_json::json!({"uri": "mock__file:///readme.md"});
+    let get = serde_json::json!({"name": "mock__greet", "arguments": {"name": "Ada"}});
+    for group in ["free", "pro"] {
+        let denied = group == "free";
+        let resources: serde_json::Value = rest("/mcp/resources/list", group, serde_json::json!({}))
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        assert_eq!(resources["resources"].as_array().unwrap().is_empty(), denied);
+        let templates: serde_json::Value = rest("/mcp/resources/templates/list", group, serde_json::json!({}))
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        assert_eq!(templates["resource_templates"].as_array().unwrap().is_empty(), denied);
+        let prompts: serde_json::Value = rest("/mcp/prompts/list", group, serde_json::json!({}))
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        assert_eq!(prompts["prompts"].as_array().unwrap().is_empty(), denied);
+
+        let status = if denied { 403 } else { 200 };
+        assert_eq!(
+            rest("/mcp/resources/read", group, read.clone()).await.unwrap().status(),
+            status
+        );
+        assert_eq!(
+            rest("/mcp/prompts/get", group, get.clone()).await.unwrap().status(),
+            status
+        );
+    }
+
+    // The MCP endpoint applies the same policies
+    let headers = HashMap::from([
+        (
+            HeaderName::from_static("x-client-id"),
+            HeaderValue::from_static("alice"),
+        ),
+        (HeaderName::from_static("x-group"), HeaderValue::from_static("free")),
+    ]);
+    let transport = StreamableHttpClientTransport::from_config(
+        StreamableHttpClientTransportConfig::with_uri(server.url("/mcp")).custom_headers(headers),
+    );
+    let client = ().serve(transport).await.unwrap();
+
+    assert!(client.list_all_resources().await.unwrap().is_empty());
+    assert!(client.list_all_resource_templates().await.unwrap().is_empty());
+    assert!(client.list_all_prompts().await.unwrap().is_empty());
+    let error = client
+        .read_resource(ReadResourceRequestParams::new("mock__file:///readme.md"))
+        .await
+        .unwrap_err();
+    let rmcp::ServiceError::McpError(error) = error else {
+        panic!("expected an MCP error, got {error:?}");
+    };
+    assert_eq!(error.data.unwrap()["type"], "access_denied");
+    let error = client
+        .get_prompt(GetPromptRequestParams::new("mock__greet"))
+        .await
+        .unwrap_err();
+    let rmcp::ServiceError::McpError(error) = error else {
+        panic!("expected an MCP error, got {error:?}");
+    };
+    assert_eq!(error.data.unwrap()["type"], "access_denied");
+
+    client.cancel().await.unwrap();
+}
+
+/// Poll the visible tools until one named `name` appears
+async fn wait_for_tool(server: &TestServer, name: &str) {
+    for _ in 0..50 {
+        if tool_names(server, None).await.iter().any(|tool| tool == name) {
+            return;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    }
+    panic!("tool {name} never appeared");
+}
+
+/// Status of one downstream server from the status endpoint
+async fn server_status(server: &TestServer, name: &str) -> serde_json::Value {
+    let body: serde_json::Value = server
+        .client()
+        .get(server.url("/mcp/servers"))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    body["servers"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|status| status["name"] == name)
+        .cloned()
+        .unwrap()
+}
+
+#[tokio::test]
+async fn failed_servers_are_retried_until_they_connect() {
+    // Reserve an address nothing listens on yet
+    let addr = std::net::TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap();
+    let mut config = ConfigBuilder::new()
+        .with_mcp_server("late", &format!("http://{addr}/mcp"), &[])
+        .build();
+    config.mcp.supervisor.check_interval_secs = 1;
+    config.mcp.supervisor.max_backoff_secs = 1;
+    let server = TestServer::start(config).await.unwrap();
+
+    let status = server_status(&server, "late").await;
+    assert_eq!(status["connected"], false);
+    assert!(status["last_error"].is_string());
+
+    let _mock = MockMcp::start_on(addr).await.unwrap();
+    wait_for_tool(&server, "late__echo").await;
+
+    let status = server_status(&server, "late").await;
+    assert_eq!(status["connected"], true);
+    assert_eq!(status["restarts"], 1);
+    assert!(status.get("last_error").is_none());
+}
+
+#[tokio::test]
+async fn tool_list_changes_are_picked_up() {
+    let mock = MockMcp::start().await.unwrap();
+    let config = ConfigBuilder::new().with_mcp_server("mock", &mock.url(), &[]).build();
+    let server = TestServer::start(config).await.unwrap();
+    assert!(!tool_names(&server, None).await.contains(&"mock__shout".to_owned()));
+
+    mock.add_tool("shout").await;
+    wait_for_tool(&server, "mock__shout").await;
+
+    let search: serde_json::Value = server
+        .client()
+        .get(server.url("/mcp/search?q=shout"))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(search["results"][0]["qualified_name"], "mock__shout");
+    assert_eq!(server_status(&server, "mock").await["restarts"], 0);
+}
+
+async fn search(server: &TestServer, query: &str) -> reqwest::Response {
+    server
+        .client()
+        .get(server.url(&format!("/mcp/search?{query}")))
+        .send()
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn semantic_search_finds_tools_without_shared_words() {
+    let mock = MockMcp::start().await.unwrap();
+    let llm = MockLlm::start().await.unwrap();
+    let mut config = ConfigBuilder::new()
+        .with_mcp_server("mock", &mock.url(), &[])
+        .with_embeddings_provider("mock", &llm.base_url())
+        .build();
+    config.mcp.search.embedding_model = Some("mock/text-embedding-test".to_owned());
+    let server = TestServer::start(config).await.unwrap();
+
+    // Keyword search has nothing to match
+    let keyword: serde_json::Value = search(&server, "q=sum+of+values&mode=keyword")
+        .await
+        .json()
+        .await
+        .unwrap();
+    assert!(keyword["results"].as_array().unwrap().is_empty());
+
+    for query in ["q=sum+of+values&mode=semantic", "q=sum+of+values"] {
+        let results: serde_json::Value = search(&server, query).await.json().await.unwrap();
+        let top = &results["results"][0];
+        assert_eq!(top["qualified_name"], "mock__add", "{query}");
+        assert_eq!(top["explanation"]["semantic_rank"], 1, "{query}");
+    }
+
+    // Hybrid results explain both rankings
+    let hybrid: serde_json::Value = search(&server, "q=echo").await.json().await.unwrap();
+    let top = &hybrid["results"][0];
+    assert_eq!(top["qualified_name"], "mock__echo");
+    assert_eq!(top["explanation"]["keyword_rank"], 1);
+    assert_eq!(top["explanation"]["semantic_rank"], 1);
+
+    // Tool texts are embedded once, the query each search
+    let embedded = llm.embedding_count();
+    search(&server, "q=echo").await;
+    assert_eq!(llm.embedding_count(), embedded + 1);
+}
+
+#[tokio::test]
+async fn semantic_search_requires_an_embedding_model() {
+    let mock = MockMcp::start().await.unwrap();
+    let config = ConfigBuilder::new().with_mcp_server("mock", &mock.url(), &[]).build();
+    let server = TestServer::start(config).await.unwrap();
+
+    let response = search(&server, "q=sum&mode=semantic").await;
+    assert_eq!(response.status(), 400);
+
+    let results: serde_json::Value = search(&server, "q=echo").await.json().await.unwrap();
+    assert_eq!(results["results"][0]["qualified_name"], "mock__echo");
+    assert!(results["results"][0]["explanation"].get("semantic_rank").is_none());
+}
+
+/// Gateway with the mock MCP server, `delete_all` denied, and the mock LLM
+async fn tool_loop_server(mock: &MockMcp, llm: &MockLlm) -> TestServer {
+    let config = ConfigBuilder::new()
+        .with_mcp_server("mock", &mock.url(), &["delete_all"])
+        .with_openai_provider("mock", &llm.base_url())
+        .build();
+    TestServer::start(config).await.unwrap()
+}
+
+async fn chat(server: &TestServer, body: serde_json::Value) -> reqwest::Response {
+    server
+        .client()
+        .post(server.url("/v1/chat/completions"))
+        .json(&body)
+        .send()
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn chat_completions_run_mcp_tools_server_side() {
+    let mock = MockMcp::start().await.unwrap();
+    let llm = MockLlm::start().await.unwrap();
+    let server = tool_loop_server(&mock, &llm).await;
+
+    let response = chat(
+        &server,
+        serde_json::json!({
+            "model": "mock-model-1",
+            "messages": [{"role": "user", "content": "Say San Francisco"}],
+            "mcp": {"tools": ["mock__echo"]}
+        }),
+    )
+    .await;
+    assert_eq!(response.status(), 200);
+    let body: serde_json::Value = response.json().await.unwrap();
+
+    // The model saw the echo result and answered; usage covers both turns
+    assert_eq!(body["choices"][0]["message"]["content"], "Tool said: San Francisco");
+    assert_eq!(body["choices"][0]["finish_reason"], "stop");
+    assert_eq!(body["usage"]["total_tokens"], 30);
+    assert_eq!(mock.call_count(), 1);
+    assert_eq!(llm.completion_count(), 2);
+}
+
+#[tokio::test]
+async fn streamed_tool_loops_report_each_step() {
+    let mock = MockMcp::start().await.unwrap();
+    let llm = MockLlm::start().await.unwrap();
+    let server = tool_loop_server(&mock, &llm).await;
+
+    let response = chat(
+        &server,
+        serde_json::json!({
+            "model": "mock-model-1",
+            "messages": [{"role": "user", "content": "Say San Francisco"}],
+            "stream": true,
+            "mcp": {"tools": ["mock__echo"]}
+        }),
+    )
+    .await;
+    assert_eq!(response.status(), 200);
+    let text = response.text().await.unwrap();
+
+    let mut steps = Vec::new();
+    let mut content = String::new();
+    let mut done = 0;
+    let mut event = None;
+    for line in text.lines() {
+        if let Some(name) = line.strip_prefix("event: ") {
+            event = Some(name.to_owned());
+        } else if let Some(data) = line.strip_prefix("data: ") {
+            if data == "[DONE]" {
+                done += 1;
+            } else if event.take().as_deref() == Some("tool_step") {
+                steps.push(serde_json::from_str::<serde_json::Value>(data).unwrap());
+            } else {
+                let chunk: serde_json::Value = serde_json::from_str(data).unwrap();
+                let delta = &chunk["choices"][0]["delta"];
+                assert!(delta.get("tool_calls").is_none(), "gateway tool calls leaked: {data}");
+                content.push_str(delta["content"].as_str().unwrap_or_default());
+            }
+        }
+    }
+
+    assert_eq!(steps.len(), 2);
+    assert_eq!(steps[0]["type"], "tool_call");
+    assert_eq!(steps[0]["name"], "mock__echo");
+    assert_eq!(steps[1]["type"], "tool_result");
+    assert_eq!(steps[1]["content"], "San Francisco");
+    assert_eq!(steps[1]["is_error"], false);
+    assert_eq!(content.trim(), "Tool said: San Francisco");
+    assert_eq!(done, 1);
+}
+
+#[tokio::test]
+async fn tool_loops_offer_only_tools_the_caller_may_use() {
+    let mock = MockMcp::start().await.unwrap();
+    let llm = MockLlm::start().await.unwrap();
+    let server = tool_loop_server(&mock, &llm).await;
+
+    let response = chat(
+        &server,
+        serde_json::json!({
+            "model": "mock-model-1",
+            "messages": [{"role": "user", "content": "Delete everything"}],
+            "mcp": {"tools": ["mock__delete_all"]}
+        }),
+    )
+    .await;
+    assert_eq!(response.status(), 400);
+
+    // Calls of the client's own tools are returned to the client
+    let response = chat(
+        &server,
+        serde_json::json!({
+            "model": "mock-model-1",
+            "messages": [{"role": "user", "content": "What is the weather?"}],
+            "tools": [{"type": "function", "function": {"name": "get_weather"}}],
+            "mcp": {}
+        }),
+    )
+    .await;
+    assert_eq!(response.status(), 200);
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(
+        body["choices"][0]["message"]["tool_calls"][0]["function"]["name"],
+        "get_weather"
+    );
+    assert_eq!(mock.call_count(), 0);
+}
+
+#[tokio::test]
+async fn anthropic_messages_run_mcp_tools_server_side() {
+    let mock = MockMcp::start().await.unwrap();
+    let llm = MockLlm::start().await.unwrap();
+    let server = tool_loop_server(&mock, &llm).await;
+
+    let response = server
+        .client()
+        .post(server.url("/v1/messages"))
+        .json(&serde_json::json!({
+            "model": "mock-model-1",
+            "max_tokens": 256,
+            "messages": [{"role": "user", "content": "Say San Francisco"}],
+            "mcp": {"tools": ["mock__echo"]}
+        }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+    let body: serde_json::Value = response.json().await.unwrap();
+
+    assert_eq!(body["content"][0]["text"], "Tool said: San Francisco");
+    assert_eq!(
+        body["usage"]["input_tokens"].as_u64().unwrap() + body["usage"]["output_tokens"].as_u64().unwrap(),
+        30
+    );
+    assert_eq!(mock.call_count(), 1);
+    assert_eq!(llm.completion_count(), 2);
+}
@@ -163,6 +163,8 @@ impl Server {
             output_tokens: 0,
             estimated_cost_usd: 0.0,
             idempotency_key,
+            experiment: None,
+            experiment_arm: None,
         });
     }
 
@@ -27,6 +27,12 @@ pub struct UsageEvent {
     pub cost_cents: u32,
     /// Billing mode
     pub mode: String,
+    /// A/B experiment the request was assigned to
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub experiment: Option<String>,
+    /// Experiment arm that served the request
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub experiment_arm: Option<String>,
 }
 
 /// Async usage reporter that batches events
@@ -55,6 +55,22 @@ impl CircuitBreaker {
         }
     }
 
+    /// Whether the circuit is open and still within the recovery timeout
+    pub(crate) fn is_open(&self) -> bool {
+        self.check().is_err()
+    }
+
+    /// Whether the circuit is open but past the recovery timeout, letting a
+    /// probe request through
+    pub(crate) fn is_half_open(&self) -> bool {
+        let opened_at = self
+            .state
+            .opened_at
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        opened_at.is_some_and(|ts| ts.elapsed() >= RECOVERY_TIMEOUT)
+    }
+
     /// Record a successful request, closing the circuit
     pub(crate) fn record_success(&self) {
         self.state.failure_count.store(0, Ordering::Relaxed);
@@ -151,6 +167,8 @@ mod tests {
         }
 
         assert!(cb.check().is_ok());
+        assert!(cb.is_half_open());
+        assert!(!cb.is_open());
     }
 
     #[test]
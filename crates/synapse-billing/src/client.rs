@@ -379,6 +379,20 @@ impl AetherClient {
             Err(BillingError::Api { status, message })
         }
     }
+
+    /// Whether the circuit breaker is currently short-circuiting calls
+    ///
+    /// Clones share circuit state, so this reflects every call made
+    /// through any clone of this client.
+    pub fn is_circuit_open(&self) -> bool {
+        self.circuit.is_open()
+    }
+
+    /// Whether the circuit breaker is past its recovery timeout and letting
+    /// a probe call through to test whether Aether has recovered
+    pub fn is_circuit_half_open(&self) -> bool {
+        self.circuit.is_half_open()
+    }
 }
 
 impl std::fmt::Debug for AetherClient {
@@ -23,6 +23,10 @@ pub struct UsageEvent {
     pub estimated_cost_usd: f64,
     /// Unique key for idempotent recording
     pub idempotency_key: String,
+    /// A/B experiment the request was assigned to
+    pub experiment: Option<String>,
+    /// Experiment arm that served the request
+    pub experiment_arm: Option<String>,
 }
 
 /// Configuration for meter key names
@@ -196,6 +200,12 @@ fn build_metadata(event: &UsageEvent) -> HashMap<String, String> {
     metadata.insert("model".to_owned(), event.model.clone());
     metadata.insert("provider".to_owned(), event.provider.clone());
     metadata.insert("estimated_cost_usd".to_owned(), event.estimated_cost_usd.to_string());
+    if let Some(ref experiment) = event.experiment {
+        metadata.insert("experiment".to_owned(), experiment.clone());
+    }
+    if let Some(ref arm) = event.experiment_arm {
+        metadata.insert("experiment_arm".to_owned(), arm.clone());
+    }
     metadata
 }
 
@@ -225,6 +235,8 @@ mod tests {
             output_tokens: 0,
             estimated_cost_usd: 0.0,
             idempotency_key: "byok-key-1".to_owned(),
+            experiment: None,
+            experiment_arm: None,
         };
 
         // Verify the event is valid and metadata is constructed
@@ -250,12 +262,16 @@ mod tests {
             output_tokens: 50,
             estimated_cost_usd: 0.01,
             idempotency_key: "key-1".to_owned(),
+            experiment: Some("cheap-models".to_owned()),
+            experiment_arm: Some("treatment".to_owned()),
         };
 
         let metadata = build_metadata(&event);
         assert_eq!(metadata.get("model"), Some(&"gpt-4o".to_owned()));
         assert_eq!(metadata.get("provider"), Some(&"openai".to_owned()));
         assert!(metadata.contains_key("estimated_cost_usd"));
+        assert_eq!(metadata.get("experiment"), Some(&"cheap-models".to_owned()));
+        assert_eq!(metadata.get("experiment_arm"), Some(&"treatment".to_owned()));
     }
 
     #[tokio::test]
@@ -322,6 +338,8 @@ mod tests {
             output_tokens: 0,
             estimated_cost_usd: 0.0,
             idempotency_key: "byok-idem-1".to_owned(),
+            experiment: None,
+            experiment_arm: None,
         };
 
         record_event(&client, &MeterKeys::default(), &event).await;
@@ -386,6 +404,8 @@ mod tests {
             output_tokens: 200,
             estimated_cost_usd: 0.05,
             idempotency_key: "managed-idem-1".to_owned(),
+            experiment: None,
+            experiment_arm: None,
         };
 
         record_event(&client, &MeterKeys::default(), &event).await;
@@ -112,6 +112,26 @@ impl ResponseCache {
         tracing::debug!(cache_key, ttl_secs, "cached response");
         Ok(())
     }
+
+    /// Check that the Valkey backend is reachable
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the connection or `PING` fails
+    pub async fn ping(&self) -> Result<(), CacheError> {
+        let mut conn = self
+            .client
+            .get_multiplexed_async_connection()
+            .await
+            .map_err(|e| CacheError::Backend(format!("connection failed: {e}")))?;
+
+        let _: String = redis::cmd("PING")
+            .query_async(&mut conn)
+            .await
+            .map_err(|e| CacheError::Backend(format!("PING failed: {e}")))?;
+
+        Ok(())
+    }
 }
 
 /// Compute a SHA-256 cache key from a serializable request
@@ -13,8 +13,9 @@ use url::Url;
 
 use crate::error::{Result, SynapseClientError};
 use crate::types::{
-    ChatEvent, ChatRequest, ChatResponse, EmbedRequest, EmbeddingResponse, ImageRequest, ImageResponse, McpTool, Model,
-    ModelList, SpeechRequest, StreamChunk, ToolResult, ToolSearchResult, Transcription,
+    ChatEvent, ChatRequest, ChatResponse, EmbedRequest, EmbeddingResponse, ImageRequest, ImageResponse, McpPrompt,
+    McpResource, McpResourceTemplate, McpTool, Model, ModelList, PromptResult, ResourceContent, SpeechRequest,
+    StreamChunk, ToolResult, ToolSearchResult, Transcription,
 };
 
 /// Backend mode for the Synapse client
@@ -494,6 +495,107 @@ impl SynapseClient {
         }
     }
 
+    /// List available MCP resources
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the request fails
+    pub async fn list_resources(&self, server: Option<&str>) -> Result<Vec<McpResource>> {
+        #[derive(Deserialize)]
+        struct Resp {
+            resources: Vec<McpResource>,
+        }
+
+        let body = serde_json::json!({ "server": server });
+        let resp: Resp = self.post_mcp("/mcp/resources/list", &body).await?;
+        Ok(resp.resources)
+    }
+
+    /// List available MCP resource templates
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the request fails
+    pub async fn list_resource_templates(&self, server: Option<&str>) -> Result<Vec<McpResourceTemplate>> {
+        #[derive(Deserialize)]
+        struct Resp {
+            resource_templates: Vec<McpResourceTemplate>,
+        }
+
+        let body = serde_json::json!({ "server": server });
+        let resp: Resp = self.post_mcp("/mcp/resources/templates/list", &body).await?;
+        Ok(resp.resource_templates)
+    }
+
+    /// Read an MCP resource by its qualified URI
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the request fails
+    pub async fn read_resource(&self, uri: &str) -> Result<Vec<ResourceContent>> {
+        #[derive(Deserialize)]
+        struct Resp {
+            contents: Vec<ResourceContent>,
+        }
+
+        let body = serde_json::json!({ "uri": uri });
+        let resp: Resp = self.post_mcp("/mcp/resources/read", &body).await?;
+        Ok(resp.contents)
+    }
+
+    /// List available MCP prompts
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the request fails
+    pub async fn list_prompts(&self, server: Option<&str>) -> Result<Vec<McpPrompt>> {
+        #[derive(Deserialize)]
+        struct Resp {
+            prompts: Vec<McpPrompt>,
+        }
+
+        let body = serde_json::json!({ "server": server });
+        let resp: Resp = self.post_mcp("/mcp/prompts/list", &body).await?;
+        Ok(resp.prompts)
+    }
+
+    /// Render an MCP prompt by its qualified name
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the request fails
+    pub async fn get_prompt(&self, name: &str, arguments: serde_json::Value) -> Result<PromptResult> {
+        let body = serde_json::json!({
+            "name": name,
+            "arguments": arguments,
+        });
+        self.post_mcp("/mcp/prompts/get", &body).await
+    }
+
+    /// POST to an MCP REST route, which only remote backends serve
+    async fn post_mcp<T: serde::de::DeserializeOwned>(&self, path: &str, body: &serde_json::Value) -> Result<T> {
+        match &self.backend {
+            Backend::Remote {
+                base_url,
+                http,
+                api_key,
+            } => {
+                let url = make_url(base_url, path);
+
+                let response = make_request(http, reqwest::Method::POST, &url, api_key.as_deref())
+                    .json(body)
+                    .send()
+                    .await?;
+
+                handle_error(response).await?.json().await.map_err(Into::into)
+            }
+            #[cfg(feature = "embedded")]
+            Backend::Embedded { .. } => Err(SynapseClientError::Config(
+                "feature not available in embedded mode".to_owned(),
+            )),
+        }
+    }
+
     // -- Embeddings --
 
     /// Generate embeddings for text input
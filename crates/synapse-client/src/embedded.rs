@@ -152,6 +152,7 @@ pub fn stream_to_chat_events(
                     total_tokens: usage.total_tokens,
                 }),
             })),
+            Ok(llm::StreamEvent::ToolStep(_)) => None,
             Ok(llm::StreamEvent::Delta(delta)) => {
                 // Handle tool calls
                 if let Some(ref tc) = delta.tool_call
@@ -405,6 +405,98 @@ pub struct ToolSearchResult {
     pub score: f32,
 }
 
+/// Resource info from MCP listing
+#[derive(Debug, Clone, Deserialize)]
+pub struct McpResource {
+    /// Qualified resource URI (`server__uri`)
+    pub uri: String,
+    /// Qualified resource name
+    pub name: String,
+    /// Server that provides the resource
+    pub server: String,
+    /// Resource description
+    pub description: String,
+    /// MIME type of the resource content
+    #[serde(default)]
+    pub mime_type: Option<String>,
+}
+
+/// Resource template info from MCP listing
+#[derive(Debug, Clone, Deserialize)]
+pub struct McpResourceTemplate {
+    /// Qualified URI template (`server__template`)
+    pub uri_template: String,
+    /// Qualified template name
+    pub name: String,
+    /// Server that provides the template
+    pub server: String,
+    /// Template description
+    pub description: String,
+    /// MIME type of resources created from the template
+    #[serde(default)]
+    pub mime_type: Option<String>,
+}
+
+/// Contents of a read resource
+#[derive(Debug, Clone, Deserialize)]
+pub struct ResourceContent {
+    /// Qualified resource URI
+    pub uri: String,
+    /// MIME type
+    #[serde(default)]
+    pub mime_type: Option<String>,
+    /// Text contents
+    #[serde(default)]
+    pub text: Option<String>,
+    /// Base64-encoded binary contents
+    #[serde(default)]
+    pub blob: Option<String>,
+}
+
+/// Prompt info from MCP listing
+#[derive(Debug, Clone, Deserialize)]
+pub struct McpPrompt {
+    /// Qualified prompt name (`server__prompt`)
+    pub name: String,
+    /// Server that provides the prompt
+    pub server: String,
+    /// Prompt description
+    pub description: String,
+    /// Arguments the prompt accepts
+    pub arguments: Vec<McpPromptArgument>,
+}
+
+/// Argument accepted by an MCP prompt
+#[derive(Debug, Clone, Deserialize)]
+pub struct McpPromptArgument {
+    /// Argument name
+    pub name: String,
+    /// Argument description
+    #[serde(default)]
+    pub description: Option<String>,
+    /// Whether the argument must be supplied
+    pub required: bool,
+}
+
+/// Rendered MCP prompt
+#[derive(Debug, Clone, Deserialize)]
+pub struct PromptResult {
+    /// Prompt description
+    #[serde(default)]
+    pub description: Option<String>,
+    /// Rendered messages
+    pub messages: Vec<PromptMessage>,
+}
+
+/// Message of a rendered MCP prompt
+#[derive(Debug, Clone, Deserialize)]
+pub struct PromptMessage {
+    /// Message role (`user` or `assistant`)
+    pub role: String,
+    /// Message content
+    pub content: ContentBlock,
+}
+
 // -- STT types --
 
 /// Transcription response
@@ -3,6 +3,11 @@ use std::net::SocketAddr;
 use serde::Deserialize;
 
 /// Health check endpoint configuration
+///
+/// The liveness endpoint (`path`) always answers `ok` while the process is
+/// serving. The readiness endpoint (`readiness_path`) reports per-subsystem
+/// status as JSON. When `listen_address` is set, both are served on a
+/// dedicated listener that bypasses auth, rate limiting and other middleware.
 #[derive(Debug, Deserialize)]
 #[serde(deny_unknown_fields)]
 pub struct HealthConfig {
@@ -11,6 +16,18 @@ pub struct HealthConfig {
     pub listen_address: Option<SocketAddr>,
     #[serde(default = "default_path")]
     pub path: String,
+    /// Path of the detailed readiness endpoint
+    #[serde(default = "default_readiness_path")]
+    pub readiness_path: String,
+    /// Timeout in milliseconds for each readiness probe (e.g. Valkey `PING`)
+    #[serde(default = "default_probe_timeout_ms")]
+    pub probe_timeout_ms: u64,
+    /// Whether unreachable MCP servers make the gateway not ready
+    ///
+    /// Off by default, so a gateway that mainly serves LLM traffic keeps
+    /// receiving requests and only reports MCP as degraded.
+    #[serde(default)]
+    pub mcp_required: bool,
 }
 
 impl Default for HealthConfig {
@@ -19,6 +36,9 @@ impl Default for HealthConfig {
             enabled: true,
             listen_address: None,
             path: "/health".to_string(),
+            readiness_path: default_readiness_path(),
+            probe_timeout_ms: default_probe_timeout_ms(),
+            mcp_required: false,
         }
     }
 }
@@ -31,3 +51,12 @@ fn default_enabled() -> bool {
 fn default_path() -> String {
     "/health".to_string()
 }
+
+fn default_readiness_path() -> String {
+    "/health/ready".to_string()
+}
+
+#[allow(clippy::missing_const_for_fn)]
+fn default_probe_timeout_ms() -> u64 {
+    2000
+}
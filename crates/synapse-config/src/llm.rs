@@ -1,4 +1,5 @@
 use std::collections::HashMap;
+use std::path::PathBuf;
 
 use indexmap::IndexMap;
 use secrecy::SecretString;
@@ -20,6 +21,21 @@ pub struct LlmConfig {
     /// Smart model routing configuration
     #[serde(default)]
     pub routing: RoutingConfig,
+    /// Share circuit breaker and routing feedback state across replicas
+    #[serde(default)]
+    pub state_sync: Option<StateSyncConfig>,
+    /// Persist routing feedback across restarts
+    #[serde(default)]
+    pub feedback_persistence: Option<FeedbackPersistenceConfig>,
+    /// Mirror sampled requests to candidate models for offline comparison
+    #[serde(default)]
+    pub shadow: Option<ShadowConfig>,
+    /// External catalog of model pricing, context windows, and capabilities
+    #[serde(default)]
+    pub catalog: Option<ModelCatalogConfig>,
+    /// Export routing decisions and outcomes as training data for the ONNX router
+    #[serde(default)]
+    pub training_data: Option<TrainingDataConfig>,
 }
 
 /// Configuration for a single LLM provider
@@ -47,6 +63,10 @@ pub struct LlmProviderConfig {
     /// Rate limit for this provider (requests per window)
     #[serde(default)]
     pub rate_limit: Option<ProviderRateLimit>,
+    /// Model catalog provider ID describing this provider's models,
+    /// defaulting to the provider name
+    #[serde(default)]
+    pub catalog_id: Option<String>,
 }
 
 /// Supported LLM provider protocols
@@ -196,6 +216,231 @@ const fn default_recovery_seconds() -> u64 {
     30
 }
 
+// -- Shared state configuration --
+
+/// Valkey-backed sharing of circuit breaker and feedback state
+///
+/// Each replica periodically pushes its local error counts and latency
+/// samples and adopts the merged fleet-wide state, so circuit decisions
+/// and feedback-driven routing are consistent across instances.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct StateSyncConfig {
+    /// Whether state sharing is enabled
+    #[serde(default)]
+    pub enabled: bool,
+    /// Valkey connection URL
+    pub url: Url,
+    /// Key prefix in Valkey
+    #[serde(default = "default_state_key_prefix")]
+    pub key_prefix: String,
+    /// Milliseconds between sync rounds
+    #[serde(default = "default_state_sync_interval_ms")]
+    pub interval_ms: u64,
+    /// Seconds of fleet-wide request and error counts merged into local
+    /// feedback; older counts expire from Valkey
+    #[serde(default = "default_feedback_window_secs")]
+    pub feedback_window_secs: u64,
+    /// Seconds of fleet-wide user ratings merged into local ratings; older
+    /// ratings expire from Valkey
+    #[serde(default = "default_ratings_window_secs")]
+    pub ratings_window_secs: u64,
+}
+
+fn default_state_key_prefix() -> String {
+    "synapse:state".to_owned()
+}
+
+const fn default_state_sync_interval_ms() -> u64 {
+    1000
+}
+
+const fn default_feedback_window_secs() -> u64 {
+    300
+}
+
+const fn default_ratings_window_secs() -> u64 {
+    7 * 24 * 3600
+}
+
+/// Periodic snapshotting of routing feedback
+///
+/// Snapshots are restored on startup with age-based decay so feedback-driven
+/// scoring does not restart cold after every deploy.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FeedbackPersistenceConfig {
+    /// Whether feedback persistence is enabled
+    #[serde(default)]
+    pub enabled: bool,
+    /// Where snapshots are stored
+    pub storage: FeedbackStorage,
+    /// Seconds between snapshots
+    #[serde(default = "default_snapshot_interval_secs")]
+    pub interval_secs: u64,
+    /// Seconds after which restored observations carry half their weight
+    #[serde(default = "default_feedback_half_life_secs")]
+    pub half_life_secs: u64,
+    /// Snapshots older than this many seconds are discarded on restore
+    #[serde(default = "default_feedback_max_age_secs")]
+    pub max_age_secs: u64,
+}
+
+/// Feedback snapshot storage backend
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum FeedbackStorage {
+    /// JSON file on local disk
+    File {
+        /// Snapshot file path
+        path: PathBuf,
+    },
+    /// Valkey key
+    Cache {
+        /// Valkey connection URL
+        url: Url,
+        /// Key holding the snapshot
+        #[serde(default = "default_feedback_snapshot_key")]
+        key: String,
+    },
+}
+
+const fn default_snapshot_interval_secs() -> u64 {
+    60
+}
+
+const fn default_feedback_half_life_secs() -> u64 {
+    6 * 3600
+}
+
+const fn default_feedback_max_age_secs() -> u64 {
+    7 * 86_400
+}
+
+fn default_feedback_snapshot_key() -> String {
+    "synapse:feedback:snapshot".to_owned()
+}
+
+/// External model catalog
+///
+/// A models.dev-style JSON or TOML file listing pricing, context windows,
+/// modalities, and capability flags per catalog provider and model.
+/// Discovered models found in the catalog get a routing profile
+/// automatically; hand-written `routing.models` profiles take precedence
+/// and only have their unset fields filled in. The file is re-read when it
+/// changes.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ModelCatalogConfig {
+    /// Whether the catalog is used
+    #[serde(default)]
+    pub enabled: bool,
+    /// Catalog file path; parsed as TOML for a `.toml` extension, JSON otherwise
+    pub path: PathBuf,
+    /// Seconds between checks for catalog changes and newly discovered models
+    #[serde(default = "default_catalog_reload_interval_secs")]
+    pub reload_interval_secs: u64,
+    /// Quality score (0.0 to 1.0) of catalog models without a hand-written
+    /// profile; the default of 0 keeps them out of quality-ranked routing
+    #[serde(default)]
+    pub default_quality: f64,
+}
+
+const fn default_catalog_reload_interval_secs() -> u64 {
+    30
+}
+
+/// Routing training-data export
+///
+/// Writes one sample per smart-routed request: the feature vector the ONNX
+/// strategy computes, the routing decision, latency, token usage, and the
+/// user's rating when feedback arrives within the rating window. See
+/// `models/README.md` for training a router on the exported data.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TrainingDataConfig {
+    /// Whether samples are exported
+    #[serde(default)]
+    pub enabled: bool,
+    /// Output format
+    #[serde(default)]
+    pub format: TrainingDataFormat,
+    /// CSV file samples are appended to, or directory Parquet files are
+    /// written into
+    pub path: PathBuf,
+    /// Seconds a sample waits for user feedback before it is written
+    #[serde(default = "default_training_rating_window_secs")]
+    pub rating_window_secs: u64,
+    /// Rows per Parquet file; partial files are written at least every
+    /// five minutes and on shutdown
+    #[serde(default = "default_training_batch_size")]
+    pub batch_size: usize,
+}
+
+/// Training data file format
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TrainingDataFormat {
+    /// A single CSV file with a header row
+    #[default]
+    Csv,
+    /// Parquet files, one per batch
+    Parquet,
+}
+
+const fn default_training_rating_window_secs() -> u64 {
+    300
+}
+
+const fn default_training_batch_size() -> usize {
+    10_000
+}
+
+/// Shadow traffic mirroring
+///
+/// After the primary response returns, sampled requests are replayed
+/// against a shadow model in the background. Both outputs, latencies, and
+/// token costs are appended to a JSONL file; the client never sees the
+/// shadow response. Only non-streaming requests are mirrored.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ShadowConfig {
+    /// Whether mirroring is enabled
+    #[serde(default)]
+    pub enabled: bool,
+    /// JSONL file comparison records are appended to
+    pub sink: PathBuf,
+    /// Requests containing these PII types are never mirrored
+    #[serde(default = "default_shadow_pii")]
+    pub skip_pii: Vec<synapse_guardrails::PiiType>,
+    /// Models or routing classes to mirror
+    #[serde(default)]
+    pub mirrors: Vec<ShadowMirrorConfig>,
+}
+
+/// Mirror one requested model or routing class to a shadow model
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ShadowMirrorConfig {
+    /// Requested model or routing class (e.g. "auto") to mirror
+    pub model: String,
+    /// Shadow model in "provider/model" format
+    pub shadow_model: String,
+    /// Fraction of matching requests to mirror (0.0 to 1.0)
+    #[serde(default = "default_shadow_sample_rate")]
+    pub sample_rate: f64,
+}
+
+fn default_shadow_pii() -> Vec<synapse_guardrails::PiiType> {
+    use synapse_guardrails::PiiType;
+
+    vec![PiiType::Ssn, PiiType::CreditCard, PiiType::Email, PiiType::Phone]
+}
+
+const fn default_shadow_sample_rate() -> f64 {
+    0.1
+}
+
 // -- Routing configuration --
 
 /// Smart model routing configuration
@@ -223,6 +468,29 @@ pub struct RoutingConfig {
     /// Score strategy configuration
     #[serde(default)]
     pub score: ScoreConfig,
+    /// Embedding-similarity (kNN) strategy configuration
+    #[serde(default)]
+    pub knn: KnnConfig,
+    /// LLM classifier strategy configuration
+    #[serde(default)]
+    pub classifier: ClassifierConfig,
+    /// Custom strategies implemented as Rhai scripts
+    #[serde(default)]
+    pub scripts: Vec<ScriptStrategyConfig>,
+    /// User quality feedback configuration
+    #[serde(default)]
+    pub user_feedback: UserFeedbackConfig,
+    /// A/B experiments splitting a virtual model across arms
+    #[serde(default)]
+    pub experiments: Vec<ExperimentConfig>,
+    /// Upgrade requests whose prompt plus `max_tokens` exceeds the model's
+    /// context window to the nearest larger-window model in the same
+    /// failover equivalence group, instead of rejecting them
+    #[serde(default)]
+    pub context_upgrade: bool,
+    /// Keep multi-turn conversations on the model chosen for their first turn
+    #[serde(default)]
+    pub affinity: AffinityConfig,
 }
 
 /// Available routing strategies
@@ -238,7 +506,12 @@ pub enum RoutingStrategy {
     Cascade,
     /// Multi-objective weighted scoring (quality + cost + latency)
     Score,
-    /// User-registered custom strategy referenced by name
+    /// Pick the model that did best on the most similar labeled prompts
+    Knn,
+    /// Classify the query with a cheap model, then route by complexity
+    Classifier,
+    /// Custom strategy referenced by name, either a configured script or
+    /// one registered in code
     Custom(String),
 }
 
@@ -268,18 +541,21 @@ pub struct ModelProfileConfig {
 }
 
 /// Capabilities a model may support
+///
+/// Unset capabilities are filled in from the model catalog when one is
+/// configured, and default to unsupported otherwise.
 #[derive(Debug, Clone, Default, Deserialize)]
 #[serde(deny_unknown_fields)]
 pub struct ModelCapabilities {
     /// Whether the model supports tool/function calling
     #[serde(default)]
-    pub tool_calling: bool,
+    pub tool_calling: Option<bool>,
     /// Whether the model supports vision/image inputs
     #[serde(default)]
-    pub vision: bool,
+    pub vision: Option<bool>,
     /// Whether the model handles long contexts well
     #[serde(default)]
-    pub long_context: bool,
+    pub long_context: Option<bool>,
 }
 
 /// Configuration for threshold-based routing
@@ -339,6 +615,10 @@ pub struct CascadeConfig {
     /// Seconds to wait for buffered response before committing
     #[serde(default = "default_buffer_timeout_secs")]
     pub buffer_timeout_secs: u64,
+    /// LLM self-verification of the initial answer, replacing the
+    /// heuristic confidence estimate
+    #[serde(default)]
+    pub verifier: Option<CascadeVerifierConfig>,
 }
 
 impl Default for CascadeConfig {
@@ -349,10 +629,32 @@ impl Default for CascadeConfig {
             confidence_threshold: default_confidence_threshold(),
             max_buffer_bytes: default_max_buffer_bytes(),
             buffer_timeout_secs: default_buffer_timeout_secs(),
+            verifier: None,
         }
     }
 }
 
+/// Configuration for AutoMix-style cascade verification
+///
+/// After the initial model answers, the verifier model grades the answer's
+/// correctness from 0.0 to 1.0. Grades below `confidence_threshold`
+/// escalate. If the verifier fails or exceeds its timeout, the heuristic
+/// confidence estimate is used instead.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CascadeVerifierConfig {
+    /// Verifier model ("provider/model"); defaults to the initial model
+    #[serde(default)]
+    pub model: Option<String>,
+    /// Latency budget for a verification in milliseconds
+    #[serde(default = "default_verifier_timeout_ms")]
+    pub timeout_ms: u64,
+}
+
+const fn default_verifier_timeout_ms() -> u64 {
+    2000
+}
+
 const fn default_confidence_threshold() -> f64 {
     0.5
 }
@@ -365,6 +667,79 @@ const fn default_buffer_timeout_secs() -> u64 {
     10
 }
 
+/// Configuration for user quality feedback (`POST /v1/feedback`)
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct UserFeedbackConfig {
+    /// Whether responses are recorded so callers can rate them
+    #[serde(default)]
+    pub enabled: bool,
+    /// Seconds a response stays rateable after it was served
+    #[serde(default = "default_feedback_retention_secs")]
+    pub retention_secs: u64,
+    /// Weight of the seeded quality score, in virtual ratings, when
+    /// blending in user ratings
+    #[serde(default = "default_feedback_prior_weight")]
+    pub prior_weight: f64,
+}
+
+impl Default for UserFeedbackConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            retention_secs: default_feedback_retention_secs(),
+            prior_weight: default_feedback_prior_weight(),
+        }
+    }
+}
+
+const fn default_feedback_retention_secs() -> u64 {
+    3600
+}
+
+const fn default_feedback_prior_weight() -> f64 {
+    10.0
+}
+
+/// A/B experiment splitting traffic for a virtual model across arms
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ExperimentConfig {
+    /// Experiment name, reported with every response it serves
+    pub name: String,
+    /// Virtual model name clients request to enter the experiment
+    pub model: String,
+    /// How requests are assigned to arms
+    #[serde(default)]
+    pub sticky: ExperimentStickiness,
+    /// Arms receiving a share of traffic proportional to their weight
+    pub arms: Vec<ExperimentArmConfig>,
+}
+
+/// One arm of an A/B experiment
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ExperimentArmConfig {
+    /// Arm name (e.g. "control", "candidate")
+    pub name: String,
+    /// Model serving this arm ("provider/model")
+    pub model: String,
+    /// Relative share of traffic
+    pub weight: u32,
+}
+
+/// Key that keeps a caller on the same experiment arm
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExperimentStickiness {
+    /// Same arm for every request from a client; falls back to the
+    /// conversation when the client is not identified
+    #[default]
+    Client,
+    /// Same arm for every turn of a conversation
+    Conversation,
+}
+
 /// Configuration for multi-objective score routing
 #[derive(Debug, Clone, Deserialize)]
 #[serde(deny_unknown_fields)]
@@ -417,3 +792,206 @@ const fn default_error_penalty() -> f64 {
 const fn default_min_samples() -> usize {
     10
 }
+
+/// Configuration for embedding-similarity (kNN) routing
+///
+/// The latest user turn is embedded and compared against a labeled set of
+/// example prompts. The model with the best similarity-weighted score on
+/// the nearest examples is selected.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct KnnConfig {
+    /// JSONL file of labeled examples, one
+    /// `{"prompt": ..., "scores": {"provider/model": 0.9}}` object per line;
+    /// an optional precomputed `embedding` skips embedding at startup
+    #[serde(default)]
+    pub examples: Option<PathBuf>,
+    /// Embeddings model in "provider/model" format, where the provider is
+    /// configured under `[embeddings.providers]`
+    #[serde(default)]
+    pub embedding_model: Option<String>,
+    /// Number of nearest examples to consider
+    #[serde(default = "default_knn_neighbors")]
+    pub neighbors: usize,
+    /// Examples less similar than this (cosine, -1.0 to 1.0) are ignored
+    #[serde(default)]
+    pub min_similarity: f64,
+    /// Latency budget for embedding a query in milliseconds; slower
+    /// queries fall back to the heuristic strategy
+    #[serde(default = "default_knn_timeout_ms")]
+    pub timeout_ms: u64,
+}
+
+impl Default for KnnConfig {
+    fn default() -> Self {
+        Self {
+            examples: None,
+            embedding_model: None,
+            neighbors: default_knn_neighbors(),
+            min_similarity: 0.0,
+            timeout_ms: default_knn_timeout_ms(),
+        }
+    }
+}
+
+const fn default_knn_neighbors() -> usize {
+    10
+}
+
+const fn default_knn_timeout_ms() -> u64 {
+    300
+}
+
+/// Configuration for LLM-classifier routing
+///
+/// A small, cheap model classifies the latest user turn into a task type
+/// and complexity, replacing the heuristic classification. Models are then
+/// selected by complexity as in the threshold strategy. On timeout or an
+/// unparsable answer the heuristic classification is used.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ClassifierConfig {
+    /// Classifier model in "provider/model" format
+    #[serde(default)]
+    pub model: Option<String>,
+    /// Latency budget for a classification in milliseconds
+    #[serde(default = "default_classifier_timeout_ms")]
+    pub timeout_ms: u64,
+    /// Maximum number of cached classifications, keyed by prompt hash
+    #[serde(default = "default_classifier_cache_size")]
+    pub cache_size: u64,
+    /// How long a cached classification is reused, in seconds
+    #[serde(default = "default_classifier_cache_ttl_secs")]
+    pub cache_ttl_secs: u64,
+}
+
+impl Default for ClassifierConfig {
+    fn default() -> Self {
+        Self {
+            model: None,
+            timeout_ms: default_classifier_timeout_ms(),
+            cache_size: default_classifier_cache_size(),
+            cache_ttl_secs: default_classifier_cache_ttl_secs(),
+        }
+    }
+}
+
+const fn default_classifier_timeout_ms() -> u64 {
+    300
+}
+
+const fn default_classifier_cache_size() -> u64 {
+    10_000
+}
+
+const fn default_classifier_cache_ttl_secs() -> u64 {
+    3600
+}
+
+/// Custom routing strategy implemented as a Rhai script
+///
+/// The script defines `fn route(query, models, client)` and returns the
+/// selected model as a "provider/model" string, or a map with `model` and
+/// an optional `alternatives` array. Select it with
+/// `strategy = { custom = "<name>" }`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ScriptStrategyConfig {
+    /// Strategy name
+    pub name: String,
+    /// Path to the Rhai script
+    pub path: PathBuf,
+    /// Wall-clock limit for one routing call in milliseconds
+    #[serde(default = "default_script_timeout_ms")]
+    pub timeout_ms: u64,
+    /// Maximum number of script operations for one routing call
+    #[serde(default = "default_script_max_operations")]
+    pub max_operations: u64,
+}
+
+const fn default_script_timeout_ms() -> u64 {
+    10
+}
+
+const fn default_script_max_operations() -> u64 {
+    100_000
+}
+
+/// Conversation affinity for smart routing
+///
+/// Remembers the model chosen for the first routed turn of a conversation
+/// and keeps later turns on it, so provider-side prompt caches stay warm.
+/// Conversations are identified by a client-supplied header or, when it is
+/// absent, by a hash of the messages up to and including the first user
+/// turn. A pinned model whose provider circuit is open is re-routed.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AffinityConfig {
+    /// Whether conversation affinity is enabled
+    #[serde(default)]
+    pub enabled: bool,
+    /// Header carrying a client-supplied conversation ID
+    #[serde(default = "default_affinity_header")]
+    pub header: String,
+    /// Seconds a pinned model is kept after the conversation's last turn
+    #[serde(default = "default_affinity_ttl_secs")]
+    pub ttl_secs: u64,
+    /// Where pinned models are stored
+    #[serde(default)]
+    pub storage: AffinityStorage,
+}
+
+impl Default for AffinityConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            header: default_affinity_header(),
+            ttl_secs: default_affinity_ttl_secs(),
+            storage: AffinityStorage::default(),
+        }
+    }
+}
+
+/// Conversation affinity storage backend
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum AffinityStorage {
+    /// In-process cache, local to each replica
+    Memory {
+        /// Maximum number of tracked conversations
+        #[serde(default = "default_affinity_max_entries")]
+        max_entries: u64,
+    },
+    /// Valkey keys, shared across replicas
+    Cache {
+        /// Valkey connection URL
+        url: Url,
+        /// Key prefix in Valkey
+        #[serde(default = "default_affinity_key_prefix")]
+        key_prefix: String,
+    },
+}
+
+impl Default for AffinityStorage {
+    fn default() -> Self {
+        Self::Memory {
+            max_entries: default_affinity_max_entries(),
+        }
+    }
+}
+
+fn default_affinity_header() -> String {
+    "x-synapse-conversation-id".to_owned()
+}
+
+const fn default_affinity_ttl_secs() -> u64 {
+    1800
+}
+
+const fn default_affinity_max_entries() -> u64 {
+    100_000
+}
+
+fn default_affinity_key_prefix() -> String {
+    "synapse:affinity".to_owned()
+}
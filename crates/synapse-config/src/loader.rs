@@ -37,6 +37,7 @@ impl Config {
     pub fn validate(&self) -> anyhow::Result<()> {
         self.validate_has_downstreams()?;
         self.validate_llm_config()?;
+        self.validate_routing_config()?;
         self.validate_mcp_config()?;
         self.validate_auth_config()?;
         Ok(())
@@ -45,7 +46,7 @@ impl Config {
     /// Ensure at least one downstream service is configured
     fn validate_has_downstreams(&self) -> anyhow::Result<()> {
         let has_llm = !self.llm.providers.is_empty();
-        let has_mcp = !self.mcp.servers.is_empty();
+        let has_mcp = !self.mcp.servers.is_empty() || self.mcp.admin.enabled;
         let has_stt = !self.stt.providers.is_empty();
         let has_tts = !self.tts.providers.is_empty();
 
@@ -72,6 +73,70 @@ impl Config {
             }
         }
 
+        self.validate_experiments()?;
+
+        // Validate kNN routing has examples and an embeddings model
+        let routing = &self.llm.routing;
+        if routing.enabled && matches!(routing.strategy, crate::RoutingStrategy::Knn) {
+            let Some(ref embedding_model) = routing.knn.embedding_model else {
+                anyhow::bail!("knn routing requires llm.routing.knn.embedding_model");
+            };
+            if routing.knn.examples.is_none() {
+                anyhow::bail!("knn routing requires llm.routing.knn.examples");
+            }
+            let provider = embedding_model.split_once('/').map_or("", |(provider, _)| provider);
+            if !self.embeddings.providers.contains_key(provider) {
+                anyhow::bail!(
+                    "knn routing embedding_model '{embedding_model}' must name a provider configured under [embeddings.providers]"
+                );
+            }
+            if routing.knn.neighbors == 0 {
+                anyhow::bail!("llm.routing.knn.neighbors must be at least 1");
+            }
+        }
+
+        // Validate classifier routing names a configured LLM provider
+        if routing.enabled && matches!(routing.strategy, crate::RoutingStrategy::Classifier) {
+            let Some(ref model) = routing.classifier.model else {
+                anyhow::bail!("classifier routing requires llm.routing.classifier.model");
+            };
+            let provider = model.split_once('/').map_or("", |(provider, _)| provider);
+            if !self.llm.providers.contains_key(provider) {
+                anyhow::bail!(
+                    "classifier routing model '{model}' must name a provider configured under [llm.providers]"
+                );
+            }
+            if routing.classifier.timeout_ms == 0 {
+                anyhow::bail!("llm.routing.classifier.timeout_ms must be greater than 0");
+            }
+        }
+
+        // Validate shadow mirrors
+        if let Some(ref shadow) = self.llm.shadow {
+            for mirror in &shadow.mirrors {
+                if !(0.0..=1.0).contains(&mirror.sample_rate) {
+                    anyhow::bail!(
+                        "shadow mirror for '{}' sample_rate must be between 0.0 and 1.0",
+                        mirror.model
+                    );
+                }
+                if !mirror.shadow_model.contains('/') {
+                    anyhow::bail!(
+                        "shadow mirror for '{}' shadow_model must be in 'provider/model' format",
+                        mirror.model
+                    );
+                }
+            }
+        }
+
+        // Validate training-data export
+        if let Some(ref training) = self.llm.training_data
+            && training.enabled
+            && training.batch_size == 0
+        {
+            anyhow::bail!("llm.training_data.batch_size must be greater than 0");
+        }
+
         // Validate token rate limits require client identification
         if let Some(ref rate_limit) = self.server.rate_limit
             && rate_limit.tokens.is_some()
@@ -83,14 +148,166 @@ impl Config {
         Ok(())
     }
 
+    /// Validate experiments can assign every request to a distinct arm
+    fn validate_experiments(&self) -> anyhow::Result<()> {
+        let mut experiment_names = std::collections::HashSet::new();
+        for experiment in &self.llm.routing.experiments {
+            if !experiment_names.insert(experiment.name.as_str()) {
+                anyhow::bail!("duplicate experiment name '{}'", experiment.name);
+            }
+            if experiment.arms.iter().map(|arm| u64::from(arm.weight)).sum::<u64>() == 0 {
+                anyhow::bail!(
+                    "experiment '{}' needs at least one arm with a positive weight",
+                    experiment.name
+                );
+            }
+            if let Some(arm) = experiment.arms.iter().find(|arm| !arm.model.contains('/')) {
+                anyhow::bail!(
+                    "experiment '{}' arm '{}' model must be in 'provider/model' format",
+                    experiment.name,
+                    arm.name
+                );
+            }
+
+            // Outcomes are reported per arm, so arms must be told apart
+            let mut arm_names = std::collections::HashSet::new();
+            let mut arm_models = std::collections::HashSet::new();
+            for arm in &experiment.arms {
+                if arm.name.trim().is_empty() {
+                    anyhow::bail!("experiment '{}' has an arm without a name", experiment.name);
+                }
+                if !arm_names.insert(arm.name.as_str()) {
+                    anyhow::bail!("experiment '{}' has duplicate arm '{}'", experiment.name, arm.name);
+                }
+                if !arm_models.insert(arm.model.as_str()) {
+                    anyhow::bail!(
+                        "experiment '{}' arm '{}' repeats model '{}' of another arm",
+                        experiment.name,
+                        arm.name,
+                        arm.model
+                    );
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Validate scripted routing strategies and other routing extensions
+    fn validate_routing_config(&self) -> anyhow::Result<()> {
+        let routing = &self.llm.routing;
+
+        // Validate the cascade verifier
+        if let Some(ref verifier) = routing.cascade.verifier {
+            if verifier.model.as_ref().is_some_and(|model| !model.contains('/')) {
+                anyhow::bail!("llm.routing.cascade.verifier.model must be in 'provider/model' format");
+            }
+            if verifier.timeout_ms == 0 {
+                anyhow::bail!("llm.routing.cascade.verifier.timeout_ms must be greater than 0");
+            }
+        }
+
+        // Validate scripted strategies
+        let mut script_names = std::collections::HashSet::new();
+        for script in &routing.scripts {
+            if matches!(
+                script.name.as_str(),
+                "threshold" | "cost" | "cascade" | "score" | "knn" | "classifier"
+            ) {
+                anyhow::bail!("routing script '{}' shadows a built-in strategy", script.name);
+            }
+            if !script_names.insert(script.name.as_str()) {
+                anyhow::bail!("duplicate routing script name '{}'", script.name);
+            }
+            if script.timeout_ms == 0 || script.max_operations == 0 {
+                anyhow::bail!(
+                    "routing script '{}' timeout_ms and max_operations must be greater than 0",
+                    script.name
+                );
+            }
+        }
+
+        // Validate the model catalog
+        if let Some(ref catalog) = self.llm.catalog
+            && catalog.enabled
+        {
+            if catalog.reload_interval_secs == 0 {
+                anyhow::bail!("llm.catalog.reload_interval_secs must be greater than 0");
+            }
+            if !(0.0..=1.0).contains(&catalog.default_quality) {
+                anyhow::bail!("llm.catalog.default_quality must be between 0.0 and 1.0");
+            }
+        }
+
+        // Validate conversation affinity
+        if routing.affinity.enabled {
+            if http::HeaderName::from_bytes(routing.affinity.header.as_bytes()).is_err() {
+                anyhow::bail!(
+                    "llm.routing.affinity.header '{}' is not a valid header name",
+                    routing.affinity.header
+                );
+            }
+            if routing.affinity.ttl_secs == 0 {
+                anyhow::bail!("llm.routing.affinity.ttl_secs must be greater than 0");
+            }
+        }
+
+        Ok(())
+    }
+
     /// Validate MCP-specific configuration
     fn validate_mcp_config(&self) -> anyhow::Result<()> {
         for (name, server) in &self.mcp.servers {
-            if let Some(ref access) = server.access
-                && !access.allow.is_empty()
-                && !access.deny.is_empty()
-            {
-                anyhow::bail!("MCP server '{name}' cannot have both allow and deny lists");
+            server.validate(name)?;
+        }
+
+        let admin = &self.mcp.admin;
+        if admin.enabled
+            && admin
+                .secret
+                .as_ref()
+                .is_none_or(|secret| secret.expose_secret().is_empty())
+        {
+            anyhow::bail!("mcp.admin.secret must be set when the admin API is enabled");
+        }
+
+        if let Some(ref embedding_model) = self.mcp.search.embedding_model {
+            let provider = embedding_model.split_once('/').map_or("", |(provider, _)| provider);
+            if !self.embeddings.providers.contains_key(provider) {
+                anyhow::bail!(
+                    "mcp.search.embedding_model '{embedding_model}' must name a provider configured under [embeddings.providers]"
+                );
+            }
+        }
+
+        let supervisor = &self.mcp.supervisor;
+        if supervisor.check_interval_secs == 0 || supervisor.refresh_interval_secs == 0 {
+            anyhow::bail!("mcp.supervisor intervals must be greater than 0");
+        }
+        if supervisor.max_backoff_secs < supervisor.check_interval_secs {
+            anyhow::bail!("mcp.supervisor.max_backoff_secs must be at least check_interval_secs");
+        }
+
+        for policy in &self.mcp.policies {
+            if policy.arguments.iter().any(|constraint| constraint.allow.is_empty()) {
+                anyhow::bail!("mcp.policies argument constraints must allow at least one value");
+            }
+        }
+
+        if self.mcp.tool_loop.max_iterations == 0 {
+            anyhow::bail!("mcp.tool_loop.max_iterations must be greater than 0");
+        }
+
+        let endpoint = &self.mcp.endpoint;
+        if endpoint.enabled {
+            if !endpoint.path.starts_with('/') || endpoint.path.starts_with("/mcp/") {
+                anyhow::bail!(
+                    "mcp.endpoint.path '{}' must start with '/' and not overlap the /mcp/ REST routes",
+                    endpoint.path
+                );
+            }
+            if endpoint.sse_keep_alive_secs == 0 {
+                anyhow::bail!("mcp.endpoint.sse_keep_alive_secs must be greater than 0");
             }
         }
 
@@ -122,3 +339,58 @@ impl Config {
         Ok(())
     }
 }
+
+impl crate::McpServerConfig {
+    /// Validate the configuration of the MCP server called `name`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the name cannot be qualified or a setting is
+    /// out of range
+    pub fn validate(&self, name: &str) -> anyhow::Result<()> {
+        if name.is_empty() || name.contains("__") {
+            anyhow::bail!("MCP server name '{name}' must be non-empty and not contain '__'");
+        }
+
+        let rate_limits = self.rate_limit.iter().chain(self.tool_rate_limits.values());
+        if rate_limits.into_iter().any(|rate_limit| rate_limit.requests == 0) {
+            anyhow::bail!("MCP server '{name}' rate limits must allow at least one request");
+        }
+
+        if self.pool_size == 0 {
+            anyhow::bail!("MCP server '{name}' pool_size must be at least 1");
+        }
+        if self.max_concurrent_requests == Some(0) {
+            anyhow::bail!("MCP server '{name}' max_concurrent_requests must be at least 1");
+        }
+
+        for header in &self.headers {
+            if http::HeaderName::from_bytes(header.name.as_bytes()).is_err()
+                || http::HeaderValue::from_str(&header.value).is_err()
+            {
+                anyhow::bail!("MCP server '{name}' has an invalid header '{}'", header.name);
+            }
+        }
+
+        if let crate::McpServerType::Sse(ref http) | crate::McpServerType::StreamableHttp(ref http) = self.server_type
+            && let Some(ref tls) = http.tls
+            && tls.client_cert.is_some() != tls.client_key.is_some()
+        {
+            anyhow::bail!("MCP server '{name}' tls.client_cert and tls.client_key must be set together");
+        }
+
+        let Some(ref access) = self.access else {
+            return Ok(());
+        };
+        if !access.allow.is_empty() && !access.deny.is_empty() {
+            anyhow::bail!("MCP server '{name}' cannot have both allow and deny lists");
+        }
+        for (kind, rules) in [("resources", &access.resources), ("prompts", &access.prompts)] {
+            if !rules.allow.is_empty() && !rules.deny.is_empty() {
+                anyhow::bail!("MCP server '{name}' cannot have both allow and deny lists for {kind}");
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -15,15 +15,235 @@ pub struct McpConfig {
     /// Dynamic downstream connection cache settings
     #[serde(default)]
     pub cache: Option<McpCacheConfig>,
+    /// MCP endpoint serving the aggregated tools to MCP clients
+    #[serde(default)]
+    pub endpoint: McpEndpointConfig,
+    /// Admin API for managing downstream servers at runtime
+    #[serde(default)]
+    pub admin: McpAdminConfig,
+    /// Reconnection and refresh of downstream servers
+    #[serde(default)]
+    pub supervisor: McpSupervisorConfig,
+    /// Tool search settings
+    #[serde(default)]
+    pub search: McpSearchConfig,
+    /// Server-side execution of MCP tool calls in chat completions
+    #[serde(default)]
+    pub tool_loop: McpToolLoopConfig,
+    /// Access policies for callers matching an identity
+    #[serde(default)]
+    pub policies: Vec<McpPolicyConfig>,
+}
+
+/// Server-side execution of MCP tool calls in chat completions
+///
+/// A chat completion request carrying an `mcp` extension is offered the
+/// caller's MCP tools; the gateway runs the tool calls the model makes and
+/// feeds the results back until the model answers or the iteration cap is
+/// reached.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct McpToolLoopConfig {
+    /// Whether requests may opt into the tool loop
+    #[serde(default = "default_tool_loop_enabled")]
+    pub enabled: bool,
+    /// Upper bound on rounds of tool calls per request; requests may ask
+    /// for fewer
+    #[serde(default = "default_max_iterations")]
+    pub max_iterations: u32,
+}
+
+impl Default for McpToolLoopConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_tool_loop_enabled(),
+            max_iterations: default_max_iterations(),
+        }
+    }
+}
+
+const fn default_tool_loop_enabled() -> bool {
+    true
+}
+
+const fn default_max_iterations() -> u32 {
+    5
+}
+
+/// Tool search settings
+///
+/// With an embeddings model configured, tool names, descriptions, and
+/// input parameter names are embedded so `/mcp/search` can match by
+/// meaning as well as by keyword, fusing both rankings.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct McpSearchConfig {
+    /// Embeddings model in "provider/model" format, where the provider is
+    /// configured under `[embeddings.providers]`; keyword search only if
+    /// unset
+    #[serde(default)]
+    pub embedding_model: Option<String>,
+    /// Constant `k` of reciprocal rank fusion, `1 / (k + rank)`; larger
+    /// values flatten the difference between top and lower ranks
+    #[serde(default = "default_rrf_k")]
+    pub rrf_k: u32,
+}
+
+impl Default for McpSearchConfig {
+    fn default() -> Self {
+        Self {
+            embedding_model: None,
+            rrf_k: default_rrf_k(),
+        }
+    }
+}
+
+const fn default_rrf_k() -> u32 {
+    60
+}
+
+/// Supervision of downstream servers
+///
+/// Retries servers that failed to connect with exponential backoff,
+/// restarts sessions whose transport closed (such as crashed STDIO child
+/// processes), and re-lists a server's tools, resources, and prompts when
+/// it sends a `list_changed` notification or on a fixed interval.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct McpSupervisorConfig {
+    /// Whether servers are supervised
+    #[serde(default = "default_supervisor_enabled")]
+    pub enabled: bool,
+    /// Seconds between checks for closed sessions, and the first retry
+    /// delay of a failed server
+    #[serde(default = "default_check_interval_secs")]
+    pub check_interval_secs: u64,
+    /// Upper bound in seconds of the retry delay of a failed server
+    #[serde(default = "default_max_backoff_secs")]
+    pub max_backoff_secs: u64,
+    /// Seconds between re-listing every server, for servers that do not
+    /// send `list_changed` notifications
+    #[serde(default = "default_refresh_interval_secs")]
+    pub refresh_interval_secs: u64,
+}
+
+impl Default for McpSupervisorConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_supervisor_enabled(),
+            check_interval_secs: default_check_interval_secs(),
+            max_backoff_secs: default_max_backoff_secs(),
+            refresh_interval_secs: default_refresh_interval_secs(),
+        }
+    }
+}
+
+const fn default_supervisor_enabled() -> bool {
+    true
+}
+
+const fn default_check_interval_secs() -> u64 {
+    10
+}
+
+const fn default_max_backoff_secs() -> u64 {
+    300
+}
+
+const fn default_refresh_interval_secs() -> u64 {
+    300
+}
+
+/// Admin API for registering, updating, disabling, and removing
+/// downstream servers without a restart
+///
+/// Requests must carry the secret in the `x-admin-secret` header. Changes
+/// live in memory and are lost on restart.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct McpAdminConfig {
+    /// Whether the admin routes are served
+    #[serde(default)]
+    pub enabled: bool,
+    /// Shared secret admin requests must present
+    #[serde(default)]
+    pub secret: Option<SecretString>,
+    /// Whether stdio servers may be registered, which runs the given
+    /// command on the gateway host
+    #[serde(default)]
+    pub allow_stdio: bool,
+}
+
+/// Streamable HTTP MCP endpoint
+///
+/// Serves the tools aggregated from all downstream servers over the MCP
+/// Streamable HTTP transport, so MCP clients can connect to the gateway
+/// directly. Tools are named `server__tool` as in the REST routes.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct McpEndpointConfig {
+    /// Whether the endpoint is served
+    #[serde(default = "default_endpoint_enabled")]
+    pub enabled: bool,
+    /// Path the endpoint is served at
+    #[serde(default = "default_endpoint_path")]
+    pub path: String,
+    /// Keep sessions, letting clients open an SSE stream for
+    /// notifications such as `tools/list_changed`
+    #[serde(default = "default_endpoint_stateful")]
+    pub stateful: bool,
+    /// Answer requests with plain JSON instead of an SSE stream; only
+    /// applies to stateless endpoints
+    #[serde(default)]
+    pub json_response: bool,
+    /// Seconds between keep-alive pings on SSE streams
+    #[serde(default = "default_endpoint_keep_alive_secs")]
+    pub sse_keep_alive_secs: u64,
+}
+
+impl Default for McpEndpointConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_endpoint_enabled(),
+            path: default_endpoint_path(),
+            stateful: default_endpoint_stateful(),
+            json_response: false,
+            sse_keep_alive_secs: default_endpoint_keep_alive_secs(),
+        }
+    }
+}
+
+const fn default_endpoint_enabled() -> bool {
+    true
+}
+
+fn default_endpoint_path() -> String {
+    "/mcp".to_owned()
+}
+
+const fn default_endpoint_stateful() -> bool {
+    true
+}
+
+const fn default_endpoint_keep_alive_secs() -> u64 {
+    15
 }
 
 /// Configuration for a single MCP server
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Deserialize)]
 #[serde(deny_unknown_fields)]
 pub struct McpServerConfig {
     /// Server transport type
     #[serde(rename = "type")]
     pub server_type: McpServerType,
+    /// Whether the server is connected; disabled servers keep their
+    /// configuration but offer nothing
+    #[serde(default = "default_server_enabled")]
+    pub enabled: bool,
+    /// Client identification group the server is limited to (visible to
+    /// every client if unset)
+    #[serde(default)]
+    pub tenant: Option<String>,
     /// Rate limit for this server
     #[serde(default)]
     pub rate_limit: Option<McpRateLimit>,
@@ -39,6 +259,21 @@ pub struct McpServerConfig {
     /// Enable structured content responses
     #[serde(default)]
     pub structured_content: bool,
+    /// Maximum requests in flight to this server (unbounded if unset)
+    #[serde(default)]
+    pub max_concurrent_requests: Option<usize>,
+    /// Sessions to spread requests over; each is its own child process
+    /// for STDIO servers
+    #[serde(default = "default_pool_size")]
+    pub pool_size: usize,
+}
+
+const fn default_server_enabled() -> bool {
+    true
+}
+
+const fn default_pool_size() -> usize {
+    1
 }
 
 /// MCP server transport types
@@ -87,7 +322,8 @@ pub struct HttpConfig {
 pub enum McpAuthConfig {
     /// Static bearer token
     Token { token: SecretString },
-    /// Forward the client's authorization header
+    /// Forward the client's authorization header, connecting to the
+    /// server once per distinct credential
     Forward,
 }
 
@@ -117,7 +353,7 @@ pub struct McpRateLimit {
 }
 
 /// Access control for MCP servers/tools
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Default, Deserialize)]
 #[serde(deny_unknown_fields)]
 pub struct McpAccessConfig {
     /// Allowed tool names (if set, only these tools are accessible)
@@ -126,6 +362,97 @@ pub struct McpAccessConfig {
     /// Denied tool names (if set, these tools are blocked)
     #[serde(default)]
     pub deny: Vec<String>,
+    /// Resource access by URI; resource templates match by URI template
+    #[serde(default)]
+    pub resources: McpAccessRules,
+    /// Prompt access by name
+    #[serde(default)]
+    pub prompts: McpAccessRules,
+}
+
+/// Allow/deny lists for one kind of MCP capability
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct McpAccessRules {
+    /// Allowed names (if set, only these are accessible)
+    #[serde(default)]
+    pub allow: Vec<String>,
+    /// Denied names (if set, these are blocked)
+    #[serde(default)]
+    pub deny: Vec<String>,
+}
+
+/// Access policy for the callers matching an identity
+///
+/// Tools are named `server__tool`, resources `server__uri`, and prompts
+/// `server__prompt`; patterns may use `*` wildcards, as in
+/// `github__*_issue`. A caller must satisfy every policy that matches it,
+/// on top of the per-server access lists.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct McpPolicyConfig {
+    /// Callers the policy applies to; every condition given must hold,
+    /// and a policy without conditions applies to every caller
+    #[serde(default, rename = "match")]
+    pub subject: McpPolicySubject,
+    /// Tool patterns the callers may use (if set, only these tools are
+    /// accessible)
+    #[serde(default)]
+    pub allow: Vec<String>,
+    /// Tool patterns the callers may not use, taking precedence over
+    /// `allow`
+    #[serde(default)]
+    pub deny: Vec<String>,
+    /// Values the callers may pass for tool arguments
+    #[serde(default)]
+    pub arguments: Vec<McpArgumentConstraint>,
+    /// Resource patterns the callers may list and read; resource
+    /// templates match by URI template
+    #[serde(default)]
+    pub resources: McpAccessRules,
+    /// Prompt patterns the callers may list and get
+    #[serde(default)]
+    pub prompts: McpAccessRules,
+}
+
+/// Identity a policy applies to
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct McpPolicySubject {
+    /// Client identification group
+    #[serde(default)]
+    pub group: Option<String>,
+    /// Plan of the caller's API key
+    #[serde(default)]
+    pub plan: Option<String>,
+    /// JWT claim the caller's token must carry
+    #[serde(default)]
+    pub claim: Option<McpClaimMatch>,
+}
+
+/// JWT claim with a required value
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct McpClaimMatch {
+    /// Claim path, nested with dots (e.g. "org.role")
+    pub path: String,
+    /// Required claim value
+    pub value: String,
+}
+
+/// Allowed values of one argument of the matching tools
+///
+/// Calls without the argument are denied. For array arguments every
+/// element must match.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct McpArgumentConstraint {
+    /// Tool pattern the constraint applies to
+    pub tool: String,
+    /// Argument name
+    pub argument: String,
+    /// Value patterns the argument must match
+    pub allow: Vec<String>,
 }
 
 /// Header to insert on MCP requests
@@ -150,6 +477,15 @@ pub struct McpCacheConfig {
     pub ttl: u64,
 }
 
+impl Default for McpCacheConfig {
+    fn default() -> Self {
+        Self {
+            max_connections: default_cache_max(),
+            ttl: default_cache_ttl(),
+        }
+    }
+}
+
 #[allow(clippy::missing_const_for_fn)]
 fn default_cache_max() -> u64 {
     100
@@ -289,3 +289,19 @@ impl<'de> Deserialize<'de> for NameOrPattern {
         }
     }
 }
+
+/// Client IP address from `X-Forwarded-For` (first hop) or `X-Real-IP`
+#[must_use]
+pub fn client_ip(headers: &HeaderMap) -> Option<String> {
+    if let Some(forwarded) = headers.get("x-forwarded-for")
+        && let Ok(val) = forwarded.to_str()
+        && let Some(first) = val.split(',').next()
+    {
+        return Some(first.trim().to_owned());
+    }
+
+    headers
+        .get("x-real-ip")
+        .and_then(|real_ip| real_ip.to_str().ok())
+        .map(|val| val.trim().to_owned())
+}
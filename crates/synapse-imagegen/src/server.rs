@@ -171,6 +171,8 @@ impl Server {
                 Self::estimate_image_cost(request)
             },
             idempotency_key,
+            experiment: None,
+            experiment_arm: None,
         });
     }
 
@@ -0,0 +1,243 @@
+//! Conversation affinity for smart routing
+//!
+//! Pins the model chosen for a conversation's first routed turn so later
+//! turns reuse it and keep provider-side prompt caches warm. Pins live in an
+//! in-process cache, or in Valkey when replicas share conversations, and
+//! expire after a period without turns.
+
+use std::fmt::Write;
+use std::time::Duration;
+
+use mini_moka::sync::Cache;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use synapse_config::{AffinityConfig, AffinityStorage};
+use synapse_core::RequestContext;
+
+use crate::error::LlmError;
+use crate::types::CompletionRequest;
+use crate::types::message::Role;
+
+/// Provider and model pinned to a conversation
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct PinnedModel {
+    pub(crate) provider: String,
+    pub(crate) model: String,
+}
+
+enum Store {
+    Memory(Cache<[u8; 32], PinnedModel>),
+    Cache { client: redis::Client, key_prefix: String },
+}
+
+/// Conversation key derivation and pinned model storage
+pub(crate) struct Affinity {
+    header: http::HeaderName,
+    ttl: Duration,
+    store: Store,
+}
+
+impl Affinity {
+    pub(crate) fn new(config: &AffinityConfig) -> Result<Self, LlmError> {
+        let header = http::HeaderName::from_bytes(config.header.as_bytes())
+            .map_err(|e| LlmError::Internal(anyhow::anyhow!("invalid affinity header: {e}")))?;
+        let ttl = Duration::from_secs(config.ttl_secs);
+
+        let store = match &config.storage {
+            AffinityStorage::Memory { max_entries } => {
+                Store::Memory(Cache::builder().max_capacity(*max_entries).time_to_idle(ttl).build())
+            }
+            AffinityStorage::Cache { url, key_prefix } => Store::Cache {
+                client: redis::Client::open(url.as_str())
+                    .map_err(|e| LlmError::Internal(anyhow::anyhow!("invalid affinity cache URL: {e}")))?,
+                key_prefix: key_prefix.clone(),
+            },
+        };
+
+        Ok(Self { header, ttl, store })
+    }
+
+    /// Conversation key for a request routed through a routing class
+    ///
+    /// Uses the conversation header when present, otherwise the messages up
+    /// to and including the first user turn, which every later turn repeats.
+    /// Keys are scoped to the routing class and client. Returns `None` when
+    /// there is neither a header nor a user turn.
+    pub(crate) fn key(
+        &self,
+        routing_class: &str,
+        request: &CompletionRequest,
+        context: &RequestContext,
+    ) -> Option<[u8; 32]> {
+        let mut hasher = Sha256::new();
+        hasher.update(routing_class.as_bytes());
+        hasher.update([0]);
+        if let Some(ref identity) = context.client_identity {
+            hasher.update(identity.client_id.as_bytes());
+        }
+        hasher.update([0]);
+
+        let conversation_id = context
+            .parts
+            .headers
+            .get(&self.header)
+            .map(http::HeaderValue::as_bytes)
+            .filter(|id| !id.is_empty());
+
+        if let Some(id) = conversation_id {
+            hasher.update(b"header:");
+            hasher.update(id);
+        } else {
+            let first_user = request.messages.iter().position(|m| m.role == Role::User)?;
+            let prefix = serde_json::to_vec(&request.messages[..=first_user]).ok()?;
+            hasher.update(b"prefix:");
+            hasher.update(prefix);
+        }
+
+        Some(hasher.finalize().into())
+    }
+
+    /// Model pinned to a conversation, refreshing its expiry
+    ///
+    /// Storage errors are logged and treated as no pin.
+    pub(crate) async fn get(&self, key: &[u8; 32]) -> Option<PinnedModel> {
+        match &self.store {
+            Store::Memory(cache) => cache.get(key),
+            Store::Cache { client, key_prefix } => {
+                let result: redis::RedisResult<Option<String>> = async {
+                    let mut conn = client.get_multiplexed_async_connection().await?;
+                    redis::cmd("GETEX")
+                        .arg(cache_key(key_prefix, key))
+                        .arg("EX")
+                        .arg(self.ttl.as_secs())
+                        .query_async(&mut conn)
+                        .await
+                }
+                .await;
+
+                match result {
+                    Ok(data) => data.and_then(|data| serde_json::from_str(&data).ok()),
+                    Err(e) => {
+                        tracing::warn!(error = %e, "failed to look up conversation affinity");
+                        None
+                    }
+                }
+            }
+        }
+    }
+
+    /// Pin a model to a conversation
+    ///
+    /// Storage errors are logged; the next turn is routed afresh.
+    pub(crate) async fn pin(&self, key: [u8; 32], pin: PinnedModel) {
+        match &self.store {
+            Store::Memory(cache) => cache.insert(key, pin),
+            Store::Cache { client, key_prefix } => {
+                let Ok(data) = serde_json::to_string(&pin) else {
+                    return;
+                };
+                let result: redis::RedisResult<()> = async {
+                    let mut conn = client.get_multiplexed_async_connection().await?;
+                    redis::cmd("SET")
+                        .arg(cache_key(key_prefix, &key))
+                        .arg(data)
+                        .arg("EX")
+                        .arg(self.ttl.as_secs())
+                        .query_async(&mut conn)
+                        .await
+                }
+                .await;
+
+                if let Err(e) = result {
+                    tracing::warn!(error = %e, "failed to store conversation affinity");
+                }
+            }
+        }
+    }
+}
+
+fn cache_key(prefix: &str, key: &[u8; 32]) -> String {
+    key.iter().fold(format!("{prefix}:"), |mut out, byte| {
+        let _ = write!(out, "{byte:02x}");
+        out
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use synapse_core::ClientIdentity;
+
+    use super::*;
+    use crate::types::message::{Content, Message};
+
+    fn affinity() -> Affinity {
+        Affinity::new(&AffinityConfig {
+            enabled: true,
+            ..AffinityConfig::default()
+        })
+        .unwrap()
+    }
+
+    fn message(role: Role, text: &str) -> Message {
+        Message {
+            role,
+            content: Content::Text(text.to_owned()),
+            name: None,
+            tool_calls: None,
+            tool_call_id: None,
+        }
+    }
+
+    fn request(messages: &[Message]) -> CompletionRequest {
+        serde_json::from_value(serde_json::json!({"model": "auto", "messages": messages})).unwrap()
+    }
+
+    #[test]
+    fn later_turns_share_the_first_turn_key() {
+        let affinity = affinity();
+        let context = RequestContext::empty();
+        let first = request(&[message(Role::System, "Be terse"), message(Role::User, "Hi")]);
+        let second = request(&[
+            message(Role::System, "Be terse"),
+            message(Role::User, "Hi"),
+            message(Role::Assistant, "Hello"),
+            message(Role::User, "Explain monads"),
+        ]);
+        let other = request(&[message(Role::System, "Be terse"), message(Role::User, "Bye")]);
+
+        let key = affinity.key("auto", &first, &context).unwrap();
+        assert_eq!(affinity.key("auto", &second, &context), Some(key));
+        assert_ne!(affinity.key("auto", &other, &context), Some(key));
+        assert_ne!(affinity.key("best", &second, &context), Some(key));
+    }
+
+    #[test]
+    fn header_and_client_scope_the_key() {
+        let affinity = affinity();
+        let first = request(&[message(Role::User, "Hi")]);
+        let second = request(&[message(Role::User, "Something else")]);
+
+        let mut context = RequestContext::empty();
+        context
+            .parts
+            .headers
+            .insert("x-synapse-conversation-id", http::HeaderValue::from_static("conv-1"));
+        let key = affinity.key("auto", &first, &context).unwrap();
+        assert_eq!(affinity.key("auto", &second, &context), Some(key));
+
+        context.client_identity = Some(ClientIdentity {
+            client_id: "team-a".to_owned(),
+            group: None,
+        });
+        assert_ne!(affinity.key("auto", &second, &context), Some(key));
+    }
+
+    #[test]
+    fn cache_keys_are_hex() {
+        assert_eq!(
+            cache_key("synapse:affinity", &[0xab; 32]).len(),
+            "synapse:affinity:".len() + 64
+        );
+        assert!(cache_key("p", &[0x0f; 32]).starts_with("p:0f0f"));
+    }
+}
@@ -0,0 +1,177 @@
+//! Model catalog loading and hot reload
+//!
+//! Merges the configured catalog file with hand-written routing profiles
+//! and discovered models into the model registry. A background task
+//! rebuilds the registry when the file changes or discovery reports new
+//! models; a catalog that fails to reload leaves the previous registry in
+//! place.
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Arc, PoisonError, RwLock};
+use std::time::{Duration, SystemTime};
+
+use synapse_config::{LlmConfig, ModelCatalogConfig, ModelProfileConfig};
+use synapse_routing::{CatalogSource, ModelCatalog, ModelRegistry};
+use tokio_util::sync::CancellationToken;
+
+use crate::error::LlmError;
+
+/// Discovered models per provider, as maintained by discovery
+type KnownModels = Arc<tokio::sync::RwLock<HashMap<String, Vec<String>>>>;
+
+/// Model registry that can be swapped while requests read it
+pub(crate) struct SharedRegistry {
+    current: RwLock<Arc<ModelRegistry>>,
+}
+
+impl SharedRegistry {
+    pub(crate) fn new(registry: ModelRegistry) -> Self {
+        Self {
+            current: RwLock::new(Arc::new(registry)),
+        }
+    }
+
+    /// The registry as of now; later swaps do not affect the returned snapshot
+    pub(crate) fn current(&self) -> Arc<ModelRegistry> {
+        Arc::clone(&self.current.read().unwrap_or_else(PoisonError::into_inner))
+    }
+
+    fn replace(&self, registry: ModelRegistry) {
+        *self.current.write().unwrap_or_else(PoisonError::into_inner) = Arc::new(registry);
+    }
+}
+
+/// Everything needed to rebuild the registry from a catalog
+pub(crate) struct CatalogLoader {
+    config: ModelCatalogConfig,
+    profiles: Vec<ModelProfileConfig>,
+    /// Configured provider name and the catalog provider ID describing it
+    catalog_ids: Vec<(String, String)>,
+}
+
+impl CatalogLoader {
+    /// Returns `None` when no catalog is enabled
+    pub(crate) fn new(config: &LlmConfig) -> Option<Self> {
+        let catalog = config.catalog.as_ref().filter(|catalog| catalog.enabled)?;
+        let catalog_ids = config
+            .providers
+            .iter()
+            .map(|(name, provider)| {
+                (
+                    name.clone(),
+                    provider.catalog_id.clone().unwrap_or_else(|| name.clone()),
+                )
+            })
+            .collect();
+
+        Some(Self {
+            config: catalog.clone(),
+            profiles: config.routing.models.clone(),
+            catalog_ids,
+        })
+    }
+
+    /// Read and parse the catalog file, with its modification time
+    pub(crate) fn load(&self) -> Result<(ModelCatalog, Option<SystemTime>), LlmError> {
+        let catalog =
+            synapse_routing::load_catalog(&self.config.path).map_err(|e| LlmError::Internal(anyhow::anyhow!("{e}")))?;
+
+        Ok((catalog, modified(&self.config.path)))
+    }
+
+    /// Merge the catalog with hand-written profiles and discovered models
+    pub(crate) fn build(&self, catalog: &ModelCatalog, discovered: &HashMap<String, Vec<String>>) -> ModelRegistry {
+        let sources: Vec<CatalogSource<'_>> = self
+            .catalog_ids
+            .iter()
+            .map(|(provider, catalog_id)| CatalogSource {
+                provider,
+                catalog_id,
+                models: discovered.get(provider).map_or(&[], Vec::as_slice),
+            })
+            .collect();
+
+        ModelRegistry::from_catalog(&self.profiles, catalog, &sources, self.config.default_quality)
+    }
+}
+
+/// Build the model registry smart routing starts from
+///
+/// Without a catalog this is the hand-written profiles. With one, the
+/// catalog is merged with the profiles and the models each provider lists
+/// right now, as the gateway does once discovery has run.
+///
+/// # Errors
+///
+/// Returns an error if the catalog cannot be read or parsed
+pub async fn runtime_registry(config: &LlmConfig) -> Result<ModelRegistry, LlmError> {
+    let Some(loader) = CatalogLoader::new(config) else {
+        return Ok(ModelRegistry::from_config(&config.routing.models));
+    };
+
+    let (catalog, _) = loader.load()?;
+    let discovered = crate::discovery::discover_models(config).await;
+    Ok(loader.build(&catalog, &discovered))
+}
+
+/// Start the background task rebuilding the registry on catalog or
+/// discovery changes
+///
+/// The task stops when `shutdown` is cancelled.
+pub(crate) fn start_catalog_reload(
+    loader: CatalogLoader,
+    mut catalog: ModelCatalog,
+    mut catalog_modified: Option<SystemTime>,
+    registry: Arc<SharedRegistry>,
+    known_models: KnownModels,
+    shutdown: CancellationToken,
+) {
+    let interval = Duration::from_secs(loader.config.reload_interval_secs.max(1));
+
+    tokio::spawn(shutdown.run_until_cancelled_owned(async move {
+        let mut ticker = tokio::time::interval(interval);
+        let mut discovered = HashMap::new();
+
+        loop {
+            ticker.tick().await;
+
+            let mut changed = false;
+            let modified = modified(&loader.config.path);
+            if modified != catalog_modified {
+                catalog_modified = modified;
+                match reload(loader.config.path.clone()).await {
+                    Ok(reloaded) => {
+                        tracing::info!(models = reloaded.len(), "reloaded model catalog");
+                        catalog = reloaded;
+                        changed = true;
+                    }
+                    Err(e) => tracing::warn!(error = %e, "failed to reload model catalog, keeping the previous one"),
+                }
+            }
+
+            let latest = known_models.read().await.clone();
+            if latest != discovered {
+                discovered = latest;
+                changed = true;
+            }
+
+            if changed {
+                let rebuilt = loader.build(&catalog, &discovered);
+                tracing::debug!(
+                    profiles = rebuilt.profiles().len(),
+                    "rebuilt model registry from catalog"
+                );
+                registry.replace(rebuilt);
+            }
+        }
+    }));
+}
+
+async fn reload(path: PathBuf) -> anyhow::Result<ModelCatalog> {
+    Ok(tokio::task::spawn_blocking(move || synapse_routing::load_catalog(&path)).await??)
+}
+
+fn modified(path: &std::path::Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|metadata| metadata.modified()).ok()
+}
@@ -0,0 +1,109 @@
+//! Query classification with a cheap LLM
+//!
+//! Backs the classifier routing strategy. Answers are cached by a hash of
+//! the classified prompt so repeated prompts skip the model call, and each
+//! call runs under a strict latency budget.
+
+use std::time::Duration;
+
+use mini_moka::sync::Cache;
+use sha2::{Digest, Sha256};
+use synapse_config::ClassifierConfig;
+use synapse_routing::strategy::classifier::{CLASSIFIER_PROMPT, Classification};
+
+use crate::types::CompletionRequest;
+
+/// Tokens the classifier may generate; the JSON answer is far shorter
+const CLASSIFIER_MAX_TOKENS: u32 = 64;
+
+/// Classifier model, latency budget, and cached classifications
+pub(crate) struct Classifier {
+    pub(crate) provider: String,
+    pub(crate) model: String,
+    pub(crate) timeout: Duration,
+    cache: Cache<[u8; 32], Classification>,
+}
+
+impl Classifier {
+    /// Returns `None` when no classifier model is configured
+    pub(crate) fn new(config: &ClassifierConfig) -> Option<Self> {
+        let (provider, model) = config.model.as_deref()?.split_once('/')?;
+
+        Some(Self {
+            provider: provider.to_owned(),
+            model: model.to_owned(),
+            timeout: Duration::from_millis(config.timeout_ms),
+            cache: Cache::builder()
+                .max_capacity(config.cache_size)
+                .time_to_live(Duration::from_secs(config.cache_ttl_secs))
+                .build(),
+        })
+    }
+
+    /// Cache key for a prompt
+    pub(crate) fn key(prompt: &str) -> [u8; 32] {
+        Sha256::digest(prompt.as_bytes()).into()
+    }
+
+    pub(crate) fn cached(&self, key: &[u8; 32]) -> Option<Classification> {
+        self.cache.get(key)
+    }
+
+    pub(crate) fn remember(&self, key: [u8; 32], classification: Classification) {
+        self.cache.insert(key, classification);
+    }
+
+    /// Request asking the classifier model to label a prompt
+    pub(crate) fn request(&self, prompt: String) -> CompletionRequest {
+        CompletionRequest::instruction(self.model.clone(), CLASSIFIER_PROMPT, prompt, CLASSIFIER_MAX_TOKENS)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use synapse_routing::{Complexity, TaskType};
+
+    use super::*;
+
+    fn classifier() -> Classifier {
+        Classifier::new(&ClassifierConfig {
+            model: Some("openai/gpt-4o-mini".to_owned()),
+            ..ClassifierConfig::default()
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn requires_provider_and_model() {
+        assert!(Classifier::new(&ClassifierConfig::default()).is_none());
+
+        let classifier = classifier();
+        assert_eq!(classifier.provider, "openai");
+        assert_eq!(classifier.model, "gpt-4o-mini");
+    }
+
+    #[test]
+    fn caches_by_prompt_hash() {
+        let classifier = classifier();
+        let classification = Classification {
+            task_type: TaskType::Math,
+            complexity: Complexity::High,
+        };
+
+        classifier.remember(Classifier::key("Prove Fermat's last theorem"), classification);
+        assert_eq!(
+            classifier.cached(&Classifier::key("Prove Fermat's last theorem")),
+            Some(classification)
+        );
+        assert!(classifier.cached(&Classifier::key("What is 2 + 2?")).is_none());
+    }
+
+    #[test]
+    fn request_is_deterministic_and_short() {
+        let request = classifier().request("Write a haiku".to_owned());
+        assert_eq!(request.model, "gpt-4o-mini");
+        assert_eq!(request.messages.len(), 2);
+        assert_eq!(request.params.temperature, Some(0.0));
+        assert_eq!(request.params.max_tokens, Some(CLASSIFIER_MAX_TOKENS));
+    }
+}
@@ -219,6 +219,7 @@ impl From<&CompletionRequest> for AnthropicRequest {
             stream: if req.stream { Some(true) } else { None },
             tools,
             tool_choice,
+            mcp: None,
         }
     }
 }
@@ -645,6 +646,8 @@ pub fn internal_to_anthropic_stream_events(
                 }),
             }]
         }
+        // Sent by the handler as `tool_step` events, outside the Anthropic format
+        StreamEvent::ToolStep(_) => Vec::new(),
         StreamEvent::Done => {
             vec![AnthropicStreamEvent::MessageStop]
         }
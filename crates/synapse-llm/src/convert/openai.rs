@@ -205,6 +205,7 @@ impl From<&CompletionRequest> for OpenAiRequest {
             } else {
                 None
             },
+            mcp: None,
         }
     }
 }
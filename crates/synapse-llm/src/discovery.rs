@@ -36,6 +36,16 @@ pub fn start_discovery(config: LlmConfig, known_models: Arc<RwLock<HashMap<Strin
     });
 }
 
+/// Fetch the model lists of all providers once
+///
+/// Providers whose lists cannot be fetched are left out, as they are by
+/// background discovery.
+pub async fn discover_models(config: &LlmConfig) -> HashMap<String, Vec<String>> {
+    let known_models = Arc::new(RwLock::new(HashMap::new()));
+    refresh_all(&Client::new(), config, &known_models).await;
+    Arc::try_unwrap(known_models).map_or_else(|_| HashMap::new(), RwLock::into_inner)
+}
+
 /// Refresh models from all providers
 async fn refresh_all(client: &Client, config: &LlmConfig, known_models: &Arc<RwLock<HashMap<String, Vec<String>>>>) {
     for (name, provider_config) in &config.providers {
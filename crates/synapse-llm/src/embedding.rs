@@ -0,0 +1,18 @@
+//! Query embeddings for similarity-based routing
+
+use async_trait::async_trait;
+
+use crate::error::LlmError;
+
+/// Number of examples embedded per request when building the kNN index
+pub(crate) const EMBEDDING_BATCH_SIZE: usize = 64;
+
+/// Embeds text for similarity-based routing strategies
+///
+/// Implemented by the server on top of the configured embeddings provider,
+/// so this crate does not depend on the embeddings subsystem.
+#[async_trait]
+pub trait QueryEmbedder: Send + Sync {
+    /// Embed each input, returning one vector per input in order
+    async fn embed(&self, inputs: Vec<String>) -> Result<Vec<Vec<f32>>, LlmError>;
+}
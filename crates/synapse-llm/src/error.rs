@@ -9,6 +9,10 @@ pub enum LlmError {
     #[error("model not found: {model}")]
     ModelNotFound { model: String },
 
+    /// Response is unknown, expired, or already rated
+    #[error("response not found: {id}")]
+    ResponseNotFound { id: String },
+
     /// Named provider does not exist in configuration
     #[error("provider not found: {provider}")]
     ProviderNotFound { provider: String },
@@ -61,7 +65,9 @@ impl LlmError {
 impl HttpError for LlmError {
     fn status_code(&self) -> StatusCode {
         match self {
-            Self::ModelNotFound { .. } | Self::ProviderNotFound { .. } => StatusCode::NOT_FOUND,
+            Self::ModelNotFound { .. } | Self::ProviderNotFound { .. } | Self::ResponseNotFound { .. } => {
+                StatusCode::NOT_FOUND
+            }
             Self::Upstream(_) => StatusCode::BAD_GATEWAY,
             Self::Streaming(_) | Self::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
             Self::InvalidRequest(_) => StatusCode::BAD_REQUEST,
@@ -73,7 +79,9 @@ impl HttpError for LlmError {
 
     fn error_type(&self) -> &str {
         match self {
-            Self::ModelNotFound { .. } | Self::ProviderNotFound { .. } => "not_found_error",
+            Self::ModelNotFound { .. } | Self::ProviderNotFound { .. } | Self::ResponseNotFound { .. } => {
+                "not_found_error"
+            }
             Self::Upstream(_) => "upstream_error",
             Self::Streaming(_) => "streaming_error",
             Self::InvalidRequest(_) => "invalid_request_error",
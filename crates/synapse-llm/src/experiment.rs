@@ -0,0 +1,294 @@
+//! A/B experiments between models
+//!
+//! Splits a virtual model across weighted arms. Assignment hashes the
+//! client or conversation, so a caller stays on the same arm across
+//! requests and replicas. Per-arm outcomes and ratings are recorded in the
+//! routing feedback tracker under an experiment-scoped key so arms can be
+//! compared side by side.
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use synapse_config::{ExperimentArmConfig, ExperimentConfig, ExperimentStickiness};
+use synapse_core::RequestContext;
+use synapse_routing::FeedbackTracker;
+
+use crate::types::CompletionRequest;
+use crate::types::message::Role;
+
+/// Arm a request was assigned to
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExperimentAssignment {
+    /// Experiment name
+    pub experiment: String,
+    /// Arm name
+    pub arm: String,
+    /// Model serving the arm
+    pub model: String,
+}
+
+impl ExperimentAssignment {
+    /// Feedback tracker key ("provider", "model") for this arm
+    pub fn feedback_key(&self) -> (String, String) {
+        feedback_key(&self.experiment, &self.arm)
+    }
+}
+
+/// Configured experiments, keyed by their virtual model name
+pub(crate) struct Experiments {
+    experiments: Vec<ExperimentConfig>,
+}
+
+impl Experiments {
+    pub(crate) const fn new(experiments: Vec<ExperimentConfig>) -> Self {
+        Self { experiments }
+    }
+
+    /// Assign a request for an experiment's virtual model to an arm
+    ///
+    /// Returns `None` when the requested model is not part of an experiment.
+    pub(crate) fn assign(&self, request: &CompletionRequest, context: &RequestContext) -> Option<ExperimentAssignment> {
+        let experiment = self.experiments.iter().find(|e| e.model == request.model)?;
+
+        let client_id = context.client_identity.as_ref().map(|c| c.client_id.as_str());
+        let key = match (experiment.sticky, client_id) {
+            (ExperimentStickiness::Client, Some(client_id)) => format!("client:{client_id}"),
+            _ => format!("conversation:{}", conversation_key(request)),
+        };
+
+        let arm = pick_arm(&experiment.arms, bucket(&experiment.name, &key))?;
+
+        Some(ExperimentAssignment {
+            experiment: experiment.name.clone(),
+            arm: arm.name.clone(),
+            model: arm.model.clone(),
+        })
+    }
+
+    /// Per-arm outcomes of every experiment
+    pub(crate) fn report(&self, feedback: &FeedbackTracker) -> Vec<ExperimentReport> {
+        self.experiments
+            .iter()
+            .map(|experiment| ExperimentReport {
+                name: experiment.name.clone(),
+                model: experiment.model.clone(),
+                arms: experiment
+                    .arms
+                    .iter()
+                    .map(|arm| {
+                        let (provider, model) = feedback_key(&experiment.name, &arm.name);
+                        let observed = feedback.snapshot(&provider, &model);
+                        let latency = feedback.latency_stats(&provider, &model);
+                        let rating = feedback.rating(&provider, &model, None);
+
+                        ArmReport {
+                            name: arm.name.clone(),
+                            model: arm.model.clone(),
+                            weight: arm.weight,
+                            requests: observed.sample_count,
+                            error_rate: observed.error_rate,
+                            latency_p50_ms: latency.as_ref().map(|l| l.p50),
+                            latency_p95_ms: latency.as_ref().map(|l| l.p95),
+                            rating: rating.map(|r| r.mean),
+                            ratings: rating.map_or(0, |r| r.count),
+                        }
+                    })
+                    .collect(),
+            })
+            .collect()
+    }
+}
+
+/// Outcomes of one experiment
+#[derive(Debug, Clone, Serialize)]
+pub struct ExperimentReport {
+    /// Experiment name
+    pub name: String,
+    /// Virtual model name
+    pub model: String,
+    /// Per-arm outcomes
+    pub arms: Vec<ArmReport>,
+}
+
+/// Outcomes of one experiment arm
+#[derive(Debug, Clone, Serialize)]
+pub struct ArmReport {
+    /// Arm name
+    pub name: String,
+    /// Model serving the arm
+    pub model: String,
+    /// Configured traffic weight
+    pub weight: u32,
+    /// Requests served
+    pub requests: usize,
+    /// Fraction of requests that failed
+    pub error_rate: Option<f64>,
+    /// Median latency in milliseconds
+    pub latency_p50_ms: Option<f64>,
+    /// 95th percentile latency in milliseconds
+    pub latency_p95_ms: Option<f64>,
+    /// Mean user rating (0.0 to 1.0)
+    pub rating: Option<f64>,
+    /// Number of user ratings
+    pub ratings: u64,
+}
+
+/// Feedback tracker key for an experiment arm
+fn feedback_key(experiment: &str, arm: &str) -> (String, String) {
+    (format!("experiment:{experiment}"), arm.to_owned())
+}
+
+/// Identify a conversation by its opening system prompt and user turn
+fn conversation_key(request: &CompletionRequest) -> String {
+    let opening = |role: Role| {
+        request
+            .messages
+            .iter()
+            .find(|m| m.role == role)
+            .map(|m| m.content.as_text())
+            .unwrap_or_default()
+    };
+
+    format!("{}\n{}", opening(Role::System), opening(Role::User))
+}
+
+/// Map a sticky key to a stable bucket, independent of process and platform
+fn bucket(experiment: &str, key: &str) -> u64 {
+    let digest = Sha256::new()
+        .chain_update(experiment.as_bytes())
+        .chain_update(b"\0")
+        .chain_update(key.as_bytes())
+        .finalize();
+
+    let mut bytes = [0u8; 8];
+    bytes.copy_from_slice(&digest[..8]);
+    u64::from_be_bytes(bytes)
+}
+
+/// Pick the arm owning a bucket, in proportion to arm weights
+fn pick_arm(arms: &[ExperimentArmConfig], bucket: u64) -> Option<&ExperimentArmConfig> {
+    let total: u64 = arms.iter().map(|arm| u64::from(arm.weight)).sum();
+    if total == 0 {
+        return None;
+    }
+
+    let mut point = bucket % total;
+    arms.iter().find(|arm| {
+        let weight = u64::from(arm.weight);
+        if point < weight {
+            true
+        } else {
+            point -= weight;
+            false
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use synapse_core::ClientIdentity;
+
+    use super::*;
+    use crate::types::CompletionParams;
+    use crate::types::message::{Content, Message};
+
+    fn experiment(sticky: ExperimentStickiness) -> Experiments {
+        Experiments::new(vec![ExperimentConfig {
+            name: "rollout".to_owned(),
+            model: "chat".to_owned(),
+            sticky,
+            arms: vec![
+                ExperimentArmConfig {
+                    name: "control".to_owned(),
+                    model: "openai/gpt-4o".to_owned(),
+                    weight: 50,
+                },
+                ExperimentArmConfig {
+                    name: "candidate".to_owned(),
+                    model: "anthropic/claude-sonnet".to_owned(),
+                    weight: 50,
+                },
+            ],
+        }])
+    }
+
+    fn request(model: &str, prompt: &str) -> CompletionRequest {
+        CompletionRequest {
+            model: model.to_owned(),
+            messages: vec![Message {
+                role: Role::User,
+                content: Content::Text(prompt.to_owned()),
+                name: None,
+                tool_calls: None,
+                tool_call_id: None,
+            }],
+            params: CompletionParams::default(),
+            tools: None,
+            tool_choice: None,
+            stream: false,
+        }
+    }
+
+    fn client(id: &str) -> RequestContext {
+        let mut context = RequestContext::empty();
+        context.client_identity = Some(ClientIdentity {
+            client_id: id.to_owned(),
+            group: None,
+        });
+        context
+    }
+
+    #[test]
+    fn ignores_models_outside_experiments() {
+        let experiments = experiment(ExperimentStickiness::Client);
+        assert!(
+            experiments
+                .assign(&request("gpt-4o", "hi"), &RequestContext::empty())
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn client_assignment_is_sticky() {
+        let experiments = experiment(ExperimentStickiness::Client);
+        let first = experiments
+            .assign(&request("chat", "hello"), &client("user-1"))
+            .unwrap();
+        let second = experiments
+            .assign(&request("chat", "something else"), &client("user-1"))
+            .unwrap();
+        assert_eq!(first, second);
+    }
+
+    #[test]
+    fn conversation_assignment_is_sticky() {
+        let experiments = experiment(ExperimentStickiness::Conversation);
+        let first = experiments
+            .assign(&request("chat", "hello"), &client("user-1"))
+            .unwrap();
+        let second = experiments
+            .assign(&request("chat", "hello"), &client("user-2"))
+            .unwrap();
+        assert_eq!(first, second);
+    }
+
+    #[test]
+    fn splits_traffic_by_weight() {
+        let arms = vec![
+            ExperimentArmConfig {
+                name: "control".to_owned(),
+                model: "a/a".to_owned(),
+                weight: 90,
+            },
+            ExperimentArmConfig {
+                name: "candidate".to_owned(),
+                model: "b/b".to_owned(),
+                weight: 10,
+            },
+        ];
+
+        let candidate = (0..10_000)
+            .filter(|i| pick_arm(&arms, bucket("rollout", &format!("client:{i}"))).unwrap().name == "candidate")
+            .count();
+        assert!((800..1200).contains(&candidate), "candidate share was {candidate}");
+    }
+}
@@ -0,0 +1,346 @@
+//! User quality feedback on served responses
+//!
+//! Remembers which provider, model, and task type produced each response
+//! for a limited time so callers can rate it via `POST /v1/feedback`.
+//! Ratings feed the routing feedback tracker, where they adjust model
+//! quality per task type. With state sync enabled, served responses are
+//! kept in Valkey so any replica can take the rating.
+
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use mini_moka::sync::Cache;
+use serde::{Deserialize, Serialize};
+use synapse_config::StateSyncConfig;
+use synapse_routing::TaskType;
+use tokio::sync::OnceCell;
+
+use crate::error::LlmError;
+use crate::experiment::ExperimentAssignment;
+
+/// Response identifier assigned by the handler before a stream starts
+///
+/// Streaming responses are identified by the handler rather than the
+/// provider, so the handler passes the id in via request extensions.
+#[derive(Debug, Clone)]
+pub struct ResponseId(pub String);
+
+/// Routing details of a response that can still be rated
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct ServedResponse {
+    pub(crate) provider: String,
+    pub(crate) model: String,
+    pub(crate) task_type: TaskType,
+    /// Client that received the response; only it may rate the response
+    pub(crate) client_id: Option<String>,
+    /// Experiment arm that served the response, if any
+    pub(crate) experiment: Option<ExperimentAssignment>,
+    /// When the response was served (unix timestamp seconds)
+    pub(crate) served_at: u64,
+}
+
+impl ServedResponse {
+    /// Whether `client_id` may rate the response
+    fn rateable_by(&self, client_id: Option<&str>) -> bool {
+        self.client_id.is_none() || self.client_id.as_deref() == client_id
+    }
+}
+
+/// Served response saved in a feedback snapshot
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct SavedResponse {
+    pub(crate) response_id: String,
+    #[serde(flatten)]
+    pub(crate) served: ServedResponse,
+}
+
+/// Recently served responses keyed by response id
+///
+/// Each entry is taken at most once: the slot is emptied under its lock,
+/// so concurrent ratings of one response cannot both succeed.
+pub(crate) struct ResponseLog {
+    responses: Cache<String, Arc<Mutex<Option<ServedResponse>>>>,
+    retention: Duration,
+    shared: Option<SharedResponses>,
+}
+
+/// Served responses kept in Valkey for every replica
+struct SharedResponses {
+    client: redis::Client,
+    connection: OnceCell<redis::aio::ConnectionManager>,
+    prefix: String,
+}
+
+impl SharedResponses {
+    fn key(&self, response_id: &str) -> String {
+        format!("{}:response:{response_id}", self.prefix)
+    }
+
+    async fn connection(&self) -> redis::RedisResult<redis::aio::ConnectionManager> {
+        self.connection
+            .get_or_try_init(|| redis::aio::ConnectionManager::new(self.client.clone()))
+            .await
+            .cloned()
+    }
+
+    async fn insert(&self, response_id: &str, served: &ServedResponse, retention: Duration) -> anyhow::Result<()> {
+        let data = serde_json::to_string(served)?;
+        let mut conn = self.connection().await?;
+        let _: () = redis::cmd("SET")
+            .arg(self.key(response_id))
+            .arg(data)
+            .arg("EX")
+            .arg(retention.as_secs().max(1))
+            .query_async(&mut conn)
+            .await?;
+        Ok(())
+    }
+
+    /// Take a response if `client_id` may rate it; only the replica whose
+    /// delete removes the key gets it
+    async fn take(&self, response_id: &str, client_id: Option<&str>) -> anyhow::Result<Option<ServedResponse>> {
+        let key = self.key(response_id);
+        let mut conn = self.connection().await?;
+        let data: Option<String> = redis::cmd("GET").arg(&key).query_async(&mut conn).await?;
+        let Some(data) = data else {
+            return Ok(None);
+        };
+
+        let served: ServedResponse = serde_json::from_str(&data)?;
+        if !served.rateable_by(client_id) {
+            return Ok(None);
+        }
+
+        let deleted: u32 = redis::cmd("DEL").arg(&key).query_async(&mut conn).await?;
+        Ok((deleted == 1).then_some(served))
+    }
+}
+
+impl ResponseLog {
+    pub(crate) fn new(retention: Duration, sync: Option<&StateSyncConfig>) -> Result<Self, redis::RedisError> {
+        let shared = sync
+            .filter(|sync| sync.enabled)
+            .map(|sync| {
+                Ok::<_, redis::RedisError>(SharedResponses {
+                    client: redis::Client::open(sync.url.as_str())?,
+                    connection: OnceCell::new(),
+                    prefix: sync.key_prefix.clone(),
+                })
+            })
+            .transpose()?;
+
+        Ok(Self {
+            responses: Cache::builder().max_capacity(100_000).time_to_live(retention).build(),
+            retention,
+            shared,
+        })
+    }
+
+    pub(crate) async fn insert(&self, response_id: String, served: ServedResponse) {
+        if let Some(ref shared) = self.shared
+            && let Err(e) = shared.insert(&response_id, &served, self.retention).await
+        {
+            tracing::warn!(error = %e, "failed to share served response for feedback");
+        }
+
+        self.responses.insert(response_id, Arc::new(Mutex::new(Some(served))));
+    }
+
+    /// Remove and return a response if `client_id` may rate it, so it can
+    /// only be rated once
+    ///
+    /// Falls back to this replica's responses when Valkey is unreachable.
+    pub(crate) async fn take(&self, response_id: &str, client_id: Option<&str>) -> Option<ServedResponse> {
+        if let Some(ref shared) = self.shared {
+            match shared.take(response_id, client_id).await {
+                Ok(served) => {
+                    if served.is_some() {
+                        self.responses.invalidate(&response_id.to_owned());
+                    }
+                    return served;
+                }
+                Err(e) => tracing::warn!(error = %e, "failed to take shared served response"),
+            }
+        }
+
+        let key = response_id.to_owned();
+        let slot = self.responses.get(&key)?;
+        let served = {
+            let mut slot = slot.lock().unwrap_or_else(PoisonError::into_inner);
+            let expired = slot
+                .as_ref()
+                .is_some_and(|served| served.served_at + self.retention.as_secs() < now_secs());
+            if expired || !slot.as_ref()?.rateable_by(client_id) {
+                return None;
+            }
+            slot.take()
+        };
+        self.responses.invalidate(&key);
+        served
+    }
+
+    /// Responses that can still be rated, for feedback snapshots
+    pub(crate) fn export(&self) -> Vec<SavedResponse> {
+        self.responses
+            .iter()
+            .filter_map(|entry| {
+                let served = entry.value().lock().unwrap_or_else(PoisonError::into_inner).clone()?;
+                Some(SavedResponse {
+                    response_id: entry.key().clone(),
+                    served,
+                })
+            })
+            .collect()
+    }
+
+    /// Restore responses from a snapshot, skipping those past retention
+    pub(crate) fn restore(&self, saved: Vec<SavedResponse>) {
+        let cutoff = now_secs().saturating_sub(self.retention.as_secs());
+        for SavedResponse { response_id, served } in saved {
+            if served.served_at >= cutoff {
+                self.responses.insert(response_id, Arc::new(Mutex::new(Some(served))));
+            }
+        }
+    }
+}
+
+/// Current unix timestamp in seconds
+pub(crate) fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// Thumbs up/down rating
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Thumbs {
+    /// Good response
+    Up,
+    /// Bad response
+    Down,
+}
+
+/// Body of `POST /v1/feedback`
+///
+/// Exactly one of `rating` or `thumbs` must be set.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FeedbackRequest {
+    /// Id of the response being rated
+    pub response_id: String,
+    /// Rating from 1 (worst) to 5 (best)
+    #[serde(default)]
+    pub rating: Option<u8>,
+    /// Thumbs up or down
+    #[serde(default)]
+    pub thumbs: Option<Thumbs>,
+}
+
+impl FeedbackRequest {
+    /// Normalize the rating to 0.0 (worst) to 1.0 (best)
+    pub fn score(&self) -> Result<f64, LlmError> {
+        match (self.rating, self.thumbs) {
+            (Some(rating @ 1..=5), None) => Ok(f64::from(rating - 1) / 4.0),
+            (Some(rating), None) => Err(LlmError::InvalidRequest(format!(
+                "rating must be between 1 and 5, got {rating}"
+            ))),
+            (None, Some(Thumbs::Up)) => Ok(1.0),
+            (None, Some(Thumbs::Down)) => Ok(0.0),
+            _ => Err(LlmError::InvalidRequest(
+                "exactly one of rating or thumbs is required".to_owned(),
+            )),
+        }
+    }
+}
+
+/// Routing decision a rating was attributed to
+#[derive(Debug, Clone, Serialize)]
+pub struct FeedbackReceipt {
+    /// Id of the rated response
+    pub response_id: String,
+    /// Provider that served the response
+    pub provider: String,
+    /// Model that served the response
+    pub model: String,
+    /// Task type the request was classified as
+    pub task_type: TaskType,
+    /// Normalized score recorded (0.0 to 1.0)
+    pub score: f64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(rating: Option<u8>, thumbs: Option<Thumbs>) -> FeedbackRequest {
+        FeedbackRequest {
+            response_id: "chatcmpl-1".to_owned(),
+            rating,
+            thumbs,
+        }
+    }
+
+    #[test]
+    fn normalizes_scores() {
+        assert!((request(Some(1), None).score().unwrap()).abs() < f64::EPSILON);
+        assert!((request(Some(4), None).score().unwrap() - 0.75).abs() < f64::EPSILON);
+        assert!((request(None, Some(Thumbs::Up)).score().unwrap() - 1.0).abs() < f64::EPSILON);
+        assert!((request(None, Some(Thumbs::Down)).score().unwrap()).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn rejects_invalid_ratings() {
+        assert!(request(Some(0), None).score().is_err());
+        assert!(request(Some(6), None).score().is_err());
+        assert!(request(None, None).score().is_err());
+        assert!(request(Some(5), Some(Thumbs::Up)).score().is_err());
+    }
+
+    fn served(client_id: Option<&str>) -> ServedResponse {
+        ServedResponse {
+            provider: "openai".to_owned(),
+            model: "gpt-4o".to_owned(),
+            task_type: TaskType::Code,
+            client_id: client_id.map(str::to_owned),
+            experiment: None,
+            served_at: now_secs(),
+        }
+    }
+
+    #[tokio::test]
+    async fn responses_are_rated_once() {
+        let log = ResponseLog::new(Duration::from_secs(60), None).unwrap();
+        log.insert("chatcmpl-1".to_owned(), served(None)).await;
+
+        assert!(log.take("chatcmpl-1", None).await.is_some());
+        assert!(log.take("chatcmpl-1", None).await.is_none());
+    }
+
+    #[tokio::test]
+    async fn only_the_receiving_client_can_rate() {
+        let log = ResponseLog::new(Duration::from_secs(60), None).unwrap();
+        log.insert("chatcmpl-1".to_owned(), served(Some("alice"))).await;
+
+        assert!(log.take("chatcmpl-1", Some("bob")).await.is_none());
+        assert!(log.take("chatcmpl-1", Some("alice")).await.is_some());
+    }
+
+    #[tokio::test]
+    async fn snapshots_keep_responses_within_retention() {
+        let log = ResponseLog::new(Duration::from_secs(60), None).unwrap();
+        log.insert("chatcmpl-1".to_owned(), served(None)).await;
+
+        let mut saved = log.export();
+        let mut stale = saved[0].clone();
+        stale.response_id = "chatcmpl-2".to_owned();
+        stale.served.served_at -= 120;
+        saved.push(stale);
+
+        let restored = ResponseLog::new(Duration::from_secs(60), None).unwrap();
+        restored.restore(saved);
+        assert!(restored.take("chatcmpl-1", None).await.is_some());
+        assert!(restored.take("chatcmpl-2", None).await.is_none());
+    }
+}
@@ -7,12 +7,15 @@ use axum::response::sse::{Event, KeepAlive, Sse};
 use axum::response::{IntoResponse, Response};
 use axum::{Json, Router, routing};
 use futures_util::{Stream, StreamExt};
+use http::{HeaderName, HeaderValue};
 use synapse_core::RequestContext;
 
 use crate::convert;
 use crate::error::LlmError;
+use crate::feedback::{FeedbackRequest, ResponseId};
 use crate::protocol::anthropic::{AnthropicRequest, AnthropicResponse};
 use crate::protocol::openai::{OpenAiModel, OpenAiModelList, OpenAiRequest, OpenAiResponse};
+use crate::routing::RoutingTrace;
 use crate::state::LlmState;
 use crate::types::{CompletionRequest, StreamEvent};
 
@@ -24,6 +27,12 @@ pub fn llm_router(state: LlmState) -> Router {
         .route("/v1/models", routing::get(openai_list_models))
         // Anthropic-compatible endpoint
         .route("/v1/messages", routing::post(anthropic_messages))
+        // Quality feedback on served responses
+        .route("/v1/feedback", routing::post(submit_feedback))
+        // Smart routing dry run
+        .route("/v1/routing/explain", routing::post(explain_routing))
+        // Per-arm outcomes of A/B experiments
+        .route("/v1/routing/experiments", routing::get(experiment_report))
         .with_state(state)
 }
 
@@ -32,26 +41,47 @@ pub fn llm_router(state: LlmState) -> Router {
 /// Handle `POST /v1/chat/completions`
 async fn openai_chat_completions(
     State(state): State<LlmState>,
-    axum::Extension(context): axum::Extension<RequestContext>,
-    Json(wire_request): Json<OpenAiRequest>,
+    axum::Extension(mut context): axum::Extension<RequestContext>,
+    Json(mut wire_request): Json<OpenAiRequest>,
 ) -> Response {
     let is_stream = wire_request.stream.unwrap_or(false);
+    let tool_loop = wire_request.mcp.take();
     let internal_request: CompletionRequest = wire_request.into();
 
-    if is_stream {
-        match state.complete_stream(internal_request, context).await {
-            Ok((actual_model, stream)) => openai_stream_response(stream, actual_model).into_response(),
+    let trace = RoutingTrace::default();
+    context.parts.extensions.insert(trace.clone());
+
+    let response = if is_stream {
+        let response_id = format!("chatcmpl-{}", uuid_simple());
+        context.parts.extensions.insert(ResponseId(response_id.clone()));
+
+        let result = match tool_loop {
+            Some(ref options) => {
+                state
+                    .complete_stream_with_tools(internal_request, context, options)
+                    .await
+            }
+            None => state.complete_stream(internal_request, context).await,
+        };
+        match result {
+            Ok((actual_model, stream)) => openai_stream_response(stream, actual_model, response_id).into_response(),
             Err(e) => error_to_openai_response(e),
         }
     } else {
-        match state.complete(internal_request, context).await {
+        let result = match tool_loop {
+            Some(ref options) => state.complete_with_tools(internal_request, context, options).await,
+            None => state.complete(internal_request, context).await,
+        };
+        match result {
             Ok(response) => {
                 let wire_response: OpenAiResponse = response.into();
                 Json(wire_response).into_response()
             }
             Err(e) => error_to_openai_response(e),
         }
-    }
+    };
+
+    with_routing_headers(response, &trace)
 }
 
 /// Handle `GET /v1/models`
@@ -85,14 +115,13 @@ async fn openai_list_models(State(state): State<LlmState>) -> Response {
 fn openai_stream_response(
     stream: std::pin::Pin<Box<dyn Stream<Item = Result<StreamEvent, LlmError>> + Send>>,
     model: String,
+    response_id: String,
 ) -> Sse<impl Stream<Item = Result<Event, axum::Error>>> {
     let now = SystemTime::now()
         .duration_since(UNIX_EPOCH)
         .map(|d| d.as_secs())
         .unwrap_or(0);
 
-    let response_id = format!("chatcmpl-{now}");
-
     let event_stream = stream.map(move |result| match result {
         Ok(StreamEvent::Delta(delta)) => {
             let chunk = convert::openai::delta_to_openai_chunk(&delta, &response_id, &model, now);
@@ -104,6 +133,10 @@ fn openai_stream_response(
             let data = serde_json::to_string(&chunk).unwrap_or_default();
             Ok(Event::default().data(data))
         }
+        Ok(StreamEvent::ToolStep(step)) => {
+            let data = serde_json::to_string(&step).unwrap_or_default();
+            Ok(Event::default().event("tool_step").data(data))
+        }
         Ok(StreamEvent::Done) => Ok(Event::default().data("[DONE]")),
         Err(e) => {
             let error_data = serde_json::json!({
@@ -136,41 +169,140 @@ fn error_to_openai_response(error: LlmError) -> Response {
     (status, Json(body)).into_response()
 }
 
+// -- Feedback handler --
+
+/// Handle `POST /v1/feedback`
+async fn submit_feedback(
+    State(state): State<LlmState>,
+    axum::Extension(context): axum::Extension<RequestContext>,
+    Json(feedback): Json<FeedbackRequest>,
+) -> Response {
+    match state.submit_feedback(&feedback, &context).await {
+        Ok(receipt) => Json(receipt).into_response(),
+        Err(e) => error_to_openai_response(e),
+    }
+}
+
+// -- Routing handlers --
+
+/// Handle `POST /v1/routing/explain`
+///
+/// Accepts a chat completion request for a routing class and reports the
+/// routing decision without calling a provider.
+async fn explain_routing(
+    State(state): State<LlmState>,
+    axum::Extension(context): axum::Extension<RequestContext>,
+    Json(wire_request): Json<OpenAiRequest>,
+) -> Response {
+    let internal_request: CompletionRequest = wire_request.into();
+
+    match state.explain_routing(&internal_request, &context).await {
+        Ok(explanation) => Json(explanation).into_response(),
+        Err(e) => error_to_openai_response(e),
+    }
+}
+
+/// Handle `GET /v1/routing/experiments`
+async fn experiment_report(State(state): State<LlmState>) -> Response {
+    Json(state.experiment_report()).into_response()
+}
+
+/// Attach `x-synapse-routing-*` and `x-synapse-experiment-*` headers
+/// summarizing a smart routing decision and experiment assignment
+fn with_routing_headers(mut response: Response, trace: &RoutingTrace) -> Response {
+    let mut values = Vec::new();
+
+    if let Some(summary) = trace.get() {
+        // Report the model that answered, not the routed one it failed over
+        // or escalated from
+        let (provider, model) = trace
+            .served()
+            .unwrap_or_else(|| (summary.provider.clone(), summary.model.clone()));
+        values.extend([
+            ("x-synapse-routing-model", format!("{provider}/{model}")),
+            ("x-synapse-routing-strategy", summary.strategy.clone()),
+            ("x-synapse-routing-reason", summary.reason.as_str().to_owned()),
+            ("x-synapse-routing-task-type", summary.task_type.as_str().to_owned()),
+            ("x-synapse-routing-complexity", summary.complexity.as_str().to_owned()),
+        ]);
+    }
+
+    if let Some(assignment) = trace.experiment() {
+        values.extend([
+            ("x-synapse-experiment", assignment.experiment.clone()),
+            ("x-synapse-experiment-arm", assignment.arm.clone()),
+        ]);
+    }
+
+    let headers = response.headers_mut();
+    for (name, value) in values {
+        if let Ok(value) = HeaderValue::from_str(&value) {
+            headers.insert(HeaderName::from_static(name), value);
+        }
+    }
+
+    response
+}
+
 // -- Anthropic-compatible handler --
 
 /// Handle `POST /v1/messages`
 async fn anthropic_messages(
     State(state): State<LlmState>,
-    axum::Extension(context): axum::Extension<RequestContext>,
-    Json(wire_request): Json<AnthropicRequest>,
+    axum::Extension(mut context): axum::Extension<RequestContext>,
+    Json(mut wire_request): Json<AnthropicRequest>,
 ) -> Response {
     let is_stream = wire_request.stream.unwrap_or(false);
+    let tool_loop = wire_request.mcp.take();
     let internal_request: CompletionRequest = wire_request.into();
 
-    if is_stream {
-        match state.complete_stream(internal_request, context).await {
-            Ok((actual_model, stream)) => anthropic_stream_response(stream, actual_model).into_response(),
+    let trace = RoutingTrace::default();
+    context.parts.extensions.insert(trace.clone());
+
+    let response = if is_stream {
+        let response_id = format!("msg_{}", uuid_simple());
+        context.parts.extensions.insert(ResponseId(response_id.clone()));
+
+        let result = match tool_loop {
+            Some(ref options) => {
+                state
+                    .complete_stream_with_tools(internal_request, context, options)
+                    .await
+            }
+            None => state.complete_stream(internal_request, context).await,
+        };
+        match result {
+            Ok((actual_model, stream)) => anthropic_stream_response(stream, actual_model, response_id).into_response(),
             Err(e) => error_to_anthropic_response(e),
         }
     } else {
-        match state.complete(internal_request, context).await {
+        let result = match tool_loop {
+            Some(ref options) => state.complete_with_tools(internal_request, context, options).await,
+            None => state.complete(internal_request, context).await,
+        };
+        match result {
             Ok(response) => {
                 let wire_response: AnthropicResponse = response.into();
                 Json(wire_response).into_response()
             }
             Err(e) => error_to_anthropic_response(e),
         }
-    }
+    };
+
+    with_routing_headers(response, &trace)
 }
 
 /// Build a streaming SSE response in Anthropic format
 fn anthropic_stream_response(
     stream: std::pin::Pin<Box<dyn Stream<Item = Result<StreamEvent, LlmError>> + Send>>,
     model: String,
+    response_id: String,
 ) -> Sse<impl Stream<Item = Result<Event, axum::Error>>> {
-    let response_id = format!("msg_{}", uuid_simple());
-
     let event_stream = stream.map(move |result| match result {
+        Ok(StreamEvent::ToolStep(step)) => {
+            let data = serde_json::to_string(&step).unwrap_or_default();
+            Ok(Event::default().event("tool_step").data(data))
+        }
         Ok(event) => {
             let anthropic_events =
                 convert::anthropic::internal_to_anthropic_stream_events(&event, &model, &response_id);
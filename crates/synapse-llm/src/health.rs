@@ -3,14 +3,15 @@
 //! Tracks provider health and prevents sending requests to providers
 //! that are consistently failing, allowing them time to recover.
 
-use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering};
 use std::time::{SystemTime, UNIX_EPOCH};
 
 use dashmap::DashMap;
 use synapse_config::CircuitBreakerConfig;
 
 /// Circuit breaker state for a provider
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
 pub enum CircuitState {
     /// Normal operation, requests flow through
     Closed,
@@ -28,6 +29,10 @@ struct ProviderHealth {
     window_start: AtomicU64,
     /// When the circuit was opened (unix timestamp seconds, 0 = not open)
     opened_at: AtomicU64,
+    /// Failures recorded since the last shared-state sync
+    pending_errors: AtomicU32,
+    /// Whether a success was recorded since the last shared-state sync
+    pending_reset: AtomicBool,
 }
 
 impl ProviderHealth {
@@ -36,6 +41,8 @@ impl ProviderHealth {
             error_count: AtomicU32::new(0),
             window_start: AtomicU64::new(now_secs()),
             opened_at: AtomicU64::new(0),
+            pending_errors: AtomicU32::new(0),
+            pending_reset: AtomicBool::new(false),
         }
     }
 }
@@ -90,6 +97,8 @@ impl ProviderHealthTracker {
         health.opened_at.store(0, Ordering::Relaxed);
         health.error_count.store(0, Ordering::Relaxed);
         health.window_start.store(now_secs(), Ordering::Relaxed);
+        health.pending_errors.store(0, Ordering::Relaxed);
+        health.pending_reset.store(true, Ordering::Relaxed);
     }
 
     /// Record a failed request to a provider
@@ -99,6 +108,8 @@ impl ProviderHealthTracker {
             .entry(provider.to_owned())
             .or_insert_with(ProviderHealth::new);
 
+        health.pending_errors.fetch_add(1, Ordering::Relaxed);
+
         let now = now_secs();
         let window_start = health.window_start.load(Ordering::Relaxed);
 
@@ -119,6 +130,82 @@ impl ProviderHealthTracker {
     }
 }
 
+/// Local circuit breaker changes accumulated since the last sync
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HealthDelta {
+    /// Provider name
+    pub provider: String,
+    /// Failures recorded locally since the last sync
+    pub errors: u32,
+    /// Whether a success was recorded (closes the circuit fleet-wide)
+    pub reset: bool,
+}
+
+/// Circuit breaker state shared across gateway replicas
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SharedHealth {
+    /// Errors in the current window across all replicas
+    pub error_count: u32,
+    /// When the circuit was opened (unix timestamp seconds, 0 = not open)
+    pub opened_at: u64,
+}
+
+impl ProviderHealthTracker {
+    /// Circuit breaker configuration this tracker was built with
+    pub const fn config(&self) -> &CircuitBreakerConfig {
+        &self.config
+    }
+
+    /// Drain local changes recorded since the last call
+    ///
+    /// Only providers with pending changes are returned.
+    pub fn drain_pending(&self) -> Vec<HealthDelta> {
+        self.providers
+            .iter()
+            .filter_map(|entry| {
+                let errors = entry.pending_errors.swap(0, Ordering::Relaxed);
+                let reset = entry.pending_reset.swap(false, Ordering::Relaxed);
+                (errors > 0 || reset).then(|| HealthDelta {
+                    provider: entry.key().clone(),
+                    errors,
+                    reset,
+                })
+            })
+            .collect()
+    }
+
+    /// Adopt circuit state aggregated across replicas
+    ///
+    /// Replaces the local error count and open timestamp so every replica
+    /// makes the same circuit decision. A shared error count at or above
+    /// the threshold opens the circuit even if no replica has yet.
+    pub fn apply_shared(&self, provider: &str, shared: SharedHealth) {
+        let health = self
+            .providers
+            .entry(provider.to_owned())
+            .or_insert_with(ProviderHealth::new);
+
+        let was_open = health.opened_at.load(Ordering::Relaxed) != 0;
+        let opened_at = if shared.opened_at == 0 && shared.error_count >= self.config.error_threshold {
+            now_secs()
+        } else {
+            shared.opened_at
+        };
+
+        health.error_count.store(shared.error_count, Ordering::Relaxed);
+        health.opened_at.store(opened_at, Ordering::Relaxed);
+        drop(health);
+
+        if !was_open && opened_at != 0 {
+            tracing::warn!(
+                provider,
+                error_count = shared.error_count,
+                "circuit breaker opened for provider by shared state"
+            );
+        }
+    }
+}
+
 fn now_secs() -> u64 {
     SystemTime::now()
         .duration_since(UNIX_EPOCH)
@@ -176,6 +263,58 @@ mod tests {
         assert!(tracker.is_available("test"));
     }
 
+    #[test]
+    fn drain_pending_reports_and_clears_changes() {
+        let tracker = ProviderHealthTracker::new(test_config());
+        tracker.record_failure("a");
+        tracker.record_failure("a");
+        tracker.record_success("b");
+
+        let mut pending = tracker.drain_pending();
+        pending.sort_by(|x, y| x.provider.cmp(&y.provider));
+        assert_eq!(
+            pending,
+            vec![
+                HealthDelta {
+                    provider: "a".to_owned(),
+                    errors: 2,
+                    reset: false,
+                },
+                HealthDelta {
+                    provider: "b".to_owned(),
+                    errors: 0,
+                    reset: true,
+                },
+            ]
+        );
+        assert!(tracker.drain_pending().is_empty());
+    }
+
+    #[test]
+    fn shared_state_opens_and_closes_circuit() {
+        let tracker = ProviderHealthTracker::new(test_config());
+
+        // Errors reported by other replicas trip the local breaker
+        tracker.apply_shared(
+            "test",
+            SharedHealth {
+                error_count: 3,
+                opened_at: 0,
+            },
+        );
+        assert_eq!(tracker.state("test"), CircuitState::Open);
+
+        // A success elsewhere clears the shared state
+        tracker.apply_shared(
+            "test",
+            SharedHealth {
+                error_count: 0,
+                opened_at: 0,
+            },
+        );
+        assert_eq!(tracker.state("test"), CircuitState::Closed);
+    }
+
     #[test]
     fn independent_provider_tracking() {
         let tracker = ProviderHealthTracker::new(test_config());
@@ -6,22 +6,36 @@
 
 #![allow(clippy::must_use_candidate, clippy::missing_errors_doc)]
 
+pub mod affinity;
+pub mod catalog;
+pub mod classifier;
 pub mod convert;
 pub mod discovery;
+pub mod embedding;
 pub mod error;
+pub mod experiment;
+pub mod feedback;
 #[cfg(feature = "http")]
 pub mod handler;
 pub mod health;
+pub mod persistence;
 pub mod protocol;
 pub mod provider;
 pub mod routing;
+pub mod shadow;
 pub mod state;
+pub mod sync;
+pub mod tool_loop;
+pub mod training;
 pub mod types;
 
+pub use embedding::QueryEmbedder;
 pub use error::LlmError;
+pub use experiment::{ArmReport, ExperimentAssignment, ExperimentReport};
 #[cfg(feature = "http")]
 pub use handler::llm_router;
 pub use provider::{Provider, ProviderCapabilities};
-pub use routing::{ModelRouter, ResolvedModel};
+pub use routing::{ModelRouter, ResolvedModel, RoutingSummary, RoutingTrace};
 pub use state::LlmState;
+pub use tool_loop::{ToolExecutor, ToolLoopRequest, ToolOutput};
 pub use types::{CompletionRequest, CompletionResponse, StreamEvent};
@@ -0,0 +1,259 @@
+//! Routing feedback persistence across restarts
+//!
+//! Periodically snapshots the `FeedbackTracker`, including user ratings,
+//! and the responses still open to rating to a local file or Valkey, and
+//! restores the latest snapshot on startup, decayed by its age.
+
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use synapse_config::{FeedbackPersistenceConfig, FeedbackStorage};
+use synapse_routing::{FeedbackSnapshot, FeedbackTracker};
+
+use crate::feedback::{ResponseLog, SavedResponse};
+
+/// Stored snapshot: routing feedback plus responses that can still be rated
+#[derive(Serialize, Deserialize)]
+struct Snapshot {
+    #[serde(flatten)]
+    feedback: FeedbackSnapshot,
+    #[serde(default)]
+    responses: Vec<SavedResponse>,
+}
+
+/// Restore the most recent feedback snapshot into the tracker and the
+/// response log
+///
+/// A missing or unreadable snapshot is logged and leaves the tracker empty;
+/// it never prevents startup.
+pub(crate) async fn restore_feedback(
+    config: &FeedbackPersistenceConfig,
+    feedback: &FeedbackTracker,
+    responses: Option<&ResponseLog>,
+) {
+    let Snapshot {
+        feedback: snapshot,
+        responses: saved,
+    } = match load(&config.storage).await {
+        Ok(Some(snapshot)) => snapshot,
+        Ok(None) => {
+            tracing::debug!("no routing feedback snapshot to restore");
+            return;
+        }
+        Err(e) => {
+            tracing::warn!(error = %e, "failed to load routing feedback snapshot");
+            return;
+        }
+    };
+
+    let saved_at = snapshot.saved_at;
+    let decayed = snapshot.decayed(
+        now_secs(),
+        Duration::from_secs(config.half_life_secs),
+        Duration::from_secs(config.max_age_secs),
+    );
+    let models = decayed.models.len();
+    feedback.restore(decayed);
+    if let Some(responses) = responses {
+        responses.restore(saved);
+    }
+
+    tracing::info!(saved_at, models, "restored routing feedback snapshot");
+}
+
+/// Start the background snapshot task
+pub(crate) fn start_feedback_snapshots(
+    config: &FeedbackPersistenceConfig,
+    feedback: Arc<FeedbackTracker>,
+    responses: Option<Arc<ResponseLog>>,
+) {
+    let storage = config.storage.clone();
+    let interval = Duration::from_secs(config.interval_secs.max(1));
+
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        // The first tick fires immediately; skip it so a fresh start does
+        // not overwrite the snapshot it just restored with less data
+        ticker.tick().await;
+
+        loop {
+            ticker.tick().await;
+
+            let snapshot = Snapshot {
+                feedback: feedback.export(now_secs()),
+                responses: responses.as_ref().map(|r| r.export()).unwrap_or_default(),
+            };
+            if let Err(e) = save(&storage, &snapshot).await {
+                tracing::warn!(error = %e, "failed to save routing feedback snapshot");
+            } else {
+                tracing::debug!(
+                    models = snapshot.feedback.models.len(),
+                    "saved routing feedback snapshot"
+                );
+            }
+        }
+    });
+}
+
+async fn load(storage: &FeedbackStorage) -> anyhow::Result<Option<Snapshot>> {
+    let data = match storage {
+        FeedbackStorage::File { path } => match tokio::fs::read_to_string(path).await {
+            Ok(data) => data,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        },
+        FeedbackStorage::Cache { url, key } => {
+            let client = redis::Client::open(url.as_str())?;
+            let mut conn = client.get_multiplexed_async_connection().await?;
+            let data: Option<String> = redis::cmd("GET").arg(key).query_async(&mut conn).await?;
+            let Some(data) = data else {
+                return Ok(None);
+            };
+            data
+        }
+    };
+
+    Ok(Some(serde_json::from_str(&data)?))
+}
+
+async fn save(storage: &FeedbackStorage, snapshot: &Snapshot) -> anyhow::Result<()> {
+    let data = serde_json::to_string(snapshot)?;
+
+    match storage {
+        FeedbackStorage::File { path } => {
+            // Write then rename so a crash never leaves a truncated snapshot
+            let tmp = path.with_extension("tmp");
+            tokio::fs::write(&tmp, data).await?;
+            tokio::fs::rename(&tmp, path).await?;
+        }
+        FeedbackStorage::Cache { url, key } => {
+            let client = redis::Client::open(url.as_str())?;
+            let mut conn = client.get_multiplexed_async_connection().await?;
+            let _: () = redis::cmd("SET").arg(key).arg(data).query_async(&mut conn).await?;
+        }
+    }
+
+    Ok(())
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use synapse_routing::{RequestFeedback, TaskType};
+
+    use super::*;
+    use crate::feedback::ServedResponse;
+
+    fn config(path: &Path) -> FeedbackPersistenceConfig {
+        FeedbackPersistenceConfig {
+            enabled: true,
+            storage: FeedbackStorage::File {
+                path: path.to_path_buf(),
+            },
+            interval_secs: 60,
+            half_life_secs: 86_400,
+            max_age_secs: 604_800,
+        }
+    }
+
+    fn tracker() -> FeedbackTracker {
+        let tracker = FeedbackTracker::new();
+        for (ms, success) in [(100, true), (200, true), (300, false), (400, true)] {
+            tracker.record(&RequestFeedback {
+                provider: "openai".to_owned(),
+                model: "gpt-4o".to_owned(),
+                latency: Duration::from_millis(ms),
+                success,
+                input_tokens: None,
+                output_tokens: None,
+            });
+        }
+        tracker.record_rating("openai", "gpt-4o", Some(TaskType::Code), 0.75);
+        tracker.record_rating("openai", "gpt-4o", Some(TaskType::Code), 0.25);
+        tracker
+    }
+
+    #[tokio::test]
+    async fn snapshots_round_trip_through_a_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = config(&dir.path().join("feedback.json"));
+
+        let original = tracker();
+        let responses = ResponseLog::new(Duration::from_secs(60), None).unwrap();
+        responses
+            .insert(
+                "chatcmpl-1".to_owned(),
+                ServedResponse {
+                    provider: "openai".to_owned(),
+                    model: "gpt-4o".to_owned(),
+                    task_type: TaskType::Code,
+                    client_id: None,
+                    experiment: None,
+                    served_at: now_secs(),
+                },
+            )
+            .await;
+        let snapshot = Snapshot {
+            feedback: original.export(now_secs()),
+            responses: responses.export(),
+        };
+        save(&config.storage, &snapshot).await.unwrap();
+
+        let restored = FeedbackTracker::new();
+        let restored_responses = ResponseLog::new(Duration::from_secs(60), None).unwrap();
+        restore_feedback(&config, &restored, Some(&restored_responses)).await;
+
+        let (before, after) = (
+            original.snapshot("openai", "gpt-4o"),
+            restored.snapshot("openai", "gpt-4o"),
+        );
+        assert_eq!(after.sample_count, before.sample_count);
+        assert_eq!(after.latency_p50_ms, before.latency_p50_ms);
+        let (before_rate, after_rate) = (before.error_rate.unwrap(), after.error_rate.unwrap());
+        assert!((after_rate - before_rate).abs() < 0.01);
+
+        let before = original.rating("openai", "gpt-4o", Some(TaskType::Code)).unwrap();
+        let after = restored.rating("openai", "gpt-4o", Some(TaskType::Code)).unwrap();
+        assert_eq!(after.count, before.count);
+        assert!((after.mean - before.mean).abs() < 0.01);
+
+        let served = restored_responses.take("chatcmpl-1", None).await.unwrap();
+        assert_eq!(served.model, "gpt-4o");
+    }
+
+    #[tokio::test]
+    async fn missing_snapshot_leaves_the_tracker_empty() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = config(&dir.path().join("feedback.json"));
+
+        assert!(load(&config.storage).await.unwrap().is_none());
+
+        let tracker = FeedbackTracker::new();
+        restore_feedback(&config, &tracker, None).await;
+        assert_eq!(tracker.snapshot("openai", "gpt-4o").sample_count, 0);
+    }
+
+    #[tokio::test]
+    async fn corrupt_snapshot_is_reported_and_skipped() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("feedback.json");
+        std::fs::write(&path, "{ not json").unwrap();
+        let config = config(&path);
+
+        assert!(load(&config.storage).await.is_err());
+
+        let tracker = FeedbackTracker::new();
+        restore_feedback(&config, &tracker, None).await;
+        assert_eq!(tracker.snapshot("openai", "gpt-4o").sample_count, 0);
+        assert!(tracker.rating("openai", "gpt-4o", Some(TaskType::Code)).is_none());
+    }
+}
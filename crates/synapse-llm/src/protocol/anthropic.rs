@@ -2,6 +2,8 @@
 
 use serde::{Deserialize, Serialize};
 
+use crate::tool_loop::ToolLoopRequest;
+
 // -- Request types --
 
 /// Anthropic messages API request
@@ -37,6 +39,9 @@ pub struct AnthropicRequest {
     /// Tool choice configuration
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub tool_choice: Option<AnthropicToolChoice>,
+    /// Gateway extension: run MCP tool calls server-side
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mcp: Option<ToolLoopRequest>,
 }
 
 /// Anthropic message
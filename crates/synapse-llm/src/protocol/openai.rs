@@ -2,6 +2,8 @@
 
 use serde::{Deserialize, Serialize};
 
+use crate::tool_loop::ToolLoopRequest;
+
 // -- Request types --
 
 /// `OpenAI` chat completion request
@@ -44,6 +46,9 @@ pub struct OpenAiRequest {
     /// Stream options (e.g. `include_usage`)
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub stream_options: Option<OpenAiStreamOptions>,
+    /// Gateway extension: run MCP tool calls server-side
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mcp: Option<ToolLoopRequest>,
 }
 
 /// `OpenAI` stream options
@@ -4,13 +4,16 @@
 //! and discovered model lists.
 
 use std::collections::HashMap;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex, OnceLock, PoisonError};
 
 use regex::Regex;
 use synapse_config::{EquivalenceGroup, LlmConfig};
+use synapse_routing::strategy::onnx::NUM_FEATURES;
+use synapse_routing::{Complexity, RoutingReason, TaskType};
 use tokio::sync::RwLock;
 
 use crate::error::LlmError;
+use crate::experiment::ExperimentAssignment;
 
 /// Resolved target for a model request
 #[derive(Debug, Clone)]
@@ -23,6 +26,73 @@ pub struct ResolvedModel {
     pub explicit_provider: bool,
 }
 
+/// Summary of a smart routing decision
+#[derive(Debug, Clone)]
+pub struct RoutingSummary {
+    /// Strategy that made the decision
+    pub strategy: String,
+    /// Selected provider name
+    pub provider: String,
+    /// Selected model identifier
+    pub model: String,
+    /// Why the model was selected
+    pub reason: RoutingReason,
+    /// Classified task type of the request
+    pub task_type: TaskType,
+    /// Classified complexity of the request
+    pub complexity: Complexity,
+    /// ONNX router features of the request, for training-data export
+    pub features: [f32; NUM_FEATURES],
+}
+
+/// Receives the smart routing decision for a request
+///
+/// Handlers place a trace in the request extensions before completing and
+/// read it afterwards to report the decision, e.g. as response headers.
+/// Stays empty when the request was not smart-routed or assigned to an
+/// experiment.
+#[derive(Debug, Clone, Default)]
+pub struct RoutingTrace {
+    summary: Arc<OnceLock<RoutingSummary>>,
+    experiment: Arc<OnceLock<ExperimentAssignment>>,
+    /// Provider and model that served the response, which differ from the
+    /// routed ones after failover or cascade escalation
+    served: Arc<Mutex<Option<(String, String)>>>,
+}
+
+impl RoutingTrace {
+    /// The recorded routing decision, if any
+    pub fn get(&self) -> Option<&RoutingSummary> {
+        self.summary.get()
+    }
+
+    /// The experiment arm the request was assigned to, if any
+    pub fn experiment(&self) -> Option<&ExperimentAssignment> {
+        self.experiment.get()
+    }
+
+    /// Provider and model that served the response, if it was served
+    pub fn served(&self) -> Option<(String, String)> {
+        self.served.lock().unwrap_or_else(PoisonError::into_inner).clone()
+    }
+
+    /// Record the routing decision; only the first decision is kept
+    pub(crate) fn record(&self, summary: RoutingSummary) {
+        let _ = self.summary.set(summary);
+    }
+
+    /// Record the experiment assignment; only the first assignment is kept
+    pub(crate) fn record_experiment(&self, assignment: ExperimentAssignment) {
+        let _ = self.experiment.set(assignment);
+    }
+
+    /// Record the provider and model that served the response; the latest
+    /// one is kept
+    pub(crate) fn record_served(&self, provider: &str, model: &str) {
+        *self.served.lock().unwrap_or_else(PoisonError::into_inner) = Some((provider.to_owned(), model.to_owned()));
+    }
+}
+
 /// Routing-relevant model configuration extracted from a provider
 #[derive(Debug, Clone, Default)]
 struct ProviderModelConfig {
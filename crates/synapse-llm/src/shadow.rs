@@ -0,0 +1,256 @@
+//! Shadow traffic mirroring
+//!
+//! Replays a sample of requests against a candidate model after the primary
+//! response has returned and appends both results to a JSONL file for
+//! offline comparison. Mirroring runs in the background and never delays or
+//! alters the client response. Requests containing PII are never mirrored.
+
+use std::io::Write;
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::Serialize;
+use synapse_config::{ShadowConfig, ShadowMirrorConfig};
+use synapse_guardrails::{Action, GuardrailEngine, Rule};
+use synapse_routing::ModelRegistry;
+use tokio::sync::mpsc;
+
+use crate::error::LlmError;
+use crate::types::{CompletionRequest, CompletionResponse};
+
+/// Records buffered for the sink before new ones are dropped
+const SINK_CAPACITY: usize = 1024;
+
+/// Marks the context of a shadow request
+///
+/// Shadow requests are routed without conversation affinity, so they
+/// neither follow nor overwrite the primary's pinned model.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct ShadowTraffic;
+
+/// Configured mirrors and the JSONL sink they write to
+pub(crate) struct Shadow {
+    mirrors: Vec<ShadowMirrorConfig>,
+    pii: GuardrailEngine,
+    sink: mpsc::Sender<ShadowRecord>,
+}
+
+impl Shadow {
+    /// Compile the PII filter and start the sink writer
+    pub(crate) fn new(config: &ShadowConfig) -> Result<Self, LlmError> {
+        let (sink, records) = mpsc::channel(SINK_CAPACITY);
+        let shadow = Self::with_sink(config, sink)?;
+        start_sink(config.sink.clone(), records);
+
+        tracing::info!(
+            mirrors = config.mirrors.len(),
+            sink = %config.sink.display(),
+            "shadow mirroring enabled"
+        );
+
+        Ok(shadow)
+    }
+
+    fn with_sink(config: &ShadowConfig, sink: mpsc::Sender<ShadowRecord>) -> Result<Self, LlmError> {
+        let pii = GuardrailEngine::new(&[Rule::Pii {
+            name: "shadow_pii".to_owned(),
+            detect: config.skip_pii.clone(),
+            action: Action::Block,
+        }])
+        .map_err(|e| LlmError::Internal(anyhow::anyhow!("failed to compile shadow PII filter: {e}")))?;
+
+        Ok(Self {
+            mirrors: config.mirrors.clone(),
+            pii,
+            sink,
+        })
+    }
+
+    /// Pick the mirror for a request, if it is sampled and free of PII
+    pub(crate) fn select(&self, request: &CompletionRequest) -> Option<ShadowMirrorConfig> {
+        let mirror = self.mirrors.iter().find(|m| m.model == request.model)?;
+
+        if rand::random::<f64>() >= mirror.sample_rate {
+            return None;
+        }
+
+        let content = request
+            .messages
+            .iter()
+            .map(|m| m.content.as_text())
+            .collect::<Vec<_>>()
+            .join("\n");
+        if self.pii.check(&content).blocked {
+            tracing::debug!(model = %request.model, "request contains PII, not mirroring");
+            return None;
+        }
+
+        Some(mirror.clone())
+    }
+
+    /// Queue a comparison record for the sink, dropping it if the sink is behind
+    pub(crate) fn record(&self, record: ShadowRecord) {
+        if self.sink.try_send(record).is_err() {
+            tracing::warn!("shadow sink is full or closed, dropping record");
+        }
+    }
+}
+
+/// One mirrored request: the primary result next to the shadow result
+#[derive(Debug, Serialize)]
+pub(crate) struct ShadowRecord {
+    /// Unix timestamp in seconds
+    pub(crate) timestamp: u64,
+    /// Requested model or routing class
+    pub(crate) requested_model: String,
+    /// Result the client received
+    pub(crate) primary: ShadowOutcome,
+    /// Result of the shadow model
+    pub(crate) shadow: ShadowOutcome,
+}
+
+impl ShadowRecord {
+    pub(crate) fn new(requested_model: String, primary: ShadowOutcome, shadow: ShadowOutcome) -> Self {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+
+        Self {
+            timestamp,
+            requested_model,
+            primary,
+            shadow,
+        }
+    }
+}
+
+/// Output, latency, and token cost of one completion
+#[derive(Debug, Serialize)]
+pub(crate) struct ShadowOutcome {
+    provider: String,
+    model: String,
+    latency_ms: u64,
+    input_tokens: Option<u32>,
+    output_tokens: Option<u32>,
+    cost_usd: Option<f64>,
+    output: Option<String>,
+    error: Option<String>,
+}
+
+impl ShadowOutcome {
+    pub(crate) fn new(
+        provider: &str,
+        model: &str,
+        latency: Duration,
+        result: Result<&CompletionResponse, &LlmError>,
+        registry: &ModelRegistry,
+    ) -> Self {
+        let (response, error) = match result {
+            Ok(response) => (Some(response), None),
+            Err(e) => (None, Some(e.to_string())),
+        };
+        let usage = response.and_then(|r| r.usage.as_ref());
+
+        Self {
+            provider: provider.to_owned(),
+            model: model.to_owned(),
+            latency_ms: u64::try_from(latency.as_millis()).unwrap_or(u64::MAX),
+            input_tokens: usage.map(|u| u.prompt_tokens),
+            output_tokens: usage.map(|u| u.completion_tokens),
+            cost_usd: usage.and_then(|u| {
+                registry
+                    .find(provider, model)
+                    .map(|p| p.estimate_cost(u.prompt_tokens as usize, u.completion_tokens as usize))
+            }),
+            output: response
+                .and_then(|r| r.choices.first())
+                .and_then(|c| c.message.content.clone()),
+            error,
+        }
+    }
+}
+
+/// Append records to the sink file as JSON lines on a blocking thread
+fn start_sink(path: PathBuf, mut records: mpsc::Receiver<ShadowRecord>) {
+    tokio::task::spawn_blocking(move || {
+        let mut file = match std::fs::OpenOptions::new().create(true).append(true).open(&path) {
+            Ok(file) => file,
+            Err(e) => {
+                tracing::error!(path = %path.display(), error = %e, "failed to open shadow sink");
+                return;
+            }
+        };
+
+        while let Some(record) = records.blocking_recv() {
+            let Ok(mut line) = serde_json::to_vec(&record) else {
+                continue;
+            };
+            line.push(b'\n');
+
+            if let Err(e) = file.write_all(&line) {
+                tracing::warn!(path = %path.display(), error = %e, "failed to write shadow record");
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use synapse_guardrails::PiiType;
+
+    use super::*;
+    use crate::types::CompletionParams;
+    use crate::types::message::{Content, Message, Role};
+
+    fn shadow(sample_rate: f64) -> Shadow {
+        let config = ShadowConfig {
+            enabled: true,
+            sink: PathBuf::from("shadow.jsonl"),
+            skip_pii: vec![PiiType::Email],
+            mirrors: vec![ShadowMirrorConfig {
+                model: "auto".to_owned(),
+                shadow_model: "anthropic/claude-haiku".to_owned(),
+                sample_rate,
+            }],
+        };
+        let (sink, _records) = mpsc::channel(1);
+        Shadow::with_sink(&config, sink).unwrap()
+    }
+
+    fn request(model: &str, prompt: &str) -> CompletionRequest {
+        CompletionRequest {
+            model: model.to_owned(),
+            messages: vec![Message {
+                role: Role::User,
+                content: Content::Text(prompt.to_owned()),
+                name: None,
+                tool_calls: None,
+                tool_call_id: None,
+            }],
+            params: CompletionParams::default(),
+            tools: None,
+            tool_choice: None,
+            stream: false,
+        }
+    }
+
+    #[test]
+    fn mirrors_matching_models() {
+        let shadow = shadow(1.0);
+        let mirror = shadow.select(&request("auto", "Summarize this article")).unwrap();
+        assert_eq!(mirror.shadow_model, "anthropic/claude-haiku");
+        assert!(shadow.select(&request("gpt-4o", "Summarize this article")).is_none());
+    }
+
+    #[test]
+    fn respects_sample_rate() {
+        assert!(shadow(0.0).select(&request("auto", "Summarize this article")).is_none());
+    }
+
+    #[test]
+    fn skips_requests_with_pii() {
+        let shadow = shadow(1.0);
+        assert!(shadow.select(&request("auto", "Email jane@example.com")).is_none());
+    }
+}
@@ -3,24 +3,42 @@
 use std::collections::HashMap;
 use std::pin::Pin;
 use std::sync::Arc;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
 use futures_util::{Stream, StreamExt};
 use secrecy::SecretString;
-use synapse_config::{FailoverConfig, LlmConfig, LlmProviderType, RoutingConfig};
+use synapse_config::{CascadeVerifierConfig, FailoverConfig, LlmConfig, LlmProviderType, RoutingConfig};
 use synapse_core::RequestContext;
-use synapse_routing::{FeedbackTracker, ModelRegistry, RequestFeedback, StrategyRegistry};
-
+use synapse_routing::strategy::classifier::Classification;
+use synapse_routing::strategy::onnx;
+use synapse_routing::{
+    FeedbackTracker, ModelRegistry, QueryProfile, RequestFeedback, RoutingClient, RoutingExplanation, RoutingReason,
+    StrategyRegistry,
+};
+use tokio_util::sync::CancellationToken;
+
+use crate::affinity::{Affinity, PinnedModel};
+use crate::catalog::{CatalogLoader, SharedRegistry};
+use crate::classifier::Classifier;
 use crate::discovery;
+use crate::embedding::{EMBEDDING_BATCH_SIZE, QueryEmbedder};
 use crate::error::LlmError;
-use crate::health::ProviderHealthTracker;
+use crate::experiment::{ExperimentAssignment, ExperimentReport, Experiments};
+use crate::feedback::{FeedbackReceipt, FeedbackRequest, ResponseId, ResponseLog, ServedResponse, now_secs};
+use crate::health::{CircuitState, ProviderHealthTracker};
 use crate::provider::Provider;
-use crate::routing::ModelRouter;
-use crate::types::{CompletionRequest, CompletionResponse, StreamEvent};
+use crate::routing::{ModelRouter, RoutingSummary, RoutingTrace};
+use crate::shadow::{Shadow, ShadowOutcome, ShadowRecord, ShadowTraffic};
+use crate::tool_loop::{ToolLoop, ToolTurns};
+use crate::training::{TrainingSample, TrainingSink};
+use crate::types::{CompletionRequest, CompletionResponse, StreamEvent, Usage};
 
 /// Virtual model names that trigger smart routing
 pub(crate) const ROUTING_CLASSES: &[&str] = &["auto", "fast", "best", "cheap"];
 
+/// Tokens the cascade verifier may generate; the JSON grade is far shorter
+const VERIFIER_MAX_TOKENS: u32 = 32;
+
 /// Shared state for LLM route handlers
 #[derive(Clone)]
 pub struct LlmState {
@@ -30,12 +48,27 @@ pub struct LlmState {
 pub(crate) struct LlmStateInner {
     pub(crate) router: ModelRouter,
     pub(crate) providers: HashMap<String, Arc<dyn Provider>>,
-    pub(crate) health: ProviderHealthTracker,
+    pub(crate) health: Arc<ProviderHealthTracker>,
     pub(crate) failover: FailoverConfig,
     pub(crate) routing_config: RoutingConfig,
-    pub(crate) model_registry: ModelRegistry,
+    /// Model profiles, rebuilt when the model catalog changes
+    pub(crate) model_registry: Arc<SharedRegistry>,
     pub(crate) strategy_registry: StrategyRegistry,
-    pub(crate) feedback: FeedbackTracker,
+    /// Embeds queries for similarity-based routing, when enabled
+    pub(crate) query_embedder: Option<Arc<dyn QueryEmbedder>>,
+    /// Cheap model classifying queries for classifier routing, when configured
+    pub(crate) classifier: Option<Classifier>,
+    /// Models pinned to conversations, when conversation affinity is enabled
+    pub(crate) affinity: Option<Affinity>,
+    pub(crate) feedback: Arc<FeedbackTracker>,
+    /// Recently served responses open to user rating, when enabled
+    pub(crate) responses: Option<Arc<ResponseLog>>,
+    /// A/B experiments splitting virtual models across arms
+    pub(crate) experiments: Experiments,
+    /// Shadow traffic mirroring, when enabled
+    pub(crate) shadow: Option<Shadow>,
+    /// Routing training-data export, when enabled
+    pub(crate) training: Option<TrainingSink>,
     /// Managed provider keys (provider name → API key) for managed billing mode
     pub(crate) managed_keys: HashMap<String, SecretString>,
     /// Managed provider margins (provider name → margin multiplier)
@@ -51,6 +84,10 @@ pub(crate) struct LlmStateInner {
     /// Response cache (cache feature only)
     #[cfg(feature = "cache")]
     pub(crate) response_cache: Option<synapse_cache::ResponseCache>,
+    /// Runs gateway tools for completions that opt in, when enabled
+    pub(crate) tool_loop: Option<ToolLoop>,
+    /// Cancelled on shutdown to stop background tasks
+    pub(crate) shutdown: CancellationToken,
 }
 
 impl LlmState {
@@ -60,15 +97,44 @@ impl LlmState {
     /// # Errors
     ///
     /// Returns an error if model resolution or all provider attempts fail
-    #[allow(clippy::cognitive_complexity)]
     pub async fn complete(
         &self,
         request: CompletionRequest,
+        context: RequestContext,
+    ) -> Result<CompletionResponse, LlmError> {
+        self.complete_routed(request, context, None).await
+    }
+
+    /// Route and execute a non-streaming completion, running gateway tool
+    /// turns on the served model when `tools` is set
+    ///
+    /// Experiments, shadow traffic, training samples, and metering see the
+    /// request once, however many tool turns it takes.
+    #[allow(clippy::cognitive_complexity, clippy::too_many_lines)]
+    pub(crate) async fn complete_routed(
+        &self,
+        mut request: CompletionRequest,
         mut context: RequestContext,
+        tools: Option<ToolTurns>,
     ) -> Result<CompletionResponse, LlmError> {
-        // Check response cache for deterministic requests
+        // BYOK callers pay their own providers, so never spend their keys on shadow traffic
+        let byok = context
+            .billing_identity
+            .as_ref()
+            .is_some_and(|identity| identity.mode == synapse_core::BillingMode::Byok);
+        let mirror = self
+            .inner
+            .shadow
+            .as_ref()
+            .filter(|_| !byok)
+            .and_then(|shadow| shadow.select(&request));
+        let requested_model = request.model.clone();
+        let experiment = self.assign_experiment(&mut request, &mut context);
+        let training_trace = self.training_trace(&mut context);
+
+        // Check response cache for deterministic requests; tool outputs are not
         #[cfg(feature = "cache")]
-        let cache_key = if synapse_cache::is_cacheable(request.params.temperature, request.stream) {
+        let cache_key = if tools.is_none() && synapse_cache::is_cacheable(request.params.temperature, request.stream) {
             if let Some(ref cache) = self.inner.response_cache {
                 let key = synapse_cache::compute_cache_key(&request);
                 match cache.get(&key).await {
@@ -76,6 +142,11 @@ impl LlmState {
                         tracing::info!("serving cached response");
                         let response: CompletionResponse = serde_json::from_str(&cached.body)
                             .map_err(|e| LlmError::Internal(anyhow::anyhow!("cache deserialization: {e}")))?;
+                        if let Some(trace) = context.parts.extensions.get::<RoutingTrace>() {
+                            trace.record_served(&cached.provider, &cached.model);
+                        }
+                        self.remember_response(&response.id, &cached.provider, &cached.model, &request, &context)
+                            .await;
                         return Ok(response);
                     }
                     Ok(None) => {}
@@ -91,7 +162,7 @@ impl LlmState {
             None
         };
 
-        let (provider_name, model_id, provider, explicit_provider) =
+        let (mut provider_name, mut model_id, provider, explicit_provider) =
             self.resolve_provider(&request.model, &request, &context).await?;
 
         // Resolve API key based on billing mode
@@ -109,57 +180,61 @@ impl LlmState {
         // Skip failover when the user explicitly selected a provider (e.g.
         // "nvidia/moonshotai/kimi-k2.5") — surface the error instead of
         // silently routing to a different model
-        let response = if explicit_provider {
+        let started = Instant::now();
+        let result = if explicit_provider {
             self.complete_direct(&request, &context, &provider_name, &model_id, &provider)
-                .await?
+                .await
+                .map(|response| (provider_name.clone(), model_id.clone(), response))
+        } else if self.is_cascade_strategy(&request.model) {
+            self.complete_with_cascade(
+                &request,
+                &context,
+                &provider_name,
+                &model_id,
+                &provider,
+                &self.inner.routing_config.cascade,
+            )
+            .await
         } else {
             self.complete_with_failover(&request, &context, &provider_name, &model_id, &provider)
-                .await?
+                .await
         };
-
-        // Record usage for billing
-        #[cfg(feature = "billing")]
-        if let Some(ref recorder) = self.inner.usage_recorder
-            && let Some(ref usage) = response.usage
+        let result = match (result, tools) {
+            (Ok(served), Some(tools)) => {
+                self.run_tool_turns(&request, &context, served, explicit_provider, tools)
+                    .await
+            }
+            (result, _) => result,
+        };
+        // Everything below is attributed to the model that served the
+        // response, which differs from the routed one after failover
+        let result = result.map(|(served_provider, served_model, response)| {
+            provider_name = served_provider;
+            model_id = served_model;
+            response
+        });
+        if result.is_ok()
+            && let Some(trace) = context.parts.extensions.get::<RoutingTrace>()
         {
-            dispatch_usage_event(
-                recorder,
-                &context,
-                &provider_name,
-                &model_id,
-                usage.prompt_tokens,
-                usage.completion_tokens,
-                &self.inner.model_registry,
-                &self.inner.managed_margins,
-                &self.inner.tier_margins,
-            );
+            trace.record_served(&provider_name, &model_id);
         }
+        let latency = started.elapsed();
 
-        // Post-completion credit deduction based on actual usage
-        #[cfg(feature = "billing")]
-        if let Some(ref usage) = response.usage {
-            self.deduct_credits_for_usage(
-                &context,
-                &provider_name,
-                &model_id,
-                usage.prompt_tokens,
-                usage.completion_tokens,
-            )
+        if let Some(ref assignment) = experiment {
+            let usage = result.as_ref().ok().and_then(|r| r.usage.as_ref());
+            self.record_experiment_outcome(assignment, latency, result.is_ok(), usage);
+        }
+        let response = result?;
+
+        self.remember_response(&response.id, &provider_name, &model_id, &request, &context)
             .await;
+
+        if let Some(sample) = self.training_sample(training_trace.as_ref(), &response.id, &provider_name, &model_id) {
+            self.record_training_sample(sample.with_outcome(latency, response.usage.as_ref()));
         }
 
-        // Report usage to synapse-api for dashboard charts
         if let Some(ref usage) = response.usage {
-            dispatch_usage_report(
-                &context,
-                &provider_name,
-                &model_id,
-                usage.prompt_tokens,
-                usage.completion_tokens,
-                &self.inner.model_registry,
-                &self.inner.managed_margins,
-                &self.inner.tier_margins,
-            );
+            self.meter_usage(&context, &provider_name, &model_id, usage).await;
         }
 
         // Store successful response in cache
@@ -178,6 +253,18 @@ impl LlmState {
             }
         }
 
+        // Mirror to the shadow model now that the client response is settled
+        if let Some(mirror) = mirror {
+            let primary = ShadowOutcome::new(
+                &provider_name,
+                &model_id,
+                latency,
+                Ok(&response),
+                &self.inner.model_registry.current(),
+            );
+            self.spawn_shadow(mirror.shadow_model, requested_model, request, &context, primary);
+        }
+
         Ok(response)
     }
 
@@ -187,11 +274,31 @@ impl LlmState {
     /// # Errors
     ///
     /// Returns an error if model resolution or all provider attempts fail
-    #[allow(clippy::too_many_lines)]
     pub async fn complete_stream(
         &self,
         request: CompletionRequest,
+        context: RequestContext,
+    ) -> Result<
+        (
+            String,
+            Pin<Box<dyn Stream<Item = Result<StreamEvent, LlmError>> + Send>>,
+        ),
+        LlmError,
+    > {
+        self.complete_stream_routed(request, context, None).await
+    }
+
+    /// Route and execute a streaming completion, streaming gateway tool
+    /// turns on the served model when `tools` is set
+    ///
+    /// Experiments, training samples, and metering see the request once;
+    /// the tool loop reports usage summed over its turns as one event.
+    #[allow(clippy::too_many_lines)]
+    pub(crate) async fn complete_stream_routed(
+        &self,
+        mut request: CompletionRequest,
         mut context: RequestContext,
+        tools: Option<ToolTurns>,
     ) -> Result<
         (
             String,
@@ -199,6 +306,8 @@ impl LlmState {
         ),
         LlmError,
     > {
+        let experiment = self.assign_experiment(&mut request, &mut context);
+        let training_trace = self.training_trace(&mut context);
         let original_model = request.model.clone();
         let (provider_name, model_id, provider, explicit_provider) =
             self.resolve_provider(&request.model, &request, &context).await?;
@@ -218,9 +327,10 @@ impl LlmState {
         // Skip failover/cascade when the user explicitly selected a
         // provider — surface the error instead of silently routing to a
         // different model
-        let (actual_model, stream) = if explicit_provider {
+        let started = Instant::now();
+        let result = if explicit_provider {
             self.complete_stream_direct(&request, &context, &provider_name, &model_id, &provider)
-                .await?
+                .await
         } else if self.is_cascade_strategy(&original_model) {
             self.complete_stream_with_cascade(
                 &request,
@@ -230,12 +340,61 @@ impl LlmState {
                 &provider,
                 &self.inner.routing_config.cascade,
             )
-            .await?
+            .await
         } else {
             self.complete_stream_with_failover(&request, &context, &provider_name, &model_id, &provider)
-                .await?
+                .await
         };
 
+        // Stream outcomes are recorded at initiation, like provider feedback
+        if let Some(ref assignment) = experiment {
+            self.record_experiment_outcome(assignment, started.elapsed(), result.is_ok(), None);
+        }
+        let (provider_name, model_id, stream) = result?;
+        let stream = match tools {
+            Some(tools) => self.stream_tool_turns(
+                stream,
+                &request,
+                &context,
+                (&provider_name, &model_id),
+                explicit_provider,
+                tools,
+            ),
+            None => stream,
+        };
+        let actual_model = model_id.clone();
+        if let Some(trace) = context.parts.extensions.get::<RoutingTrace>() {
+            trace.record_served(&provider_name, &model_id);
+        }
+
+        if let Some(ResponseId(response_id)) = context.parts.extensions.get::<ResponseId>() {
+            self.remember_response(response_id, &provider_name, &model_id, &request, &context)
+                .await;
+        }
+
+        // Export the training sample once the stream reports usage
+        let response_id = context
+            .parts
+            .extensions
+            .get::<ResponseId>()
+            .map_or_else(|| uuid::Uuid::new_v4().to_string(), |ResponseId(id)| id.clone());
+        let stream: Pin<Box<dyn Stream<Item = Result<StreamEvent, LlmError>> + Send>> =
+            match self.training_sample(training_trace.as_ref(), &response_id, &provider_name, &model_id) {
+                Some(sample) => {
+                    let state = self.clone();
+                    let mut sample = Some(sample);
+                    Box::pin(stream.map(move |item| {
+                        if let Ok(StreamEvent::Usage(ref usage)) = item
+                            && let Some(sample) = sample.take()
+                        {
+                            state.record_training_sample(sample.with_outcome(started.elapsed(), Some(usage)));
+                        }
+                        item
+                    }))
+                }
+                None => stream,
+            };
+
         // Wrap stream to intercept usage events for billing and reporting
         let usage_reporter = context.parts.extensions.get::<synapse_auth::UsageReporter>().cloned();
         let resolved_key = context.parts.extensions.get::<synapse_auth::ResolvedKey>().cloned();
@@ -249,6 +408,7 @@ impl LlmState {
             let mdl = model_id.clone();
             let reporter = usage_reporter;
             let resolved = resolved_key;
+            let experiment = experiment.clone();
 
             let billing_client = inner.billing_client.clone();
             let metered_stream = stream.map(move |item| {
@@ -258,9 +418,10 @@ impl LlmState {
                         &ctx,
                         &prov,
                         &mdl,
+                        experiment.as_ref(),
                         usage.prompt_tokens,
                         usage.completion_tokens,
-                        &inner.model_registry,
+                        &inner.model_registry.current(),
                         &inner.managed_margins,
                         &inner.tier_margins,
                     );
@@ -274,7 +435,7 @@ impl LlmState {
                             &mdl,
                             usage.prompt_tokens,
                             usage.completion_tokens,
-                            &inner.model_registry,
+                            &inner.model_registry.current(),
                             &inner.managed_margins,
                             &inner.tier_margins,
                         );
@@ -289,9 +450,10 @@ impl LlmState {
                             resolved,
                             &prov,
                             &mdl,
+                            experiment.as_ref(),
                             usage.prompt_tokens,
                             usage.completion_tokens,
-                            &inner.model_registry,
+                            &inner.model_registry.current(),
                             &inner.managed_margins,
                             &inner.tier_margins,
                         );
@@ -316,9 +478,10 @@ impl LlmState {
                         &resolved,
                         &prov,
                         &mdl,
+                        experiment.as_ref(),
                         usage.prompt_tokens,
                         usage.completion_tokens,
-                        &inner.model_registry,
+                        &inner.model_registry.current(),
                         &inner.managed_margins,
                         &inner.tier_margins,
                     );
@@ -337,38 +500,84 @@ impl LlmState {
     /// # Errors
     ///
     /// Returns an error if any provider fails to initialize.
+    #[allow(clippy::too_many_lines)]
     pub async fn from_config(config: LlmConfig) -> Result<Self, LlmError> {
-        let mut providers: HashMap<String, Arc<dyn Provider>> = HashMap::new();
-
-        for (name, provider_config) in &config.providers {
-            let provider: Arc<dyn Provider> = match &provider_config.provider_type {
-                LlmProviderType::Openai => Arc::new(crate::provider::openai::OpenAiProvider::new(
-                    name.clone(),
-                    provider_config,
-                )?),
-                LlmProviderType::Anthropic => Arc::new(crate::provider::anthropic::AnthropicProvider::new(
-                    name.clone(),
-                    provider_config,
-                )?),
-                LlmProviderType::Google => Arc::new(crate::provider::google::GoogleProvider::new(
-                    name.clone(),
-                    provider_config,
-                )?),
-                LlmProviderType::Bedrock(_) => {
-                    Arc::new(crate::provider::bedrock::BedrockProvider::new(name.clone(), provider_config).await?)
-                }
-            };
-
-            providers.insert(name.clone(), provider);
-        }
-
-        let health = ProviderHealthTracker::new(config.failover.circuit_breaker.clone());
+        let providers = build_providers(&config).await?;
+        let health = Arc::new(ProviderHealthTracker::new(config.failover.circuit_breaker.clone()));
         let failover = config.failover.clone();
         let routing_config = config.routing.clone();
-        let model_registry = ModelRegistry::from_config(&config.routing.models);
-        let strategy_registry = StrategyRegistry::from_config(&config.routing);
+        let catalog = CatalogLoader::new(&config)
+            .map(|loader| loader.load().map(|(catalog, modified)| (loader, catalog, modified)))
+            .transpose()?;
+        let model_registry = Arc::new(SharedRegistry::new(catalog.as_ref().map_or_else(
+            || ModelRegistry::from_config(&config.routing.models),
+            |(loader, catalog, _)| loader.build(catalog, &HashMap::new()),
+        )));
+        let mut strategy_registry = StrategyRegistry::from_config(&config.routing);
+        for script in &config.routing.scripts {
+            let strategy = synapse_routing::strategy::script::ScriptStrategy::load(script)
+                .map_err(|e| LlmError::Internal(anyhow::anyhow!("{e}")))?;
+            strategy_registry.register(&script.name, Box::new(strategy));
+        }
+        let classifier = Classifier::new(&config.routing.classifier);
+        let affinity = config
+            .routing
+            .affinity
+            .enabled
+            .then(|| Affinity::new(&config.routing.affinity))
+            .transpose()?;
         let router = ModelRouter::new(&config);
-        let feedback = FeedbackTracker::new();
+        let feedback = Arc::new(FeedbackTracker::new());
+        let experiments = Experiments::new(config.routing.experiments.clone());
+        let (shadow, training) = build_exports(&config)?;
+        let responses = config
+            .routing
+            .user_feedback
+            .enabled
+            .then(|| {
+                ResponseLog::new(
+                    Duration::from_secs(config.routing.user_feedback.retention_secs),
+                    config.state_sync.as_ref(),
+                )
+                .map(Arc::new)
+            })
+            .transpose()
+            .map_err(|e| LlmError::Internal(anyhow::anyhow!("failed to initialize response log: {e}")))?;
+
+        // Restore persisted feedback so scoring does not restart cold
+        if let Some(ref persistence_config) = config.feedback_persistence
+            && persistence_config.enabled
+        {
+            crate::persistence::restore_feedback(persistence_config, &feedback, responses.as_deref()).await;
+            crate::persistence::start_feedback_snapshots(persistence_config, Arc::clone(&feedback), responses.clone());
+        }
+
+        // Share circuit breaker and feedback state across replicas
+        if let Some(ref sync_config) = config.state_sync
+            && sync_config.enabled
+        {
+            crate::sync::start_state_sync(
+                sync_config,
+                providers.keys().cloned().collect(),
+                Arc::clone(&health),
+                Arc::clone(&feedback),
+            )
+            .map_err(|e| LlmError::Internal(anyhow::anyhow!("failed to initialize state sync: {e}")))?;
+        }
+
+        // Keep catalog-derived profiles current as the file and discovered models change
+        let shutdown = CancellationToken::new();
+        if let Some((loader, catalog, modified)) = catalog {
+            tracing::info!(models = catalog.len(), "loaded model catalog");
+            crate::catalog::start_catalog_reload(
+                loader,
+                catalog,
+                modified,
+                Arc::clone(&model_registry),
+                router.known_models(),
+                shutdown.clone(),
+            );
+        }
 
         // Start background model discovery
         discovery::start_discovery(config, router.known_models());
@@ -382,7 +591,14 @@ impl LlmState {
                 routing_config,
                 model_registry,
                 strategy_registry,
+                query_embedder: None,
+                classifier,
+                affinity,
                 feedback,
+                responses,
+                experiments,
+                shadow,
+                training,
                 managed_keys: HashMap::new(),
                 managed_margins: HashMap::new(),
                 tier_margins: HashMap::new(),
@@ -392,10 +608,17 @@ impl LlmState {
                 billing_client: None,
                 #[cfg(feature = "cache")]
                 response_cache: None,
+                tool_loop: None,
+                shutdown,
             }),
         })
     }
 
+    /// Stop background tasks such as catalog reloading on shutdown
+    pub fn stop_background_tasks(&self) {
+        self.inner.shutdown.cancel();
+    }
+
     /// Configure managed provider keys for billing
     ///
     /// Must be called before the state is shared with handlers.
@@ -467,6 +690,72 @@ impl LlmState {
             .response_cache = Some(cache);
     }
 
+    /// Enable embedding-similarity (kNN) routing
+    ///
+    /// Loads the labeled examples file, embeds examples that have no
+    /// precomputed embedding, and registers the `knn` strategy. Queries are
+    /// embedded with the same embedder at routing time.
+    ///
+    /// Must be called before the state is shared with handlers.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the examples cannot be loaded or embedded
+    ///
+    /// # Panics
+    ///
+    /// Panics if called after the inner `Arc` has been cloned
+    pub async fn enable_knn_routing(&mut self, embedder: Arc<dyn QueryEmbedder>) -> Result<(), LlmError> {
+        let knn_config = self.inner.routing_config.knn.clone();
+        let path = knn_config
+            .examples
+            .as_ref()
+            .ok_or_else(|| LlmError::InvalidRequest("knn routing requires an examples file".to_owned()))?;
+
+        let mut examples = synapse_routing::strategy::knn::load_examples(path)
+            .map_err(|e| LlmError::Internal(anyhow::anyhow!("{e}")))?;
+
+        // Embed examples without a precomputed embedding
+        let missing: Vec<usize> = (0..examples.len())
+            .filter(|&i| examples[i].embedding.is_none())
+            .collect();
+        for batch in missing.chunks(EMBEDDING_BATCH_SIZE) {
+            let inputs = batch.iter().map(|&i| examples[i].prompt.clone()).collect();
+            let embeddings = embedder.embed(inputs).await?;
+            if embeddings.len() != batch.len() {
+                return Err(LlmError::Upstream(format!(
+                    "embeddings provider returned {} vectors for {} inputs",
+                    embeddings.len(),
+                    batch.len()
+                )));
+            }
+            for (&i, embedding) in batch.iter().zip(embeddings) {
+                examples[i].embedding = Some(embedding);
+            }
+        }
+
+        let strategy = synapse_routing::strategy::knn::KnnStrategy::new(examples, &knn_config)
+            .map_err(|e| LlmError::Internal(anyhow::anyhow!("{e}")))?;
+
+        let inner = Arc::get_mut(&mut self.inner).expect("enable_knn_routing must be called before state is shared");
+        inner.strategy_registry.register("knn", Box::new(strategy));
+        inner.query_embedder = Some(embedder);
+
+        Ok(())
+    }
+
+    /// Current circuit breaker state of every configured provider, sorted by name
+    pub fn provider_health(&self) -> Vec<(String, CircuitState)> {
+        let mut states: Vec<(String, CircuitState)> = self
+            .inner
+            .providers
+            .keys()
+            .map(|name| (name.clone(), self.inner.health.state(name)))
+            .collect();
+        states.sort_by(|a, b| a.0.cmp(&b.0));
+        states
+    }
+
     /// List all available models across providers
     pub async fn list_models(&self) -> Vec<(String, String)> {
         self.inner.router.list_models().await
@@ -486,87 +775,136 @@ impl LlmState {
         if self.inner.routing_config.enabled && ROUTING_CLASSES.contains(&model) {
             let entitled = self.check_smart_routing_entitlement(context).await;
             if entitled {
-                let (pn, mi, p) = self.resolve_via_routing(model, request)?;
+                let (pn, mi, p) = self.resolve_via_routing(model, request, context).await?;
                 return Ok((pn, mi, p, false));
             }
             tracing::debug!("smart routing not entitled, falling back to requested model");
         }
 
         let resolved = self.inner.router.resolve(model).await?;
+        let (provider_name, model_id) = self.fit_context_window(
+            resolved.provider_name,
+            resolved.model_id,
+            request,
+            !resolved.explicit_provider,
+        )?;
         let provider = self
             .inner
             .providers
-            .get(&resolved.provider_name)
+            .get(&provider_name)
             .ok_or_else(|| LlmError::ProviderNotFound {
-                provider: resolved.provider_name.clone(),
+                provider: provider_name.clone(),
             })?;
         Ok((
-            resolved.provider_name.clone(),
-            resolved.model_id,
+            provider_name,
+            model_id,
             Arc::clone(provider),
             resolved.explicit_provider,
         ))
     }
 
-    /// Resolve a virtual model name via the smart routing system
-    fn resolve_via_routing(
+    /// Check the prompt plus `max_tokens` against the model's context window
+    ///
+    /// When context upgrades are enabled and `upgrade` allows it, a request
+    /// that does not fit moves to the model with the smallest sufficient
+    /// window in the same equivalence group; explicitly selected models are
+    /// never swapped. Models without a profile or configured window are not
+    /// checked.
+    fn fit_context_window(
         &self,
-        routing_class: &str,
+        provider_name: String,
+        model_id: String,
         request: &CompletionRequest,
-    ) -> Result<(String, String, Arc<dyn Provider>), LlmError> {
-        use crate::types::message::{Content, ContentPart, Role};
-
-        // Convert internal messages to JSON values for analysis
-        let messages: Vec<serde_json::Value> = request
-            .messages
-            .iter()
-            .map(|m| {
-                serde_json::json!({
-                    "role": serde_json::to_value(&m.role).unwrap_or_default(),
-                    "content": m.content.as_text()
-                })
-            })
-            .collect();
-
-        let has_tools = request.tools.as_ref().is_some_and(|t| !t.is_empty());
-
-        // Extract conversation signals
-        let message_count = request.messages.len();
+        upgrade: bool,
+    ) -> Result<(String, String), LlmError> {
+        let registry = self.inner.model_registry.current();
+        let Some(profile) = registry
+            .find(&provider_name, &model_id)
+            .filter(|p| p.context_window > 0)
+        else {
+            return Ok((provider_name, model_id));
+        };
 
-        let has_system_prompt = request.messages.iter().any(|m| m.role == Role::System);
+        let needed = context_tokens(
+            request,
+            synapse_routing::estimate_prompt_tokens(&analysis_messages(request)),
+        );
+        if profile.fits_context(needed) {
+            return Ok((provider_name, model_id));
+        }
 
-        let has_images = request.messages.iter().any(|m| {
-            matches!(&m.content, Content::Parts(parts) if parts.iter().any(|p| matches!(p, ContentPart::Image { .. })))
-        });
+        if upgrade && self.inner.routing_config.context_upgrade {
+            let upgrade =
+                ModelRouter::find_equivalents(&provider_name, &model_id, &self.inner.failover.equivalence_groups)
+                    .into_iter()
+                    .filter(|(provider, _)| self.inner.providers.contains_key(provider))
+                    .filter_map(|(provider, model)| registry.find(&provider, &model))
+                    .filter(|candidate| candidate.context_window > 0 && candidate.fits_context(needed))
+                    .min_by_key(|candidate| candidate.context_window);
+
+            if let Some(upgrade) = upgrade {
+                tracing::info!(
+                    from_provider = %provider_name,
+                    from_model = %model_id,
+                    to_provider = %upgrade.provider,
+                    to_model = %upgrade.model,
+                    context_tokens = needed,
+                    "upgrading request to a model with a larger context window"
+                );
+                return Ok((upgrade.provider.clone(), upgrade.model.clone()));
+            }
+        }
 
-        let tool_call_turns = request
-            .messages
-            .iter()
-            .filter(|m| m.role == Role::Assistant && m.tool_calls.as_ref().is_some_and(|tc| !tc.is_empty()))
-            .count();
+        Err(LlmError::InvalidRequest(format!(
+            "request needs about {needed} tokens including max_tokens, exceeding the {}-token context window of {provider_name}/{model_id}",
+            profile.context_window
+        )))
+    }
 
-        let user_message_count = request.messages.iter().filter(|m| m.role == Role::User).count();
-        let is_multi_turn = user_message_count > 1;
+    /// Whether a failover alternative's context window fits the request
+    ///
+    /// Models without a profile or configured window are assumed to fit.
+    fn fits_context_window(&self, provider_name: &str, model_id: &str, needed: usize) -> bool {
+        self.inner
+            .model_registry
+            .current()
+            .find(provider_name, model_id)
+            .filter(|p| p.context_window > 0)
+            .is_none_or(|p| p.fits_context(needed))
+    }
 
+    /// Resolve a virtual model name via the smart routing system
+    async fn resolve_via_routing(
+        &self,
+        routing_class: &str,
+        request: &CompletionRequest,
+        context: &RequestContext,
+    ) -> Result<(String, String, Arc<dyn Provider>), LlmError> {
         // Apply routing class overrides
         let config = self.map_routing_class(routing_class);
 
-        let input = synapse_routing::AnalysisInput {
-            messages: &messages,
-            has_tools,
-            has_images,
-            message_count,
-            has_system_prompt,
-            tool_call_turns,
-            is_multi_turn,
-        };
+        // Keep the conversation on the model chosen for its earlier turns;
+        // shadow requests are routed on their own
+        let affinity = self
+            .inner
+            .affinity
+            .as_ref()
+            .filter(|_| context.parts.extensions.get::<ShadowTraffic>().is_none())
+            .and_then(|affinity| Some((affinity, affinity.key(routing_class, request, context)?)));
+        if let Some((affinity, ref key)) = affinity
+            && let Some(pinned) = self.resolve_pinned(affinity, key, request, context, &config).await
+        {
+            return Ok(pinned);
+        }
+
+        let profile = self.routing_profile(request, context, &config, false).await;
 
-        let decision = synapse_routing::route_with_strategy_registry(
-            &input,
-            &self.inner.model_registry,
+        let decision = synapse_routing::route_profile(
+            &profile,
+            &self.inner.model_registry.current(),
             &config,
             &self.inner.strategy_registry,
-            Some(&self.inner.feedback),
+            Some(&*self.inner.feedback),
         )
         .map_err(|e| LlmError::InvalidRequest(format!("routing failed: {e}")))?;
 
@@ -586,9 +924,257 @@ impl LlmState {
             "smart routing resolved virtual model"
         );
 
+        if let Some((affinity, key)) = affinity {
+            let pin = PinnedModel {
+                provider: decision.provider.clone(),
+                model: decision.model.clone(),
+            };
+            affinity.pin(key, pin).await;
+        }
+
+        if let Some(trace) = context.parts.extensions.get::<RoutingTrace>() {
+            trace.record(RoutingSummary {
+                strategy: StrategyRegistry::resolve_name(&config).to_owned(),
+                provider: decision.provider.clone(),
+                model: decision.model.clone(),
+                reason: decision.reason.clone(),
+                task_type: profile.task_type,
+                complexity: profile.complexity,
+                features: onnx::features(&profile),
+            });
+        }
+
         Ok((decision.provider, decision.model, Arc::clone(provider)))
     }
 
+    /// Reuse the model pinned to the request's conversation
+    ///
+    /// Returns `None`, so the request is routed afresh, when nothing is
+    /// pinned, the pinned provider is gone or its circuit is open, or the
+    /// conversation has outgrown the pinned model's context window.
+    async fn resolve_pinned(
+        &self,
+        affinity: &Affinity,
+        key: &[u8; 32],
+        request: &CompletionRequest,
+        context: &RequestContext,
+        config: &RoutingConfig,
+    ) -> Option<(String, String, Arc<dyn Provider>)> {
+        let pin = affinity.get(key).await?;
+        let provider = self.inner.providers.get(&pin.provider)?;
+        if !self.inner.health.is_available(&pin.provider) {
+            tracing::info!(
+                provider = %pin.provider,
+                model = %pin.model,
+                "pinned provider unavailable, re-routing conversation"
+            );
+            return None;
+        }
+
+        let messages = analysis_messages(request);
+        let profile = synapse_routing::analyze_query_structured(&analysis_input(request, &messages));
+        let needed = context_tokens(request, profile.estimated_input_tokens);
+        if self
+            .inner
+            .model_registry
+            .current()
+            .find(&pin.provider, &pin.model)
+            .is_some_and(|model| !model.fits_context(needed))
+        {
+            tracing::info!(
+                provider = %pin.provider,
+                model = %pin.model,
+                context_tokens = needed,
+                "conversation outgrew the pinned model's context window, re-routing"
+            );
+            return None;
+        }
+
+        tracing::debug!(provider = %pin.provider, model = %pin.model, "conversation affinity resolved virtual model");
+
+        if let Some(trace) = context.parts.extensions.get::<RoutingTrace>() {
+            trace.record(RoutingSummary {
+                strategy: StrategyRegistry::resolve_name(config).to_owned(),
+                provider: pin.provider.clone(),
+                model: pin.model.clone(),
+                reason: RoutingReason::ConversationAffinity,
+                task_type: profile.task_type,
+                complexity: profile.complexity,
+                features: onnx::features(&profile),
+            });
+        }
+
+        Some((pin.provider, pin.model, Arc::clone(provider)))
+    }
+
+    /// Explain how a virtual model would be routed, without calling a provider
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if smart routing is disabled, the model is not a
+    /// routing class, or no model can serve the request
+    pub async fn explain_routing(
+        &self,
+        request: &CompletionRequest,
+        context: &RequestContext,
+    ) -> Result<RoutingExplanation, LlmError> {
+        if !self.inner.routing_config.enabled {
+            return Err(LlmError::InvalidRequest("smart routing is not enabled".to_owned()));
+        }
+        if !ROUTING_CLASSES.contains(&request.model.as_str()) {
+            return Err(LlmError::InvalidRequest(format!(
+                "model must be a routing class ({}), got {}",
+                ROUTING_CLASSES.join(", "),
+                request.model
+            )));
+        }
+
+        let config = self.map_routing_class(&request.model);
+        let profile = self.routing_profile(request, context, &config, true).await;
+
+        synapse_routing::explain_profile(
+            profile,
+            &self.inner.model_registry.current(),
+            &config,
+            &self.inner.strategy_registry,
+            Some(&*self.inner.feedback),
+        )
+        .map_err(|e| LlmError::InvalidRequest(format!("routing failed: {e}")))
+    }
+
+    /// Analyze a request for routing, embedding the latest user turn when
+    /// the strategy compares queries by similarity and classifying it when
+    /// the strategy relies on the classifier model
+    ///
+    /// Embedding and classification failures and timeouts are logged and
+    /// leave the heuristic profile in place, so the strategy falls back
+    /// instead of failing the request. A `dry_run` skips query embedding and
+    /// only uses cached classifications.
+    async fn routing_profile(
+        &self,
+        request: &CompletionRequest,
+        context: &RequestContext,
+        config: &RoutingConfig,
+        dry_run: bool,
+    ) -> QueryProfile {
+        let messages = analysis_messages(request);
+        let mut profile = synapse_routing::analyze_query_structured(&analysis_input(request, &messages));
+        profile.required_capabilities.context_tokens = context_tokens(request, profile.estimated_input_tokens);
+        profile.client = context.client_identity.as_ref().map(|identity| RoutingClient {
+            id: identity.client_id.clone(),
+            group: identity.group.clone(),
+        });
+
+        if let Some(ref embedder) = self.inner.query_embedder
+            && !dry_run
+            && matches!(config.strategy, synapse_config::RoutingStrategy::Knn)
+            && let Some(text) = latest_user_text(request)
+        {
+            let timeout = Duration::from_millis(config.knn.timeout_ms);
+            match tokio::time::timeout(timeout, embedder.embed(vec![text])).await {
+                Ok(Ok(mut embeddings)) => profile.embedding = embeddings.pop(),
+                Ok(Err(e)) => tracing::warn!(error = %e, "failed to embed query for knn routing"),
+                Err(_) => tracing::warn!(
+                    timeout_ms = config.knn.timeout_ms,
+                    "query embedding exceeded latency budget, using heuristic routing"
+                ),
+            }
+        }
+
+        if matches!(config.strategy, synapse_config::RoutingStrategy::Classifier)
+            && let Some(classification) = self.classify(request, context, dry_run).await
+        {
+            classification.apply(&mut profile);
+        }
+
+        profile
+    }
+
+    /// Classify the latest user turn with the classifier model
+    ///
+    /// Returns `None` on timeout, provider error, an unparsable answer, an
+    /// open circuit on the classifier provider, or a cache miss in a `dry_run`.
+    async fn classify(
+        &self,
+        request: &CompletionRequest,
+        context: &RequestContext,
+        dry_run: bool,
+    ) -> Option<Classification> {
+        let classifier = self.inner.classifier.as_ref()?;
+        let text = latest_user_text(request)?;
+        let key = Classifier::key(&text);
+        if let Some(classification) = classifier.cached(&key) {
+            return Some(classification);
+        }
+        if dry_run {
+            return None;
+        }
+
+        if !self.inner.health.is_available(&classifier.provider) {
+            tracing::debug!(
+                provider = %classifier.provider,
+                "classifier provider unhealthy, using heuristic analysis"
+            );
+            return None;
+        }
+        let provider = self.inner.providers.get(&classifier.provider)?;
+        let context = self.classifier_context(context, &classifier.provider);
+
+        let started = Instant::now();
+        let answer = match tokio::time::timeout(
+            classifier.timeout,
+            provider.complete(&classifier.request(text), &context),
+        )
+        .await
+        {
+            Ok(Ok(response)) => {
+                self.inner.health.record_success(&classifier.provider);
+                response.choices.into_iter().next().and_then(|c| c.message.content)
+            }
+            Ok(Err(e)) => {
+                self.inner.health.record_failure(&classifier.provider);
+                tracing::warn!(error = %e, "classifier model failed, using heuristic analysis");
+                return None;
+            }
+            Err(_) => {
+                tracing::warn!(
+                    timeout_ms = classifier.timeout.as_millis(),
+                    "classifier model exceeded latency budget, using heuristic analysis"
+                );
+                return None;
+            }
+        };
+
+        let Some(classification) = answer.as_deref().and_then(Classification::parse) else {
+            tracing::warn!(answer = ?answer, "unparsable classifier answer, using heuristic analysis");
+            return None;
+        };
+
+        tracing::debug!(
+            task_type = classification.task_type.as_str(),
+            complexity = classification.complexity.as_str(),
+            latency_ms = started.elapsed().as_millis(),
+            "classified query"
+        );
+        classifier.remember(key, classification);
+        Some(classification)
+    }
+
+    /// Context for the gateway's own classifier calls
+    ///
+    /// Keeps the headers, which header rules forward, but none of the
+    /// caller's keys or billing identity, so classification never spends a
+    /// BYOK key or bills a managed caller. The call uses the provider's
+    /// managed key when one is configured, else its own key.
+    fn classifier_context(&self, context: &RequestContext, provider_name: &str) -> RequestContext {
+        let mut internal = RequestContext::empty();
+        internal.parts.method.clone_from(&context.parts.method);
+        internal.parts.uri.clone_from(&context.parts.uri);
+        internal.parts.headers.clone_from(&context.parts.headers);
+        internal.api_key = self.inner.managed_keys.get(provider_name).cloned();
+        internal
+    }
+
     /// Map a routing class name to an appropriate routing config
     fn map_routing_class(&self, class: &str) -> RoutingConfig {
         let mut config = self.inner.routing_config.clone();
@@ -709,6 +1295,7 @@ impl LlmState {
         provider: &Arc<dyn Provider>,
     ) -> Result<
         (
+            String,
             String,
             Pin<Box<dyn Stream<Item = Result<StreamEvent, LlmError>> + Send>>,
         ),
@@ -729,7 +1316,7 @@ impl LlmState {
                     input_tokens: None,
                     output_tokens: None,
                 });
-                Ok((model_id.to_owned(), stream))
+                Ok((provider_name.to_owned(), model_id.to_owned(), stream))
             }
             Err(e) => {
                 self.inner.health.record_failure(provider_name);
@@ -761,7 +1348,7 @@ impl LlmState {
         provider_name: &str,
         model_id: &str,
         provider: &Arc<dyn Provider>,
-    ) -> Result<CompletionResponse, LlmError> {
+    ) -> Result<(String, String, CompletionResponse), LlmError> {
         // Try primary provider
         let mut req = request.clone();
         model_id.clone_into(&mut req.model);
@@ -778,7 +1365,7 @@ impl LlmState {
                     input_tokens: response.usage.as_ref().map(|u| u.prompt_tokens),
                     output_tokens: response.usage.as_ref().map(|u| u.completion_tokens),
                 });
-                Ok(response)
+                Ok((provider_name.to_owned(), model_id.to_owned(), response))
             }
             Err(e) => {
                 self.inner.health.record_failure(provider_name);
@@ -804,6 +1391,10 @@ impl LlmState {
 
                 let alternatives =
                     ModelRouter::find_equivalents(provider_name, model_id, &self.inner.failover.equivalence_groups);
+                let needed = context_tokens(
+                    request,
+                    synapse_routing::estimate_prompt_tokens(&analysis_messages(request)),
+                );
 
                 // max_attempts includes the primary, so remaining = max_attempts - 1
                 let remaining = self.inner.failover.max_attempts.saturating_sub(1);
@@ -817,6 +1408,15 @@ impl LlmState {
                         );
                         continue;
                     }
+                    if !self.fits_context_window(&alt_provider, &alt_model, needed) {
+                        tracing::debug!(
+                            provider = %alt_provider,
+                            model = %alt_model,
+                            context_tokens = needed,
+                            "skipping alternative with too small a context window"
+                        );
+                        continue;
+                    }
 
                     let Some(alt_provider_impl) = self.inner.providers.get(&alt_provider) else {
                         continue;
@@ -835,7 +1435,7 @@ impl LlmState {
                     match alt_provider_impl.complete(&alt_req, context).await {
                         Ok(response) => {
                             self.inner.health.record_success(&alt_provider);
-                            return Ok(response);
+                            return Ok((alt_provider, alt_model, response));
                         }
                         Err(e) => {
                             self.inner.health.record_failure(&alt_provider);
@@ -867,6 +1467,7 @@ impl LlmState {
         provider: &Arc<dyn Provider>,
     ) -> Result<
         (
+            String,
             String,
             Pin<Box<dyn Stream<Item = Result<StreamEvent, LlmError>> + Send>>,
         ),
@@ -889,7 +1490,7 @@ impl LlmState {
                     input_tokens: None,
                     output_tokens: None,
                 });
-                Ok((model_id.to_owned(), stream))
+                Ok((provider_name.to_owned(), model_id.to_owned(), stream))
             }
             Err(e) => {
                 self.inner.health.record_failure(provider_name);
@@ -915,12 +1516,18 @@ impl LlmState {
 
                 let alternatives =
                     ModelRouter::find_equivalents(provider_name, model_id, &self.inner.failover.equivalence_groups);
+                let needed = context_tokens(
+                    request,
+                    synapse_routing::estimate_prompt_tokens(&analysis_messages(request)),
+                );
 
                 let remaining = self.inner.failover.max_attempts.saturating_sub(1);
                 let mut last_error = e;
 
                 for (alt_provider, alt_model) in alternatives.into_iter().take(remaining) {
-                    if !self.inner.health.is_available(&alt_provider) {
+                    if !self.inner.health.is_available(&alt_provider)
+                        || !self.fits_context_window(&alt_provider, &alt_model, needed)
+                    {
                         continue;
                     }
 
@@ -941,7 +1548,7 @@ impl LlmState {
                     match alt_provider_impl.complete_stream(&alt_req, context).await {
                         Ok(stream) => {
                             self.inner.health.record_success(&alt_provider);
-                            return Ok((alt_model, stream));
+                            return Ok((alt_provider, alt_model, stream));
                         }
                         Err(e) => {
                             self.inner.health.record_failure(&alt_provider);
@@ -958,7 +1565,7 @@ impl LlmState {
     /// Execute a streaming cascade: buffer initial model's response, evaluate
     /// confidence, then either replay the buffer or re-request with the
     /// escalation model
-    #[allow(clippy::cognitive_complexity)]
+    #[allow(clippy::cognitive_complexity, clippy::too_many_lines)]
     pub(crate) async fn complete_stream_with_cascade(
         &self,
         request: &CompletionRequest,
@@ -969,6 +1576,7 @@ impl LlmState {
         cascade_config: &synapse_config::CascadeConfig,
     ) -> Result<
         (
+            String,
             String,
             Pin<Box<dyn Stream<Item = Result<StreamEvent, LlmError>> + Send>>,
         ),
@@ -978,7 +1586,7 @@ impl LlmState {
         let escalation = self.resolve_escalation_model(cascade_config)?;
 
         // Stream from the initial (cheap) model
-        let (initial_model, mut stream) = self
+        let (initial_provider, initial_model, mut stream) = self
             .complete_stream_with_failover(request, context, provider_name, model_id, provider)
             .await?;
 
@@ -986,6 +1594,7 @@ impl LlmState {
         let mut buffered_events: Vec<StreamEvent> = Vec::new();
         let mut buffered_text = String::new();
         let mut buffer_bytes: usize = 0;
+        let mut has_tool_calls = false;
         let mut committed = false;
 
         let timeout = tokio::time::Duration::from_secs(cascade_config.buffer_timeout_secs);
@@ -1012,11 +1621,12 @@ impl LlmState {
                 // Stream event
                 Ok(Some(Ok(event))) => {
                     // Track buffer size
-                    if let StreamEvent::Delta(ref delta) = event
-                        && let Some(ref content) = delta.content
-                    {
-                        buffer_bytes += content.len();
-                        buffered_text.push_str(content);
+                    if let StreamEvent::Delta(ref delta) = event {
+                        if let Some(ref content) = delta.content {
+                            buffer_bytes += content.len();
+                            buffered_text.push_str(content);
+                        }
+                        has_tool_calls |= delta.tool_call.is_some();
                     }
 
                     buffered_events.push(event.clone());
@@ -1046,18 +1656,21 @@ impl LlmState {
             let replay: Vec<Result<StreamEvent, LlmError>> = buffered_events.into_iter().map(Ok).collect();
             let replay_stream = futures_util::stream::iter(replay);
             let combined = replay_stream.chain(remaining);
-            return Ok((initial_model, Box::pin(combined)));
+            return Ok((initial_provider, initial_model, Box::pin(combined)));
         }
 
-        // Estimate input tokens for confidence check
-        let query_tokens: usize = request.messages.iter().map(|m| m.content.as_text().len() / 4).sum();
-
-        // Evaluate confidence on buffered response
-        let is_confident = synapse_routing::strategy::cascade::evaluate_buffered_response(
-            &buffered_text,
-            query_tokens,
-            cascade_config.confidence_threshold,
-        );
+        // Evaluate confidence on buffered response; tool calls are replayed
+        // as is, there is no answer to grade yet
+        let is_confident = has_tool_calls
+            || self
+                .cascade_confident(
+                    request,
+                    context,
+                    &buffered_text,
+                    (&initial_provider, &initial_model),
+                    cascade_config,
+                )
+                .await;
 
         if is_confident {
             tracing::debug!(
@@ -1065,7 +1678,11 @@ impl LlmState {
                 "cascade: initial model response is confident, replaying buffer"
             );
             let replay: Vec<Result<StreamEvent, LlmError>> = buffered_events.into_iter().map(Ok).collect();
-            return Ok((initial_model, Box::pin(futures_util::stream::iter(replay))));
+            return Ok((
+                initial_provider,
+                initial_model,
+                Box::pin(futures_util::stream::iter(replay)),
+            ));
         }
 
         // Not confident — escalate to stronger model
@@ -1076,6 +1693,14 @@ impl LlmState {
             "cascade: escalating to stronger model"
         );
 
+        // The discarded answer was still generated upstream
+        for event in &buffered_events {
+            if let StreamEvent::Usage(usage) = event {
+                self.meter_usage(context, &initial_provider, &initial_model, usage)
+                    .await;
+            }
+        }
+
         let (esc_provider_name, esc_model_id) = &escalation;
         let esc_provider = self
             .inner
@@ -1084,11 +1709,181 @@ impl LlmState {
             .ok_or_else(|| LlmError::ProviderNotFound {
                 provider: esc_provider_name.clone(),
             })?;
+        let mut esc_context = context.clone();
+        self.resolve_api_key_for_request(&mut esc_context, esc_provider_name)?;
 
-        self.complete_stream_with_failover(request, context, esc_provider_name, esc_model_id, esc_provider)
+        self.complete_stream_with_failover(request, &esc_context, esc_provider_name, esc_model_id, esc_provider)
             .await
     }
 
+    /// Execute a non-streaming cascade: answer with the initial model, then
+    /// re-request with the escalation model if the answer is not confident
+    ///
+    /// Returns the provider and model that produced the returned response.
+    pub(crate) async fn complete_with_cascade(
+        &self,
+        request: &CompletionRequest,
+        context: &RequestContext,
+        provider_name: &str,
+        model_id: &str,
+        provider: &Arc<dyn Provider>,
+        cascade_config: &synapse_config::CascadeConfig,
+    ) -> Result<(String, String, CompletionResponse), LlmError> {
+        let (esc_provider_name, esc_model_id) = self.resolve_escalation_model(cascade_config)?;
+
+        let (initial_provider, initial_model, initial) = self
+            .complete_with_failover(request, context, provider_name, model_id, provider)
+            .await?;
+
+        // Tool calls are returned as is; there is no answer to grade yet
+        let message = initial.choices.first().map(|c| &c.message);
+        let answer = message.and_then(|m| m.content.as_deref()).unwrap_or_default();
+        let has_tool_calls = message
+            .and_then(|m| m.tool_calls.as_ref())
+            .is_some_and(|calls| !calls.is_empty());
+        if has_tool_calls
+            || self
+                .cascade_confident(
+                    request,
+                    context,
+                    answer,
+                    (&initial_provider, &initial_model),
+                    cascade_config,
+                )
+                .await
+        {
+            tracing::debug!(model = %initial_model, "cascade: initial model response is confident");
+            return Ok((initial_provider, initial_model, initial));
+        }
+
+        tracing::info!(
+            initial_model = %initial_model,
+            escalation_provider = %esc_provider_name,
+            escalation_model = %esc_model_id,
+            "cascade: escalating to stronger model"
+        );
+
+        // The discarded answer was still generated upstream
+        if let Some(ref usage) = initial.usage {
+            self.meter_usage(context, &initial_provider, &initial_model, usage)
+                .await;
+        }
+
+        let esc_provider = self
+            .inner
+            .providers
+            .get(&esc_provider_name)
+            .ok_or_else(|| LlmError::ProviderNotFound {
+                provider: esc_provider_name.clone(),
+            })?;
+        let mut esc_context = context.clone();
+        self.resolve_api_key_for_request(&mut esc_context, &esc_provider_name)?;
+
+        self.complete_with_failover(request, &esc_context, &esc_provider_name, &esc_model_id, esc_provider)
+            .await
+    }
+
+    /// Decide whether the initial cascade answer is confident enough to keep
+    ///
+    /// Uses the verifier's grade when a verifier is configured and answers
+    /// in time, otherwise the heuristic confidence estimate.
+    async fn cascade_confident(
+        &self,
+        request: &CompletionRequest,
+        context: &RequestContext,
+        answer: &str,
+        initial: (&str, &str),
+        cascade_config: &synapse_config::CascadeConfig,
+    ) -> bool {
+        if let Some(ref verifier) = cascade_config.verifier
+            && let Some(grade) = self.verify_answer(request, context, answer, initial, verifier).await
+        {
+            tracing::debug!(
+                grade,
+                threshold = cascade_config.confidence_threshold,
+                "cascade verifier graded answer"
+            );
+            return grade >= cascade_config.confidence_threshold;
+        }
+
+        // Estimate input tokens for the heuristic confidence check
+        let query_tokens: usize = request.messages.iter().map(|m| m.content.as_text().len() / 4).sum();
+
+        synapse_routing::strategy::cascade::evaluate_buffered_response(
+            answer,
+            query_tokens,
+            cascade_config.confidence_threshold,
+        )
+    }
+
+    /// Ask the verifier model to grade an answer to the latest user turn
+    ///
+    /// Returns `None` on timeout, provider error, or an unparsable grade.
+    async fn verify_answer(
+        &self,
+        request: &CompletionRequest,
+        context: &RequestContext,
+        answer: &str,
+        initial: (&str, &str),
+        verifier: &CascadeVerifierConfig,
+    ) -> Option<f64> {
+        use synapse_routing::strategy::cascade::{VERIFIER_PROMPT, parse_grade, verification_input};
+
+        let (provider_name, model_id) = verifier
+            .model
+            .as_deref()
+            .and_then(|model| model.split_once('/'))
+            .unwrap_or(initial);
+        let Some(provider) = self.inner.providers.get(provider_name) else {
+            tracing::warn!(provider = provider_name, "cascade verifier provider not configured");
+            return None;
+        };
+        let question = latest_user_text(request)?;
+
+        let mut context = context.clone();
+        if let Err(e) = self.resolve_api_key_for_request(&mut context, provider_name) {
+            tracing::warn!(error = %e, "no API key for cascade verifier, using heuristic confidence");
+            return None;
+        }
+
+        let verification = CompletionRequest::instruction(
+            model_id.to_owned(),
+            VERIFIER_PROMPT,
+            verification_input(&question, answer),
+            VERIFIER_MAX_TOKENS,
+        );
+        let timeout = Duration::from_millis(verifier.timeout_ms);
+        match tokio::time::timeout(timeout, provider.complete(&verification, &context)).await {
+            Ok(Ok(response)) => {
+                if let Some(ref usage) = response.usage {
+                    self.meter_usage(&context, provider_name, model_id, usage).await;
+                }
+                let grade = response
+                    .choices
+                    .into_iter()
+                    .next()
+                    .and_then(|c| c.message.content)
+                    .as_deref()
+                    .and_then(parse_grade);
+                if grade.is_none() {
+                    tracing::warn!("unparsable cascade verifier grade, using heuristic confidence");
+                }
+                grade
+            }
+            Ok(Err(e)) => {
+                tracing::warn!(error = %e, "cascade verifier failed, using heuristic confidence");
+                None
+            }
+            Err(_) => {
+                tracing::warn!(
+                    timeout_ms = verifier.timeout_ms,
+                    "cascade verifier exceeded latency budget, using heuristic confidence"
+                );
+                None
+            }
+        }
+    }
+
     /// Resolve the escalation model for cascade routing
     fn resolve_escalation_model(
         &self,
@@ -1102,9 +1897,8 @@ impl LlmState {
         }
 
         // Default to best quality model in registry
-        let best = self
-            .inner
-            .model_registry
+        let registry = self.inner.model_registry.current();
+        let best = registry
             .best_quality()
             .ok_or_else(|| LlmError::InvalidRequest("no escalation model available".to_owned()))?;
         Ok((best.provider.clone(), best.model.clone()))
@@ -1151,6 +1945,315 @@ impl LlmState {
         Ok(())
     }
 
+    /// Routing trace to read the decision from for training-data export
+    ///
+    /// Inserts a trace when the caller did not, so library callers are
+    /// sampled too. Returns `None` when export is disabled.
+    fn training_trace(&self, context: &mut RequestContext) -> Option<RoutingTrace> {
+        self.inner.training.as_ref()?;
+
+        if let Some(trace) = context.parts.extensions.get::<RoutingTrace>() {
+            return Some(trace.clone());
+        }
+        let trace = RoutingTrace::default();
+        context.parts.extensions.insert(trace.clone());
+        Some(trace)
+    }
+
+    /// Training sample for a smart-routed request, labelled with the model
+    /// that served it
+    fn training_sample(
+        &self,
+        trace: Option<&RoutingTrace>,
+        response_id: &str,
+        provider_name: &str,
+        model_id: &str,
+    ) -> Option<TrainingSample> {
+        let summary = trace?.get()?;
+        let class = self.inner.model_registry.current().class_of(provider_name, model_id);
+
+        Some(TrainingSample {
+            response_id: response_id.to_owned(),
+            timestamp: TrainingSample::now(),
+            features: summary.features,
+            class,
+            provider: provider_name.to_owned(),
+            model: model_id.to_owned(),
+            strategy: summary.strategy.clone(),
+            reason: summary.reason.as_str(),
+            latency_ms: 0,
+            prompt_tokens: None,
+            completion_tokens: None,
+            rating: None,
+        })
+    }
+
+    /// Bill, credit, and report the usage of one upstream call
+    ///
+    /// Every call made on the caller's behalf is metered, including cascade
+    /// answers that were escalated and verifier grades.
+    async fn meter_usage(&self, context: &RequestContext, provider_name: &str, model_id: &str, usage: &Usage) {
+        #[cfg(feature = "billing")]
+        {
+            if let Some(ref recorder) = self.inner.usage_recorder {
+                dispatch_usage_event(
+                    recorder,
+                    context,
+                    provider_name,
+                    model_id,
+                    context.parts.extensions.get::<ExperimentAssignment>(),
+                    usage.prompt_tokens,
+                    usage.completion_tokens,
+                    &self.inner.model_registry.current(),
+                    &self.inner.managed_margins,
+                    &self.inner.tier_margins,
+                );
+            }
+
+            // Post-completion credit deduction based on actual usage
+            self.deduct_credits_for_usage(
+                context,
+                provider_name,
+                model_id,
+                usage.prompt_tokens,
+                usage.completion_tokens,
+            )
+            .await;
+        }
+
+        // Report usage to synapse-api for dashboard charts
+        dispatch_usage_report(
+            context,
+            provider_name,
+            model_id,
+            usage.prompt_tokens,
+            usage.completion_tokens,
+            &self.inner.model_registry.current(),
+            &self.inner.managed_margins,
+            &self.inner.tier_margins,
+        );
+    }
+
+    fn record_training_sample(&self, sample: TrainingSample) {
+        if let Some(ref training) = self.inner.training {
+            training.record(sample);
+        }
+    }
+
+    /// Remember the routing details of a served response so it can be rated
+    ///
+    /// The task type comes from the routing decision; requests that were
+    /// not smart-routed are classified without a full analysis.
+    async fn remember_response(
+        &self,
+        response_id: &str,
+        provider_name: &str,
+        model_id: &str,
+        request: &CompletionRequest,
+        context: &RequestContext,
+    ) {
+        let Some(ref responses) = self.inner.responses else {
+            return;
+        };
+
+        let task_type = context
+            .parts
+            .extensions
+            .get::<RoutingTrace>()
+            .and_then(RoutingTrace::get)
+            .map_or_else(
+                || synapse_routing::classify_task_type(&analysis_messages(request)),
+                |summary| summary.task_type,
+            );
+
+        responses
+            .insert(
+                response_id.to_owned(),
+                ServedResponse {
+                    provider: provider_name.to_owned(),
+                    model: model_id.to_owned(),
+                    task_type,
+                    client_id: context.client_identity.as_ref().map(|c| c.client_id.clone()),
+                    experiment: context.parts.extensions.get::<ExperimentAssignment>().cloned(),
+                    served_at: now_secs(),
+                },
+            )
+            .await;
+    }
+
+    /// Record a user rating for a previously served response
+    ///
+    /// The rating is attributed to the provider, model, and task type that
+    /// produced the response and adjusts that model's quality for future
+    /// routing decisions. Each response can be rated once, and only by the
+    /// client that received it.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if feedback is disabled, the rating is invalid, or
+    /// the response is unknown, expired, or already rated
+    pub async fn submit_feedback(
+        &self,
+        feedback: &FeedbackRequest,
+        context: &RequestContext,
+    ) -> Result<FeedbackReceipt, LlmError> {
+        let Some(ref responses) = self.inner.responses else {
+            return Err(LlmError::InvalidRequest("user feedback is not enabled".to_owned()));
+        };
+
+        let score = feedback.score()?;
+        let not_found = || LlmError::ResponseNotFound {
+            id: feedback.response_id.clone(),
+        };
+
+        let caller = context.client_identity.as_ref().map(|c| c.client_id.as_str());
+        let served = responses
+            .take(&feedback.response_id, caller)
+            .await
+            .ok_or_else(not_found)?;
+
+        self.inner
+            .feedback
+            .record_rating(&served.provider, &served.model, Some(served.task_type), score);
+
+        // Ratings on experiment traffic also count towards the arm
+        if let Some(ref assignment) = served.experiment {
+            let (provider, model) = assignment.feedback_key();
+            self.inner.feedback.record_rating(&provider, &model, None, score);
+        }
+
+        if let Some(ref training) = self.inner.training {
+            training.rate(&feedback.response_id, score);
+        }
+
+        tracing::info!(
+            provider = %served.provider,
+            model = %served.model,
+            task_type = ?served.task_type,
+            score,
+            "user feedback recorded"
+        );
+
+        Ok(FeedbackReceipt {
+            response_id: feedback.response_id.clone(),
+            provider: served.provider,
+            model: served.model,
+            task_type: served.task_type,
+            score,
+        })
+    }
+
+    /// Assign a request for an experiment's virtual model to an arm
+    ///
+    /// Rewrites the requested model to the arm's model and records the
+    /// assignment in the request extensions and routing trace.
+    fn assign_experiment(
+        &self,
+        request: &mut CompletionRequest,
+        context: &mut RequestContext,
+    ) -> Option<ExperimentAssignment> {
+        let assignment = self.inner.experiments.assign(request, context)?;
+
+        tracing::info!(
+            experiment = %assignment.experiment,
+            arm = %assignment.arm,
+            model = %assignment.model,
+            "experiment arm assigned"
+        );
+        synapse_telemetry::set_span_attribute(
+            synapse_telemetry::metrics::EXPERIMENT_NAME,
+            assignment.experiment.clone(),
+        );
+        synapse_telemetry::set_span_attribute(synapse_telemetry::metrics::EXPERIMENT_ARM, assignment.arm.clone());
+        synapse_telemetry::metrics::record_experiment_request(&assignment.experiment, &assignment.arm);
+
+        request.model.clone_from(&assignment.model);
+        if let Some(trace) = context.parts.extensions.get::<RoutingTrace>() {
+            trace.record_experiment(assignment.clone());
+        }
+        context.parts.extensions.insert(assignment.clone());
+
+        Some(assignment)
+    }
+
+    /// Record the outcome of a request served by an experiment arm
+    fn record_experiment_outcome(
+        &self,
+        assignment: &ExperimentAssignment,
+        latency: Duration,
+        success: bool,
+        usage: Option<&crate::types::Usage>,
+    ) {
+        let (provider, model) = assignment.feedback_key();
+        self.inner.feedback.record(&RequestFeedback {
+            provider,
+            model,
+            latency,
+            success,
+            input_tokens: usage.map(|u| u.prompt_tokens),
+            output_tokens: usage.map(|u| u.completion_tokens),
+        });
+    }
+
+    /// Replay a request against a shadow model in the background and
+    /// record it next to the primary outcome
+    ///
+    /// The shadow call bypasses failover, health tracking, routing feedback,
+    /// and billing so it cannot affect live traffic. Caller-supplied keys are
+    /// dropped so shadow calls only ever use managed or configured keys.
+    fn spawn_shadow(
+        &self,
+        shadow_model: String,
+        requested_model: String,
+        request: CompletionRequest,
+        context: &RequestContext,
+        primary: ShadowOutcome,
+    ) {
+        let state = self.clone();
+        let mut context = shadow_context(context);
+
+        tokio::spawn(async move {
+            let started = Instant::now();
+            let (provider_name, model_id, result) =
+                match state.resolve_provider(&shadow_model, &request, &context).await {
+                    Ok((provider_name, model_id, provider, _)) => {
+                        let mut req = request;
+                        model_id.clone_into(&mut req.model);
+
+                        let result = match state.resolve_api_key_for_request(&mut context, &provider_name) {
+                            Ok(()) => provider.complete(&req, &context).await,
+                            Err(e) => Err(e),
+                        };
+                        (provider_name, model_id, result)
+                    }
+                    Err(e) => {
+                        let (provider_name, model_id) = shadow_model.split_once('/').unwrap_or(("", &shadow_model));
+                        (provider_name.to_owned(), model_id.to_owned(), Err(e))
+                    }
+                };
+
+            if let Err(ref e) = result {
+                tracing::debug!(model = %shadow_model, error = %e, "shadow request failed");
+            }
+
+            let shadow = ShadowOutcome::new(
+                &provider_name,
+                &model_id,
+                started.elapsed(),
+                result.as_ref(),
+                &state.inner.model_registry.current(),
+            );
+            if let Some(ref sink) = state.inner.shadow {
+                sink.record(ShadowRecord::new(requested_model, primary, shadow));
+            }
+        });
+    }
+
+    /// Per-arm outcomes of every configured experiment
+    pub fn experiment_report(&self) -> Vec<ExperimentReport> {
+        self.inner.experiments.report(&self.inner.feedback)
+    }
+
     /// Check if the current routing strategy is cascade
     pub(crate) fn is_cascade_strategy(&self, model: &str) -> bool {
         if !self.inner.routing_config.enabled || !ROUTING_CLASSES.contains(&model) {
@@ -1191,6 +2294,7 @@ impl LlmState {
         let cost = self
             .inner
             .model_registry
+            .current()
             .find(provider_name, &request.model)
             .map_or(0.0, |profile| {
                 let base = profile.estimate_cost(estimated_input, estimated_output);
@@ -1272,6 +2376,7 @@ impl LlmState {
         let actual_cost = self
             .inner
             .model_registry
+            .current()
             .find(provider_name, model_id)
             .map_or(0.0, |profile| {
                 let base = profile.estimate_cost(input_tokens as usize, output_tokens as usize);
@@ -1310,6 +2415,117 @@ impl LlmState {
     }
 }
 
+/// Start the shadow mirror and training-data sinks, when enabled
+fn build_exports(config: &LlmConfig) -> Result<(Option<Shadow>, Option<TrainingSink>), LlmError> {
+    let shadow = config
+        .shadow
+        .as_ref()
+        .filter(|shadow| shadow.enabled)
+        .map(Shadow::new)
+        .transpose()?;
+    let training = config
+        .training_data
+        .as_ref()
+        .filter(|training| training.enabled)
+        .map(TrainingSink::new)
+        .transpose()?;
+
+    Ok((shadow, training))
+}
+
+/// Instantiate every configured provider
+async fn build_providers(config: &LlmConfig) -> Result<HashMap<String, Arc<dyn Provider>>, LlmError> {
+    let mut providers: HashMap<String, Arc<dyn Provider>> = HashMap::new();
+
+    for (name, provider_config) in &config.providers {
+        let provider: Arc<dyn Provider> = match &provider_config.provider_type {
+            LlmProviderType::Openai => Arc::new(crate::provider::openai::OpenAiProvider::new(
+                name.clone(),
+                provider_config,
+            )?),
+            LlmProviderType::Anthropic => Arc::new(crate::provider::anthropic::AnthropicProvider::new(
+                name.clone(),
+                provider_config,
+            )?),
+            LlmProviderType::Google => Arc::new(crate::provider::google::GoogleProvider::new(
+                name.clone(),
+                provider_config,
+            )?),
+            LlmProviderType::Bedrock(_) => {
+                Arc::new(crate::provider::bedrock::BedrockProvider::new(name.clone(), provider_config).await?)
+            }
+        };
+
+        providers.insert(name.clone(), provider);
+    }
+
+    Ok(providers)
+}
+
+/// Text of the latest user turn, if it has any
+fn latest_user_text(request: &CompletionRequest) -> Option<String> {
+    request
+        .messages
+        .iter()
+        .rev()
+        .find(|m| m.role == crate::types::message::Role::User)
+        .map(|m| m.content.as_text())
+        .filter(|text| !text.trim().is_empty())
+}
+
+/// Tokens a model's context window must hold for a request
+fn context_tokens(request: &CompletionRequest, prompt_tokens: usize) -> usize {
+    prompt_tokens + request.params.max_tokens.map_or(0, |tokens| tokens as usize)
+}
+
+/// Convert internal messages to JSON values for query analysis
+fn analysis_messages(request: &CompletionRequest) -> Vec<serde_json::Value> {
+    request
+        .messages
+        .iter()
+        .map(|m| {
+            serde_json::json!({
+                "role": serde_json::to_value(&m.role).unwrap_or_default(),
+                "content": m.content.as_text()
+            })
+        })
+        .collect()
+}
+
+/// Extract conversation signals for query analysis
+fn analysis_input<'a>(
+    request: &CompletionRequest,
+    messages: &'a [serde_json::Value],
+) -> synapse_routing::AnalysisInput<'a> {
+    use crate::types::message::{Content, ContentPart, Role};
+
+    let has_tools = request.tools.as_ref().is_some_and(|t| !t.is_empty());
+
+    let has_system_prompt = request.messages.iter().any(|m| m.role == Role::System);
+
+    let has_images = request.messages.iter().any(
+        |m| matches!(&m.content, Content::Parts(parts) if parts.iter().any(|p| matches!(p, ContentPart::Image { .. }))),
+    );
+
+    let tool_call_turns = request
+        .messages
+        .iter()
+        .filter(|m| m.role == Role::Assistant && m.tool_calls.as_ref().is_some_and(|tc| !tc.is_empty()))
+        .count();
+
+    let user_message_count = request.messages.iter().filter(|m| m.role == Role::User).count();
+
+    synapse_routing::AnalysisInput {
+        messages,
+        has_tools,
+        has_images,
+        message_count: request.messages.len(),
+        has_system_prompt,
+        tool_call_turns,
+        is_multi_turn: user_message_count > 1,
+    }
+}
+
 /// Spawn a fire-and-forget credit deduction task for streaming usage events
 #[cfg(feature = "billing")]
 #[allow(clippy::too_many_arguments)]
@@ -1371,6 +2587,24 @@ fn spawn_credit_deduction(
     });
 }
 
+/// Context for a shadow request, stripped of the caller's request state
+///
+/// Keeps the headers, which header rules forward, and the billing identity
+/// that selects managed keys. Extensions such as the routing trace and
+/// experiment assignment, the caller's keys, and its identity are left out
+/// so the shadow cannot record routing decisions or spend the caller's
+/// keys, and the context is marked as [`ShadowTraffic`] so routing skips
+/// conversation affinity.
+fn shadow_context(context: &RequestContext) -> RequestContext {
+    let mut shadow = RequestContext::empty();
+    shadow.parts.method.clone_from(&context.parts.method);
+    shadow.parts.uri.clone_from(&context.parts.uri);
+    shadow.parts.headers.clone_from(&context.parts.headers);
+    shadow.parts.extensions.insert(ShadowTraffic);
+    shadow.billing_identity.clone_from(&context.billing_identity);
+    shadow
+}
+
 /// Dispatch a usage event to the billing recorder
 #[cfg(feature = "billing")]
 #[allow(clippy::too_many_arguments)]
@@ -1379,6 +2613,7 @@ fn dispatch_usage_event(
     context: &RequestContext,
     provider_name: &str,
     model_id: &str,
+    experiment: Option<&ExperimentAssignment>,
     input_tokens: u32,
     output_tokens: u32,
     model_registry: &ModelRegistry,
@@ -1421,6 +2656,8 @@ fn dispatch_usage_event(
         output_tokens: metered_output,
         estimated_cost_usd,
         idempotency_key,
+        experiment: experiment.map(|e| e.experiment.clone()),
+        experiment_arm: experiment.map(|e| e.arm.clone()),
     });
 }
 
@@ -1449,6 +2686,7 @@ fn dispatch_usage_report(
         resolved,
         provider_name,
         model_id,
+        context.parts.extensions.get::<ExperimentAssignment>(),
         input_tokens,
         output_tokens,
         model_registry,
@@ -1464,6 +2702,7 @@ fn record_usage_report(
     resolved: &synapse_auth::ResolvedKey,
     provider_name: &str,
     model_id: &str,
+    experiment: Option<&ExperimentAssignment>,
     input_tokens: u32,
     output_tokens: u32,
     model_registry: &ModelRegistry,
@@ -1493,6 +2732,8 @@ fn record_usage_report(
         output_tokens,
         cost_cents,
         mode: mode.to_owned(),
+        experiment: experiment.map(|e| e.experiment.clone()),
+        experiment_arm: experiment.map(|e| e.arm.clone()),
     });
 }
 
@@ -1513,7 +2754,139 @@ fn resolve_margin(
 
 #[cfg(test)]
 mod tests {
+    use std::sync::Mutex;
+
+    use async_trait::async_trait;
+    use secrecy::ExposeSecret;
+
     use super::*;
+    use crate::provider::ProviderCapabilities;
+    use crate::types::{CompletionParams, Content, Message, Role, StreamDelta};
+
+    /// Provider recording the API key of every request, answering each
+    /// stream with `answer`
+    struct RecordingProvider {
+        answer: &'static str,
+        keys: Arc<Mutex<Vec<Option<String>>>>,
+    }
+
+    impl RecordingProvider {
+        fn record(&self, context: &RequestContext) {
+            let key = context.api_key.as_ref().map(|key| key.expose_secret().to_owned());
+            self.keys.lock().unwrap().push(key);
+        }
+    }
+
+    #[async_trait]
+    impl Provider for RecordingProvider {
+        fn name(&self) -> &'static str {
+            "recording"
+        }
+
+        fn capabilities(&self) -> ProviderCapabilities {
+            ProviderCapabilities {
+                streaming: true,
+                tool_calling: false,
+            }
+        }
+
+        async fn complete(
+            &self,
+            _request: &CompletionRequest,
+            context: &RequestContext,
+        ) -> Result<CompletionResponse, LlmError> {
+            self.record(context);
+            Err(LlmError::Upstream("not implemented".to_owned()))
+        }
+
+        async fn complete_stream(
+            &self,
+            _request: &CompletionRequest,
+            context: &RequestContext,
+        ) -> Result<Pin<Box<dyn Stream<Item = Result<StreamEvent, LlmError>> + Send>>, LlmError> {
+            self.record(context);
+            let delta = StreamDelta {
+                index: 0,
+                content: Some(self.answer.to_owned()),
+                tool_call: None,
+                finish_reason: None,
+            };
+            Ok(Box::pin(futures_util::stream::iter([
+                Ok(StreamEvent::Delta(delta)),
+                Ok(StreamEvent::Done),
+            ])))
+        }
+    }
+
+    /// State over recording providers, with a managed key per provider
+    async fn recording_state(providers: &[&'static str]) -> (LlmState, Arc<Mutex<Vec<Option<String>>>>) {
+        let keys = Arc::default();
+        let mut state = LlmState::from_config(LlmConfig::default()).await.unwrap();
+        let inner = Arc::get_mut(&mut state.inner).unwrap();
+        for &name in providers {
+            let provider = RecordingProvider {
+                answer: "I don't know.",
+                keys: Arc::clone(&keys),
+            };
+            inner.providers.insert(name.to_owned(), Arc::new(provider));
+            inner
+                .managed_keys
+                .insert(name.to_owned(), SecretString::from(format!("sk-{name}")));
+        }
+        (state, keys)
+    }
+
+    fn user_request(model: &str, prompt: &str) -> CompletionRequest {
+        CompletionRequest {
+            model: model.to_owned(),
+            messages: vec![Message {
+                role: Role::User,
+                content: Content::Text(prompt.to_owned()),
+                name: None,
+                tool_calls: None,
+                tool_call_id: None,
+            }],
+            params: CompletionParams::default(),
+            tools: None,
+            tool_choice: None,
+            stream: true,
+        }
+    }
+
+    fn managed_context() -> RequestContext {
+        let mut context = RequestContext::empty();
+        context.billing_identity = Some(synapse_core::BillingIdentity {
+            entity_type: "user".to_owned(),
+            entity_id: "alice".to_owned(),
+            mode: synapse_core::BillingMode::Managed,
+        });
+        context
+    }
+
+    #[tokio::test]
+    async fn streamed_cascade_escalation_uses_the_escalation_provider_key() {
+        let (state, keys) = recording_state(&["cheap", "strong"]).await;
+        let request = user_request("cheap-model", "What is the capital of France?");
+        let mut context = managed_context();
+        state.resolve_api_key_for_request(&mut context, "cheap").unwrap();
+        let cascade = synapse_config::CascadeConfig {
+            escalation_model: Some("strong/strong-model".to_owned()),
+            confidence_threshold: 1.5,
+            ..synapse_config::CascadeConfig::default()
+        };
+
+        let provider = Arc::clone(&state.inner.providers["cheap"]);
+        let (provider_name, _, _) = state
+            .complete_stream_with_cascade(&request, &context, "cheap", "cheap-model", &provider, &cascade)
+            .await
+            .unwrap();
+
+        assert_eq!(provider_name, "strong");
+        assert_eq!(
+            *keys.lock().unwrap(),
+            [Some("sk-cheap".to_owned()), Some("sk-strong".to_owned())]
+        );
+    }
 
     // Verify the public API surface compiles
     #[test]
@@ -1556,4 +2929,149 @@ mod tests {
         let m = resolve_margin(None, &tier_margins, &managed_margins, "unknown");
         assert!((m - 1.0).abs() < f64::EPSILON);
     }
+
+    #[test]
+    fn shadow_context_drops_request_state() {
+        let mut context = RequestContext::empty();
+        context
+            .parts
+            .headers
+            .insert("x-conversation-id", "abc".parse().unwrap());
+        context.parts.extensions.insert(RoutingTrace::default());
+        context.api_key = Some(SecretString::from("sk-caller".to_owned()));
+        context
+            .provider_keys
+            .insert("openai".to_owned(), SecretString::from("sk-byok".to_owned()));
+
+        let shadow = shadow_context(&context);
+        assert!(shadow.parts.extensions.get::<RoutingTrace>().is_none());
+        assert!(shadow.api_key.is_none());
+        assert!(shadow.provider_keys.is_empty());
+        assert_eq!(shadow.parts.headers["x-conversation-id"], "abc");
+        assert!(shadow.parts.extensions.get::<ShadowTraffic>().is_some());
+    }
+
+    /// Embedder answering after `delay`, counting calls
+    struct SlowEmbedder {
+        delay: Duration,
+        calls: Arc<Mutex<u32>>,
+    }
+
+    #[async_trait]
+    impl QueryEmbedder for SlowEmbedder {
+        async fn embed(&self, inputs: Vec<String>) -> Result<Vec<Vec<f32>>, LlmError> {
+            *self.calls.lock().unwrap() += 1;
+            tokio::time::sleep(self.delay).await;
+            Ok(inputs.iter().map(|_| vec![1.0, 0.0]).collect())
+        }
+    }
+
+    #[tokio::test]
+    async fn knn_query_embedding_is_bounded_and_skipped_on_dry_runs() {
+        let calls = Arc::new(Mutex::new(0));
+        let mut state = LlmState::from_config(LlmConfig::default()).await.unwrap();
+        let mut config = RoutingConfig {
+            strategy: synapse_config::RoutingStrategy::Knn,
+            ..RoutingConfig::default()
+        };
+        config.knn.timeout_ms = 20;
+        let request = user_request("auto", "Write a haiku");
+        let context = RequestContext::empty();
+
+        let embed = |state: &mut LlmState, delay| {
+            Arc::get_mut(&mut state.inner).unwrap().query_embedder = Some(Arc::new(SlowEmbedder {
+                delay,
+                calls: Arc::clone(&calls),
+            }));
+        };
+        embed(&mut state, Duration::ZERO);
+        let profile = state.routing_profile(&request, &context, &config, false).await;
+        assert!(profile.embedding.is_some());
+        let profile = state.routing_profile(&request, &context, &config, true).await;
+        assert!(profile.embedding.is_none());
+        assert_eq!(*calls.lock().unwrap(), 1);
+
+        // A slow embeddings provider falls back to the heuristic profile
+        embed(&mut state, Duration::from_secs(60));
+        let started = Instant::now();
+        let profile = state.routing_profile(&request, &context, &config, false).await;
+        assert!(profile.embedding.is_none());
+        assert!(started.elapsed() < Duration::from_secs(30));
+    }
+
+    #[tokio::test]
+    async fn classifier_calls_never_use_caller_keys() {
+        let (mut state, keys) = recording_state(&["openai", "byok-only"]).await;
+        let inner = Arc::get_mut(&mut state.inner).unwrap();
+        inner.classifier = Classifier::new(&synapse_config::ClassifierConfig {
+            model: Some("openai/classifier".to_owned()),
+            ..synapse_config::ClassifierConfig::default()
+        });
+        inner.managed_keys.remove("byok-only");
+        let request = user_request("auto", "Write a haiku");
+        let mut context = RequestContext::empty();
+        context.billing_identity = Some(synapse_core::BillingIdentity {
+            entity_type: "user".to_owned(),
+            entity_id: "alice".to_owned(),
+            mode: synapse_core::BillingMode::Byok,
+        });
+        context.api_key = Some(SecretString::from("sk-caller".to_owned()));
+        context
+            .provider_keys
+            .insert("openai".to_owned(), SecretString::from("sk-byok".to_owned()));
+
+        // The managed key when configured, the provider's own key otherwise
+        state.classify(&request, &context, false).await;
+        Arc::get_mut(&mut state.inner).unwrap().classifier = Classifier::new(&synapse_config::ClassifierConfig {
+            model: Some("byok-only/classifier".to_owned()),
+            ..synapse_config::ClassifierConfig::default()
+        });
+        state.classify(&request, &context, false).await;
+
+        assert_eq!(*keys.lock().unwrap(), [Some("sk-openai".to_owned()), None]);
+    }
+
+    #[tokio::test]
+    async fn shadow_routing_ignores_conversation_pins() {
+        let profile = |model: &str, quality: f64| synapse_config::ModelProfileConfig {
+            provider: "mock".to_owned(),
+            model: model.to_owned(),
+            context_window: 128_000,
+            input_per_mtok: 1.0,
+            output_per_mtok: 4.0,
+            quality,
+            capabilities: synapse_config::ModelCapabilities::default(),
+        };
+        let mut config = LlmConfig::default();
+        config.routing.enabled = true;
+        config.routing.affinity.enabled = true;
+        config.routing.models = vec![profile("mock-small", 0.75), profile("mock-large", 0.95)];
+        let mut state = LlmState::from_config(config).await.unwrap();
+        let provider = RecordingProvider {
+            answer: "",
+            keys: Arc::default(),
+        };
+        Arc::get_mut(&mut state.inner)
+            .unwrap()
+            .providers
+            .insert("mock".to_owned(), Arc::new(provider));
+
+        let request = user_request("auto", "Hi");
+        let context = RequestContext::empty();
+        let affinity = state.inner.affinity.as_ref().unwrap();
+        let key = affinity.key("auto", &request, &context).unwrap();
+        let pin = PinnedModel {
+            provider: "mock".to_owned(),
+            model: "pinned-model".to_owned(),
+        };
+        affinity.pin(key, pin.clone()).await;
+
+        // The primary follows the pin, the shadow routes on its own
+        let (_, model, _, _) = state.resolve_provider("auto", &request, &context).await.unwrap();
+        assert_eq!(model, "pinned-model");
+        let shadow = shadow_context(&context);
+        let (_, model, _, _) = state.resolve_provider("auto", &request, &shadow).await.unwrap();
+        assert_ne!(model, "pinned-model");
+        assert_eq!(affinity.get(&key).await, Some(pin));
+    }
 }
@@ -0,0 +1,478 @@
+//! Distributed circuit breaker and feedback state
+//!
+//! Periodically pushes local circuit breaker errors, routing feedback, and
+//! user ratings to Valkey and adopts the merged fleet-wide state, so every
+//! replica makes the same circuit and scoring decisions. Request and error
+//! counts are kept in per-minute buckets that expire after the feedback
+//! window, and only other replicas' counts are merged into local feedback.
+//! Ratings are bucketed the same way over the longer ratings window, so
+//! old ratings age out instead of counting forever.
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+use std::time::Duration;
+
+use synapse_config::StateSyncConfig;
+use synapse_routing::feedback::MAX_SAMPLES;
+use synapse_routing::{FeedbackDelta, FeedbackTracker, RatingTotal, SharedFeedback, TaskType};
+
+use crate::health::{HealthDelta, ProviderHealthTracker, SharedHealth};
+
+/// Seconds covered by one feedback count bucket
+const BUCKET_SECS: u64 = 60;
+
+/// Buckets the ratings window is split into
+const RATING_BUCKETS: u64 = 12;
+
+/// Start the background state sync task
+///
+/// Sync failures are logged and retried on the next round; local state
+/// keeps working in the meantime.
+pub fn start_state_sync(
+    config: &StateSyncConfig,
+    providers: Vec<String>,
+    health: Arc<ProviderHealthTracker>,
+    feedback: Arc<FeedbackTracker>,
+) -> Result<(), redis::RedisError> {
+    let client = redis::Client::open(config.url.as_str())?;
+    let prefix = config.key_prefix.clone();
+    let interval = Duration::from_millis(config.interval_ms.max(1));
+    let mut pushed = PushedFeedback::new(config.feedback_window_secs);
+    let ratings = RatingWindow::new(config.ratings_window_secs);
+
+    tokio::spawn(async move {
+        let mut conn: Option<redis::aio::MultiplexedConnection> = None;
+        let mut ticker = tokio::time::interval(interval);
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+        loop {
+            ticker.tick().await;
+
+            if conn.is_none() {
+                match client.get_multiplexed_async_connection().await {
+                    Ok(c) => conn = Some(c),
+                    Err(e) => {
+                        tracing::warn!(error = %e, "state sync connection failed");
+                        continue;
+                    }
+                }
+            }
+            let Some(ref mut c) = conn else { continue };
+
+            let health_result = sync_health(c, &prefix, &providers, &health).await;
+            let feedback_result = sync_feedback(c, &prefix, &feedback, &mut pushed).await;
+            let ratings_result = sync_ratings(c, &prefix, &feedback, &ratings).await;
+
+            if let Err(e) = health_result.and(feedback_result).and(ratings_result) {
+                tracing::warn!(error = %e, "state sync round failed");
+                conn = None;
+            }
+        }
+    });
+
+    tracing::info!(
+        interval_ms = config.interval_ms,
+        "distributed routing state sync enabled"
+    );
+    Ok(())
+}
+
+fn circuit_keys(prefix: &str, provider: &str) -> (String, String) {
+    (
+        format!("{prefix}:circuit:{provider}:errors"),
+        format!("{prefix}:circuit:{provider}:opened_at"),
+    )
+}
+
+/// Push local circuit changes and adopt shared circuit state
+async fn sync_health(
+    conn: &mut redis::aio::MultiplexedConnection,
+    prefix: &str,
+    providers: &[String],
+    health: &ProviderHealthTracker,
+) -> redis::RedisResult<()> {
+    let config = health.config();
+    let window = config.window_seconds.max(1);
+    // Keep the open marker long enough to cover the recovery period
+    let open_ttl = config.recovery_seconds.saturating_add(window);
+
+    // Push: a local success closes the circuit fleet-wide, local errors add
+    // to the shared window count
+    let pending = health.drain_pending();
+    for HealthDelta {
+        provider,
+        errors,
+        reset,
+    } in &pending
+    {
+        let (errors_key, opened_key) = circuit_keys(prefix, provider);
+
+        if *reset {
+            let _: () = redis::pipe()
+                .del(&errors_key)
+                .del(&opened_key)
+                .query_async(conn)
+                .await?;
+        }
+
+        if *errors > 0 {
+            let (count,): (u32,) = redis::pipe()
+                .incr(&errors_key, *errors)
+                .expire(&errors_key, i64::try_from(window).unwrap_or(i64::MAX))
+                .arg("NX")
+                .ignore()
+                .query_async(conn)
+                .await?;
+
+            if count >= config.error_threshold {
+                let _: () = redis::cmd("SET")
+                    .arg(&opened_key)
+                    .arg(now_secs())
+                    .arg("EX")
+                    .arg(open_ttl)
+                    .query_async(conn)
+                    .await?;
+            }
+        }
+    }
+
+    // Pull: adopt the merged state for every configured provider
+    for provider in providers {
+        let (errors_key, opened_key) = circuit_keys(prefix, provider);
+        let (error_count, opened_at): (Option<u32>, Option<u64>) = redis::pipe()
+            .get(&errors_key)
+            .get(&opened_key)
+            .query_async(conn)
+            .await?;
+
+        health.apply_shared(
+            provider,
+            SharedHealth {
+                error_count: error_count.unwrap_or(0),
+                opened_at: opened_at.unwrap_or(0),
+            },
+        );
+    }
+
+    Ok(())
+}
+
+/// Feedback counts this replica pushed, per model and bucket
+///
+/// Subtracted from the fleet totals so only other replicas' counts are
+/// merged into the local statistics, which already include these.
+struct PushedFeedback {
+    /// Number of buckets in the feedback window
+    buckets: u64,
+    counts: HashMap<String, BTreeMap<u64, (u64, u64)>>,
+}
+
+impl PushedFeedback {
+    fn new(window_secs: u64) -> Self {
+        Self {
+            buckets: window_secs.div_ceil(BUCKET_SECS).max(1),
+            counts: HashMap::new(),
+        }
+    }
+
+    /// Buckets inside the window ending at `current`, oldest first
+    const fn window(&self, current: u64) -> std::ops::RangeInclusive<u64> {
+        current.saturating_sub(self.buckets - 1)..=current
+    }
+
+    /// Seconds a bucket is kept in Valkey; one bucket longer than the
+    /// window so a bucket never expires while it is still read
+    const fn ttl_secs(&self) -> u64 {
+        (self.buckets + 1) * BUCKET_SECS
+    }
+
+    fn record(&mut self, key: &str, bucket: u64, requests: u64, errors: u64) {
+        let count = self
+            .counts
+            .entry(key.to_owned())
+            .or_default()
+            .entry(bucket)
+            .or_default();
+        count.0 += requests;
+        count.1 += errors;
+    }
+
+    /// Requests and errors this replica pushed within the window, dropping
+    /// buckets that have left it
+    fn within(&mut self, key: &str, current: u64) -> (u64, u64) {
+        let oldest = *self.window(current).start();
+        let Some(buckets) = self.counts.get_mut(key) else {
+            return (0, 0);
+        };
+        buckets.retain(|&bucket, _| bucket >= oldest);
+        buckets
+            .values()
+            .fold((0, 0), |(requests, errors), &(r, e)| (requests + r, errors + e))
+    }
+}
+
+fn feedback_bucket_keys(base: &str, part: &str, buckets: impl IntoIterator<Item = u64>) -> Vec<String> {
+    buckets
+        .into_iter()
+        .map(|bucket| format!("{base}:{part}:{bucket}"))
+        .collect()
+}
+
+/// Push local feedback samples and merge other replicas' recent feedback
+async fn sync_feedback(
+    conn: &mut redis::aio::MultiplexedConnection,
+    prefix: &str,
+    feedback: &FeedbackTracker,
+    pushed: &mut PushedFeedback,
+) -> redis::RedisResult<()> {
+    let models_key = format!("{prefix}:feedback:models");
+    let max_samples = isize::try_from(MAX_SAMPLES).unwrap_or(isize::MAX);
+    let ttl = i64::try_from(pushed.ttl_secs()).unwrap_or(i64::MAX);
+    let bucket = now_secs() / BUCKET_SECS;
+
+    for FeedbackDelta {
+        key,
+        latencies_ms,
+        requests,
+        errors,
+    } in feedback.drain_pending()
+    {
+        let base = format!("{prefix}:feedback:{key}");
+        let requests_key = format!("{base}:requests:{bucket}");
+        let mut pipe = redis::pipe();
+        pipe.sadd(&models_key, &key).ignore();
+        pipe.incr(&requests_key, requests).ignore();
+        pipe.expire(&requests_key, ttl).ignore();
+        if errors > 0 {
+            let errors_key = format!("{base}:errors:{bucket}");
+            pipe.incr(&errors_key, errors).ignore();
+            pipe.expire(&errors_key, ttl).ignore();
+        }
+        if !latencies_ms.is_empty() {
+            pipe.rpush(format!("{base}:latency"), &latencies_ms).ignore();
+            pipe.ltrim(format!("{base}:latency"), -max_samples, -1).ignore();
+            pipe.expire(format!("{base}:latency"), ttl).ignore();
+        }
+        let _: () = pipe.query_async(conn).await?;
+        pushed.record(&key, bucket, requests, errors);
+    }
+
+    // Include models only other replicas have seen
+    let mut keys: Vec<String> = redis::cmd("SMEMBERS").arg(&models_key).query_async(conn).await?;
+    for key in feedback.model_keys() {
+        if !keys.contains(&key) {
+            keys.push(key);
+        }
+    }
+
+    let bucket = now_secs() / BUCKET_SECS;
+    for key in keys {
+        let base = format!("{prefix}:feedback:{key}");
+        let mut pipe = redis::pipe();
+        pipe.lrange(format!("{base}:latency"), 0, -1);
+        pipe.cmd("MGET")
+            .arg(feedback_bucket_keys(&base, "requests", pushed.window(bucket)));
+        pipe.cmd("MGET")
+            .arg(feedback_bucket_keys(&base, "errors", pushed.window(bucket)));
+        let (latencies_ms, fleet_requests, fleet_errors): (Vec<f64>, Vec<Option<u64>>, Vec<Option<u64>>) =
+            pipe.query_async(conn).await?;
+
+        let fleet_requests: u64 = fleet_requests.into_iter().flatten().sum();
+        let fleet_errors: u64 = fleet_errors.into_iter().flatten().sum();
+        let (own_requests, own_errors) = pushed.within(&key, bucket);
+
+        feedback.apply_shared(
+            &key,
+            SharedFeedback {
+                latencies_ms,
+                peer_requests: fleet_requests.saturating_sub(own_requests),
+                peer_errors: fleet_errors.saturating_sub(own_errors),
+            },
+        );
+    }
+
+    Ok(())
+}
+
+/// Bucketing of fleet ratings over the ratings window
+struct RatingWindow {
+    bucket_secs: u64,
+}
+
+impl RatingWindow {
+    fn new(window_secs: u64) -> Self {
+        Self {
+            bucket_secs: window_secs.div_ceil(RATING_BUCKETS).max(1),
+        }
+    }
+
+    const fn bucket(&self, now: u64) -> u64 {
+        now / self.bucket_secs
+    }
+
+    /// Time to live of a rating bucket, longer than it stays in the window
+    const fn ttl_secs(&self) -> u64 {
+        (RATING_BUCKETS + 2) * self.bucket_secs
+    }
+}
+
+/// Rating buckets within the window ending at `current`, including the
+/// partly elapsed oldest one
+const fn rating_buckets(current: u64) -> std::ops::RangeInclusive<u64> {
+    current.saturating_sub(RATING_BUCKETS)..=current
+}
+
+/// Push local user ratings and adopt the totals merged over the window
+///
+/// Each model's ratings live in one hash per bucket with `<task>:sum` and
+/// `<task>:count` fields, `any` standing for ratings without a task type.
+async fn sync_ratings(
+    conn: &mut redis::aio::MultiplexedConnection,
+    prefix: &str,
+    feedback: &FeedbackTracker,
+    window: &RatingWindow,
+) -> redis::RedisResult<()> {
+    let models_key = format!("{prefix}:ratings:models");
+    let ttl = i64::try_from(window.ttl_secs()).unwrap_or(i64::MAX);
+    let bucket = window.bucket(now_secs());
+
+    for RatingTotal {
+        key,
+        task_type,
+        sum,
+        count,
+    } in feedback.drain_pending_ratings()
+    {
+        let hash = format!("{prefix}:ratings:{key}:{bucket}");
+        let task = task_type.map_or("any", TaskType::as_str);
+        let _: () = redis::pipe()
+            .sadd(&models_key, &key)
+            .ignore()
+            .expire(&models_key, ttl)
+            .ignore()
+            .hincr(&hash, format!("{task}:sum"), sum)
+            .ignore()
+            .hincr(&hash, format!("{task}:count"), count)
+            .ignore()
+            .expire(&hash, ttl)
+            .ignore()
+            .query_async(conn)
+            .await?;
+    }
+
+    // Include models whose fleet ratings have all expired
+    let mut keys: Vec<String> = redis::cmd("SMEMBERS").arg(&models_key).query_async(conn).await?;
+    for key in feedback.rated_keys() {
+        if !keys.contains(&key) {
+            keys.push(key);
+        }
+    }
+
+    for key in keys {
+        let mut pipe = redis::pipe();
+        for bucket in rating_buckets(bucket) {
+            pipe.cmd("HGETALL").arg(format!("{prefix}:ratings:{key}:{bucket}"));
+        }
+        let buckets: Vec<HashMap<String, String>> = pipe.query_async(conn).await?;
+
+        let totals = rating_totals(&key, buckets.into_iter().flatten());
+        feedback.replace_shared_ratings(&key, totals);
+    }
+
+    Ok(())
+}
+
+/// Sum `<task>:sum` and `<task>:count` fields into totals per task type
+fn rating_totals(key: &str, fields: impl IntoIterator<Item = (String, String)>) -> Vec<RatingTotal> {
+    let mut totals: HashMap<Option<TaskType>, (f64, u64)> = HashMap::new();
+    for (field, value) in fields {
+        let Some((task, part)) = field.rsplit_once(':') else {
+            continue;
+        };
+        let task_type = if task == "any" {
+            None
+        } else {
+            match serde_json::from_value(serde_json::Value::String(task.to_owned())) {
+                Ok(task_type) => Some(task_type),
+                Err(_) => continue,
+            }
+        };
+        let total = totals.entry(task_type).or_default();
+        match part {
+            "sum" => total.0 += value.parse().unwrap_or(0.0),
+            "count" => total.1 += value.parse().unwrap_or(0),
+            _ => {}
+        }
+    }
+
+    totals
+        .into_iter()
+        .map(|(task_type, (sum, count))| RatingTotal {
+            key: key.to_owned(),
+            task_type,
+            sum,
+            count,
+        })
+        .collect()
+}
+
+fn now_secs() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pushed_feedback_only_counts_buckets_within_the_window() {
+        let mut pushed = PushedFeedback::new(180);
+        assert_eq!(pushed.window(100), 98..=100);
+
+        pushed.record("p/m", 97, 5, 5);
+        pushed.record("p/m", 99, 3, 1);
+        pushed.record("p/m", 100, 2, 0);
+        assert_eq!(pushed.within("p/m", 100), (5, 1));
+        assert_eq!(pushed.within("p/other", 100), (0, 0));
+
+        // Buckets that left the window are dropped for good
+        assert_eq!(pushed.within("p/m", 102), (2, 0));
+        assert_eq!(pushed.within("p/m", 100), (2, 0));
+    }
+
+    #[test]
+    fn ratings_age_out_of_the_window() {
+        let window = RatingWindow::new(12 * 3600);
+        assert_eq!(window.bucket(7 * 3600 + 5), 7);
+        assert_eq!(rating_buckets(20), 8..=20);
+        assert!(window.ttl_secs() > 13 * 3600);
+    }
+
+    #[test]
+    fn rating_totals_sum_buckets_per_task_type() {
+        let field = |name: &str, value: &str| (name.to_owned(), value.to_owned());
+        let mut totals = rating_totals(
+            "p/m",
+            [
+                field("code:sum", "1.5"),
+                field("code:count", "2"),
+                field("code:sum", "0.5"),
+                field("code:count", "1"),
+                field("any:count", "1"),
+                field("any:sum", "1"),
+                field("unknown:sum", "9"),
+            ],
+        );
+        totals.sort_by_key(|total| total.task_type.is_some());
+
+        assert_eq!(totals.len(), 2);
+        assert_eq!(totals[0].task_type, None);
+        assert_eq!(totals[0].count, 1);
+        assert_eq!(totals[1].task_type, Some(TaskType::Code));
+        assert_eq!(totals[1].count, 3);
+        assert!((totals[1].sum - 2.0).abs() < f64::EPSILON);
+    }
+}
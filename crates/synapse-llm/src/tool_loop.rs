@@ -0,0 +1,593 @@
+//! Server-side execution of gateway tool calls
+//!
+//! A completion that opts in is offered the tools the caller may use on
+//! the gateway. While the model calls only those tools, the gateway runs
+//! the calls, appends the results to the conversation, and asks the model
+//! again, until it answers or the iteration cap is reached. Calls to tools
+//! the client defined itself end the loop and are returned to the client.
+
+use std::collections::{HashSet, VecDeque};
+use std::pin::Pin;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use futures_util::{Stream, StreamExt};
+use serde::{Deserialize, Serialize};
+use synapse_core::{HttpError, RequestContext};
+
+use crate::error::LlmError;
+use crate::provider::Provider;
+use crate::state::LlmState;
+use crate::types::{
+    CompletionRequest, CompletionResponse, Content, FunctionCall, Message, Role, StreamDelta, StreamEvent, ToolCall,
+    ToolDefinition, ToolStep, Usage,
+};
+
+type EventStream = Pin<Box<dyn Stream<Item = Result<StreamEvent, LlmError>> + Send>>;
+
+/// Most tool calls one streamed model turn may make
+const MAX_TOOL_CALLS: usize = 128;
+
+/// Request extension opting a chat completion into the tool loop
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ToolLoopRequest {
+    /// Tools to offer, by qualified tool name or server name; every tool
+    /// the caller may use when empty
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tools: Vec<String>,
+    /// Rounds of tool calls before the response is returned, capped by
+    /// the configured maximum
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_iterations: Option<u32>,
+}
+
+/// Output of a tool call, as text for the model
+#[derive(Debug, Clone)]
+pub struct ToolOutput {
+    /// Tool output
+    pub content: String,
+    /// Whether the tool failed
+    pub is_error: bool,
+}
+
+/// Runs gateway tools on behalf of callers
+///
+/// Implemented by the server on top of the MCP subsystem, so this crate
+/// does not depend on it. Both methods apply the caller's access rules.
+#[async_trait]
+pub trait ToolExecutor: Send + Sync {
+    /// Tools the caller may use, narrowed to `selection` when not empty
+    async fn tools(&self, context: &RequestContext, selection: &[String]) -> Result<Vec<ToolDefinition>, LlmError>;
+
+    /// Call a tool with JSON-encoded arguments
+    ///
+    /// Failures of the tool itself are outputs, not errors.
+    async fn call(&self, context: &RequestContext, name: &str, arguments: &str) -> Result<ToolOutput, LlmError>;
+}
+
+/// Tool executor and iteration cap of an enabled tool loop
+pub(crate) struct ToolLoop {
+    executor: Arc<dyn ToolExecutor>,
+    max_iterations: u32,
+}
+
+impl LlmState {
+    /// Run gateway tools for completions that opt in
+    ///
+    /// Must be called before the state is shared with handlers.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the state has already been cloned
+    pub fn enable_tool_loop(&mut self, executor: Arc<dyn ToolExecutor>, max_iterations: u32) {
+        let inner = Arc::get_mut(&mut self.inner).expect("enable_tool_loop must be called before state is shared");
+        inner.tool_loop = Some(ToolLoop {
+            executor,
+            max_iterations,
+        });
+    }
+
+    /// Execute a non-streaming completion, running the gateway tools the
+    /// model calls until it answers
+    ///
+    /// The request is routed once; later turns go to the model that served
+    /// the first. The returned usage covers every model turn.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the tool loop is disabled, the caller has no
+    /// tools, or a model turn fails
+    pub async fn complete_with_tools(
+        &self,
+        mut request: CompletionRequest,
+        context: RequestContext,
+        options: &ToolLoopRequest,
+    ) -> Result<CompletionResponse, LlmError> {
+        let turns = self.offer_tools(&mut request, &context, options).await?;
+        self.complete_routed(request, context, Some(turns)).await
+    }
+
+    /// Execute a streaming completion, running the gateway tools the
+    /// model calls until it answers
+    ///
+    /// The request is routed once; later turns go to the model that served
+    /// the first. Text of every model turn streams through. Tool calls and
+    /// their results stream as [`ToolStep`] events, and usage summed over
+    /// all turns is sent before the stream completes.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the tool loop is disabled, the caller has no
+    /// tools, or the first model turn fails
+    pub async fn complete_stream_with_tools(
+        &self,
+        mut request: CompletionRequest,
+        context: RequestContext,
+        options: &ToolLoopRequest,
+    ) -> Result<(String, EventStream), LlmError> {
+        let turns = self.offer_tools(&mut request, &context, options).await?;
+        self.complete_stream_routed(request, context, Some(turns)).await
+    }
+
+    /// Run the gateway tools called in a served response and ask again
+    /// until the model answers
+    ///
+    /// Returns the final response, with usage summed over every turn, and
+    /// the provider and model that served it.
+    pub(crate) async fn run_tool_turns(
+        &self,
+        request: &CompletionRequest,
+        context: &RequestContext,
+        served: (String, String, CompletionResponse),
+        explicit_provider: bool,
+        mut turns: ToolTurns,
+    ) -> Result<(String, String, CompletionResponse), LlmError> {
+        let mut request = request.clone();
+        let (mut provider_name, mut model_id, mut response) = served;
+        let mut usage = None;
+
+        loop {
+            if let Some(ref turn_usage) = response.usage {
+                add_usage(&mut usage, turn_usage);
+            }
+
+            let calls = response
+                .choices
+                .first()
+                .and_then(|choice| choice.message.tool_calls.as_deref())
+                .filter(|calls| turns.remaining > 0 && only_gateway_calls(calls, &turns.gateway_tools))
+                .map(<[ToolCall]>::to_vec);
+            let Some(calls) = calls else {
+                response.usage = usage;
+                return Ok((provider_name, model_id, response));
+            };
+            turns.remaining -= 1;
+
+            let text = response.choices[0].message.content.take().unwrap_or_default();
+            request.messages.push(assistant_turn(text, calls.clone()));
+            let outputs = run_calls(turns.executor.as_ref(), context, &calls).await;
+            for (call, output) in calls.iter().zip(outputs) {
+                request.messages.push(tool_turn(call, output));
+            }
+
+            let provider = self.served_provider(&provider_name)?;
+            (provider_name, model_id, response) = if explicit_provider {
+                self.complete_direct(&request, context, &provider_name, &model_id, &provider)
+                    .await
+                    .map(|response| (provider_name, model_id, response))?
+            } else {
+                self.complete_with_failover(&request, context, &provider_name, &model_id, &provider)
+                    .await?
+            };
+        }
+    }
+
+    /// Run the gateway tools called in a served stream, streaming the
+    /// following turns after it
+    pub(crate) fn stream_tool_turns(
+        &self,
+        stream: EventStream,
+        request: &CompletionRequest,
+        context: &RequestContext,
+        served: (&str, &str),
+        explicit_provider: bool,
+        turns: ToolTurns,
+    ) -> EventStream {
+        let run = StreamingLoop {
+            state: self.clone(),
+            request: request.clone(),
+            context: context.clone(),
+            provider_name: served.0.to_owned(),
+            model_id: served.1.to_owned(),
+            explicit_provider,
+            turns,
+            stream: Some(stream),
+            turn: Turn::default(),
+            queue: VecDeque::new(),
+            pending: None,
+            usage: None,
+        };
+
+        Box::pin(futures_util::stream::unfold(run, |mut run| async move {
+            run.next_event().await.map(|event| (event, run))
+        }))
+    }
+
+    fn served_provider(&self, provider_name: &str) -> Result<Arc<dyn Provider>, LlmError> {
+        self.inner
+            .providers
+            .get(provider_name)
+            .cloned()
+            .ok_or_else(|| LlmError::ProviderNotFound {
+                provider: provider_name.to_owned(),
+            })
+    }
+
+    /// Add the caller's gateway tools to a request
+    ///
+    async fn offer_tools(
+        &self,
+        request: &mut CompletionRequest,
+        context: &RequestContext,
+        options: &ToolLoopRequest,
+    ) -> Result<ToolTurns, LlmError> {
+        let tool_loop = self
+            .inner
+            .tool_loop
+            .as_ref()
+            .ok_or_else(|| LlmError::InvalidRequest("server-side tool execution is not enabled".to_owned()))?;
+
+        let tools = tool_loop.executor.tools(context, &options.tools).await?;
+        if tools.is_empty() {
+            return Err(LlmError::InvalidRequest(
+                "no tools are available for server-side execution".to_owned(),
+            ));
+        }
+        let gateway_tools = tools.iter().map(|tool| tool.function.name.clone()).collect();
+        request.tools.get_or_insert_with(Vec::new).extend(tools);
+
+        Ok(ToolTurns {
+            executor: Arc::clone(&tool_loop.executor),
+            gateway_tools,
+            remaining: options
+                .max_iterations
+                .map_or(tool_loop.max_iterations, |n| n.min(tool_loop.max_iterations)),
+        })
+    }
+}
+
+/// Gateway tools offered to a request and the rounds of tool calls left
+pub(crate) struct ToolTurns {
+    executor: Arc<dyn ToolExecutor>,
+    gateway_tools: HashSet<String>,
+    remaining: u32,
+}
+
+/// State of a streaming tool loop between events
+struct StreamingLoop {
+    state: LlmState,
+    request: CompletionRequest,
+    context: RequestContext,
+    /// Provider and model that served the latest turn
+    provider_name: String,
+    model_id: String,
+    /// Whether the caller selected the provider, ruling out failover
+    explicit_provider: bool,
+    turns: ToolTurns,
+    /// Stream of the current model turn, `None` once the loop is over
+    stream: Option<EventStream>,
+    /// What the model said so far in the current turn
+    turn: Turn,
+    /// Events ready to be sent
+    queue: VecDeque<Result<StreamEvent, LlmError>>,
+    /// Tool calls announced and waiting to run
+    pending: Option<Vec<ToolCall>>,
+    /// Usage summed over finished turns
+    usage: Option<Usage>,
+}
+
+/// Text and tool calls of one streamed model turn
+#[derive(Default)]
+struct Turn {
+    content: String,
+    calls: Vec<ToolCall>,
+    /// Tool call and finish deltas, held back until it is known whether
+    /// the loop goes on
+    held: Vec<StreamDelta>,
+}
+
+impl Turn {
+    /// Record a delta, returning what passes through to the client
+    ///
+    /// Text passes through; tool calls and the finish reason are held back.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a tool call index exceeds [`MAX_TOOL_CALLS`]
+    fn push(&mut self, mut delta: StreamDelta) -> Result<Option<StreamDelta>, LlmError> {
+        if let Some(ref content) = delta.content {
+            self.content.push_str(content);
+        }
+        if delta.tool_call.is_none() && delta.finish_reason.is_none() {
+            return Ok(Some(delta));
+        }
+
+        if let Some(ref tool_call) = delta.tool_call {
+            let index = tool_call.index as usize;
+            if index >= MAX_TOOL_CALLS {
+                return Err(LlmError::Upstream(format!(
+                    "model streamed tool call index {index}, at most {MAX_TOOL_CALLS} tool calls are supported"
+                )));
+            }
+            if self.calls.len() <= index {
+                self.calls.resize_with(index + 1, || ToolCall {
+                    id: String::new(),
+                    function: FunctionCall {
+                        name: String::new(),
+                        arguments: String::new(),
+                    },
+                });
+            }
+            let call = &mut self.calls[index];
+            if let Some(ref id) = tool_call.id {
+                call.id.clone_from(id);
+            }
+            if let Some(ref function) = tool_call.function {
+                if let Some(ref name) = function.name {
+                    call.function.name.clone_from(name);
+                }
+                if let Some(ref arguments) = function.arguments {
+                    call.function.arguments.push_str(arguments);
+                }
+            }
+        }
+
+        let text = delta.content.take().map(|content| StreamDelta {
+            index: delta.index,
+            content: Some(content),
+            tool_call: None,
+            finish_reason: None,
+        });
+        self.held.push(delta);
+        Ok(text)
+    }
+}
+
+impl StreamingLoop {
+    async fn next_event(&mut self) -> Option<Result<StreamEvent, LlmError>> {
+        loop {
+            if let Some(event) = self.queue.pop_front() {
+                return Some(event);
+            }
+
+            if let Some(calls) = self.pending.take()
+                && let Err(e) = self.run_calls(calls).await
+            {
+                self.stream = None;
+                return Some(Err(e));
+            }
+
+            match self.stream.as_mut()?.next().await {
+                Some(Ok(StreamEvent::Delta(delta))) => self.on_delta(delta),
+                Some(Ok(StreamEvent::Usage(usage))) => add_usage(&mut self.usage, &usage),
+                Some(Ok(step @ StreamEvent::ToolStep(_))) => self.queue.push_back(Ok(step)),
+                Some(Ok(StreamEvent::Done)) | None => self.end_turn(),
+                Some(Err(e)) => {
+                    self.stream = None;
+                    return Some(Err(e));
+                }
+            }
+        }
+    }
+
+    fn on_delta(&mut self, delta: StreamDelta) {
+        match self.turn.push(delta) {
+            Ok(Some(delta)) => self.queue.push_back(Ok(StreamEvent::Delta(delta))),
+            Ok(None) => {}
+            Err(e) => {
+                self.queue.push_back(Err(e));
+                self.stream = None;
+            }
+        }
+    }
+
+    /// Announce the tool calls of a finished turn, or finish the stream
+    fn end_turn(&mut self) {
+        let turn = std::mem::take(&mut self.turn);
+
+        if self.turns.remaining > 0 && only_gateway_calls(&turn.calls, &self.turns.gateway_tools) {
+            self.turns.remaining -= 1;
+            for call in &turn.calls {
+                self.queue.push_back(Ok(StreamEvent::ToolStep(ToolStep::ToolCall {
+                    id: call.id.clone(),
+                    name: call.function.name.clone(),
+                    arguments: call.function.arguments.clone(),
+                })));
+            }
+            self.request
+                .messages
+                .push(assistant_turn(turn.content, turn.calls.clone()));
+            self.pending = Some(turn.calls);
+            return;
+        }
+
+        self.queue
+            .extend(turn.held.into_iter().map(|delta| Ok(StreamEvent::Delta(delta))));
+        if let Some(usage) = self.usage.take() {
+            self.queue.push_back(Ok(StreamEvent::Usage(usage)));
+        }
+        self.queue.push_back(Ok(StreamEvent::Done));
+        self.stream = None;
+    }
+
+    /// Run announced tool calls and start the next model turn
+    async fn run_calls(&mut self, calls: Vec<ToolCall>) -> Result<(), LlmError> {
+        let outputs = run_calls(self.turns.executor.as_ref(), &self.context, &calls).await;
+        for (call, output) in calls.iter().zip(outputs) {
+            self.queue.push_back(Ok(StreamEvent::ToolStep(ToolStep::ToolResult {
+                id: call.id.clone(),
+                name: call.function.name.clone(),
+                content: output.content.clone(),
+                is_error: output.is_error,
+            })));
+            self.request.messages.push(tool_turn(call, output));
+        }
+
+        let provider = self.state.served_provider(&self.provider_name)?;
+        let (provider_name, model_id, stream) = if self.explicit_provider {
+            self.state
+                .complete_stream_direct(
+                    &self.request,
+                    &self.context,
+                    &self.provider_name,
+                    &self.model_id,
+                    &provider,
+                )
+                .await?
+        } else {
+            self.state
+                .complete_stream_with_failover(
+                    &self.request,
+                    &self.context,
+                    &self.provider_name,
+                    &self.model_id,
+                    &provider,
+                )
+                .await?
+        };
+        self.provider_name = provider_name;
+        self.model_id = model_id;
+        self.stream = Some(stream);
+        Ok(())
+    }
+}
+
+/// Whether the model called tools, all of them run by the gateway
+fn only_gateway_calls(calls: &[ToolCall], gateway_tools: &HashSet<String>) -> bool {
+    !calls.is_empty() && calls.iter().all(|call| gateway_tools.contains(&call.function.name))
+}
+
+/// Run tool calls concurrently, turning errors into outputs the model can
+/// read
+async fn run_calls(executor: &dyn ToolExecutor, context: &RequestContext, calls: &[ToolCall]) -> Vec<ToolOutput> {
+    let runs = calls.iter().map(|call| async move {
+        executor
+            .call(context, &call.function.name, &call.function.arguments)
+            .await
+            .unwrap_or_else(|e| {
+                tracing::warn!(tool = %call.function.name, error = %e, "server-side tool call failed");
+                ToolOutput {
+                    content: e.client_message(),
+                    is_error: true,
+                }
+            })
+    });
+
+    futures_util::future::join_all(runs).await
+}
+
+const fn assistant_turn(content: String, calls: Vec<ToolCall>) -> Message {
+    Message {
+        role: Role::Assistant,
+        content: Content::Text(content),
+        name: None,
+        tool_calls: Some(calls),
+        tool_call_id: None,
+    }
+}
+
+fn tool_turn(call: &ToolCall, output: ToolOutput) -> Message {
+    Message {
+        role: Role::Tool,
+        content: Content::Text(output.content),
+        name: None,
+        tool_calls: None,
+        tool_call_id: Some(call.id.clone()),
+    }
+}
+
+fn add_usage(total: &mut Option<Usage>, usage: &Usage) {
+    let total = total.get_or_insert_with(Usage::default);
+    total.prompt_tokens += usage.prompt_tokens;
+    total.completion_tokens += usage.completion_tokens;
+    total.total_tokens += usage.total_tokens;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{StreamFunctionCall, StreamToolCall};
+
+    fn tool_call(name: &str) -> ToolCall {
+        ToolCall {
+            id: format!("call_{name}"),
+            function: FunctionCall {
+                name: name.to_owned(),
+                arguments: "{}".to_owned(),
+            },
+        }
+    }
+
+    #[test]
+    fn loop_continues_only_when_every_call_is_a_gateway_tool() {
+        let gateway_tools = HashSet::from(["mock__echo".to_owned()]);
+
+        assert!(only_gateway_calls(&[tool_call("mock__echo")], &gateway_tools));
+        assert!(!only_gateway_calls(
+            &[tool_call("mock__echo"), tool_call("get_weather")],
+            &gateway_tools
+        ));
+        assert!(!only_gateway_calls(&[], &gateway_tools));
+    }
+
+    #[test]
+    fn streamed_tool_calls_are_assembled_from_fragments() {
+        let mut turn = Turn::default();
+        let fragments = [
+            (Some("call_1"), Some("mock__echo"), None),
+            (None, None, Some("{\"text\":")),
+            (None, None, Some("\"hi\"}")),
+        ];
+
+        for (id, name, arguments) in fragments {
+            let passed = turn.push(StreamDelta {
+                index: 0,
+                content: None,
+                tool_call: Some(StreamToolCall {
+                    index: 0,
+                    id: id.map(str::to_owned),
+                    function: Some(StreamFunctionCall {
+                        name: name.map(str::to_owned),
+                        arguments: arguments.map(str::to_owned),
+                    }),
+                }),
+                finish_reason: None,
+            });
+            assert!(passed.unwrap().is_none());
+        }
+
+        assert_eq!(turn.calls.len(), 1);
+        assert_eq!(turn.calls[0].id, "call_1");
+        assert_eq!(turn.calls[0].function.name, "mock__echo");
+        assert_eq!(turn.calls[0].function.arguments, "{\"text\":\"hi\"}");
+        assert_eq!(turn.held.len(), 3);
+    }
+
+    #[test]
+    fn streamed_tool_call_indices_are_capped() {
+        let mut turn = Turn::default();
+        let result = turn.push(StreamDelta {
+            index: 0,
+            content: None,
+            tool_call: Some(StreamToolCall {
+                index: u32::try_from(MAX_TOOL_CALLS).unwrap(),
+                id: Some("call_1".to_owned()),
+                function: None,
+            }),
+            finish_reason: None,
+        });
+
+        assert!(matches!(result, Err(LlmError::Upstream(_))));
+        assert!(turn.calls.is_empty());
+    }
+}
@@ -0,0 +1,525 @@
+//! Routing training-data export
+//!
+//! Writes one sample per smart-routed request for training the ONNX router
+//! offline: the feature vector `OnnxStrategy` computes, the routing
+//! decision, latency, token usage, and the user's rating when feedback
+//! arrives within the rating window. Samples are held on a blocking thread
+//! for that window, then appended to a CSV file or collected into Parquet
+//! files of `batch_size` rows.
+
+use std::collections::{HashMap, VecDeque};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, SyncSender};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use parquet::data_type::{ByteArray, ByteArrayType, DoubleType, FloatType, Int64Type};
+use parquet::file::properties::WriterProperties;
+use parquet::file::writer::SerializedFileWriter;
+use parquet::schema::parser::parse_message_type;
+use synapse_config::{TrainingDataConfig, TrainingDataFormat};
+use synapse_routing::strategy::onnx::{FEATURE_NAMES, NUM_FEATURES};
+
+use crate::error::LlmError;
+use crate::types::Usage;
+
+/// Samples and ratings buffered for the writer before new ones are dropped
+const SINK_CAPACITY: usize = 4096;
+
+/// Samples held for ratings before the oldest is written early
+const MAX_PENDING: usize = 100_000;
+
+/// Longest the writer sleeps between checks for expired samples
+const TICK: Duration = Duration::from_secs(1);
+
+/// Oldest a partial Parquet batch gets before it is written
+const MAX_BATCH_AGE: Duration = Duration::from_secs(300);
+
+/// Columns after the features and class label, shared by both formats
+const COLUMNS: [&str; 10] = [
+    "provider",
+    "model",
+    "strategy",
+    "reason",
+    "latency_ms",
+    "prompt_tokens",
+    "completion_tokens",
+    "rating",
+    "response_id",
+    "timestamp",
+];
+
+/// One smart-routed request and its outcome
+#[derive(Debug, Clone)]
+pub(crate) struct TrainingSample {
+    /// Response ID the user rates the response by
+    pub(crate) response_id: String,
+    /// Unix timestamp in seconds
+    pub(crate) timestamp: u64,
+    /// ONNX router features of the request
+    pub(crate) features: [f32; NUM_FEATURES],
+    /// ONNX class of the serving model (its position in the unfiltered
+    /// model registry), the class label
+    pub(crate) class: Option<usize>,
+    pub(crate) provider: String,
+    pub(crate) model: String,
+    pub(crate) strategy: String,
+    pub(crate) reason: &'static str,
+    pub(crate) latency_ms: u64,
+    pub(crate) prompt_tokens: Option<u32>,
+    pub(crate) completion_tokens: Option<u32>,
+    /// User rating (0.0 to 1.0), filled in by the writer
+    pub(crate) rating: Option<f64>,
+}
+
+impl TrainingSample {
+    /// Fill in the latency and token usage of the completed request
+    pub(crate) fn with_outcome(mut self, latency: Duration, usage: Option<&Usage>) -> Self {
+        self.latency_ms = u64::try_from(latency.as_millis()).unwrap_or(u64::MAX);
+        self.prompt_tokens = usage.map(|u| u.prompt_tokens);
+        self.completion_tokens = usage.map(|u| u.completion_tokens);
+        self
+    }
+
+    /// Current Unix timestamp in seconds
+    pub(crate) fn now() -> u64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0)
+    }
+}
+
+enum SinkMessage {
+    Sample(Box<TrainingSample>),
+    Rating { response_id: String, rating: f64 },
+}
+
+/// Queue feeding the training-data writer thread
+pub(crate) struct TrainingSink {
+    sender: SyncSender<SinkMessage>,
+}
+
+impl TrainingSink {
+    /// Open the output and start the writer
+    pub(crate) fn new(config: &TrainingDataConfig) -> Result<Self, LlmError> {
+        let writer = SampleWriter::open(config)
+            .map_err(|e| LlmError::Internal(anyhow::anyhow!("failed to open training data sink: {e}")))?;
+        let (sender, receiver) = mpsc::sync_channel(SINK_CAPACITY);
+        let window = Duration::from_secs(config.rating_window_secs);
+
+        tokio::task::spawn_blocking(move || run(writer, &receiver, window));
+
+        tracing::info!(
+            path = %config.path.display(),
+            format = ?config.format,
+            "routing training data export enabled"
+        );
+
+        Ok(Self { sender })
+    }
+
+    /// Queue a sample, dropping it if the writer is behind
+    pub(crate) fn record(&self, sample: TrainingSample) {
+        if self.sender.try_send(SinkMessage::Sample(Box::new(sample))).is_err() {
+            tracing::warn!("training data sink is full or closed, dropping sample");
+        }
+    }
+
+    /// Attach a user rating to a sample still held by the writer
+    pub(crate) fn rate(&self, response_id: &str, rating: f64) {
+        let message = SinkMessage::Rating {
+            response_id: response_id.to_owned(),
+            rating,
+        };
+        if self.sender.try_send(message).is_err() {
+            tracing::warn!("training data sink is full or closed, dropping rating");
+        }
+    }
+}
+
+/// Hold samples for the rating window, then write them
+fn run(mut writer: SampleWriter, receiver: &Receiver<SinkMessage>, window: Duration) {
+    let mut pending: VecDeque<(Instant, TrainingSample)> = VecDeque::new();
+    let mut ratings: HashMap<String, f64> = HashMap::new();
+
+    loop {
+        let timeout = pending.front().map_or(TICK, |(received, _)| {
+            window.saturating_sub(received.elapsed()).min(TICK)
+        });
+
+        let closed = match receiver.recv_timeout(timeout) {
+            Ok(SinkMessage::Sample(sample)) => {
+                pending.push_back((Instant::now(), *sample));
+                false
+            }
+            Ok(SinkMessage::Rating { response_id, rating }) => {
+                if pending.iter().any(|(_, sample)| sample.response_id == response_id) {
+                    ratings.insert(response_id, rating);
+                }
+                false
+            }
+            Err(RecvTimeoutError::Timeout) => false,
+            Err(RecvTimeoutError::Disconnected) => true,
+        };
+
+        while let Some((received, _)) = pending.front()
+            && (closed || received.elapsed() >= window || pending.len() > MAX_PENDING)
+        {
+            let Some((_, mut sample)) = pending.pop_front() else {
+                break;
+            };
+            sample.rating = ratings.remove(&sample.response_id);
+            writer.write(sample);
+        }
+
+        writer.flush(closed);
+        if closed {
+            return;
+        }
+    }
+}
+
+/// Output file or directory for samples
+enum SampleWriter {
+    Csv {
+        path: PathBuf,
+        file: BufWriter<File>,
+    },
+    Parquet {
+        dir: PathBuf,
+        batch: Vec<TrainingSample>,
+        batch_size: usize,
+        started: Instant,
+    },
+}
+
+impl SampleWriter {
+    fn open(config: &TrainingDataConfig) -> std::io::Result<Self> {
+        match config.format {
+            TrainingDataFormat::Csv => {
+                let file = std::fs::OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(&config.path)?;
+                let mut file = BufWriter::new(file);
+                if file.get_ref().metadata()?.len() == 0 {
+                    writeln!(file, "{}", csv_header())?;
+                    file.flush()?;
+                }
+                Ok(Self::Csv {
+                    path: config.path.clone(),
+                    file,
+                })
+            }
+            TrainingDataFormat::Parquet => {
+                std::fs::create_dir_all(&config.path)?;
+                Ok(Self::Parquet {
+                    dir: config.path.clone(),
+                    batch: Vec::new(),
+                    batch_size: config.batch_size.max(1),
+                    started: Instant::now(),
+                })
+            }
+        }
+    }
+
+    fn write(&mut self, sample: TrainingSample) {
+        match self {
+            Self::Csv { path, file } => {
+                if let Err(e) = writeln!(file, "{}", csv_row(&sample)) {
+                    tracing::warn!(path = %path.display(), error = %e, "failed to write training sample");
+                }
+            }
+            Self::Parquet { batch, started, .. } => {
+                if batch.is_empty() {
+                    *started = Instant::now();
+                }
+                batch.push(sample);
+            }
+        }
+    }
+
+    /// Flush buffered CSV rows, or write the Parquet batch once it is full,
+    /// old enough, or `force` is set
+    fn flush(&mut self, force: bool) {
+        match self {
+            Self::Csv { path, file } => {
+                if let Err(e) = file.flush() {
+                    tracing::warn!(path = %path.display(), error = %e, "failed to flush training data");
+                }
+            }
+            Self::Parquet {
+                dir,
+                batch,
+                batch_size,
+                started,
+            } => {
+                let due = batch.len() >= *batch_size || started.elapsed() >= MAX_BATCH_AGE || force;
+                if batch.is_empty() || !due {
+                    return;
+                }
+
+                let path = dir.join(format!(
+                    "routing-{}-{}.parquet",
+                    TrainingSample::now(),
+                    uuid::Uuid::new_v4()
+                ));
+                match write_parquet(&path, batch) {
+                    Ok(()) => tracing::debug!(path = %path.display(), rows = batch.len(), "wrote training data batch"),
+                    Err(e) => tracing::warn!(path = %path.display(), error = %e, "failed to write training data batch"),
+                }
+                batch.clear();
+            }
+        }
+    }
+}
+
+/// Features, class label, then the remaining columns, in file order
+fn column_names() -> impl Iterator<Item = &'static str> {
+    FEATURE_NAMES.into_iter().chain(["selected_model"]).chain(COLUMNS)
+}
+
+fn csv_header() -> String {
+    column_names().collect::<Vec<_>>().join(",")
+}
+
+fn csv_row(sample: &TrainingSample) -> String {
+    let mut fields: Vec<String> = sample.features.iter().map(ToString::to_string).collect();
+    fields.extend([
+        optional(sample.class),
+        csv_field(&sample.provider),
+        csv_field(&sample.model),
+        csv_field(&sample.strategy),
+        sample.reason.to_owned(),
+        sample.latency_ms.to_string(),
+        optional(sample.prompt_tokens),
+        optional(sample.completion_tokens),
+        optional(sample.rating),
+        csv_field(&sample.response_id),
+        sample.timestamp.to_string(),
+    ]);
+    fields.join(",")
+}
+
+fn optional<T: ToString>(value: Option<T>) -> String {
+    value.map(|v| v.to_string()).unwrap_or_default()
+}
+
+/// Quote a field containing separators, quotes, or line breaks
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_owned()
+    }
+}
+
+fn parquet_schema() -> String {
+    let features = FEATURE_NAMES
+        .iter()
+        .map(|name| format!("required float {name};"))
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    format!(
+        "message routing_sample {{ {features} optional int64 selected_model; \
+         required binary provider (UTF8); required binary model (UTF8); \
+         required binary strategy (UTF8); required binary reason (UTF8); \
+         required int64 latency_ms; optional int64 prompt_tokens; optional int64 completion_tokens; \
+         optional double rating; required binary response_id (UTF8); required int64 timestamp; }}"
+    )
+}
+
+/// Write a batch of samples as one Parquet file, column by column
+fn write_parquet(path: &Path, samples: &[TrainingSample]) -> anyhow::Result<()> {
+    let schema = Arc::new(parse_message_type(&parquet_schema())?);
+    let file = File::create(path)?;
+    let mut writer = SerializedFileWriter::new(file, schema, Arc::new(WriterProperties::builder().build()))?;
+    let mut row_group = writer.next_row_group()?;
+    let mut names = column_names();
+
+    while let Some(mut column) = row_group.next_column()? {
+        let name = names.next().unwrap_or_default();
+        if let Some(i) = FEATURE_NAMES.iter().position(|feature| *feature == name) {
+            let values: Vec<f32> = samples.iter().map(|s| s.features[i]).collect();
+            column.typed::<FloatType>().write_batch(&values, None, None)?;
+        } else {
+            match name {
+                "selected_model" => write_optional::<Int64Type, _>(&mut column, samples, |s| {
+                    s.class.and_then(|c| i64::try_from(c).ok())
+                })?,
+                "provider" => write_strings(&mut column, samples, |s| &s.provider)?,
+                "model" => write_strings(&mut column, samples, |s| &s.model)?,
+                "strategy" => write_strings(&mut column, samples, |s| &s.strategy)?,
+                "reason" => write_strings(&mut column, samples, |s| s.reason)?,
+                "response_id" => write_strings(&mut column, samples, |s| &s.response_id)?,
+                "latency_ms" => write_required::<Int64Type, _>(&mut column, samples, |s| saturating_i64(s.latency_ms))?,
+                "timestamp" => write_required::<Int64Type, _>(&mut column, samples, |s| saturating_i64(s.timestamp))?,
+                "prompt_tokens" => {
+                    write_optional::<Int64Type, _>(&mut column, samples, |s| s.prompt_tokens.map(i64::from))?;
+                }
+                "completion_tokens" => {
+                    write_optional::<Int64Type, _>(&mut column, samples, |s| s.completion_tokens.map(i64::from))?;
+                }
+                "rating" => write_optional::<DoubleType, _>(&mut column, samples, |s| s.rating)?,
+                other => anyhow::bail!("unexpected training data column '{other}'"),
+            }
+        }
+        column.close()?;
+    }
+
+    row_group.close()?;
+    writer.close()?;
+    Ok(())
+}
+
+fn write_strings<'a>(
+    column: &mut parquet::file::writer::SerializedColumnWriter<'_>,
+    samples: &'a [TrainingSample],
+    value: impl Fn(&'a TrainingSample) -> &'a str,
+) -> parquet::errors::Result<()> {
+    let values: Vec<ByteArray> = samples.iter().map(|s| ByteArray::from(value(s))).collect();
+    column.typed::<ByteArrayType>().write_batch(&values, None, None)?;
+    Ok(())
+}
+
+fn write_required<T, F>(
+    column: &mut parquet::file::writer::SerializedColumnWriter<'_>,
+    samples: &[TrainingSample],
+    value: F,
+) -> parquet::errors::Result<()>
+where
+    T: parquet::data_type::DataType,
+    F: Fn(&TrainingSample) -> T::T,
+{
+    let values: Vec<T::T> = samples.iter().map(value).collect();
+    column.typed::<T>().write_batch(&values, None, None)?;
+    Ok(())
+}
+
+/// Write a nullable column: present values plus a definition level per row
+fn write_optional<T, F>(
+    column: &mut parquet::file::writer::SerializedColumnWriter<'_>,
+    samples: &[TrainingSample],
+    value: F,
+) -> parquet::errors::Result<()>
+where
+    T: parquet::data_type::DataType,
+    F: Fn(&TrainingSample) -> Option<T::T>,
+{
+    let values: Vec<Option<T::T>> = samples.iter().map(value).collect();
+    let levels: Vec<i16> = values.iter().map(|v| i16::from(v.is_some())).collect();
+    let present: Vec<T::T> = values.into_iter().flatten().collect();
+    column.typed::<T>().write_batch(&present, Some(&levels), None)?;
+    Ok(())
+}
+
+fn saturating_i64(value: u64) -> i64 {
+    i64::try_from(value).unwrap_or(i64::MAX)
+}
+
+#[cfg(test)]
+mod tests {
+    use parquet::file::reader::{FileReader, SerializedFileReader};
+
+    use super::*;
+
+    fn sample(response_id: &str, class: Option<usize>) -> TrainingSample {
+        TrainingSample {
+            response_id: response_id.to_owned(),
+            timestamp: 1_700_000_000,
+            features: [0.005, 4.0, 2.0, 1.0, 0.0, 0.0, 0.06, 1.0],
+            class,
+            provider: "openai".to_owned(),
+            model: "gpt-4o-mini".to_owned(),
+            strategy: "threshold".to_owned(),
+            reason: "low_complexity",
+            latency_ms: 420,
+            prompt_tokens: Some(12),
+            completion_tokens: Some(34),
+            rating: None,
+        }
+    }
+
+    fn config(format: TrainingDataFormat, path: PathBuf) -> TrainingDataConfig {
+        TrainingDataConfig {
+            enabled: true,
+            format,
+            path,
+            rating_window_secs: 0,
+            batch_size: 10,
+        }
+    }
+
+    #[test]
+    fn csv_rows_follow_the_training_layout() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("routing.csv");
+        let mut writer = SampleWriter::open(&config(TrainingDataFormat::Csv, path.clone())).unwrap();
+
+        let mut rated = sample("resp-1", Some(1));
+        rated.rating = Some(1.0);
+        writer.write(rated);
+        writer.write(sample("resp-2", None));
+        writer.flush(true);
+
+        let contents = std::fs::read_to_string(&path).unwrap();
+        let lines: Vec<&str> = contents.lines().collect();
+        assert!(lines[0].starts_with(
+            "input_tokens,task_type,complexity,tool_use,vision,long_context,message_count,system_prompt,selected_model,provider"
+        ));
+        assert_eq!(
+            lines[1],
+            "0.005,4,2,1,0,0,0.06,1,1,openai,gpt-4o-mini,threshold,low_complexity,420,12,34,1,resp-1,1700000000"
+        );
+        assert!(lines[2].contains(",0.06,1,,openai,"));
+        assert!(lines[2].contains(",34,,resp-2,"));
+
+        // Reopening an existing file does not repeat the header
+        drop(writer);
+        SampleWriter::open(&config(TrainingDataFormat::Csv, path.clone())).unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap().lines().count(), 3);
+    }
+
+    #[test]
+    fn csv_fields_are_quoted() {
+        assert_eq!(csv_field("plain"), "plain");
+        assert_eq!(csv_field("a,\"b\""), "\"a,\"\"b\"\"\"");
+    }
+
+    #[test]
+    fn parquet_batches_are_written_when_full() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut writer = SampleWriter::open(&config(TrainingDataFormat::Parquet, dir.path().to_path_buf())).unwrap();
+
+        writer.write(sample("resp-1", Some(0)));
+        writer.flush(false);
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 0);
+
+        let mut rated = sample("resp-2", None);
+        rated.rating = Some(0.0);
+        writer.write(rated);
+        writer.flush(true);
+
+        let files: Vec<PathBuf> = std::fs::read_dir(dir.path())
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .collect();
+        assert_eq!(files.len(), 1);
+
+        let reader = SerializedFileReader::new(File::open(&files[0]).unwrap()).unwrap();
+        assert_eq!(reader.metadata().file_metadata().num_rows(), 2);
+        let rows: Vec<String> = reader
+            .get_row_iter(None)
+            .unwrap()
+            .map(|row| row.unwrap().to_string())
+            .collect();
+        assert!(rows[0].contains("selected_model: 0"));
+        assert!(rows[0].contains("rating: null"));
+        assert!(rows[1].contains("selected_model: null"));
+        assert!(rows[1].contains("response_id: \"resp-2\""));
+    }
+}
@@ -12,7 +12,7 @@ pub mod tool;
 pub use message::{Content, ContentPart, FunctionCall, Message, Role, ToolCall, ToolResult};
 pub use request::{CompletionParams, CompletionRequest};
 pub use response::{Choice, ChoiceMessage, CompletionResponse, FinishReason, Usage};
-pub use stream::{StreamDelta, StreamEvent, StreamFunctionCall, StreamToolCall};
+pub use stream::{StreamDelta, StreamEvent, StreamFunctionCall, StreamToolCall, ToolStep};
 pub use tool::{
     FunctionDefinition, ToolChoice, ToolChoiceFunction, ToolChoiceFunctionName, ToolChoiceMode, ToolDefinition,
 };
@@ -1,6 +1,6 @@
 use serde::{Deserialize, Serialize};
 
-use super::message::Message;
+use super::message::{Content, Message, Role};
 use super::tool::{ToolChoice, ToolDefinition};
 
 /// Parameters controlling text generation
@@ -49,3 +49,30 @@ pub struct CompletionRequest {
     #[serde(default)]
     pub stream: bool,
 }
+
+impl CompletionRequest {
+    /// Deterministic, non-streaming request pairing a system instruction
+    /// with one user turn, for gateway-internal model calls
+    pub(crate) fn instruction(model: String, system: &str, user: String, max_tokens: u32) -> Self {
+        let message = |role, text: String| Message {
+            role,
+            content: Content::Text(text),
+            name: None,
+            tool_calls: None,
+            tool_call_id: None,
+        };
+
+        Self {
+            model,
+            messages: vec![message(Role::System, system.to_owned()), message(Role::User, user)],
+            params: CompletionParams {
+                temperature: Some(0.0),
+                max_tokens: Some(max_tokens),
+                ..CompletionParams::default()
+            },
+            tools: None,
+            tool_choice: None,
+            stream: false,
+        }
+    }
+}
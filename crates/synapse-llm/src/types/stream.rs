@@ -10,10 +10,38 @@ pub enum StreamEvent {
     Delta(StreamDelta),
     /// Final usage statistics (sent at stream end)
     Usage(Usage),
+    /// Tool call run by the gateway between model turns
+    ToolStep(ToolStep),
     /// Stream has completed
     Done,
 }
 
+/// Step of a server-side tool loop, streamed so clients can follow along
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ToolStep {
+    /// The gateway is calling a tool the model asked for
+    ToolCall {
+        /// Tool call ID assigned by the model
+        id: String,
+        /// Qualified tool name
+        name: String,
+        /// JSON-encoded arguments
+        arguments: String,
+    },
+    /// A tool call finished; its output is fed back to the model
+    ToolResult {
+        /// Tool call ID assigned by the model
+        id: String,
+        /// Qualified tool name
+        name: String,
+        /// Tool output as text
+        content: String,
+        /// Whether the tool failed
+        is_error: bool,
+    },
+}
+
 /// Incremental update within a streaming response
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct StreamDelta {
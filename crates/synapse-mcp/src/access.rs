@@ -1,23 +1,119 @@
-use synapse_config::McpAccessConfig;
+use std::sync::Arc;
+
+use serde_json::{Map, Value};
+use synapse_auth::ResolvedKey;
+use synapse_config::{McpAccessConfig, McpPolicyConfig, McpPolicySubject};
+use synapse_core::{Claims, RequestContext};
 
 use crate::error::McpError;
 
-/// Tool-level access controller based on allow/deny lists
+/// Tool-level access controller based on allow/deny lists, tenants, and
+/// per-identity policies
 #[derive(Debug)]
 pub struct AccessController {
     /// Per-server access rules keyed by server name
     rules: std::collections::HashMap<String, McpAccessConfig>,
+    /// Tenant each tenant-scoped server is limited to
+    tenants: std::collections::HashMap<String, String>,
+    /// Policies for callers matching an identity
+    policies: Arc<[McpPolicyConfig]>,
+}
+
+/// Identity of a caller, matched against access policies
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Caller<'a> {
+    /// Client identification group, which is also the caller's tenant
+    pub group: Option<&'a str>,
+    /// Plan of the caller's API key
+    pub plan: Option<&'a str>,
+    /// Claims of the caller's JWT
+    pub claims: Option<&'a Claims>,
+}
+
+impl<'a> Caller<'a> {
+    /// Identity of the caller of a request
+    pub fn from_context(context: &'a RequestContext) -> Self {
+        Self {
+            group: context
+                .client_identity
+                .as_ref()
+                .and_then(|identity| identity.group.as_deref()),
+            plan: context
+                .parts
+                .extensions
+                .get::<Arc<ResolvedKey>>()
+                .map(|key| key.plan.as_str()),
+            claims: context
+                .authentication
+                .synapse
+                .as_ref()
+                .map(|token| &token.claims().custom),
+        }
+    }
+
+    /// Whether every condition of a policy subject holds for the caller
+    fn matches(&self, subject: &McpPolicySubject) -> bool {
+        let group = subject.group.as_deref().is_none_or(|group| self.group == Some(group));
+        let plan = subject.plan.as_deref().is_none_or(|plan| self.plan == Some(plan));
+        let claim = subject.claim.as_ref().is_none_or(|claim| {
+            self.claims
+                .and_then(|claims| claims.get_claim(&claim.path))
+                .is_some_and(|value| value == claim.value)
+        });
+
+        group && plan && claim
+    }
 }
 
 impl AccessController {
     /// Build from MCP configuration
-    pub fn new(servers: &indexmap::IndexMap<String, synapse_config::McpServerConfig>) -> Self {
+    pub fn new(
+        servers: &indexmap::IndexMap<String, synapse_config::McpServerConfig>,
+        policies: Arc<[McpPolicyConfig]>,
+    ) -> Self {
         let rules = servers
             .iter()
             .filter_map(|(name, config)| config.access.as_ref().map(|access| (name.clone(), access.clone())))
             .collect();
+        let tenants = servers
+            .iter()
+            .filter_map(|(name, config)| config.tenant.as_ref().map(|tenant| (name.clone(), tenant.clone())))
+            .collect();
 
-        Self { rules }
+        Self {
+            rules,
+            tenants,
+            policies,
+        }
+    }
+
+    /// Whether the caller may see and call a tool
+    pub fn allows_tool(&self, caller: &Caller<'_>, server_name: &str, tool_name: &str) -> bool {
+        self.visible(server_name, caller.group)
+            && self.check(server_name, tool_name).is_ok()
+            && self.check_policies(caller, server_name, tool_name).is_ok()
+    }
+
+    /// Whether a server is visible to a caller in `tenant`
+    ///
+    /// Servers without a tenant are visible to every caller.
+    pub fn visible(&self, server_name: &str, tenant: Option<&str>) -> bool {
+        self.tenants
+            .get(server_name)
+            .is_none_or(|owner| Some(owner.as_str()) == tenant)
+    }
+
+    /// Check that a server is visible to a caller in `tenant`
+    ///
+    /// Servers of other tenants are reported as not found.
+    pub fn check_tenant(&self, server_name: &str, tenant: Option<&str>) -> Result<(), McpError> {
+        if self.visible(server_name, tenant) {
+            Ok(())
+        } else {
+            Err(McpError::ServerNotFound {
+                server: server_name.to_owned(),
+            })
+        }
     }
 
     /// Check whether a tool call is allowed
@@ -29,22 +125,188 @@ impl AccessController {
             return Ok(());
         };
 
-        // Deny list takes precedence
-        if access.deny.iter().any(|d| d == tool_name) {
-            return Err(McpError::AccessDenied {
-                tool: format!("{server_name}__{tool_name}"),
-            });
+        if permitted(&access.allow, &access.deny, tool_name) {
+            Ok(())
+        } else {
+            Err(McpError::AccessDenied {
+                kind: "tool",
+                name: format!("{server_name}__{tool_name}"),
+            })
         }
+    }
+
+    /// Check whether the policies matching the caller allow a tool
+    ///
+    /// Every matching policy must allow the tool.
+    pub fn check_policies(&self, caller: &Caller<'_>, server_name: &str, tool_name: &str) -> Result<(), McpError> {
+        self.check_matching(caller, "tool", format!("{server_name}__{tool_name}"), |policy| {
+            (&policy.allow, &policy.deny)
+        })
+    }
 
-        // If allow list is non-empty, tool must appear in it
-        if !access.allow.is_empty() && !access.allow.iter().any(|a| a == tool_name) {
+    /// Check a qualified name against the lists `rules` picks from every
+    /// policy matching the caller
+    fn check_matching(
+        &self,
+        caller: &Caller<'_>,
+        kind: &'static str,
+        qualified: String,
+        rules: impl Fn(&McpPolicyConfig) -> (&Vec<String>, &Vec<String>),
+    ) -> Result<(), McpError> {
+        let denied = self.matching(caller).any(|policy| {
+            let (allow, deny) = rules(policy);
+            !permitted(allow, deny, &qualified)
+        });
+
+        if denied {
+            Err(McpError::AccessDenied { kind, name: qualified })
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Check the arguments of a tool call against the argument
+    /// constraints of the policies matching the caller
+    pub fn check_arguments(
+        &self,
+        caller: &Caller<'_>,
+        server_name: &str,
+        tool_name: &str,
+        arguments: Option<&Map<String, Value>>,
+    ) -> Result<(), McpError> {
+        let qualified = format!("{server_name}__{tool_name}");
+        let violated = self
+            .matching(caller)
+            .flat_map(|policy| &policy.arguments)
+            .filter(|constraint| glob_match(&constraint.tool, &qualified))
+            .find(|constraint| {
+                !arguments
+                    .and_then(|arguments| arguments.get(&constraint.argument))
+                    .is_some_and(|value| value_permitted(&constraint.allow, value))
+            });
+
+        if let Some(constraint) = violated {
             return Err(McpError::AccessDenied {
-                tool: format!("{server_name}__{tool_name}"),
+                kind: "argument",
+                name: format!("{qualified}.{}", constraint.argument),
             });
         }
 
         Ok(())
     }
+
+    /// Check whether the caller may see and read a resource, by URI or
+    /// URI template
+    ///
+    /// Applies the server's tenant and access lists and the resource
+    /// rules of the policies matching the caller.
+    pub fn check_caller_resource(&self, caller: &Caller<'_>, server_name: &str, uri: &str) -> Result<(), McpError> {
+        self.check_tenant(server_name, caller.group)?;
+        self.check_resource(server_name, uri)?;
+        self.check_matching(caller, "resource", format!("{server_name}__{uri}"), |policy| {
+            (&policy.resources.allow, &policy.resources.deny)
+        })
+    }
+
+    /// Check whether the caller may see and get a prompt
+    ///
+    /// Applies the server's tenant and access lists and the prompt rules
+    /// of the policies matching the caller.
+    pub fn check_caller_prompt(
+        &self,
+        caller: &Caller<'_>,
+        server_name: &str,
+        prompt_name: &str,
+    ) -> Result<(), McpError> {
+        self.check_tenant(server_name, caller.group)?;
+        self.check_prompt(server_name, prompt_name)?;
+        self.check_matching(caller, "prompt", format!("{server_name}__{prompt_name}"), |policy| {
+            (&policy.prompts.allow, &policy.prompts.deny)
+        })
+    }
+
+    /// Policies that apply to the caller
+    fn matching<'a>(&'a self, caller: &'a Caller<'_>) -> impl Iterator<Item = &'a McpPolicyConfig> {
+        self.policies.iter().filter(|policy| caller.matches(&policy.subject))
+    }
+
+    /// Check whether a resource, by URI or URI template, is accessible
+    pub fn check_resource(&self, server_name: &str, uri: &str) -> Result<(), McpError> {
+        let Some(access) = self.rules.get(server_name) else {
+            return Ok(());
+        };
+
+        if permitted(&access.resources.allow, &access.resources.deny, uri) {
+            Ok(())
+        } else {
+            Err(McpError::AccessDenied {
+                kind: "resource",
+                name: format!("{server_name}__{uri}"),
+            })
+        }
+    }
+
+    /// Check whether a prompt is accessible
+    pub fn check_prompt(&self, server_name: &str, prompt_name: &str) -> Result<(), McpError> {
+        let Some(access) = self.rules.get(server_name) else {
+            return Ok(());
+        };
+
+        if permitted(&access.prompts.allow, &access.prompts.deny, prompt_name) {
+            Ok(())
+        } else {
+            Err(McpError::AccessDenied {
+                kind: "prompt",
+                name: format!("{server_name}__{prompt_name}"),
+            })
+        }
+    }
+}
+
+/// Deny list takes precedence; a non-empty allow list must match the name
+fn permitted(allow: &[String], deny: &[String], name: &str) -> bool {
+    if deny.iter().any(|d| glob_match(d, name)) {
+        return false;
+    }
+
+    allow.is_empty() || allow.iter().any(|a| glob_match(a, name))
+}
+
+/// Whether an argument value matches one of the allowed patterns
+///
+/// Arrays must match element-wise; objects and nulls never match.
+fn value_permitted(allow: &[String], value: &Value) -> bool {
+    let text = match value {
+        Value::String(s) => s.clone(),
+        Value::Number(n) => n.to_string(),
+        Value::Bool(b) => b.to_string(),
+        Value::Array(items) => return items.iter().all(|item| value_permitted(allow, item)),
+        Value::Object(_) | Value::Null => return false,
+    };
+
+    allow.iter().any(|pattern| glob_match(pattern, &text))
+}
+
+/// Match a name against a pattern where `*` stands for any run of
+/// characters
+fn glob_match(pattern: &str, name: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let Some(mut rest) = parts.next().and_then(|prefix| name.strip_prefix(prefix)) else {
+        return false;
+    };
+    let mut parts: Vec<&str> = parts.collect();
+    let Some(suffix) = parts.pop() else {
+        return rest.is_empty();
+    };
+
+    for part in parts {
+        let Some(index) = rest.find(part) else {
+            return false;
+        };
+        rest = &rest[index + part.len()..];
+    }
+
+    rest.ends_with(suffix)
 }
 
 #[cfg(test)]
@@ -57,6 +319,7 @@ mod tests {
         McpAccessConfig {
             allow: allow.into_iter().map(String::from).collect(),
             deny: deny.into_iter().map(String::from).collect(),
+            ..McpAccessConfig::default()
         }
     }
 
@@ -64,6 +327,8 @@ mod tests {
     fn no_rules_allows_everything() {
         let ctrl = AccessController {
             rules: HashMap::default(),
+            tenants: HashMap::default(),
+            policies: Arc::default(),
         };
         assert!(ctrl.check("srv", "any_tool").is_ok());
     }
@@ -72,7 +337,11 @@ mod tests {
     fn deny_blocks_tool() {
         let mut rules = std::collections::HashMap::new();
         rules.insert("srv".to_string(), config_with(vec![], vec!["blocked"]));
-        let ctrl = AccessController { rules };
+        let ctrl = AccessController {
+            rules,
+            tenants: HashMap::default(),
+            policies: Arc::default(),
+        };
 
         assert!(ctrl.check("srv", "blocked").is_err());
         assert!(ctrl.check("srv", "other").is_ok());
@@ -82,7 +351,11 @@ mod tests {
     fn allow_restricts_to_listed() {
         let mut rules = std::collections::HashMap::new();
         rules.insert("srv".to_string(), config_with(vec!["allowed"], vec![]));
-        let ctrl = AccessController { rules };
+        let ctrl = AccessController {
+            rules,
+            tenants: HashMap::default(),
+            policies: Arc::default(),
+        };
 
         assert!(ctrl.check("srv", "allowed").is_ok());
         assert!(ctrl.check("srv", "other").is_err());
@@ -92,8 +365,165 @@ mod tests {
     fn deny_overrides_allow() {
         let mut rules = std::collections::HashMap::new();
         rules.insert("srv".to_string(), config_with(vec!["tool"], vec!["tool"]));
-        let ctrl = AccessController { rules };
+        let ctrl = AccessController {
+            rules,
+            tenants: HashMap::default(),
+            policies: Arc::default(),
+        };
 
         assert!(ctrl.check("srv", "tool").is_err());
     }
+
+    #[test]
+    fn resource_and_prompt_rules_are_separate_from_tools() {
+        let mut config = config_with(vec!["search"], vec![]);
+        config.resources.deny = vec!["file:///etc/passwd".to_owned()];
+        config.prompts.allow = vec!["summarize".to_owned()];
+        let mut rules = std::collections::HashMap::new();
+        rules.insert("srv".to_string(), config);
+        let ctrl = AccessController {
+            rules,
+            tenants: HashMap::default(),
+            policies: Arc::default(),
+        };
+
+        assert!(ctrl.check_resource("srv", "file:///etc/passwd").is_err());
+        assert!(ctrl.check_resource("srv", "file:///README.md").is_ok());
+        assert!(ctrl.check_prompt("srv", "summarize").is_ok());
+        assert!(ctrl.check_prompt("srv", "translate").is_err());
+    }
+
+    #[test]
+    fn tenant_servers_are_hidden_from_other_tenants() {
+        let ctrl = AccessController {
+            rules: HashMap::default(),
+            tenants: HashMap::from([("acme-crm".to_owned(), "acme".to_owned())]),
+            policies: Arc::default(),
+        };
+
+        assert!(ctrl.visible("acme-crm", Some("acme")));
+        assert!(!ctrl.visible("acme-crm", Some("globex")));
+        assert!(!ctrl.visible("acme-crm", None));
+        assert!(ctrl.visible("github", None));
+        assert!(ctrl.check_tenant("acme-crm", None).is_err());
+    }
+
+    fn policy(config: serde_json::Value) -> McpPolicyConfig {
+        serde_json::from_value(config).unwrap()
+    }
+
+    fn with_policies(policies: Vec<McpPolicyConfig>) -> AccessController {
+        AccessController {
+            rules: HashMap::default(),
+            tenants: HashMap::default(),
+            policies: policies.into(),
+        }
+    }
+
+    #[test]
+    fn glob_patterns_match_any_run_of_characters() {
+        assert!(glob_match("github__*_issue", "github__create_issue"));
+        assert!(glob_match("github__*", "github__search"));
+        assert!(glob_match("*", "anything"));
+        assert!(glob_match("exact", "exact"));
+        assert!(!glob_match("exact", "exactly"));
+        assert!(!glob_match("github__*_issue", "github__create_issues"));
+        assert!(!glob_match("github__*_issue", "gitlab__create_issue"));
+        assert!(!glob_match("a*b*c", "acb"));
+    }
+
+    #[test]
+    fn policies_apply_only_to_matching_callers() {
+        let ctrl = with_policies(vec![policy(serde_json::json!({
+            "match": { "group": "free" },
+            "deny": ["github__*_issue"],
+        }))]);
+        let free = Caller {
+            group: Some("free"),
+            ..Caller::default()
+        };
+        let pro = Caller {
+            group: Some("pro"),
+            ..Caller::default()
+        };
+
+        assert!(ctrl.check_policies(&free, "github", "create_issue").is_err());
+        assert!(ctrl.check_policies(&free, "github", "search").is_ok());
+        assert!(ctrl.check_policies(&pro, "github", "create_issue").is_ok());
+        assert!(!ctrl.allows_tool(&free, "github", "create_issue"));
+    }
+
+    #[test]
+    fn every_matching_policy_must_allow_a_tool() {
+        let ctrl = with_policies(vec![
+            policy(serde_json::json!({ "allow": ["github__*"] })),
+            policy(serde_json::json!({ "match": { "plan": "trial" }, "deny": ["github__delete_*"] })),
+        ]);
+        let trial = Caller {
+            plan: Some("trial"),
+            ..Caller::default()
+        };
+
+        assert!(ctrl.check_policies(&Caller::default(), "github", "delete_repo").is_ok());
+        assert!(ctrl.check_policies(&Caller::default(), "slack", "post").is_err());
+        assert!(ctrl.check_policies(&trial, "github", "delete_repo").is_err());
+    }
+
+    #[test]
+    fn policies_limit_resources_and_prompts() {
+        let ctrl = with_policies(vec![policy(serde_json::json!({
+            "match": { "group": "free" },
+            "resources": { "deny": ["files__file:///secret/*"] },
+            "prompts": { "allow": ["files__summarize"] },
+        }))]);
+        let free = Caller {
+            group: Some("free"),
+            ..Caller::default()
+        };
+        let pro = Caller {
+            group: Some("pro"),
+            ..Caller::default()
+        };
+
+        assert!(
+            ctrl.check_caller_resource(&free, "files", "file:///secret/key")
+                .is_err()
+        );
+        assert!(ctrl.check_caller_resource(&free, "files", "file:///README.md").is_ok());
+        assert!(ctrl.check_caller_resource(&pro, "files", "file:///secret/key").is_ok());
+        assert!(ctrl.check_caller_prompt(&free, "files", "summarize").is_ok());
+        assert!(ctrl.check_caller_prompt(&free, "files", "translate").is_err());
+        assert!(ctrl.check_caller_prompt(&pro, "files", "translate").is_ok());
+
+        // Tool lists do not limit resources or prompts
+        assert!(ctrl.check_policies(&free, "files", "read").is_ok());
+    }
+
+    #[test]
+    fn argument_constraints_limit_values() {
+        let ctrl = with_policies(vec![policy(serde_json::json!({
+            "arguments": [{ "tool": "github__*", "argument": "repo", "allow": ["acme/*"] }],
+        }))]);
+        let caller = Caller::default();
+        let arguments = |value: serde_json::Value| serde_json::json!({ "repo": value }).as_object().cloned();
+
+        let allowed = arguments(serde_json::json!("acme/api"));
+        let denied = arguments(serde_json::json!("globex/api"));
+        let mixed = arguments(serde_json::json!(["acme/api", "globex/api"]));
+
+        assert!(
+            ctrl.check_arguments(&caller, "github", "search", allowed.as_ref())
+                .is_ok()
+        );
+        assert!(
+            ctrl.check_arguments(&caller, "github", "search", denied.as_ref())
+                .is_err()
+        );
+        assert!(
+            ctrl.check_arguments(&caller, "github", "search", mixed.as_ref())
+                .is_err()
+        );
+        assert!(ctrl.check_arguments(&caller, "github", "search", None).is_err());
+        assert!(ctrl.check_arguments(&caller, "slack", "post", None).is_ok());
+    }
 }
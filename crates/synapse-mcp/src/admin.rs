@@ -0,0 +1,241 @@
+//! Admin API for managing downstream MCP servers at runtime
+//!
+//! Registers, updates, enables, disables, and removes servers without a
+//! restart. Each change rebuilds the aggregated lists and the search
+//! index and swaps them in at once; calls already in flight finish on the
+//! state they started with. Registrations live in memory only and are lost
+//! on restart.
+
+use std::sync::Arc;
+
+use axum::extract::{Path, Request, State};
+use axum::http::{HeaderMap, StatusCode};
+use axum::middleware::{self, Next};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post, put};
+use axum::{Json, Router};
+use secrecy::ExposeSecret;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use synapse_config::{McpServerConfig, McpServerType};
+
+use crate::router::McpErrorResponse;
+use crate::{McpError, McpSnapshot, McpState};
+
+/// Header carrying the admin secret
+const ADMIN_SECRET_HEADER: &str = "x-admin-secret";
+
+/// Build the admin routes, guarded by the configured secret
+pub fn admin_router(state: &Arc<McpState>) -> Router<Arc<McpState>> {
+    Router::new()
+        .route("/mcp/admin/servers", get(list_servers))
+        .route("/mcp/admin/servers/{name}", put(upsert_server).delete(remove_server))
+        .route("/mcp/admin/servers/{name}/enable", post(enable_server))
+        .route("/mcp/admin/servers/{name}/disable", post(disable_server))
+        .route_layer(middleware::from_fn_with_state(Arc::clone(state), require_secret))
+}
+
+/// Reject requests without the admin secret
+async fn require_secret(
+    State(state): State<Arc<McpState>>,
+    headers: HeaderMap,
+    request: Request,
+    next: Next,
+) -> Response {
+    let Some(expected) = state.admin.secret.as_ref() else {
+        return StatusCode::UNAUTHORIZED.into_response();
+    };
+    let Some(presented) = headers.get(ADMIN_SECRET_HEADER) else {
+        return StatusCode::UNAUTHORIZED.into_response();
+    };
+
+    // Compare digests so the time taken reveals nothing about the secret
+    let presented = Sha256::digest(presented.as_bytes());
+    let expected = Sha256::digest(expected.expose_secret().as_bytes());
+    if presented != expected {
+        return StatusCode::UNAUTHORIZED.into_response();
+    }
+
+    next.run(request).await
+}
+
+/// Registered server and its connection state
+#[derive(Debug, Serialize)]
+struct AdminServerInfo {
+    name: String,
+    enabled: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tenant: Option<String>,
+    transport: &'static str,
+    connected: bool,
+    tool_count: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    last_error: Option<String>,
+    restarts: u32,
+}
+
+/// Response listing registered servers
+#[derive(Debug, Serialize)]
+struct ListServersResponse {
+    servers: Vec<AdminServerInfo>,
+}
+
+async fn list_servers(State(state): State<Arc<McpState>>) -> Json<ListServersResponse> {
+    let snapshot = state.current();
+    let servers = snapshot
+        .servers
+        .keys()
+        .filter_map(|name| server_info(&snapshot, name))
+        .collect();
+
+    Json(ListServersResponse { servers })
+}
+
+async fn upsert_server(
+    State(state): State<Arc<McpState>>,
+    Path(name): Path<String>,
+    Json(config): Json<McpServerConfig>,
+) -> Result<Json<AdminServerInfo>, McpErrorResponse> {
+    if matches!(config.server_type, McpServerType::Stdio(_)) && !state.admin.allow_stdio {
+        return Err(McpError::InvalidRequest(
+            "stdio servers cannot be registered at runtime unless mcp.admin.allow_stdio is set".to_owned(),
+        )
+        .into());
+    }
+
+    state.upsert_server(&name, config).await?;
+    Ok(Json(registered(&state, &name)?))
+}
+
+async fn remove_server(
+    State(state): State<Arc<McpState>>,
+    Path(name): Path<String>,
+) -> Result<StatusCode, McpErrorResponse> {
+    state.remove_server(&name).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn enable_server(
+    State(state): State<Arc<McpState>>,
+    Path(name): Path<String>,
+) -> Result<Json<AdminServerInfo>, McpErrorResponse> {
+    state.set_server_enabled(&name, true).await?;
+    Ok(Json(registered(&state, &name)?))
+}
+
+async fn disable_server(
+    State(state): State<Arc<McpState>>,
+    Path(name): Path<String>,
+) -> Result<Json<AdminServerInfo>, McpErrorResponse> {
+    state.set_server_enabled(&name, false).await?;
+    Ok(Json(registered(&state, &name)?))
+}
+
+/// Info of a server that was just changed
+///
+/// A concurrent removal can win the race, reported as not found.
+fn registered(state: &McpState, name: &str) -> Result<AdminServerInfo, McpErrorResponse> {
+    server_info(&state.current(), name).ok_or_else(|| {
+        McpError::ServerNotFound {
+            server: name.to_owned(),
+        }
+        .into()
+    })
+}
+
+fn server_info(snapshot: &McpSnapshot, name: &str) -> Option<AdminServerInfo> {
+    let config = snapshot.servers.get(name)?;
+    let status = snapshot
+        .downstream
+        .server_statuses()
+        .into_iter()
+        .find(|status| status.name == name);
+
+    Some(AdminServerInfo {
+        name: name.to_owned(),
+        enabled: config.enabled,
+        tenant: config.tenant.clone(),
+        transport: match config.server_type {
+            McpServerType::Stdio(_) => "stdio",
+            McpServerType::Sse(_) => "sse",
+            McpServerType::StreamableHttp(_) => "streamable_http",
+        },
+        connected: status.as_ref().is_some_and(|s| s.connected),
+        tool_count: status.as_ref().map_or(0, |s| s.tool_count),
+        restarts: status.as_ref().map_or(0, |s| s.restarts),
+        last_error: status.and_then(|s| s.last_error),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::body::Body;
+    use synapse_config::{McpAdminConfig, McpConfig, RateLimitStorage};
+    use tower::ServiceExt;
+
+    use super::*;
+
+    async fn state() -> Arc<McpState> {
+        let config = McpConfig {
+            admin: McpAdminConfig {
+                enabled: true,
+                secret: Some("admin-secret".to_owned().into()),
+                allow_stdio: false,
+            },
+            ..McpConfig::default()
+        };
+        Arc::new(McpState::new(&config, &RateLimitStorage::Memory).await.unwrap())
+    }
+
+    async fn send(state: &Arc<McpState>, request: http::request::Builder, body: serde_json::Value) -> StatusCode {
+        let request = request
+            .header("content-type", "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap();
+        let router = admin_router(state).with_state(Arc::clone(state));
+        router.oneshot(request).await.unwrap().status()
+    }
+
+    fn put(name: &str) -> http::request::Builder {
+        http::Request::put(format!("/mcp/admin/servers/{name}"))
+    }
+
+    #[tokio::test]
+    async fn requests_without_the_secret_are_unauthorized() {
+        let state = state().await;
+        let registration = serde_json::json!({"type": {"transport": "streamable_http", "url": "http://localhost:1"}});
+
+        assert_eq!(
+            send(&state, put("remote"), registration.clone()).await,
+            StatusCode::UNAUTHORIZED
+        );
+        let wrong = put("remote").header(ADMIN_SECRET_HEADER, "guess");
+        assert_eq!(send(&state, wrong, registration).await, StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn stdio_registrations_are_rejected() {
+        let state = state().await;
+        let registration = serde_json::json!({"type": {"transport": "stdio", "command": "sh", "args": ["-c", "true"]}});
+
+        let request = put("local").header(ADMIN_SECRET_HEADER, "admin-secret");
+        assert_eq!(send(&state, request, registration).await, StatusCode::BAD_REQUEST);
+        assert!(state.current().servers.is_empty());
+    }
+
+    #[tokio::test]
+    async fn servers_removed_during_a_change_are_not_found() {
+        let state = state().await;
+
+        // A removal that wins the race leaves nothing to report
+        let response = registered(&state, "remote").err().unwrap().into_response();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        let request =
+            http::Request::post("/mcp/admin/servers/remote/disable").header(ADMIN_SECRET_HEADER, "admin-secret");
+        assert_eq!(
+            send(&state, request, serde_json::json!({})).await,
+            StatusCode::NOT_FOUND
+        );
+    }
+}
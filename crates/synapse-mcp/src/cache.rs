@@ -2,13 +2,17 @@ use std::sync::Arc;
 use std::time::Duration;
 
 use mini_moka::sync::Cache;
+use secrecy::{ExposeSecret, SecretString};
 use sha2::{Digest, Sha256};
-use synapse_config::{McpCacheConfig, McpServerType};
+use synapse_config::{McpCacheConfig, McpServerConfig, McpServerType};
 
 use crate::downstream::client::McpClient;
 use crate::error::McpError;
 
 /// LRU cache for dynamically-created MCP downstream connections
+///
+/// Holds one connection per server and caller credential for servers that
+/// forward the caller's authorization.
 pub struct DownstreamCache {
     cache: Cache<String, Arc<McpClient>>,
 }
@@ -24,22 +28,27 @@ impl DownstreamCache {
         Self { cache }
     }
 
-    /// Get a cached client or connect a new one
-    pub async fn get_or_connect(&self, name: &str, server_type: &McpServerType) -> Result<Arc<McpClient>, McpError> {
-        let key = cache_key(name, server_type);
+    /// Get a cached client for a caller credential or connect a new one
+    pub async fn get_or_connect(
+        &self,
+        name: &str,
+        config: &McpServerConfig,
+        authorization: &SecretString,
+    ) -> Result<Arc<McpClient>, McpError> {
+        let key = cache_key(name, &config.server_type, authorization);
 
         if let Some(client) = self.cache.get(&key) {
             return Ok(client);
         }
 
-        let client = Arc::new(McpClient::connect(name, server_type).await?);
+        let client = Arc::new(McpClient::connect(name, config, Some(authorization)).await?);
         self.cache.insert(key, Arc::clone(&client));
         Ok(client)
     }
 
     /// Remove a client from the cache
-    pub fn invalidate(&self, name: &str, server_type: &McpServerType) {
-        let key = cache_key(name, server_type);
+    pub fn invalidate(&self, name: &str, server_type: &McpServerType, authorization: &SecretString) {
+        let key = cache_key(name, server_type, authorization);
         self.cache.invalidate(&key);
     }
 
@@ -54,8 +63,9 @@ impl DownstreamCache {
     }
 }
 
-/// Generate a cache key from server name and transport configuration
-fn cache_key(name: &str, server_type: &McpServerType) -> String {
+/// Generate a cache key from server name, transport configuration, and
+/// caller credential
+fn cache_key(name: &str, server_type: &McpServerType, authorization: &SecretString) -> String {
     let mut hasher = Sha256::new();
     hasher.update(name.as_bytes());
 
@@ -77,5 +87,33 @@ fn cache_key(name: &str, server_type: &McpServerType) -> String {
         }
     }
 
+    hasher.update(b"auth:");
+    hasher.update(authorization.expose_secret().as_bytes());
+
     format!("{:x}", hasher.finalize())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn credentials_get_separate_connections() {
+        let server_type = McpServerType::StreamableHttp(synapse_config::HttpConfig {
+            url: "http://localhost:8080/mcp".parse().unwrap(),
+            auth: Some(synapse_config::McpAuthConfig::Forward),
+            tls: None,
+        });
+        let alice = SecretString::from("Bearer alice");
+        let bob = SecretString::from("Bearer bob");
+
+        assert_eq!(
+            cache_key("github", &server_type, &alice),
+            cache_key("github", &server_type, &alice)
+        );
+        assert_ne!(
+            cache_key("github", &server_type, &alice),
+            cache_key("github", &server_type, &bob)
+        );
+    }
+}
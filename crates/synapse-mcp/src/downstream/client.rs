@@ -1,37 +1,203 @@
-use rmcp::model::{CallToolRequestParams, CallToolResult, Tool};
-use rmcp::service::{RoleClient, RunningService, ServiceExt as _};
-use rmcp::transport::TokioChildProcess;
-use synapse_config::{HttpConfig, McpAuthConfig, McpServerType, StdioConfig};
-use tokio::sync::Mutex;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, PoisonError, RwLock};
+
+use futures_util::future::try_join_all;
+
+use http::header::AUTHORIZATION;
+use http::{HeaderName, HeaderValue};
+use rmcp::model::{
+    CallToolRequestParams, CallToolResult, ErrorCode, GetPromptRequestParams, GetPromptResult, Prompt,
+    ReadResourceRequestParams, ReadResourceResult, Resource, ResourceTemplate, Tool,
+};
+use rmcp::service::{NotificationContext, RoleClient, RunningService, ServiceExt as _};
+use rmcp::transport::streamable_http_client::StreamableHttpClientTransportConfig;
+use rmcp::transport::{StreamableHttpClientTransport, TokioChildProcess};
+use rmcp::{ClientHandler, ServiceError};
+use secrecy::{ExposeSecret, SecretString};
+use synapse_config::{
+    HttpConfig, McpAuthConfig, McpHeaderInsert, McpServerConfig, McpServerType, McpTlsConfig, StdioConfig,
+};
+use tokio::sync::{Mutex, Notify, Semaphore, SemaphorePermit};
 
 use crate::error::McpError;
 
-/// Connected MCP downstream client wrapping a running rmcp service
+/// Service handle for one MCP session
+type Service = RunningService<RoleClient, ListChangeHandler>;
+
+/// Records `list_changed` notifications sent by a server
+#[derive(Default)]
+struct ListChanged {
+    pending: AtomicBool,
+    notify: Notify,
+}
+
+impl ListChanged {
+    fn mark(&self) {
+        self.pending.store(true, Ordering::Relaxed);
+        self.notify.notify_one();
+    }
+}
+
+/// Client handler flagging tool, resource, and prompt list changes
+#[derive(Clone)]
+struct ListChangeHandler(Arc<ListChanged>);
+
+impl ClientHandler for ListChangeHandler {
+    async fn on_tool_list_changed(&self, _context: NotificationContext<RoleClient>) {
+        self.0.mark();
+    }
+
+    async fn on_resource_list_changed(&self, _context: NotificationContext<RoleClient>) {
+        self.0.mark();
+    }
+
+    async fn on_prompt_list_changed(&self, _context: NotificationContext<RoleClient>) {
+        self.0.mark();
+    }
+}
+
+/// Connected MCP downstream client
+///
+/// Requests multiplex over each session by JSON-RPC id, so concurrent
+/// calls to one server do not wait for each other. Requests are spread
+/// round-robin over a pool of sessions and optionally bounded by a
+/// concurrency limit.
 pub struct McpClient {
-    service: Mutex<RunningService<RoleClient, ()>>,
+    /// Pooled sessions, used round-robin
+    sessions: Vec<Session>,
+    /// Index of the next session to use
+    next: AtomicUsize,
+    /// Bounds in-flight requests when a concurrency limit is configured
+    permits: Option<Semaphore>,
     server_name: String,
     server_config: McpServerType,
+    /// Headers inserted on every HTTP request
+    headers: HashMap<HeaderName, HeaderValue>,
+    /// Caller authorization forwarded to servers using `Forward` auth
+    authorization: Option<SecretString>,
+    /// Set when any session reports a list change
+    list_changed: Arc<ListChanged>,
+}
+
+/// One pooled session, replaced when its transport fails
+struct Session {
+    current: RwLock<Arc<Service>>,
+    /// Held while reconnecting so concurrent failures reconnect once
+    reconnecting: Mutex<()>,
+}
+
+impl Session {
+    fn new(service: Service) -> Self {
+        Self {
+            current: RwLock::new(Arc::new(service)),
+            reconnecting: Mutex::new(()),
+        }
+    }
+
+    /// The session as of now; requests keep it alive until they finish
+    fn current(&self) -> Arc<Service> {
+        Arc::clone(&self.current.read().unwrap_or_else(PoisonError::into_inner))
+    }
+
+    fn replace(&self, service: Arc<Service>) {
+        *self.current.write().unwrap_or_else(PoisonError::into_inner) = service;
+    }
 }
 
 impl McpClient {
     /// Connect to a downstream MCP server
-    pub async fn connect(name: &str, server_type: &McpServerType) -> Result<Self, McpError> {
-        let service = match server_type {
-            McpServerType::Stdio(config) => Self::connect_stdio(config).await?,
-            McpServerType::Sse(config) => Self::connect_sse(config).await?,
-            McpServerType::StreamableHttp(config) => Self::connect_streamable_http(config).await?,
-        };
+    ///
+    /// Opens `pool_size` sessions. Configured headers are inserted on HTTP
+    /// requests, and `authorization` is sent as the `Authorization` header
+    /// when the server forwards the caller's credentials.
+    pub async fn connect(
+        name: &str,
+        config: &McpServerConfig,
+        authorization: Option<&SecretString>,
+    ) -> Result<Self, McpError> {
+        let headers = build_headers(&config.headers)?;
+        let list_changed = Arc::new(ListChanged::default());
+        let services = try_join_all(
+            (0..config.pool_size.max(1))
+                .map(|_| Self::connect_service(&config.server_type, &headers, authorization, &list_changed)),
+        )
+        .await?;
 
-        tracing::info!(server = name, "connected to MCP server");
+        tracing::info!(server = name, sessions = services.len(), "connected to MCP server");
 
         Ok(Self {
-            service: Mutex::new(service),
+            sessions: services.into_iter().map(Session::new).collect(),
+            next: AtomicUsize::new(0),
+            permits: config.max_concurrent_requests.map(Semaphore::new),
             server_name: name.to_string(),
-            server_config: server_type.clone(),
+            server_config: config.server_type.clone(),
+            headers,
+            authorization: authorization.cloned(),
+            list_changed,
         })
     }
 
-    async fn connect_stdio(config: &StdioConfig) -> Result<RunningService<RoleClient, ()>, McpError> {
+    /// Take a concurrency permit and pick the next pooled session
+    ///
+    /// The permit must be held until the request completes.
+    async fn acquire(&self) -> Result<(Option<SemaphorePermit<'_>>, &Session), McpError> {
+        let permit = match self.permits {
+            Some(ref permits) => Some(
+                permits
+                    .acquire()
+                    .await
+                    .map_err(|_| McpError::Transport(format!("{} is shut down", self.server_name)))?,
+            ),
+            None => None,
+        };
+        let index = self.next.fetch_add(1, Ordering::Relaxed) % self.sessions.len();
+
+        Ok((permit, &self.sessions[index]))
+    }
+
+    /// Replace a session whose transport failed, unless a concurrent
+    /// request already replaced it
+    async fn reconnect(&self, session: &Session, failed: &Arc<Service>) -> Result<Arc<Service>, McpError> {
+        let _reconnecting = session.reconnecting.lock().await;
+
+        let current = session.current();
+        if !Arc::ptr_eq(&current, failed) {
+            return Ok(current);
+        }
+
+        tracing::warn!(server = %self.server_name, "MCP transport failure, reconnecting");
+
+        let service = Arc::new(
+            Self::connect_service(
+                &self.server_config,
+                &self.headers,
+                self.authorization.as_ref(),
+                &self.list_changed,
+            )
+            .await?,
+        );
+        session.replace(Arc::clone(&service));
+        Ok(service)
+    }
+
+    async fn connect_service(
+        server_type: &McpServerType,
+        headers: &HashMap<HeaderName, HeaderValue>,
+        authorization: Option<&SecretString>,
+        list_changed: &Arc<ListChanged>,
+    ) -> Result<Service, McpError> {
+        let handler = ListChangeHandler(Arc::clone(list_changed));
+        match server_type {
+            McpServerType::Stdio(config) => Self::connect_stdio(config, handler).await,
+            McpServerType::Sse(config) => Self::connect_sse(config, headers, authorization, handler).await,
+            McpServerType::StreamableHttp(config) => {
+                Self::connect_streamable_http(config, headers, authorization, handler).await
+            }
+        }
+    }
+
+    async fn connect_stdio(config: &StdioConfig, handler: ListChangeHandler) -> Result<Service, McpError> {
         let mut cmd = tokio::process::Command::new(&config.command);
         cmd.args(&config.args);
         for (k, v) in &config.env {
@@ -41,59 +207,128 @@ impl McpClient {
         let transport =
             TokioChildProcess::new(cmd).map_err(|e| McpError::Transport(format!("failed to spawn process: {e}")))?;
 
-        ().serve(transport)
+        handler
+            .serve(transport)
             .await
             .map_err(|e| McpError::Transport(format!("STDIO handshake failed: {e}")))
     }
 
-    async fn connect_sse(config: &HttpConfig) -> Result<RunningService<RoleClient, ()>, McpError> {
-        use rmcp::transport::StreamableHttpClientTransport;
-        use rmcp::transport::streamable_http_client::StreamableHttpClientTransportConfig;
+    async fn connect_sse(
+        config: &HttpConfig,
+        headers: &HashMap<HeaderName, HeaderValue>,
+        authorization: Option<&SecretString>,
+        handler: ListChangeHandler,
+    ) -> Result<Service, McpError> {
+        let transport = http_transport(config, headers, authorization)?;
+
+        handler
+            .serve(transport)
+            .await
+            .map_err(|e| McpError::Transport(format!("SSE handshake failed: {e}")))
+    }
 
-        let mut transport_config = StreamableHttpClientTransportConfig::with_uri(config.url.as_str());
+    async fn connect_streamable_http(
+        config: &HttpConfig,
+        headers: &HashMap<HeaderName, HeaderValue>,
+        authorization: Option<&SecretString>,
+        handler: ListChangeHandler,
+    ) -> Result<Service, McpError> {
+        let transport = http_transport(config, headers, authorization)?;
 
-        // Apply auth header if configured
-        if let Some(McpAuthConfig::Token { ref token }) = config.auth {
-            use secrecy::ExposeSecret;
-            transport_config = transport_config.auth_header(format!("Bearer {}", token.expose_secret()));
-        }
+        handler
+            .serve(transport)
+            .await
+            .map_err(|e| McpError::Transport(format!("StreamableHTTP handshake failed: {e}")))
+    }
 
-        let client = reqwest::Client::new();
-        let transport = StreamableHttpClientTransport::with_client(client, transport_config);
+    /// List all tools available on this server
+    pub async fn list_tools(&self) -> Result<Vec<Tool>, McpError> {
+        let (_permit, session) = self.acquire().await?;
 
-        ().serve(transport)
+        session
+            .current()
+            .list_all_tools()
             .await
-            .map_err(|e| McpError::Transport(format!("SSE handshake failed: {e}")))
+            .map_err(|e| McpError::Transport(format!("list_tools failed on {}: {e}", self.server_name)))
     }
 
-    async fn connect_streamable_http(config: &HttpConfig) -> Result<RunningService<RoleClient, ()>, McpError> {
-        use rmcp::transport::StreamableHttpClientTransport;
-        use rmcp::transport::streamable_http_client::StreamableHttpClientTransportConfig;
+    /// List all resources, or none if the server does not offer resources
+    pub async fn list_resources(&self) -> Result<Vec<Resource>, McpError> {
+        let (_permit, session) = self.acquire().await?;
+        let service = session.current();
+        if !offers(&service, |caps| caps.resources.is_some()) {
+            return Ok(Vec::new());
+        }
 
-        let mut transport_config = StreamableHttpClientTransportConfig::with_uri(config.url.as_str());
+        service
+            .list_all_resources()
+            .await
+            .map_err(|e| McpError::Transport(format!("list_resources failed on {}: {e}", self.server_name)))
+    }
 
-        // Apply auth header if configured
-        if let Some(McpAuthConfig::Token { ref token }) = config.auth {
-            use secrecy::ExposeSecret;
-            transport_config = transport_config.auth_header(format!("Bearer {}", token.expose_secret()));
+    /// List all resource templates, or none if the server does not offer resources
+    pub async fn list_resource_templates(&self) -> Result<Vec<ResourceTemplate>, McpError> {
+        let (_permit, session) = self.acquire().await?;
+        let service = session.current();
+        if !offers(&service, |caps| caps.resources.is_some()) {
+            return Ok(Vec::new());
         }
 
-        let client = reqwest::Client::new();
-        let transport = StreamableHttpClientTransport::with_client(client, transport_config);
+        service
+            .list_all_resource_templates()
+            .await
+            .map_err(|e| McpError::Transport(format!("list_resource_templates failed on {}: {e}", self.server_name)))
+    }
+
+    /// Read a resource by its URI on this server
+    pub async fn read_resource(&self, uri: &str) -> Result<ReadResourceResult, McpError> {
+        let (_permit, session) = self.acquire().await?;
 
-        ().serve(transport)
+        session
+            .current()
+            .read_resource(ReadResourceRequestParams::new(uri))
             .await
-            .map_err(|e| McpError::Transport(format!("StreamableHTTP handshake failed: {e}")))
+            .map_err(|e| match e {
+                ServiceError::McpError(ref data) if data.code == ErrorCode::RESOURCE_NOT_FOUND => {
+                    McpError::ResourceNotFound {
+                        uri: format!("{}__{uri}", self.server_name),
+                    }
+                }
+                e => McpError::Transport(format!("read_resource failed on {}: {e}", self.server_name)),
+            })
     }
 
-    /// List all tools available on this server
-    pub async fn list_tools(&self) -> Result<Vec<Tool>, McpError> {
-        self.service
-            .lock()
+    /// List all prompts, or none if the server does not offer prompts
+    pub async fn list_prompts(&self) -> Result<Vec<Prompt>, McpError> {
+        let (_permit, session) = self.acquire().await?;
+        let service = session.current();
+        if !offers(&service, |caps| caps.prompts.is_some()) {
+            return Ok(Vec::new());
+        }
+
+        service
+            .list_all_prompts()
             .await
-            .list_all_tools()
+            .map_err(|e| McpError::Transport(format!("list_prompts failed on {}: {e}", self.server_name)))
+    }
+
+    /// Render a prompt on this server
+    pub async fn get_prompt(
+        &self,
+        name: &str,
+        arguments: Option<serde_json::Map<String, serde_json::Value>>,
+    ) -> Result<GetPromptResult, McpError> {
+        let mut params = GetPromptRequestParams::new(name);
+        if let Some(arguments) = arguments {
+            params = params.with_arguments(arguments);
+        }
+        let (_permit, session) = self.acquire().await?;
+
+        session
+            .current()
+            .get_prompt(params)
             .await
-            .map_err(|e| McpError::Transport(format!("list_tools failed on {}: {e}", self.server_name)))
+            .map_err(|e| McpError::Transport(format!("get_prompt failed on {}: {e}", self.server_name)))
     }
 
     /// Call a tool on this server, reconnecting once on transport failure
@@ -102,37 +337,28 @@ impl McpClient {
         name: &str,
         arguments: Option<serde_json::Map<String, serde_json::Value>>,
     ) -> Result<CallToolResult, McpError> {
-        // First attempt — clone arguments so we retain them for the retry
-        let first_result = {
-            let guard = self.service.lock().await;
-            let mut params = CallToolRequestParams::new(name.to_string());
-            if let Some(ref args) = arguments {
-                params = params.with_arguments(args.clone());
-            }
-            guard.call_tool(params).await
-        };
-
-        if let Ok(result) = first_result {
-            return Ok(result);
-        }
-
-        // Transport failure — reconnect and retry once
-        tracing::warn!(server = %self.server_name, "MCP transport failure, reconnecting");
-
-        let new_service = match &self.server_config {
-            McpServerType::Stdio(c) => Self::connect_stdio(c).await?,
-            McpServerType::Sse(c) => Self::connect_sse(c).await?,
-            McpServerType::StreamableHttp(c) => Self::connect_streamable_http(c).await?,
-        };
-
-        let mut guard = self.service.lock().await;
-        *guard = new_service;
-
         let mut params = CallToolRequestParams::new(name.to_string());
         if let Some(args) = arguments {
             params = params.with_arguments(args);
         }
-        guard.call_tool(params).await.map_err(|e| {
+        let (_permit, session) = self.acquire().await?;
+
+        let service = session.current();
+        match service.call_tool(params.clone()).await {
+            Ok(result) => return Ok(result),
+            // The server answered, so the session is fine
+            Err(ServiceError::McpError(e)) => {
+                return Err(McpError::Execution(format!(
+                    "tool '{name}' failed on {}: {}",
+                    self.server_name, e.message
+                )));
+            }
+            Err(_) => {}
+        }
+
+        // Transport failure — reconnect and retry once
+        let service = self.reconnect(session, &service).await?;
+        service.call_tool(params).await.map_err(|e| {
             McpError::Execution(format!(
                 "tool '{}' failed on {} after reconnect: {e}",
                 name, self.server_name
@@ -140,18 +366,125 @@ impl McpClient {
         })
     }
 
+    /// Reconnect sessions whose transport closed, such as a crashed
+    /// STDIO child process, returning how many were restarted
+    pub async fn restart_closed(&self) -> Result<u32, McpError> {
+        let mut restarted = 0;
+        for session in &self.sessions {
+            let service = session.current();
+            if service.is_transport_closed() {
+                self.reconnect(session, &service).await?;
+                restarted += 1;
+            }
+        }
+
+        Ok(restarted)
+    }
+
+    /// Clear and return whether the server reported a list change
+    pub fn take_list_changed(&self) -> bool {
+        self.list_changed.pending.swap(false, Ordering::Relaxed)
+    }
+
+    /// Wait until the server reports a list change
+    pub async fn list_changed(&self) {
+        self.list_changed.notify.notified().await;
+    }
+
     /// Get the server name
     pub fn server_name(&self) -> &str {
         &self.server_name
     }
 
-    /// Gracefully shut down the connection
-    pub async fn shutdown(self) -> Result<(), McpError> {
-        self.service
-            .into_inner()
-            .cancel()
-            .await
-            .map_err(|e| McpError::Transport(format!("shutdown failed: {e}")))?;
-        Ok(())
+    /// Close all sessions; requests waiting for a permit fail
+    pub fn shutdown(&self) {
+        if let Some(ref permits) = self.permits {
+            permits.close();
+        }
+        for session in &self.sessions {
+            session.current().cancellation_token().cancel();
+        }
+    }
+}
+
+/// Build the transport for an HTTP server, applying auth, headers, and TLS
+fn http_transport(
+    config: &HttpConfig,
+    headers: &HashMap<HeaderName, HeaderValue>,
+    authorization: Option<&SecretString>,
+) -> Result<StreamableHttpClientTransport<reqwest::Client>, McpError> {
+    let mut transport_config = StreamableHttpClientTransportConfig::with_uri(config.url.as_str());
+    let mut headers = headers.clone();
+
+    match config.auth {
+        // rmcp adds the `Bearer` scheme itself
+        Some(McpAuthConfig::Token { ref token }) => {
+            transport_config = transport_config.auth_header(token.expose_secret());
+        }
+        Some(McpAuthConfig::Forward) => {
+            if let Some(authorization) = authorization {
+                let mut value = HeaderValue::from_str(authorization.expose_secret())
+                    .map_err(|_| McpError::Transport("invalid forwarded authorization header".to_owned()))?;
+                value.set_sensitive(true);
+                headers.insert(AUTHORIZATION, value);
+            }
+        }
+        None => {}
+    }
+
+    let client = http_client(config.tls.as_ref())?;
+    Ok(StreamableHttpClientTransport::with_client(
+        client,
+        transport_config.custom_headers(headers),
+    ))
+}
+
+/// Build an HTTP client trusting the configured CA and presenting the
+/// configured client certificate
+fn http_client(tls: Option<&McpTlsConfig>) -> Result<reqwest::Client, McpError> {
+    let mut builder = reqwest::Client::builder();
+
+    if let Some(tls) = tls {
+        if let Some(ref ca_cert) = tls.ca_cert {
+            let certs = reqwest::Certificate::from_pem_bundle(&read_pem(ca_cert)?)
+                .map_err(|e| McpError::Transport(format!("invalid CA certificate {ca_cert}: {e}")))?;
+            builder = builder.tls_certs_merge(certs);
+        }
+
+        if let (Some(cert), Some(key)) = (&tls.client_cert, &tls.client_key) {
+            let mut pem = read_pem(cert)?;
+            pem.extend(read_pem(key)?);
+            let identity = reqwest::Identity::from_pem(&pem)
+                .map_err(|e| McpError::Transport(format!("invalid client certificate {cert}: {e}")))?;
+            builder = builder.identity(identity);
+        }
     }
+
+    builder
+        .build()
+        .map_err(|e| McpError::Transport(format!("failed to build HTTP client: {e}")))
+}
+
+fn read_pem(path: &str) -> Result<Vec<u8>, McpError> {
+    std::fs::read(path).map_err(|e| McpError::Transport(format!("failed to read {path}: {e}")))
+}
+
+/// Parse the configured headers
+fn build_headers(headers: &[McpHeaderInsert]) -> Result<HashMap<HeaderName, HeaderValue>, McpError> {
+    headers
+        .iter()
+        .map(|header| {
+            let name = HeaderName::from_bytes(header.name.as_bytes());
+            let value = HeaderValue::from_str(&header.value);
+            match (name, value) {
+                (Ok(name), Ok(value)) => Ok((name, value)),
+                _ => Err(McpError::Transport(format!("invalid header '{}'", header.name))),
+            }
+        })
+        .collect()
+}
+
+/// Whether the server announced a capability during initialization
+fn offers(service: &Service, capability: impl FnOnce(&rmcp::model::ServerCapabilities) -> bool) -> bool {
+    service.peer_info().is_some_and(|info| capability(&info.capabilities))
 }
@@ -1,13 +1,16 @@
 use std::collections::HashMap;
+use std::sync::Arc;
 
-use rmcp::model::CallToolResult;
-use synapse_config::McpConfig;
+use rmcp::model::{CallToolResult, GetPromptResult, PromptArgument, ReadResourceResult, ResourceContents};
+use secrecy::SecretString;
+use synapse_config::{McpAuthConfig, McpCacheConfig, McpConfig, McpServerConfig, McpServerType};
 
 use super::client::McpClient;
+use crate::cache::DownstreamCache;
 use crate::error::McpError;
 
 /// Tool descriptor with server origin
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct AggregatedTool {
     /// Fully qualified name: `server_name__tool_name`
     pub qualified_name: String,
@@ -21,117 +24,596 @@ pub struct AggregatedTool {
     pub input_schema: serde_json::Value,
 }
 
-/// Separator between server name and tool name
+/// Resource descriptor with server origin
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AggregatedResource {
+    /// Fully qualified URI: `server_name__uri`
+    pub qualified_uri: String,
+    /// Original URI on the downstream server
+    pub original_uri: String,
+    /// Fully qualified name: `server_name__name`
+    pub qualified_name: String,
+    /// Server this resource belongs to
+    pub server_name: String,
+    /// Resource description
+    pub description: String,
+    /// MIME type of the resource content
+    pub mime_type: Option<String>,
+}
+
+/// Resource template descriptor with server origin
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AggregatedResourceTemplate {
+    /// Fully qualified URI template: `server_name__template`
+    pub qualified_uri_template: String,
+    /// Original URI template on the downstream server
+    pub original_uri_template: String,
+    /// Fully qualified name: `server_name__name`
+    pub qualified_name: String,
+    /// Server this template belongs to
+    pub server_name: String,
+    /// Template description
+    pub description: String,
+    /// MIME type of resources created from the template
+    pub mime_type: Option<String>,
+}
+
+/// Prompt descriptor with server origin
+#[derive(Debug, Clone, PartialEq)]
+pub struct AggregatedPrompt {
+    /// Fully qualified name: `server_name__prompt_name`
+    pub qualified_name: String,
+    /// Original prompt name on the downstream server
+    pub original_name: String,
+    /// Server this prompt belongs to
+    pub server_name: String,
+    /// Prompt description
+    pub description: String,
+    /// Arguments the prompt accepts
+    pub arguments: Vec<PromptArgument>,
+}
+
+/// Connection status of a configured downstream server
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct ServerStatus {
+    /// Configured server name
+    pub name: String,
+    /// Whether a live connection is held
+    pub connected: bool,
+    /// Number of tools aggregated from this server
+    pub tool_count: usize,
+    /// Most recent connection error, if any
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_error: Option<String>,
+    /// Times the server was reconnected after failing or crashing
+    pub restarts: u32,
+}
+
+/// Separator between server name and tool, resource, or prompt name
 const TOOL_SEPARATOR: &str = "__";
 
+/// Server that forwards the caller's authorization
+#[derive(Clone)]
+struct ForwardingServer {
+    config: McpServerConfig,
+    /// Per-credential connections
+    clients: Arc<DownstreamCache>,
+}
+
 /// Manages connections to all configured MCP downstream servers
+///
+/// Clones share connections, so a copy with one server added or removed
+/// can be built while the original keeps serving calls.
+#[derive(Clone)]
 pub struct DownstreamManager {
-    clients: HashMap<String, McpClient>,
+    clients: HashMap<String, Arc<McpClient>>,
+    /// Servers that forward the caller's authorization, keyed by name
+    forwarding: HashMap<String, ForwardingServer>,
+    /// Limits of the per-credential connection caches
+    cache_config: McpCacheConfig,
+    /// Most recent error of each server; servers without a client
+    /// failed to connect
+    errors: HashMap<String, String>,
+    /// Times each server was reconnected by the supervisor
+    restarts: HashMap<String, u32>,
     /// Cached aggregated tool list
     tools: Vec<AggregatedTool>,
+    /// Cached aggregated resource list
+    resources: Vec<AggregatedResource>,
+    /// Cached aggregated resource template list
+    resource_templates: Vec<AggregatedResourceTemplate>,
+    /// Cached aggregated prompt list
+    prompts: Vec<AggregatedPrompt>,
 }
 
 impl DownstreamManager {
     /// Connect to all configured MCP servers
     ///
     /// Servers that fail to connect are logged and skipped rather than
-    /// causing startup failure.
+    /// causing startup failure. Servers that forward the caller's
+    /// authorization are connected without it to discover their tools;
+    /// calls with a credential use a connection of their own. Disabled
+    /// servers are not connected.
     pub async fn connect(config: &McpConfig) -> Self {
-        let mut clients = HashMap::new();
+        let mut manager = Self {
+            clients: HashMap::new(),
+            forwarding: HashMap::new(),
+            cache_config: config.cache.clone().unwrap_or_default(),
+            errors: HashMap::new(),
+            restarts: HashMap::new(),
+            tools: Vec::new(),
+            resources: Vec::new(),
+            resource_templates: Vec::new(),
+            prompts: Vec::new(),
+        };
 
-        for (name, server_config) in &config.servers {
-            match McpClient::connect(name, &server_config.server_type).await {
-                Ok(client) => {
-                    clients.insert(name.clone(), client);
-                }
-                Err(e) => {
-                    tracing::warn!(
-                        server = name,
-                        error = %e,
-                        "failed to connect to MCP server, skipping"
-                    );
-                }
+        for (name, server_config) in config.servers.iter().filter(|(_, server)| server.enabled) {
+            manager.connect_server(name, server_config).await;
+        }
+
+        manager.refresh_tools().await;
+        manager.refresh_resources().await;
+        manager.refresh_prompts().await;
+        manager
+    }
+
+    /// Connect one server, recording the error if it fails
+    async fn connect_server(&mut self, name: &str, server_config: &McpServerConfig) {
+        let forwards_auth = matches!(
+            server_config.server_type,
+            McpServerType::Sse(ref http) | McpServerType::StreamableHttp(ref http)
+                if matches!(http.auth, Some(McpAuthConfig::Forward))
+        );
+        if forwards_auth {
+            self.forwarding.insert(
+                name.to_owned(),
+                ForwardingServer {
+                    config: server_config.clone(),
+                    clients: Arc::new(DownstreamCache::new(&self.cache_config)),
+                },
+            );
+        }
+
+        match McpClient::connect(name, server_config, None).await {
+            Ok(client) => {
+                self.clients.insert(name.to_owned(), Arc::new(client));
+            }
+            Err(e) => {
+                tracing::warn!(
+                    server = name,
+                    error = %e,
+                    "failed to connect to MCP server, skipping"
+                );
+                self.errors.insert(name.to_owned(), e.to_string());
             }
         }
+    }
 
-        let mut manager = Self {
-            clients,
-            tools: Vec::new(),
+    /// A copy with one server connected, replacing any server of that name
+    ///
+    /// Other servers keep their connections and aggregated lists. A
+    /// disabled server is only removed.
+    pub async fn with_server(&self, name: &str, server_config: &McpServerConfig) -> Self {
+        let mut manager = self.without_server(name);
+        if !server_config.enabled {
+            return manager;
+        }
+
+        manager.connect_server(name, server_config).await;
+        manager.list_server(name).await;
+        manager
+    }
+
+    /// A copy with a failed server connected again
+    ///
+    /// Unlike [`Self::with_server`], the restart count is kept and
+    /// bumped if the server connects.
+    pub async fn with_reconnected(&self, name: &str, server_config: &McpServerConfig) -> Self {
+        let restarts = self.restarts.get(name).copied().unwrap_or_default();
+        let mut manager = self.with_server(name, server_config).await;
+        let connected = manager.clients.contains_key(name);
+        manager
+            .restarts
+            .insert(name.to_owned(), restarts + u32::from(connected));
+        manager
+    }
+
+    /// A copy with one server's tools, resources, and prompts listed anew
+    ///
+    /// `restarted` sessions are added to the server's restart count, and
+    /// `error` is recorded as its most recent error.
+    pub async fn with_refreshed(&self, name: &str, restarted: u32, error: Option<String>) -> Self {
+        let mut manager = self.clone();
+        manager.tools.retain(|t| t.server_name != name);
+        manager.resources.retain(|r| r.server_name != name);
+        manager.resource_templates.retain(|t| t.server_name != name);
+        manager.prompts.retain(|p| p.server_name != name);
+        manager.list_server(name).await;
+
+        if restarted > 0 {
+            *manager.restarts.entry(name.to_owned()).or_default() += restarted;
+        }
+        if let Some(error) = error {
+            manager.errors.insert(name.to_owned(), error);
+        }
+        manager
+    }
+
+    /// Append the tools, resources, and prompts of a connected server
+    async fn list_server(&mut self, name: &str) {
+        let Some(client) = self.clients.get(name).cloned() else {
+            return;
         };
-        manager.refresh_tools().await;
+
+        self.tools.extend(server_tools(name, &client).await);
+        let (resources, resource_templates) = server_resources(name, &client).await;
+        self.resources.extend(resources);
+        self.resource_templates.extend(resource_templates);
+        self.prompts.extend(server_prompts(name, &client).await);
+    }
+
+    /// Whether a server offers the same tools, resources, and prompts in
+    /// both managers
+    pub fn offers_same(&self, other: &Self, name: &str) -> bool {
+        same_for_server(&self.tools, &other.tools, name)
+            && same_for_server(&self.resources, &other.resources, name)
+            && same_for_server(&self.resource_templates, &other.resource_templates, name)
+            && same_for_server(&self.prompts, &other.prompts, name)
+    }
+
+    /// A copy without one server and everything it offers
+    ///
+    /// Calls already running on the server finish on their own
+    /// connection.
+    #[must_use]
+    pub fn without_server(&self, name: &str) -> Self {
+        let mut manager = self.clone();
+        manager.clients.remove(name);
+        manager.forwarding.remove(name);
+        manager.errors.remove(name);
+        manager.restarts.remove(name);
+        manager.tools.retain(|t| t.server_name != name);
+        manager.resources.retain(|r| r.server_name != name);
+        manager.resource_templates.retain(|t| t.server_name != name);
+        manager.prompts.retain(|p| p.server_name != name);
         manager
     }
 
     /// Refresh the aggregated tool list from all connected servers
     pub async fn refresh_tools(&mut self) {
         let mut tools = Vec::new();
-
         for (server_name, client) in &self.clients {
-            match client.list_tools().await {
-                Ok(server_tools) => {
-                    for tool in server_tools {
-                        let original_name = tool.name.to_string();
-                        let qualified_name = format!("{server_name}{TOOL_SEPARATOR}{original_name}");
-                        let description = tool.description.as_deref().unwrap_or("").to_string();
-                        let input_schema = serde_json::to_value(&*tool.input_schema).unwrap_or_default();
-
-                        tools.push(AggregatedTool {
-                            qualified_name,
-                            original_name,
-                            server_name: server_name.clone(),
-                            description,
-                            input_schema,
-                        });
-                    }
-                }
-                Err(e) => {
-                    tracing::warn!(
-                        server = server_name,
-                        error = %e,
-                        "failed to list tools from MCP server"
-                    );
-                }
-            }
+            tools.extend(server_tools(server_name, client).await);
         }
 
         tracing::info!(count = tools.len(), "aggregated MCP tools from all servers");
         self.tools = tools;
     }
 
+    /// Refresh the aggregated resource and resource template lists
+    pub async fn refresh_resources(&mut self) {
+        let mut resources = Vec::new();
+        let mut resource_templates = Vec::new();
+        for (server_name, client) in &self.clients {
+            let (server_resources, templates) = server_resources(server_name, client).await;
+            resources.extend(server_resources);
+            resource_templates.extend(templates);
+        }
+
+        tracing::info!(
+            resources = resources.len(),
+            templates = resource_templates.len(),
+            "aggregated MCP resources from all servers"
+        );
+        self.resources = resources;
+        self.resource_templates = resource_templates;
+    }
+
+    /// Refresh the aggregated prompt list
+    pub async fn refresh_prompts(&mut self) {
+        let mut prompts = Vec::new();
+        for (server_name, client) in &self.clients {
+            prompts.extend(server_prompts(server_name, client).await);
+        }
+
+        tracing::info!(count = prompts.len(), "aggregated MCP prompts from all servers");
+        self.prompts = prompts;
+    }
+
     /// Get all aggregated tools
     pub fn tools(&self) -> &[AggregatedTool] {
         &self.tools
     }
 
+    /// Get all aggregated resources
+    pub fn resources(&self) -> &[AggregatedResource] {
+        &self.resources
+    }
+
+    /// Get all aggregated resource templates
+    pub fn resource_templates(&self) -> &[AggregatedResourceTemplate] {
+        &self.resource_templates
+    }
+
+    /// Get all aggregated prompts
+    pub fn prompts(&self) -> &[AggregatedPrompt] {
+        &self.prompts
+    }
+
     /// Parse a qualified tool name into (`server_name`, `tool_name`)
+    ///
+    /// Qualified resource URIs and prompt names split the same way.
     pub fn parse_tool_name(qualified: &str) -> Option<(&str, &str)> {
         qualified.split_once(TOOL_SEPARATOR)
     }
 
+    /// Extract the caller's authorization header for forwarding
+    pub fn caller_authorization(headers: &http::HeaderMap) -> Option<SecretString> {
+        headers
+            .get(http::header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .map(SecretString::from)
+    }
+
+    /// Client to reach a server on behalf of a caller
+    ///
+    /// Servers that forward authorization get a connection per caller
+    /// credential; everything else shares the startup connection.
+    async fn client_for(
+        &self,
+        server_name: &str,
+        authorization: Option<&SecretString>,
+    ) -> Result<Arc<McpClient>, McpError> {
+        if let (Some(server), Some(authorization)) = (self.forwarding.get(server_name), authorization) {
+            return server
+                .clients
+                .get_or_connect(server_name, &server.config, authorization)
+                .await;
+        }
+
+        self.clients
+            .get(server_name)
+            .cloned()
+            .ok_or_else(|| McpError::ServerNotFound {
+                server: server_name.to_string(),
+            })
+    }
+
     /// Call a tool by its qualified name
     pub async fn call_tool(
         &self,
         qualified_name: &str,
         arguments: Option<serde_json::Map<String, serde_json::Value>>,
+        authorization: Option<&SecretString>,
     ) -> Result<CallToolResult, McpError> {
         let (server_name, tool_name) = Self::parse_tool_name(qualified_name).ok_or_else(|| McpError::ToolNotFound {
             tool: qualified_name.to_string(),
         })?;
 
-        let client = self.clients.get(server_name).ok_or_else(|| McpError::ServerNotFound {
-            server: server_name.to_string(),
-        })?;
+        let client = self.client_for(server_name, authorization).await?;
 
         client.call_tool(tool_name, arguments).await
     }
 
+    /// Read a resource by its qualified URI
+    ///
+    /// URIs in the returned contents are qualified with the server name.
+    pub async fn read_resource(
+        &self,
+        qualified_uri: &str,
+        authorization: Option<&SecretString>,
+    ) -> Result<ReadResourceResult, McpError> {
+        let (server_name, uri) = Self::parse_tool_name(qualified_uri).ok_or_else(|| McpError::ResourceNotFound {
+            uri: qualified_uri.to_string(),
+        })?;
+
+        let client = self.client_for(server_name, authorization).await?;
+
+        let mut result = client.read_resource(uri).await?;
+        for contents in &mut result.contents {
+            match contents {
+                ResourceContents::TextResourceContents { uri, .. }
+                | ResourceContents::BlobResourceContents { uri, .. } => {
+                    *uri = qualify(server_name, uri);
+                }
+            }
+        }
+
+        Ok(result)
+    }
+
+    /// Render a prompt by its qualified name
+    pub async fn get_prompt(
+        &self,
+        qualified_name: &str,
+        arguments: Option<serde_json::Map<String, serde_json::Value>>,
+        authorization: Option<&SecretString>,
+    ) -> Result<GetPromptResult, McpError> {
+        let prompt = self
+            .prompts
+            .iter()
+            .find(|p| p.qualified_name == qualified_name)
+            .ok_or_else(|| McpError::PromptNotFound {
+                prompt: qualified_name.to_string(),
+            })?;
+
+        let client = self.client_for(&prompt.server_name, authorization).await?;
+
+        client.get_prompt(&prompt.original_name, arguments).await
+    }
+
     /// Get a reference to a specific server client
     pub fn get_client(&self, server_name: &str) -> Option<&McpClient> {
-        self.clients.get(server_name)
+        self.clients.get(server_name).map(AsRef::as_ref)
     }
 
     /// Get the number of connected servers
     pub fn server_count(&self) -> usize {
         self.clients.len()
     }
+
+    /// Connected clients by server name
+    pub fn clients(&self) -> impl Iterator<Item = (&str, &McpClient)> {
+        self.clients
+            .iter()
+            .map(|(name, client)| (name.as_str(), client.as_ref()))
+    }
+
+    /// Names of servers that failed to connect
+    pub fn failed_servers(&self) -> impl Iterator<Item = &str> {
+        self.errors
+            .keys()
+            .filter(|name| !self.clients.contains_key(*name))
+            .map(String::as_str)
+    }
+
+    /// Connection status of every configured server, sorted by name
+    pub fn server_statuses(&self) -> Vec<ServerStatus> {
+        let mut names: Vec<&String> = self.clients.keys().chain(self.errors.keys()).collect();
+        names.sort();
+        names.dedup();
+
+        names
+            .into_iter()
+            .map(|name| ServerStatus {
+                name: name.clone(),
+                connected: self.clients.contains_key(name),
+                tool_count: self.tools.iter().filter(|t| &t.server_name == name).count(),
+                last_error: self.errors.get(name).cloned(),
+                restarts: self.restarts.get(name).copied().unwrap_or_default(),
+            })
+            .collect()
+    }
+}
+
+/// Aggregated item offered by one server
+trait ServerItem: PartialEq {
+    fn server_name(&self) -> &str;
+}
+
+impl ServerItem for AggregatedTool {
+    fn server_name(&self) -> &str {
+        &self.server_name
+    }
+}
+
+impl ServerItem for AggregatedResource {
+    fn server_name(&self) -> &str {
+        &self.server_name
+    }
+}
+
+impl ServerItem for AggregatedResourceTemplate {
+    fn server_name(&self) -> &str {
+        &self.server_name
+    }
+}
+
+impl ServerItem for AggregatedPrompt {
+    fn server_name(&self) -> &str {
+        &self.server_name
+    }
+}
+
+/// Whether two lists hold the same items of one server, in order
+fn same_for_server<T: ServerItem>(a: &[T], b: &[T], name: &str) -> bool {
+    let of_server = |item: &&T| item.server_name() == name;
+    a.iter().filter(of_server).eq(b.iter().filter(of_server))
+}
+
+/// List the tools of one server, logging failures
+async fn server_tools(server_name: &str, client: &McpClient) -> Vec<AggregatedTool> {
+    match client.list_tools().await {
+        Ok(tools) => tools
+            .into_iter()
+            .map(|tool| AggregatedTool {
+                qualified_name: qualify(server_name, &tool.name),
+                original_name: tool.name.to_string(),
+                server_name: server_name.to_owned(),
+                description: tool.description.as_deref().unwrap_or("").to_string(),
+                input_schema: serde_json::to_value(&*tool.input_schema).unwrap_or_default(),
+            })
+            .collect(),
+        Err(e) => {
+            tracing::warn!(
+                server = server_name,
+                error = %e,
+                "failed to list tools from MCP server"
+            );
+            Vec::new()
+        }
+    }
+}
+
+/// List the resources and resource templates of one server, logging failures
+async fn server_resources(
+    server_name: &str,
+    client: &McpClient,
+) -> (Vec<AggregatedResource>, Vec<AggregatedResourceTemplate>) {
+    let resources = match client.list_resources().await {
+        Ok(resources) => resources
+            .into_iter()
+            .map(|resource| AggregatedResource {
+                qualified_uri: qualify(server_name, &resource.uri),
+                qualified_name: qualify(server_name, &resource.name),
+                original_uri: resource.raw.uri,
+                server_name: server_name.to_owned(),
+                description: resource.raw.description.unwrap_or_default(),
+                mime_type: resource.raw.mime_type,
+            })
+            .collect(),
+        Err(e) => {
+            tracing::warn!(server = server_name, error = %e, "failed to list resources from MCP server");
+            Vec::new()
+        }
+    };
+
+    let resource_templates = match client.list_resource_templates().await {
+        Ok(templates) => templates
+            .into_iter()
+            .map(|template| AggregatedResourceTemplate {
+                qualified_uri_template: qualify(server_name, &template.uri_template),
+                qualified_name: qualify(server_name, &template.name),
+                original_uri_template: template.raw.uri_template,
+                server_name: server_name.to_owned(),
+                description: template.raw.description.unwrap_or_default(),
+                mime_type: template.raw.mime_type,
+            })
+            .collect(),
+        Err(e) => {
+            tracing::warn!(
+                server = server_name,
+                error = %e,
+                "failed to list resource templates from MCP server"
+            );
+            Vec::new()
+        }
+    };
+
+    (resources, resource_templates)
+}
+
+/// List the prompts of one server, logging failures
+async fn server_prompts(server_name: &str, client: &McpClient) -> Vec<AggregatedPrompt> {
+    match client.list_prompts().await {
+        Ok(prompts) => prompts
+            .into_iter()
+            .map(|prompt| AggregatedPrompt {
+                qualified_name: qualify(server_name, &prompt.name),
+                original_name: prompt.name,
+                server_name: server_name.to_owned(),
+                description: prompt.description.unwrap_or_default(),
+                arguments: prompt.arguments.unwrap_or_default(),
+            })
+            .collect(),
+        Err(e) => {
+            tracing::warn!(server = server_name, error = %e, "failed to list prompts from MCP server");
+            Vec::new()
+        }
+    }
+}
+
+/// Qualify a downstream name or URI with its server name
+fn qualify(server_name: &str, name: &str) -> String {
+    format!("{server_name}{TOOL_SEPARATOR}{name}")
 }
@@ -0,0 +1,354 @@
+//! MCP endpoint serving the aggregated downstream tools
+//!
+//! Exposes the tools, resources, and prompts of every downstream server
+//! over the MCP Streamable HTTP transport so MCP clients (IDEs, agent
+//! runtimes) can connect to the gateway directly. Everything keeps its
+//! `server__name` qualification, access rules apply as on the REST routes,
+//! and sessions are told when the aggregated tool list changes.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use rmcp::model::{
+    AnnotateAble as _, CallToolRequestParams, CallToolResult, Content, ErrorCode, ErrorData, GetPromptRequestParams,
+    GetPromptResult, Implementation, ListPromptsResult, ListResourceTemplatesResult, ListResourcesResult,
+    ListToolsResult, PaginatedRequestParams, Prompt, RawResource, RawResourceTemplate, ReadResourceRequestParams,
+    ReadResourceResult, Resource, ResourceTemplate, ServerCapabilities, ServerInfo, Tool,
+};
+use rmcp::service::{NotificationContext, RequestContext, RoleServer};
+use rmcp::transport::streamable_http_server::session::local::LocalSessionManager;
+use rmcp::transport::streamable_http_server::{StreamableHttpServerConfig, StreamableHttpService};
+use rmcp::{Peer, ServerHandler};
+use secrecy::SecretString;
+use synapse_core::HttpError;
+use tokio_util::sync::CancellationToken;
+
+use crate::McpState;
+use crate::downstream::manager::{
+    AggregatedPrompt, AggregatedResource, AggregatedResourceTemplate, AggregatedTool, DownstreamManager,
+};
+use crate::error::McpError;
+
+/// Streamable HTTP service for the MCP endpoint
+pub fn service(state: Arc<McpState>) -> StreamableHttpService<McpEndpoint> {
+    let config = StreamableHttpServerConfig {
+        sse_keep_alive: Some(Duration::from_secs(state.endpoint.sse_keep_alive_secs)),
+        stateful_mode: state.endpoint.stateful,
+        json_response: state.endpoint.json_response,
+        cancellation_token: state.shutdown.child_token(),
+        ..StreamableHttpServerConfig::default()
+    };
+
+    StreamableHttpService::new(
+        move || Ok(McpEndpoint::new(Arc::clone(&state))),
+        Arc::new(LocalSessionManager::default()),
+        config,
+    )
+}
+
+/// MCP server handler for one endpoint session
+pub struct McpEndpoint {
+    state: Arc<McpState>,
+    /// Cancelled when the session ends, stopping its notification task
+    closed: CancellationToken,
+}
+
+impl McpEndpoint {
+    fn new(state: Arc<McpState>) -> Self {
+        Self {
+            state,
+            closed: CancellationToken::new(),
+        }
+    }
+}
+
+impl Drop for McpEndpoint {
+    fn drop(&mut self) {
+        self.closed.cancel();
+    }
+}
+
+impl ServerHandler for McpEndpoint {
+    fn get_info(&self) -> ServerInfo {
+        ServerInfo::new(
+            ServerCapabilities::builder()
+                .enable_tools()
+                .enable_tool_list_changed()
+                .enable_resources()
+                .enable_prompts()
+                .build(),
+        )
+        .with_server_info(Implementation::new("synapse", env!("CARGO_PKG_VERSION")))
+        .with_instructions(
+            "Tools, resources, and prompts aggregated from the gateway's downstream MCP servers, named server__name",
+        )
+    }
+
+    async fn on_initialized(&self, context: NotificationContext<RoleServer>) {
+        let changes = self.state.subscribe_tool_changes();
+        tokio::spawn(forward_tool_changes(changes, context.peer, self.closed.clone()));
+    }
+
+    async fn list_tools(
+        &self,
+        _request: Option<PaginatedRequestParams>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<ListToolsResult, ErrorData> {
+        let snapshot = self.state.current();
+        let gateway_context = gateway_context(&context);
+        let tools = snapshot.caller_tools(&gateway_context).map(to_mcp_tool).collect();
+
+        Ok(ListToolsResult::with_all_items(tools))
+    }
+
+    async fn call_tool(
+        &self,
+        request: CallToolRequestParams,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, ErrorData> {
+        let name = request.name.as_ref();
+        tracing::debug!(
+            tool = name,
+            client_id = caller(&context).as_deref(),
+            "MCP endpoint tool call"
+        );
+
+        match self
+            .state
+            .call_tool(&gateway_context(&context), name, request.arguments)
+            .await
+        {
+            Ok(result) => Ok(result),
+            // Failures of the tool itself are results the model can read
+            Err(e @ (McpError::Execution(_) | McpError::Transport(_))) => {
+                tracing::warn!(tool = name, error = %e, "MCP endpoint tool call failed");
+                Ok(CallToolResult::error(vec![Content::text(e.client_message())]))
+            }
+            Err(e) => Err(to_error_data(&e)),
+        }
+    }
+
+    async fn list_resources(
+        &self,
+        _request: Option<PaginatedRequestParams>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<ListResourcesResult, ErrorData> {
+        let snapshot = self.state.current();
+        let gateway = gateway_context(&context);
+        let resources = snapshot.caller_resources(&gateway).map(to_mcp_resource).collect();
+
+        Ok(ListResourcesResult::with_all_items(resources))
+    }
+
+    async fn list_resource_templates(
+        &self,
+        _request: Option<PaginatedRequestParams>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<ListResourceTemplatesResult, ErrorData> {
+        let snapshot = self.state.current();
+        let gateway = gateway_context(&context);
+        let templates = snapshot
+            .caller_resource_templates(&gateway)
+            .map(to_mcp_resource_template)
+            .collect();
+
+        Ok(ListResourceTemplatesResult::with_all_items(templates))
+    }
+
+    async fn read_resource(
+        &self,
+        request: ReadResourceRequestParams,
+        context: RequestContext<RoleServer>,
+    ) -> Result<ReadResourceResult, ErrorData> {
+        let (server_name, uri) = DownstreamManager::parse_tool_name(&request.uri).ok_or_else(|| {
+            to_error_data(&McpError::ResourceNotFound {
+                uri: request.uri.clone(),
+            })
+        })?;
+
+        let snapshot = self.state.current();
+        snapshot
+            .check_resource(&gateway_context(&context), server_name, uri)
+            .map_err(|e| to_error_data(&e))?;
+
+        snapshot
+            .downstream
+            .read_resource(&request.uri, authorization(&context).as_ref())
+            .await
+            .map_err(|e| to_error_data(&e))
+    }
+
+    async fn list_prompts(
+        &self,
+        _request: Option<PaginatedRequestParams>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<ListPromptsResult, ErrorData> {
+        let snapshot = self.state.current();
+        let gateway = gateway_context(&context);
+        let prompts = snapshot.caller_prompts(&gateway).map(to_mcp_prompt).collect();
+
+        Ok(ListPromptsResult::with_all_items(prompts))
+    }
+
+    async fn get_prompt(
+        &self,
+        request: GetPromptRequestParams,
+        context: RequestContext<RoleServer>,
+    ) -> Result<GetPromptResult, ErrorData> {
+        let (server_name, prompt_name) = DownstreamManager::parse_tool_name(&request.name).ok_or_else(|| {
+            to_error_data(&McpError::PromptNotFound {
+                prompt: request.name.clone(),
+            })
+        })?;
+
+        let snapshot = self.state.current();
+        snapshot
+            .check_prompt(&gateway_context(&context), server_name, prompt_name)
+            .map_err(|e| to_error_data(&e))?;
+
+        snapshot
+            .downstream
+            .get_prompt(&request.name, request.arguments, authorization(&context).as_ref())
+            .await
+            .map_err(|e| to_error_data(&e))
+    }
+}
+
+/// Send `tools/list_changed` to a session whenever the aggregate changes
+async fn forward_tool_changes(
+    mut changes: tokio::sync::watch::Receiver<u64>,
+    peer: Peer<RoleServer>,
+    closed: CancellationToken,
+) {
+    changes.mark_unchanged();
+
+    loop {
+        tokio::select! {
+            () = closed.cancelled() => return,
+            result = changes.changed() => {
+                if result.is_err() {
+                    return;
+                }
+            }
+        }
+
+        if let Err(e) = peer.notify_tool_list_changed().await {
+            tracing::debug!(error = %e, "failed to notify MCP session of tool changes");
+            return;
+        }
+    }
+}
+
+/// Identity of the caller, from the gateway's request context
+fn identity(context: &RequestContext<RoleServer>) -> Option<&synapse_core::ClientIdentity> {
+    context
+        .extensions
+        .get::<http::request::Parts>()?
+        .extensions
+        .get::<synapse_core::RequestContext>()?
+        .client_identity
+        .as_ref()
+}
+
+/// Gateway request context of the caller, empty outside of HTTP sessions
+fn gateway_context(context: &RequestContext<RoleServer>) -> synapse_core::RequestContext {
+    context
+        .extensions
+        .get::<http::request::Parts>()
+        .and_then(|parts| parts.extensions.get::<synapse_core::RequestContext>())
+        .cloned()
+        .unwrap_or_else(synapse_core::RequestContext::empty)
+}
+
+/// Client ID of the caller
+fn caller(context: &RequestContext<RoleServer>) -> Option<String> {
+    identity(context).map(|identity| identity.client_id.clone())
+}
+
+/// Authorization header of the caller, forwarded to servers that ask for it
+fn authorization(context: &RequestContext<RoleServer>) -> Option<SecretString> {
+    let parts = context.extensions.get::<http::request::Parts>()?;
+    DownstreamManager::caller_authorization(&parts.headers)
+}
+
+fn to_mcp_tool(tool: &AggregatedTool) -> Tool {
+    let schema = match tool.input_schema {
+        serde_json::Value::Object(ref schema) => schema.clone(),
+        _ => serde_json::Map::new(),
+    };
+
+    Tool::new(tool.qualified_name.clone(), tool.description.clone(), schema)
+}
+
+fn to_mcp_resource(resource: &AggregatedResource) -> Resource {
+    let mut raw = RawResource::new(resource.qualified_uri.clone(), resource.qualified_name.clone());
+    raw.description = (!resource.description.is_empty()).then(|| resource.description.clone());
+    raw.mime_type.clone_from(&resource.mime_type);
+    raw.no_annotation()
+}
+
+fn to_mcp_resource_template(template: &AggregatedResourceTemplate) -> ResourceTemplate {
+    let mut raw = RawResourceTemplate::new(template.qualified_uri_template.clone(), template.qualified_name.clone());
+    raw.description = (!template.description.is_empty()).then(|| template.description.clone());
+    raw.mime_type.clone_from(&template.mime_type);
+    raw.no_annotation()
+}
+
+fn to_mcp_prompt(prompt: &AggregatedPrompt) -> Prompt {
+    Prompt::new(
+        prompt.qualified_name.clone(),
+        (!prompt.description.is_empty()).then(|| prompt.description.clone()),
+        (!prompt.arguments.is_empty()).then(|| prompt.arguments.clone()),
+    )
+}
+
+/// Map a gateway error to a JSON-RPC error, keeping its type in `data`
+fn to_error_data(error: &McpError) -> ErrorData {
+    let code = match error {
+        McpError::ServerNotFound { .. } | McpError::ToolNotFound { .. } | McpError::PromptNotFound { .. } => {
+            ErrorCode::INVALID_PARAMS
+        }
+        McpError::ResourceNotFound { .. } => ErrorCode::RESOURCE_NOT_FOUND,
+        McpError::AccessDenied { .. } | McpError::RateLimited { .. } => ErrorCode::INVALID_REQUEST,
+        McpError::InvalidRequest(_) | McpError::InvalidConfig(_) => ErrorCode::INVALID_PARAMS,
+        McpError::Transport(_) | McpError::Execution(_) | McpError::Internal(_) => ErrorCode::INTERNAL_ERROR,
+    };
+    let mut data = serde_json::json!({ "type": error.error_type() });
+    if let McpError::RateLimited { retry_after } = error {
+        data["retry_after"] = (*retry_after).into();
+    }
+
+    ErrorData::new(code, error.client_message(), Some(data))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn errors_keep_their_type() {
+        let error = to_error_data(&McpError::AccessDenied {
+            kind: "tool",
+            name: "github__delete_repo".to_owned(),
+        });
+        assert_eq!(error.code, ErrorCode::INVALID_REQUEST);
+        assert_eq!(error.data.unwrap()["type"], "access_denied");
+
+        let error = to_error_data(&McpError::RateLimited { retry_after: 30 });
+        assert_eq!(error.data.unwrap()["retry_after"], 30);
+    }
+
+    #[test]
+    fn tools_keep_qualified_names_and_schemas() {
+        let tool = to_mcp_tool(&AggregatedTool {
+            qualified_name: "github__create_issue".to_owned(),
+            original_name: "create_issue".to_owned(),
+            server_name: "github".to_owned(),
+            description: "Create an issue".to_owned(),
+            input_schema: serde_json::json!({"type": "object", "properties": {"title": {"type": "string"}}}),
+        });
+
+        assert_eq!(tool.name, "github__create_issue");
+        assert_eq!(tool.input_schema["properties"]["title"]["type"], "string");
+    }
+}
@@ -13,9 +13,17 @@ pub enum McpError {
     #[error("tool not found: {tool}")]
     ToolNotFound { tool: String },
 
-    /// Client lacks access to the requested tool
-    #[error("access denied to tool: {tool}")]
-    AccessDenied { tool: String },
+    /// Resource not found on any connected server
+    #[error("resource not found: {uri}")]
+    ResourceNotFound { uri: String },
+
+    /// Prompt not found on any connected server
+    #[error("prompt not found: {prompt}")]
+    PromptNotFound { prompt: String },
+
+    /// Client lacks access to the requested tool, resource or prompt
+    #[error("access denied to {kind}: {name}")]
+    AccessDenied { kind: &'static str, name: String },
 
     /// Transport-level connection or communication error
     #[error("transport error: {0}")]
@@ -29,6 +37,14 @@ pub enum McpError {
     #[error("rate limit exceeded")]
     RateLimited { retry_after: u64 },
 
+    /// Request is malformed or asks for something not configured
+    #[error("invalid request: {0}")]
+    InvalidRequest(String),
+
+    /// Server configuration submitted at runtime is invalid
+    #[error("invalid server configuration: {0}")]
+    InvalidConfig(String),
+
     /// Internal error
     #[error("internal error: {0}")]
     Internal(#[from] anyhow::Error),
@@ -37,7 +53,11 @@ pub enum McpError {
 impl HttpError for McpError {
     fn status_code(&self) -> StatusCode {
         match self {
-            Self::ServerNotFound { .. } | Self::ToolNotFound { .. } => StatusCode::NOT_FOUND,
+            Self::ServerNotFound { .. }
+            | Self::ToolNotFound { .. }
+            | Self::ResourceNotFound { .. }
+            | Self::PromptNotFound { .. } => StatusCode::NOT_FOUND,
+            Self::InvalidRequest(_) | Self::InvalidConfig(_) => StatusCode::BAD_REQUEST,
             Self::AccessDenied { .. } => StatusCode::FORBIDDEN,
             Self::Transport(_) => StatusCode::BAD_GATEWAY,
             Self::Execution(_) | Self::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
@@ -47,7 +67,11 @@ impl HttpError for McpError {
 
     fn error_type(&self) -> &str {
         match self {
-            Self::ServerNotFound { .. } | Self::ToolNotFound { .. } => "not_found",
+            Self::ServerNotFound { .. }
+            | Self::ToolNotFound { .. }
+            | Self::ResourceNotFound { .. }
+            | Self::PromptNotFound { .. } => "not_found",
+            Self::InvalidRequest(_) | Self::InvalidConfig(_) => "invalid_request_error",
             Self::AccessDenied { .. } => "access_denied",
             Self::Transport(_) => "transport_error",
             Self::Execution(_) => "execution_error",
@@ -60,7 +84,11 @@ impl HttpError for McpError {
         match self {
             Self::ServerNotFound { server } => format!("MCP server not found: {server}"),
             Self::ToolNotFound { tool } => format!("tool not found: {tool}"),
-            Self::AccessDenied { tool } => format!("access denied to tool: {tool}"),
+            Self::ResourceNotFound { uri } => format!("resource not found: {uri}"),
+            Self::PromptNotFound { prompt } => format!("prompt not found: {prompt}"),
+            Self::InvalidRequest(msg) => msg.clone(),
+            Self::InvalidConfig(msg) => format!("invalid server configuration: {msg}"),
+            Self::AccessDenied { kind, name } => format!("access denied to {kind}: {name}"),
             Self::Transport(_) => "failed to communicate with MCP server".to_string(),
             Self::Execution(msg) => format!("tool execution failed: {msg}"),
             Self::RateLimited { retry_after } => {
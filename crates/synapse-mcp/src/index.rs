@@ -6,6 +6,18 @@ use tantivy::{Index, IndexReader, ReloadPolicy, doc};
 use crate::downstream::manager::AggregatedTool;
 use crate::error::McpError;
 
+/// How `/mcp/search` matches tools
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SearchMode {
+    /// BM25 over tool names and descriptions
+    Keyword,
+    /// Embedding similarity
+    Semantic,
+    /// Keyword and semantic rankings fused
+    Hybrid,
+}
+
 /// Search result from the tool index
 #[derive(Debug, Clone, serde::Serialize)]
 pub struct ToolSearchResult {
@@ -17,8 +29,28 @@ pub struct ToolSearchResult {
     pub tool_name: String,
     /// Tool description
     pub description: String,
-    /// Relevance score
+    /// Relevance score: BM25 for keyword search, cosine similarity for
+    /// semantic search, or the fused score for hybrid search
     pub score: f32,
+    /// How the tool ranked in each search
+    pub explanation: SearchExplanation,
+}
+
+/// Per-search ranks and scores behind a result
+#[derive(Debug, Clone, Default, serde::Serialize)]
+pub struct SearchExplanation {
+    /// 1-based rank in the keyword search
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub keyword_rank: Option<usize>,
+    /// BM25 score in the keyword search
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub keyword_score: Option<f32>,
+    /// 1-based rank in the semantic search
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub semantic_rank: Option<usize>,
+    /// Cosine similarity in the semantic search
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub semantic_similarity: Option<f32>,
 }
 
 /// Full-text search index for MCP tools backed by Tantivy
@@ -101,7 +133,7 @@ impl ToolIndex {
             .map_err(|_| field_err("server_name"))?;
 
         let mut results = Vec::with_capacity(top_docs.len());
-        for (score, doc_address) in top_docs {
+        for (rank, (score, doc_address)) in top_docs.into_iter().enumerate() {
             let doc: tantivy::TantivyDocument = searcher
                 .doc(doc_address)
                 .map_err(|e| McpError::Internal(anyhow::anyhow!("failed to retrieve doc: {e}")))?;
@@ -115,6 +147,11 @@ impl ToolIndex {
                 tool_name: get_text(tool_name_field),
                 description: get_text(description_field),
                 score,
+                explanation: SearchExplanation {
+                    keyword_rank: Some(rank + 1),
+                    keyword_score: Some(score),
+                    ..SearchExplanation::default()
+                },
             });
         }
 
@@ -1,39 +1,93 @@
 #![allow(clippy::must_use_candidate, clippy::missing_errors_doc)]
 
 pub mod access;
+pub mod admin;
 pub mod cache;
 pub mod downstream;
+pub mod endpoint;
 pub mod error;
 pub mod index;
 pub mod router;
+pub mod semantic;
+mod supervisor;
 
 pub use error::McpError;
 pub use router::mcp_router;
 
-use access::AccessController;
-use downstream::manager::DownstreamManager;
-use index::ToolIndex;
-use synapse_config::McpConfig;
+use std::sync::{Arc, PoisonError, RwLock};
+
+use access::{AccessController, Caller};
+use downstream::manager::{
+    AggregatedPrompt, AggregatedResource, AggregatedResourceTemplate, AggregatedTool, DownstreamManager,
+};
+use index::{SearchMode, ToolIndex, ToolSearchResult};
+use indexmap::IndexMap;
+use rmcp::model::CallToolResult;
+use semantic::{SemanticIndex, SemanticSearch, ToolEmbedder};
+use synapse_config::{
+    McpAdminConfig, McpConfig, McpEndpointConfig, McpPolicyConfig, McpSearchConfig, McpServerConfig,
+    McpSupervisorConfig, RateLimitStorage,
+};
+use synapse_core::RequestContext;
+use synapse_ratelimit::{McpLimiter, RateLimitError};
+use tokio::sync::{Mutex, watch};
+use tokio_util::sync::CancellationToken;
 
 /// Shared MCP subsystem state
 pub struct McpState {
+    /// Downstream servers and everything derived from them, swapped as a
+    /// whole when servers change at runtime
+    current: RwLock<Arc<McpSnapshot>>,
+    /// Serializes runtime server changes
+    changes: Mutex<()>,
+    /// Storage for rate limiters of servers registered at runtime
+    rate_limit_storage: RateLimitStorage,
+    /// Access policies for callers matching an identity
+    policies: Arc<[McpPolicyConfig]>,
+    /// MCP endpoint settings
+    pub endpoint: McpEndpointConfig,
+    /// Admin API settings
+    pub admin: McpAdminConfig,
+    /// Supervision of downstream servers
+    supervisor: McpSupervisorConfig,
+    /// Tool search settings
+    search: McpSearchConfig,
+    /// Embeds tools and queries once semantic search is enabled
+    semantic: Option<SemanticSearch>,
+    /// Generation of the aggregated tool list, bumped on every change
+    tool_changes: watch::Sender<u64>,
+    /// Cancelled on shutdown to close MCP endpoint sessions
+    shutdown: CancellationToken,
+}
+
+/// Downstream servers with their connections, access rules, rate limits,
+/// and search index
+///
+/// Requests hold on to the snapshot they started with, so calls in flight
+/// finish on their server even if it is removed meanwhile.
+pub struct McpSnapshot {
+    /// Configured servers by name, including disabled ones
+    pub servers: IndexMap<String, McpServerConfig>,
     /// Manages connections to downstream MCP servers
     pub downstream: DownstreamManager,
     /// Tool-level access controller
     pub access: AccessController,
+    /// Per-server and per-tool call rate limits
+    rate_limiter: McpLimiter,
     /// Full-text search index for tools (None if no tools available)
     pub tool_index: Option<ToolIndex>,
+    /// Tool embeddings (None unless semantic search is enabled and the
+    /// tools could be embedded)
+    pub semantic_index: Option<SemanticIndex>,
 }
 
-impl McpState {
-    /// Initialize the MCP subsystem from configuration
-    ///
-    /// Connects to all configured servers, aggregates tools,
-    /// and builds the search index.
-    pub async fn new(config: &McpConfig) -> Result<Self, McpError> {
-        let access = AccessController::new(&config.servers);
-        let downstream = DownstreamManager::connect(config).await;
-
+impl McpSnapshot {
+    fn new(
+        servers: IndexMap<String, McpServerConfig>,
+        downstream: DownstreamManager,
+        rate_limiter: McpLimiter,
+        policies: Arc<[McpPolicyConfig]>,
+    ) -> Result<Self, McpError> {
         // Build search index from aggregated tools
         let tool_index = if downstream.tools().is_empty() {
             None
@@ -41,16 +95,355 @@ impl McpState {
             Some(ToolIndex::build(downstream.tools())?)
         };
 
+        Ok(Self {
+            access: AccessController::new(&servers, policies),
+            servers,
+            downstream,
+            rate_limiter,
+            tool_index,
+            semantic_index: None,
+        })
+    }
+
+    /// Tools the caller may see and call
+    pub fn caller_tools<'a>(&'a self, context: &'a RequestContext) -> impl Iterator<Item = &'a AggregatedTool> {
+        let caller = Caller::from_context(context);
+        self.downstream
+            .tools()
+            .iter()
+            .filter(move |t| self.access.allows_tool(&caller, &t.server_name, &t.original_name))
+    }
+
+    /// Resources the caller may see and read
+    pub fn caller_resources<'a>(&'a self, context: &'a RequestContext) -> impl Iterator<Item = &'a AggregatedResource> {
+        let caller = Caller::from_context(context);
+        self.downstream.resources().iter().filter(move |r| {
+            self.access
+                .check_caller_resource(&caller, &r.server_name, &r.original_uri)
+                .is_ok()
+        })
+    }
+
+    /// Resource templates the caller may see and read
+    pub fn caller_resource_templates<'a>(
+        &'a self,
+        context: &'a RequestContext,
+    ) -> impl Iterator<Item = &'a AggregatedResourceTemplate> {
+        let caller = Caller::from_context(context);
+        self.downstream.resource_templates().iter().filter(move |t| {
+            self.access
+                .check_caller_resource(&caller, &t.server_name, &t.original_uri_template)
+                .is_ok()
+        })
+    }
+
+    /// Prompts the caller may see and get
+    pub fn caller_prompts<'a>(&'a self, context: &'a RequestContext) -> impl Iterator<Item = &'a AggregatedPrompt> {
+        let caller = Caller::from_context(context);
+        self.downstream.prompts().iter().filter(move |p| {
+            self.access
+                .check_caller_prompt(&caller, &p.server_name, &p.original_name)
+                .is_ok()
+        })
+    }
+
+    /// Check that the caller may read a resource
+    ///
+    /// Servers of other tenants are reported as not found.
+    pub fn check_resource(&self, context: &RequestContext, server_name: &str, uri: &str) -> Result<(), McpError> {
+        self.access
+            .check_caller_resource(&Caller::from_context(context), server_name, uri)
+    }
+
+    /// Check that the caller may get a prompt
+    ///
+    /// Servers of other tenants are reported as not found.
+    pub fn check_prompt(&self, context: &RequestContext, server_name: &str, prompt_name: &str) -> Result<(), McpError> {
+        self.access
+            .check_caller_prompt(&Caller::from_context(context), server_name, prompt_name)
+    }
+
+    /// Count a tool call by a client against the tool's and server's
+    /// rate limits
+    ///
+    /// Clients without an identity are counted by IP address; only those
+    /// whose address is unknown share one bucket.
+    pub async fn check_rate_limit(
+        &self,
+        server_name: &str,
+        tool_name: &str,
+        context: &RequestContext,
+    ) -> Result<(), McpError> {
+        let client_id = context.client_identity.as_ref().map_or_else(
+            || {
+                synapse_core::client_ip(&context.parts.headers)
+                    .map_or_else(|| "anonymous".to_owned(), |ip| format!("ip:{ip}"))
+            },
+            |identity| identity.client_id.clone(),
+        );
+        let client_id = client_id.as_str();
+
+        match self.rate_limiter.check(server_name, tool_name, client_id).await {
+            Ok(()) => Ok(()),
+            Err(RateLimitError::Exceeded { retry_after }) => {
+                tracing::debug!(
+                    server = server_name,
+                    tool = tool_name,
+                    client_id,
+                    retry_after,
+                    "MCP tool call rate limited"
+                );
+                Err(McpError::RateLimited { retry_after })
+            }
+            Err(e) => Err(McpError::Internal(anyhow::anyhow!("MCP rate limiter failed: {e}"))),
+        }
+    }
+}
+
+impl McpState {
+    /// Initialize the MCP subsystem from configuration
+    ///
+    /// Connects to all configured servers, aggregates tools,
+    /// and builds the search index. Rate limit counters live in
+    /// `rate_limit_storage`.
+    pub async fn new(config: &McpConfig, rate_limit_storage: &RateLimitStorage) -> Result<Self, McpError> {
+        let rate_limiter = McpLimiter::new(&config.servers, rate_limit_storage)
+            .map_err(|e| McpError::Internal(anyhow::anyhow!("invalid MCP rate limit: {e}")))?;
+        let downstream = DownstreamManager::connect(config).await;
+
         tracing::info!(
             servers = downstream.server_count(),
             tools = downstream.tools().len(),
             "MCP subsystem initialized"
         );
 
+        let policies: Arc<[McpPolicyConfig]> = config.policies.clone().into();
+        let snapshot = McpSnapshot::new(config.servers.clone(), downstream, rate_limiter, Arc::clone(&policies))?;
+
         Ok(Self {
-            downstream,
-            access,
-            tool_index,
+            current: RwLock::new(Arc::new(snapshot)),
+            changes: Mutex::new(()),
+            rate_limit_storage: rate_limit_storage.clone(),
+            policies,
+            endpoint: config.endpoint.clone(),
+            admin: config.admin.clone(),
+            supervisor: config.supervisor.clone(),
+            search: config.search.clone(),
+            semantic: None,
+            tool_changes: watch::Sender::new(0),
+            shutdown: CancellationToken::new(),
         })
     }
+
+    /// Current downstream servers and everything derived from them
+    pub fn current(&self) -> Arc<McpSnapshot> {
+        Arc::clone(&self.current.read().unwrap_or_else(PoisonError::into_inner))
+    }
+
+    /// Register a server, or replace the server of that name
+    ///
+    /// Other servers keep their connections; the replaced server's calls
+    /// in flight finish on its old connection.
+    pub async fn upsert_server(&self, name: &str, config: McpServerConfig) -> Result<(), McpError> {
+        config
+            .validate(name)
+            .map_err(|e| McpError::InvalidConfig(format!("{e:#}")))?;
+
+        self.apply(name, Some(config)).await
+    }
+
+    /// Enable or disable a registered server, keeping its configuration
+    pub async fn set_server_enabled(&self, name: &str, enabled: bool) -> Result<(), McpError> {
+        let Some(mut config) = self.current().servers.get(name).cloned() else {
+            return Err(McpError::ServerNotFound {
+                server: name.to_owned(),
+            });
+        };
+
+        config.enabled = enabled;
+        self.apply(name, Some(config)).await
+    }
+
+    /// Remove a registered server
+    pub async fn remove_server(&self, name: &str) -> Result<(), McpError> {
+        if !self.current().servers.contains_key(name) {
+            return Err(McpError::ServerNotFound {
+                server: name.to_owned(),
+            });
+        }
+
+        self.apply(name, None).await
+    }
+
+    /// Rebuild the snapshot with one server changed and swap it in
+    async fn apply(&self, name: &str, config: Option<McpServerConfig>) -> Result<(), McpError> {
+        let _guard = self.changes.lock().await;
+        let current = self.current();
+
+        let mut rate_limiter = current.rate_limiter.clone();
+        rate_limiter
+            .set_server(name, config.as_ref(), &self.rate_limit_storage)
+            .map_err(|e| McpError::InvalidConfig(format!("invalid rate limit: {e}")))?;
+
+        let mut servers = current.servers.clone();
+        let downstream = if let Some(config) = config {
+            let downstream = current.downstream.with_server(name, &config).await;
+            servers.insert(name.to_owned(), config);
+            downstream
+        } else {
+            servers.shift_remove(name);
+            current.downstream.without_server(name)
+        };
+
+        let snapshot = self
+            .with_semantic_index(McpSnapshot::new(
+                servers,
+                downstream,
+                rate_limiter,
+                Arc::clone(&self.policies),
+            )?)
+            .await;
+        tracing::info!(
+            server = name,
+            servers = snapshot.downstream.server_count(),
+            tools = snapshot.downstream.tools().len(),
+            "MCP servers changed"
+        );
+
+        self.install(snapshot, true);
+        Ok(())
+    }
+
+    /// Enable semantic tool search, embedding the current tools
+    ///
+    /// Must be called before the state is shared with handlers.
+    ///
+    /// # Panics
+    ///
+    /// Panics if called after the state has been shared
+    pub async fn enable_semantic_search(&mut self, embedder: Arc<dyn ToolEmbedder>) -> Result<(), McpError> {
+        let semantic = SemanticSearch::new(embedder);
+        let snapshot = Arc::get_mut(self.current.get_mut().unwrap_or_else(PoisonError::into_inner))
+            .expect("enable_semantic_search must be called before state is shared");
+        snapshot.semantic_index = Some(semantic.index(snapshot.downstream.tools()).await?);
+        self.semantic = Some(semantic);
+
+        Ok(())
+    }
+
+    /// Attach tool embeddings to a new snapshot, if semantic search is on
+    ///
+    /// Embedding failures leave the snapshot with keyword search only.
+    async fn with_semantic_index(&self, mut snapshot: McpSnapshot) -> McpSnapshot {
+        if let Some(ref semantic) = self.semantic {
+            match semantic.index(snapshot.downstream.tools()).await {
+                Ok(index) => snapshot.semantic_index = Some(index),
+                Err(e) => tracing::warn!(error = %e, "failed to embed MCP tools, falling back to keyword search"),
+            }
+        }
+        snapshot
+    }
+
+    /// Rank the tools of a snapshot against a query, best match first
+    ///
+    /// Without a mode, hybrid search is used when tools are embedded and
+    /// keyword search otherwise.
+    pub async fn search_tools(
+        &self,
+        snapshot: &McpSnapshot,
+        query: &str,
+        mode: Option<SearchMode>,
+    ) -> Result<Vec<ToolSearchResult>, McpError> {
+        let Some(ref tool_index) = snapshot.tool_index else {
+            return Ok(Vec::new());
+        };
+        let candidates = snapshot.downstream.tools().len();
+
+        let semantic = match (mode, &self.semantic, &snapshot.semantic_index) {
+            (Some(SearchMode::Keyword), _, _) | (None, _, None) => None,
+            (_, Some(semantic), Some(index)) => Some((semantic, index)),
+            _ => {
+                return Err(McpError::InvalidRequest(
+                    "semantic tool search is not available".to_owned(),
+                ));
+            }
+        };
+        let Some((semantic, semantic_index)) = semantic else {
+            return tool_index.search(query, candidates);
+        };
+
+        let query_embedding = semantic.embed_query(query).await?;
+        let semantic_results = semantic_index.search(&query_embedding, candidates);
+        if mode == Some(SearchMode::Semantic) {
+            return Ok(semantic_results);
+        }
+
+        let keyword_results = tool_index.search(query, candidates)?;
+        Ok(semantic::fuse(keyword_results, semantic_results, self.search.rrf_k))
+    }
+
+    /// Swap in a new snapshot, telling subscribers if the tools changed
+    fn install(&self, snapshot: McpSnapshot, tools_changed: bool) {
+        *self.current.write().unwrap_or_else(PoisonError::into_inner) = Arc::new(snapshot);
+        if tools_changed {
+            self.notify_tools_changed();
+        }
+    }
+
+    /// Start supervising downstream servers in the background, if enabled
+    ///
+    /// The supervisor stops when sessions are closed on shutdown.
+    pub fn supervise(self: &Arc<Self>) {
+        if self.supervisor.enabled {
+            tokio::spawn(supervisor::run(Arc::clone(self)));
+        }
+    }
+
+    /// Call a tool on behalf of a caller
+    ///
+    /// Applies the caller's tenant, the access rules and policies, and the
+    /// rate limits, and passes the caller's `Authorization` header to servers that
+    /// forward it.
+    pub async fn call_tool(
+        &self,
+        context: &RequestContext,
+        name: &str,
+        arguments: Option<serde_json::Map<String, serde_json::Value>>,
+    ) -> Result<CallToolResult, McpError> {
+        let (server_name, tool_name) =
+            DownstreamManager::parse_tool_name(name).ok_or_else(|| McpError::ToolNotFound { tool: name.to_owned() })?;
+
+        let snapshot = self.current();
+        let caller = Caller::from_context(context);
+        snapshot.access.check_tenant(server_name, caller.group)?;
+        snapshot.access.check(server_name, tool_name)?;
+        snapshot.access.check_policies(&caller, server_name, tool_name)?;
+        snapshot
+            .access
+            .check_arguments(&caller, server_name, tool_name, arguments.as_ref())?;
+        snapshot.check_rate_limit(server_name, tool_name, context).await?;
+
+        let authorization = DownstreamManager::caller_authorization(&context.parts.headers);
+        snapshot
+            .downstream
+            .call_tool(name, arguments, authorization.as_ref())
+            .await
+    }
+
+    /// Subscribe to changes of the aggregated tool list
+    pub fn subscribe_tool_changes(&self) -> watch::Receiver<u64> {
+        self.tool_changes.subscribe()
+    }
+
+    /// Tell subscribers, such as MCP endpoint sessions, that the
+    /// aggregated tool list changed
+    pub fn notify_tools_changed(&self) {
+        self.tool_changes.send_modify(|generation| *generation += 1);
+    }
+
+    /// Close open MCP endpoint sessions so graceful shutdown is not held
+    /// up by long-lived SSE streams
+    pub fn close_sessions(&self) {
+        self.shutdown.cancel();
+    }
 }
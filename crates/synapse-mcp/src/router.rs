@@ -1,26 +1,49 @@
 use std::sync::Arc;
 
 use axum::extract::{Query, State};
+use axum::http::HeaderMap;
 use axum::response::IntoResponse;
 use axum::routing::{get, post};
-use axum::{Json, Router};
+use axum::{Extension, Json, Router};
 use serde::{Deserialize, Serialize};
+use synapse_core::RequestContext;
 
 use crate::McpState;
+use crate::access::Caller;
+use crate::downstream::manager::{DownstreamManager, ServerStatus};
 use crate::error::McpError;
 
 /// Build the MCP router
+///
+/// Serves the REST routes and, when enabled, the Streamable HTTP MCP
+/// endpoint.
 pub fn mcp_router(state: Arc<McpState>) -> Router {
-    Router::new()
+    let mut router = Router::new()
         .route("/mcp/tools/list", post(list_tools))
         .route("/mcp/tools/call", post(call_tool))
+        .route("/mcp/resources/list", post(list_resources))
+        .route("/mcp/resources/templates/list", post(list_resource_templates))
+        .route("/mcp/resources/read", post(read_resource))
+        .route("/mcp/prompts/list", post(list_prompts))
+        .route("/mcp/prompts/get", post(get_prompt))
         .route("/mcp/search", get(search_tools))
-        .with_state(state)
+        .route("/mcp/servers", get(server_statuses));
+
+    if state.admin.enabled {
+        router = router.merge(crate::admin::admin_router(&state));
+    }
+
+    if state.endpoint.enabled {
+        let path = state.endpoint.path.clone();
+        router = router.route_service(&path, crate::endpoint::service(Arc::clone(&state)));
+    }
+
+    router.with_state(state)
 }
 
-/// Request to list tools with optional server filter
+/// Request to list tools, resources, or prompts with optional server filter
 #[derive(Debug, Deserialize)]
-struct ListToolsRequest {
+struct ListRequest {
     /// Filter to a specific server
     #[serde(default)]
     server: Option<String>,
@@ -43,14 +66,13 @@ struct ListToolsResponse {
 
 async fn list_tools(
     State(state): State<Arc<McpState>>,
-    Json(req): Json<ListToolsRequest>,
+    Extension(request_context): Extension<RequestContext>,
+    Json(req): Json<ListRequest>,
 ) -> Result<Json<ListToolsResponse>, McpErrorResponse> {
-    let tools = state.downstream.tools();
-
-    let filtered: Vec<ToolInfo> = tools
-        .iter()
+    let snapshot = state.current();
+    let filtered: Vec<ToolInfo> = snapshot
+        .caller_tools(&request_context)
         .filter(|t| req.server.as_ref().is_none_or(|s| s == &t.server_name))
-        .filter(|t| state.access.check(&t.server_name, &t.original_name).is_ok())
         .map(|t| ToolInfo {
             name: t.qualified_name.clone(),
             server: t.server_name.clone(),
@@ -90,17 +112,10 @@ enum ContentBlock {
 
 async fn call_tool(
     State(state): State<Arc<McpState>>,
+    Extension(request_context): Extension<RequestContext>,
     Json(req): Json<CallToolRequest>,
 ) -> Result<Json<CallToolResponse>, McpErrorResponse> {
-    // Parse and validate the qualified tool name
-    let (server_name, tool_name) = crate::downstream::manager::DownstreamManager::parse_tool_name(&req.name)
-        .ok_or_else(|| McpError::ToolNotFound { tool: req.name.clone() })?;
-
-    // Check access control
-    state.access.check(server_name, tool_name)?;
-
-    // Execute the tool call
-    let result = state.downstream.call_tool(&req.name, req.arguments).await?;
+    let result = state.call_tool(&request_context, &req.name, req.arguments).await?;
 
     // Convert rmcp content to our API format
     let content = result
@@ -125,6 +140,273 @@ async fn call_tool(
     }))
 }
 
+/// Resource info returned to clients
+#[derive(Debug, Serialize)]
+struct ResourceInfo {
+    /// Qualified URI (`server__uri`)
+    uri: String,
+    name: String,
+    server: String,
+    description: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    mime_type: Option<String>,
+}
+
+/// Response containing available resources
+#[derive(Debug, Serialize)]
+struct ListResourcesResponse {
+    resources: Vec<ResourceInfo>,
+}
+
+async fn list_resources(
+    State(state): State<Arc<McpState>>,
+    Extension(request_context): Extension<RequestContext>,
+    Json(req): Json<ListRequest>,
+) -> Result<Json<ListResourcesResponse>, McpErrorResponse> {
+    let snapshot = state.current();
+    let resources = snapshot
+        .caller_resources(&request_context)
+        .filter(|r| req.server.as_ref().is_none_or(|s| s == &r.server_name))
+        .map(|r| ResourceInfo {
+            uri: r.qualified_uri.clone(),
+            name: r.qualified_name.clone(),
+            server: r.server_name.clone(),
+            description: r.description.clone(),
+            mime_type: r.mime_type.clone(),
+        })
+        .collect();
+
+    Ok(Json(ListResourcesResponse { resources }))
+}
+
+/// Resource template info returned to clients
+#[derive(Debug, Serialize)]
+struct ResourceTemplateInfo {
+    /// Qualified URI template (`server__template`)
+    uri_template: String,
+    name: String,
+    server: String,
+    description: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    mime_type: Option<String>,
+}
+
+/// Response containing available resource templates
+#[derive(Debug, Serialize)]
+struct ListResourceTemplatesResponse {
+    resource_templates: Vec<ResourceTemplateInfo>,
+}
+
+async fn list_resource_templates(
+    State(state): State<Arc<McpState>>,
+    Extension(request_context): Extension<RequestContext>,
+    Json(req): Json<ListRequest>,
+) -> Result<Json<ListResourceTemplatesResponse>, McpErrorResponse> {
+    let snapshot = state.current();
+    let resource_templates = snapshot
+        .caller_resource_templates(&request_context)
+        .filter(|t| req.server.as_ref().is_none_or(|s| s == &t.server_name))
+        .map(|t| ResourceTemplateInfo {
+            uri_template: t.qualified_uri_template.clone(),
+            name: t.qualified_name.clone(),
+            server: t.server_name.clone(),
+            description: t.description.clone(),
+            mime_type: t.mime_type.clone(),
+        })
+        .collect();
+
+    Ok(Json(ListResourceTemplatesResponse { resource_templates }))
+}
+
+/// Request to read a resource
+#[derive(Debug, Deserialize)]
+struct ReadResourceRequest {
+    /// Qualified resource URI (`server__uri`)
+    uri: String,
+}
+
+/// Response from reading a resource
+#[derive(Debug, Serialize)]
+struct ReadResourceResponse {
+    contents: Vec<ResourceContent>,
+}
+
+/// Resource contents, either text or base64-encoded binary data
+#[derive(Debug, Serialize)]
+struct ResourceContent {
+    uri: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    mime_type: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    text: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    blob: Option<String>,
+}
+
+async fn read_resource(
+    State(state): State<Arc<McpState>>,
+    Extension(request_context): Extension<RequestContext>,
+    headers: HeaderMap,
+    Json(req): Json<ReadResourceRequest>,
+) -> Result<Json<ReadResourceResponse>, McpErrorResponse> {
+    let (server_name, uri) = DownstreamManager::parse_tool_name(&req.uri)
+        .ok_or_else(|| McpError::ResourceNotFound { uri: req.uri.clone() })?;
+
+    let snapshot = state.current();
+    snapshot.check_resource(&request_context, server_name, uri)?;
+
+    let authorization = DownstreamManager::caller_authorization(&headers);
+    let result = snapshot
+        .downstream
+        .read_resource(&req.uri, authorization.as_ref())
+        .await?;
+
+    let contents = result
+        .contents
+        .into_iter()
+        .map(|c| match c {
+            rmcp::model::ResourceContents::TextResourceContents {
+                uri, mime_type, text, ..
+            } => ResourceContent {
+                uri,
+                mime_type,
+                text: Some(text),
+                blob: None,
+            },
+            rmcp::model::ResourceContents::BlobResourceContents {
+                uri, mime_type, blob, ..
+            } => ResourceContent {
+                uri,
+                mime_type,
+                text: None,
+                blob: Some(blob),
+            },
+        })
+        .collect();
+
+    Ok(Json(ReadResourceResponse { contents }))
+}
+
+/// Prompt info returned to clients
+#[derive(Debug, Serialize)]
+struct PromptInfo {
+    name: String,
+    server: String,
+    description: String,
+    arguments: Vec<PromptArgumentInfo>,
+}
+
+/// Argument accepted by a prompt
+#[derive(Debug, Serialize)]
+struct PromptArgumentInfo {
+    name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    description: Option<String>,
+    required: bool,
+}
+
+/// Response containing available prompts
+#[derive(Debug, Serialize)]
+struct ListPromptsResponse {
+    prompts: Vec<PromptInfo>,
+}
+
+async fn list_prompts(
+    State(state): State<Arc<McpState>>,
+    Extension(request_context): Extension<RequestContext>,
+    Json(req): Json<ListRequest>,
+) -> Result<Json<ListPromptsResponse>, McpErrorResponse> {
+    let snapshot = state.current();
+    let prompts = snapshot
+        .caller_prompts(&request_context)
+        .filter(|p| req.server.as_ref().is_none_or(|s| s == &p.server_name))
+        .map(|p| PromptInfo {
+            name: p.qualified_name.clone(),
+            server: p.server_name.clone(),
+            description: p.description.clone(),
+            arguments: p
+                .arguments
+                .iter()
+                .map(|a| PromptArgumentInfo {
+                    name: a.name.clone(),
+                    description: a.description.clone(),
+                    required: a.required.unwrap_or(false),
+                })
+                .collect(),
+        })
+        .collect();
+
+    Ok(Json(ListPromptsResponse { prompts }))
+}
+
+/// Request to render a prompt
+#[derive(Debug, Deserialize)]
+struct GetPromptRequest {
+    /// Qualified prompt name (`server__prompt`)
+    name: String,
+    /// Prompt arguments
+    #[serde(default)]
+    arguments: Option<serde_json::Map<String, serde_json::Value>>,
+}
+
+/// Rendered prompt
+#[derive(Debug, Serialize)]
+struct GetPromptResponse {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    description: Option<String>,
+    messages: Vec<PromptMessageInfo>,
+}
+
+/// Message of a rendered prompt
+#[derive(Debug, Serialize)]
+struct PromptMessageInfo {
+    role: rmcp::model::PromptMessageRole,
+    content: ContentBlock,
+}
+
+async fn get_prompt(
+    State(state): State<Arc<McpState>>,
+    Extension(request_context): Extension<RequestContext>,
+    headers: HeaderMap,
+    Json(req): Json<GetPromptRequest>,
+) -> Result<Json<GetPromptResponse>, McpErrorResponse> {
+    let (server_name, prompt_name) =
+        DownstreamManager::parse_tool_name(&req.name).ok_or_else(|| McpError::PromptNotFound {
+            prompt: req.name.clone(),
+        })?;
+
+    let snapshot = state.current();
+    snapshot.check_prompt(&request_context, server_name, prompt_name)?;
+
+    let authorization = DownstreamManager::caller_authorization(&headers);
+    let result = snapshot
+        .downstream
+        .get_prompt(&req.name, req.arguments, authorization.as_ref())
+        .await?;
+
+    // Embedded resources and resource links have no REST representation
+    let messages = result
+        .messages
+        .into_iter()
+        .filter_map(|m| {
+            let content = match m.content {
+                rmcp::model::PromptMessageContent::Text { text } => ContentBlock::Text { text },
+                rmcp::model::PromptMessageContent::Image { image } => ContentBlock::Image {
+                    data: image.raw.data,
+                    mime_type: image.raw.mime_type,
+                },
+                _ => return None,
+            };
+            Some(PromptMessageInfo { role: m.role, content })
+        })
+        .collect();
+
+    Ok(Json(GetPromptResponse {
+        description: result.description,
+        messages,
+    }))
+}
+
 /// Query parameters for tool search
 #[derive(Debug, Deserialize)]
 struct SearchQuery {
@@ -133,6 +415,10 @@ struct SearchQuery {
     /// Maximum results (defaults to 10)
     #[serde(default = "default_limit")]
     limit: usize,
+    /// Keyword, semantic, or hybrid matching (hybrid when tools are
+    /// embedded, keyword otherwise)
+    #[serde(default)]
+    mode: Option<crate::index::SearchMode>,
 }
 
 const fn default_limit() -> usize {
@@ -147,18 +433,51 @@ struct SearchResponse {
 
 async fn search_tools(
     State(state): State<Arc<McpState>>,
+    Extension(request_context): Extension<RequestContext>,
     Query(query): Query<SearchQuery>,
 ) -> Result<Json<SearchResponse>, McpErrorResponse> {
-    let Some(ref index) = state.tool_index else {
-        return Ok(Json(SearchResponse { results: Vec::new() }));
-    };
+    let snapshot = state.current();
+    let caller = Caller::from_context(&request_context);
+    let results = state
+        .search_tools(&snapshot, &query.q, query.mode)
+        .await?
+        .into_iter()
+        .filter(|r| snapshot.access.allows_tool(&caller, &r.server_name, &r.tool_name))
+        .take(query.limit)
+        .collect();
 
-    let results = index.search(&query.q, query.limit)?;
     Ok(Json(SearchResponse { results }))
 }
 
+/// Response containing the status of each downstream server
+#[derive(Debug, Serialize)]
+struct ServerStatusesResponse {
+    servers: Vec<ServerStatus>,
+}
+
+async fn server_statuses(
+    State(state): State<Arc<McpState>>,
+    Extension(request_context): Extension<RequestContext>,
+) -> Json<ServerStatusesResponse> {
+    let snapshot = state.current();
+    let tenant = tenant(&request_context);
+    let servers = snapshot
+        .downstream
+        .server_statuses()
+        .into_iter()
+        .filter(|s| snapshot.access.visible(&s.name, tenant))
+        .collect();
+
+    Json(ServerStatusesResponse { servers })
+}
+
+/// Tenant of the caller, matched against tenant-scoped servers
+fn tenant(context: &RequestContext) -> Option<&str> {
+    context.client_identity.as_ref()?.group.as_deref()
+}
+
 /// Error response wrapper that implements `IntoResponse`
-struct McpErrorResponse(McpError);
+pub(crate) struct McpErrorResponse(McpError);
 
 impl From<McpError> for McpErrorResponse {
     fn from(e: McpError) -> Self {
@@ -178,6 +497,13 @@ impl IntoResponse for McpErrorResponse {
             }
         });
 
-        (status, Json(body)).into_response()
+        let mut response = (status, Json(body)).into_response();
+        if let McpError::RateLimited { retry_after } = self.0
+            && let Ok(value) = retry_after.to_string().parse()
+        {
+            response.headers_mut().insert("retry-after", value);
+        }
+
+        response
     }
 }
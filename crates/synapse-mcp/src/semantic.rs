@@ -0,0 +1,276 @@
+//! Semantic tool search
+//!
+//! Tools are embedded as their name, description, and input parameter
+//! names, and matched to queries by cosine similarity. Hybrid search fuses
+//! the semantic and keyword rankings with reciprocal rank fusion.
+
+use std::collections::HashMap;
+use std::sync::{Arc, PoisonError};
+
+use async_trait::async_trait;
+
+use crate::downstream::manager::AggregatedTool;
+use crate::error::McpError;
+use crate::index::{SearchExplanation, ToolSearchResult};
+
+/// Number of tools embedded per request
+const EMBEDDING_BATCH_SIZE: usize = 64;
+
+/// Embeds text for semantic tool search
+///
+/// Implemented by the server on top of the configured embeddings provider,
+/// so this crate does not depend on the embeddings subsystem.
+#[async_trait]
+pub trait ToolEmbedder: Send + Sync {
+    /// Embed each input, returning one vector per input in order
+    async fn embed(&self, inputs: Vec<String>) -> Result<Vec<Vec<f32>>, McpError>;
+}
+
+/// Embeddings of the aggregated tools
+pub struct SemanticIndex {
+    tools: Vec<(ToolSearchResult, Vec<f32>)>,
+}
+
+impl SemanticIndex {
+    /// Tools most similar to an embedded query, most similar first
+    pub fn search(&self, query: &[f32], limit: usize) -> Vec<ToolSearchResult> {
+        let query = normalize(query.to_vec());
+        let mut scored: Vec<(f32, &ToolSearchResult)> = self
+            .tools
+            .iter()
+            .map(|(tool, embedding)| (dot(&query, embedding), tool))
+            .collect();
+        scored.sort_by(|a, b| b.0.total_cmp(&a.0));
+
+        scored
+            .into_iter()
+            .take(limit)
+            .enumerate()
+            .map(|(rank, (similarity, tool))| ToolSearchResult {
+                score: similarity,
+                explanation: SearchExplanation {
+                    semantic_rank: Some(rank + 1),
+                    semantic_similarity: Some(similarity),
+                    ..SearchExplanation::default()
+                },
+                ..tool.clone()
+            })
+            .collect()
+    }
+}
+
+/// Embeds tools and queries, remembering tool embeddings across rebuilds
+pub struct SemanticSearch {
+    embedder: Arc<dyn ToolEmbedder>,
+    /// Unit-length embeddings by embedded text
+    cache: std::sync::Mutex<HashMap<String, Vec<f32>>>,
+}
+
+impl SemanticSearch {
+    pub fn new(embedder: Arc<dyn ToolEmbedder>) -> Self {
+        Self {
+            embedder,
+            cache: std::sync::Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Build the index, embedding only tools whose text changed
+    pub async fn index(&self, tools: &[AggregatedTool]) -> Result<SemanticIndex, McpError> {
+        let texts: Vec<String> = tools.iter().map(tool_text).collect();
+        let missing: Vec<String> = {
+            let cache = self.cache.lock().unwrap_or_else(PoisonError::into_inner);
+            let mut missing: Vec<String> = texts
+                .iter()
+                .filter(|text| !cache.contains_key(*text))
+                .cloned()
+                .collect();
+            missing.sort();
+            missing.dedup();
+            missing
+        };
+
+        for batch in missing.chunks(EMBEDDING_BATCH_SIZE) {
+            let embeddings = self.embedder.embed(batch.to_vec()).await?;
+            if embeddings.len() != batch.len() {
+                return Err(McpError::Internal(anyhow::anyhow!(
+                    "embeddings provider returned {} vectors for {} tools",
+                    embeddings.len(),
+                    batch.len()
+                )));
+            }
+
+            let mut cache = self.cache.lock().unwrap_or_else(PoisonError::into_inner);
+            for (text, embedding) in batch.iter().zip(embeddings) {
+                cache.insert(text.clone(), normalize(embedding));
+            }
+        }
+
+        let mut cache = self.cache.lock().unwrap_or_else(PoisonError::into_inner);
+        // Forget tools that are gone
+        cache.retain(|text, _| texts.contains(text));
+
+        let tools = tools
+            .iter()
+            .zip(&texts)
+            .filter_map(|(tool, text)| {
+                let embedding = cache.get(text)?.clone();
+                let result = ToolSearchResult {
+                    qualified_name: tool.qualified_name.clone(),
+                    server_name: tool.server_name.clone(),
+                    tool_name: tool.original_name.clone(),
+                    description: tool.description.clone(),
+                    score: 0.0,
+                    explanation: SearchExplanation::default(),
+                };
+                Some((result, embedding))
+            })
+            .collect();
+        drop(cache);
+
+        Ok(SemanticIndex { tools })
+    }
+
+    /// Embed a search query
+    pub async fn embed_query(&self, query: &str) -> Result<Vec<f32>, McpError> {
+        self.embedder
+            .embed(vec![query.to_owned()])
+            .await?
+            .into_iter()
+            .next()
+            .ok_or_else(|| McpError::Internal(anyhow::anyhow!("embeddings provider returned no vector")))
+    }
+}
+
+/// Fuse keyword and semantic rankings with reciprocal rank fusion
+///
+/// Each result scores `1 / (k + rank)` per ranking it appears in; the
+/// explanation keeps both ranks.
+pub fn fuse(keyword: Vec<ToolSearchResult>, semantic: Vec<ToolSearchResult>, k: u32) -> Vec<ToolSearchResult> {
+    #[allow(clippy::cast_precision_loss)]
+    let rrf = |rank: usize| 1.0 / (k as f32 + rank as f32);
+    let mut fused: Vec<ToolSearchResult> = Vec::new();
+
+    for result in keyword.into_iter().chain(semantic) {
+        let rank = result
+            .explanation
+            .keyword_rank
+            .or(result.explanation.semantic_rank)
+            .unwrap_or(usize::MAX);
+
+        if let Some(existing) = fused.iter_mut().find(|r| r.qualified_name == result.qualified_name) {
+            existing.score += rrf(rank);
+            existing.explanation.semantic_rank = result.explanation.semantic_rank;
+            existing.explanation.semantic_similarity = result.explanation.semantic_similarity;
+        } else {
+            fused.push(ToolSearchResult {
+                score: rrf(rank),
+                ..result
+            });
+        }
+    }
+
+    fused.sort_by(|a, b| b.score.total_cmp(&a.score));
+    fused
+}
+
+/// Text embedded for a tool: its name, description, and parameter names
+fn tool_text(tool: &AggregatedTool) -> String {
+    let mut text = format!("{}: {}", tool.original_name, tool.description);
+
+    let parameters: Vec<&str> = tool
+        .input_schema
+        .get("properties")
+        .and_then(serde_json::Value::as_object)
+        .map(|properties| properties.keys().map(String::as_str).collect())
+        .unwrap_or_default();
+    if !parameters.is_empty() {
+        text.push_str("\nParameters: ");
+        text.push_str(&parameters.join(", "));
+    }
+
+    text
+}
+
+/// Scale a vector to unit length
+fn normalize(mut vector: Vec<f32>) -> Vec<f32> {
+    let norm = dot(&vector, &vector).sqrt();
+    if norm > 0.0 {
+        for value in &mut vector {
+            *value /= norm;
+        }
+    }
+    vector
+}
+
+fn dot(a: &[f32], b: &[f32]) -> f32 {
+    a.iter().zip(b).map(|(x, y)| x * y).sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(name: &str, explanation: SearchExplanation) -> ToolSearchResult {
+        ToolSearchResult {
+            qualified_name: name.to_owned(),
+            server_name: "srv".to_owned(),
+            tool_name: name.to_owned(),
+            description: String::new(),
+            score: 0.0,
+            explanation,
+        }
+    }
+
+    fn keyword(name: &str, rank: usize) -> ToolSearchResult {
+        result(
+            name,
+            SearchExplanation {
+                keyword_rank: Some(rank),
+                keyword_score: Some(1.0),
+                ..SearchExplanation::default()
+            },
+        )
+    }
+
+    fn semantic(name: &str, rank: usize) -> ToolSearchResult {
+        result(
+            name,
+            SearchExplanation {
+                semantic_rank: Some(rank),
+                semantic_similarity: Some(0.5),
+                ..SearchExplanation::default()
+            },
+        )
+    }
+
+    #[test]
+    fn fusion_favors_tools_ranked_by_both_searches() {
+        let fused = fuse(
+            vec![keyword("a", 1), keyword("b", 2)],
+            vec![semantic("b", 1), semantic("c", 2)],
+            60,
+        );
+
+        let names: Vec<&str> = fused.iter().map(|r| r.qualified_name.as_str()).collect();
+        assert_eq!(names, ["b", "a", "c"]);
+        assert_eq!(fused[0].explanation.keyword_rank, Some(2));
+        assert_eq!(fused[0].explanation.semantic_rank, Some(1));
+        assert!((fused[0].score - (1.0 / 62.0 + 1.0 / 61.0)).abs() < 1e-6);
+    }
+
+    #[test]
+    fn tool_text_includes_parameter_names() {
+        let tool = AggregatedTool {
+            qualified_name: "slack__post_chat".to_owned(),
+            original_name: "post_chat".to_owned(),
+            server_name: "slack".to_owned(),
+            description: "Post to a channel".to_owned(),
+            input_schema: serde_json::json!({"properties": {"channel": {}, "text": {}}}),
+        };
+
+        assert_eq!(
+            tool_text(&tool),
+            "post_chat: Post to a channel\nParameters: channel, text"
+        );
+    }
+}
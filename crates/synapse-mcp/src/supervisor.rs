@@ -0,0 +1,202 @@
+//! Supervision of downstream MCP servers
+//!
+//! Retries servers that failed to connect with exponential backoff,
+//! restarts sessions whose transport closed, and lists a server's tools,
+//! resources, and prompts anew when it reports a change or a periodic
+//! refresh is due. Results are swapped in like admin changes, and MCP
+//! endpoint sessions are only notified when the offerings changed.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use futures_util::future::select_all;
+use tokio::time::Instant;
+
+use crate::downstream::manager::DownstreamManager;
+use crate::{McpSnapshot, McpState};
+
+/// Retry schedule of a server that failed to connect
+struct Retry {
+    at: Instant,
+    delay: Duration,
+}
+
+/// Supervise until sessions are closed on shutdown
+pub async fn run(state: Arc<McpState>) {
+    let check_interval = Duration::from_secs(state.supervisor.check_interval_secs);
+    let refresh_interval = Duration::from_secs(state.supervisor.refresh_interval_secs);
+    let max_backoff = Duration::from_secs(state.supervisor.max_backoff_secs);
+    let mut retries: HashMap<String, Retry> = HashMap::new();
+    let mut next_refresh = Instant::now() + refresh_interval;
+
+    loop {
+        let snapshot = state.current();
+        tokio::select! {
+            () = state.shutdown.cancelled() => return,
+            () = tokio::time::sleep(check_interval) => {}
+            () = list_changed(&snapshot) => {}
+        }
+        drop(snapshot);
+
+        let now = Instant::now();
+        let refresh_all = now >= next_refresh;
+        if refresh_all {
+            next_refresh = now + refresh_interval;
+        }
+
+        retry_failed(&state, &mut retries, check_interval, max_backoff).await;
+        check_connected(&state, refresh_all).await;
+    }
+}
+
+/// Wait until any connected server reports a list change
+async fn list_changed(snapshot: &McpSnapshot) {
+    let waits: Vec<_> = snapshot
+        .downstream
+        .clients()
+        .map(|(_, client)| Box::pin(client.list_changed()))
+        .collect();
+
+    if waits.is_empty() {
+        std::future::pending::<()>().await;
+    }
+    select_all(waits).await;
+}
+
+/// Reconnect failed servers whose retry is due, backing off on failure
+async fn retry_failed(
+    state: &McpState,
+    retries: &mut HashMap<String, Retry>,
+    check_interval: Duration,
+    max_backoff: Duration,
+) {
+    let failed: Vec<String> = state
+        .current()
+        .downstream
+        .failed_servers()
+        .map(ToOwned::to_owned)
+        .collect();
+    retries.retain(|name, _| failed.contains(name));
+
+    for name in failed {
+        let now = Instant::now();
+        let retry = retries.entry(name.clone()).or_insert(Retry {
+            at: now,
+            delay: check_interval,
+        });
+        if now < retry.at {
+            continue;
+        }
+
+        if state.reconnect_server(&name).await {
+            retries.remove(&name);
+        } else {
+            retry.delay = (retry.delay * 2).min(max_backoff);
+            retry.at = Instant::now() + retry.delay;
+            tracing::debug!(server = name, retry_in = ?retry.delay, "MCP server still unreachable");
+        }
+    }
+}
+
+/// Restart closed sessions and re-list servers that changed or are due
+async fn check_connected(state: &McpState, refresh_all: bool) {
+    let snapshot = state.current();
+
+    for (name, client) in snapshot.downstream.clients() {
+        let (restarted, error) = match client.restart_closed().await {
+            Ok(restarted) => (restarted, None),
+            Err(e) => {
+                tracing::warn!(server = name, error = %e, "failed to restart MCP session");
+                (0, Some(e.to_string()))
+            }
+        };
+        if restarted > 0 {
+            tracing::info!(server = name, sessions = restarted, "restarted closed MCP sessions");
+        }
+
+        let changed = client.take_list_changed();
+        if restarted > 0 || error.is_some() || changed || refresh_all {
+            state.refresh_server(name, restarted, error).await;
+        }
+    }
+}
+
+impl McpState {
+    /// Connect a server that failed to connect, returning whether it did
+    ///
+    /// Connecting happens outside the change lock, so admin changes are
+    /// not held up by an unreachable server.
+    async fn reconnect_server(&self, name: &str) -> bool {
+        let current = self.current();
+
+        let Some(config) = current.servers.get(name).filter(|config| config.enabled) else {
+            return false;
+        };
+        if current.downstream.get_client(name).is_some() {
+            return true;
+        }
+
+        let downstream = current.downstream.with_reconnected(name, config).await;
+        let connected = downstream.get_client(name).is_some();
+        if !self.replace_downstream(&current, downstream, name).await {
+            return false;
+        }
+        if connected {
+            tracing::info!(server = name, "reconnected to MCP server");
+        }
+
+        connected
+    }
+
+    /// List a connected server's offerings anew, recording restarted
+    /// sessions and the most recent error
+    async fn refresh_server(&self, name: &str, restarted: u32, error: Option<String>) {
+        let current = self.current();
+        if current.downstream.get_client(name).is_none() {
+            return;
+        }
+
+        let recorded = restarted > 0 || error.is_some();
+        let downstream = current.downstream.with_refreshed(name, restarted, error).await;
+        if recorded || !downstream.offers_same(&current.downstream, name) {
+            self.replace_downstream(&current, downstream, name).await;
+        }
+    }
+
+    /// Swap in a downstream manager derived from `current`, keeping
+    /// everything else
+    ///
+    /// Returns whether it was swapped in. When the servers changed since
+    /// `current` was taken, such as a server removed or replaced through
+    /// the admin API, the update is dropped and picked up again by a later
+    /// check.
+    async fn replace_downstream(&self, current: &Arc<McpSnapshot>, downstream: DownstreamManager, name: &str) -> bool {
+        let changed = !downstream.offers_same(&current.downstream, name);
+
+        let snapshot = match McpSnapshot::new(
+            current.servers.clone(),
+            downstream,
+            current.rate_limiter.clone(),
+            Arc::clone(&self.policies),
+        ) {
+            Ok(snapshot) => self.with_semantic_index(snapshot).await,
+            Err(e) => {
+                tracing::warn!(server = name, error = %e, "failed to rebuild MCP tool index");
+                return false;
+            }
+        };
+
+        let _guard = self.changes.lock().await;
+        if !Arc::ptr_eq(&self.current(), current) {
+            tracing::debug!(
+                server = name,
+                "MCP servers changed meanwhile, dropping supervisor update"
+            );
+            return false;
+        }
+
+        self.install(snapshot, changed);
+        true
+    }
+}
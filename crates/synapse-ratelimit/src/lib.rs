@@ -1,11 +1,13 @@
 #![allow(clippy::missing_errors_doc, clippy::must_use_candidate)]
 
 mod error;
+mod mcp;
 mod request;
 pub mod storage;
 mod token;
 
 pub use error::RateLimitError;
+pub use mcp::McpLimiter;
 pub use request::RequestLimiter;
 pub use token::TokenLimiter;
 
@@ -0,0 +1,80 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use synapse_config::{McpServerConfig, RateLimitStorage};
+
+use crate::error::RateLimitError;
+use crate::request::{Limiter, build_limiter, check_limiter};
+
+/// MCP tool-call rate limiter (per server and per tool, keyed by client)
+///
+/// A tool with its own limit is counted against that limit and its
+/// server's. Clones share their counters.
+#[derive(Clone, Default)]
+pub struct McpLimiter {
+    servers: HashMap<String, Arc<Limiter>>,
+    tools: HashMap<(String, String), Arc<Limiter>>,
+}
+
+impl McpLimiter {
+    /// Create from the configured MCP servers
+    pub fn new<'a>(
+        servers: impl IntoIterator<Item = (&'a String, &'a McpServerConfig)>,
+        storage: &RateLimitStorage,
+    ) -> Result<Self, RateLimitError> {
+        let mut limiter = Self::default();
+        for (name, config) in servers {
+            limiter.set_server(name, Some(config), storage)?;
+        }
+
+        Ok(limiter)
+    }
+
+    /// Replace the limits of one server, or drop them when `config` is
+    /// `None`
+    ///
+    /// Counters of other servers are kept; the replaced server starts
+    /// from zero.
+    pub fn set_server(
+        &mut self,
+        name: &str,
+        config: Option<&McpServerConfig>,
+        storage: &RateLimitStorage,
+    ) -> Result<(), RateLimitError> {
+        self.servers.remove(name);
+        self.tools.retain(|(server, _), _| server != name);
+
+        let Some(config) = config else {
+            return Ok(());
+        };
+
+        if let Some(ref rate_limit) = config.rate_limit {
+            self.servers.insert(
+                name.to_owned(),
+                Arc::new(build_limiter(storage, rate_limit.requests, &rate_limit.window)?),
+            );
+        }
+        for (tool, rate_limit) in &config.tool_rate_limits {
+            self.tools.insert(
+                (name.to_owned(), tool.clone()),
+                Arc::new(build_limiter(storage, rate_limit.requests, &rate_limit.window)?),
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Check a tool call by a client against the tool's and the server's
+    /// limits
+    pub async fn check(&self, server: &str, tool: &str, client_id: &str) -> Result<(), RateLimitError> {
+        if let Some(limiter) = self.tools.get(&(server.to_owned(), tool.to_owned())) {
+            check_limiter(limiter, &format!("mcp:{server}:{tool}:{client_id}")).await?;
+        }
+
+        if let Some(limiter) = self.servers.get(server) {
+            check_limiter(limiter, &format!("mcp:{server}:{client_id}")).await?;
+        }
+
+        Ok(())
+    }
+}
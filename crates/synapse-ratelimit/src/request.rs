@@ -1,6 +1,6 @@
 use std::time::Duration;
 
-use synapse_config::{RateLimitConfig, RateLimitStorage, RequestRateLimit};
+use synapse_config::{RateLimitConfig, RateLimitStorage};
 
 use crate::{
     error::RateLimitError,
@@ -13,7 +13,8 @@ pub struct RequestLimiter {
     per_ip: Option<Limiter>,
 }
 
-enum Limiter {
+/// A single limiter on either storage backend
+pub enum Limiter {
     Memory(MemoryLimiter),
     Cache(Box<CacheLimiter>),
 }
@@ -24,13 +25,13 @@ impl RequestLimiter {
         let global = config
             .global
             .as_ref()
-            .map(|rl| build_limiter(&config.storage, rl))
+            .map(|rl| build_limiter(&config.storage, rl.requests, &rl.window))
             .transpose()?;
 
         let per_ip = config
             .per_ip
             .as_ref()
-            .map(|rl| build_limiter(&config.storage, rl))
+            .map(|rl| build_limiter(&config.storage, rl.requests, &rl.window))
             .transpose()?;
 
         Ok(Self { global, per_ip })
@@ -51,22 +52,44 @@ impl RequestLimiter {
         }
         Ok(())
     }
+
+    /// Whether any limiter is backed by a shared cache store
+    pub fn uses_cache(&self) -> bool {
+        [&self.global, &self.per_ip]
+            .into_iter()
+            .flatten()
+            .any(|l| matches!(l, Limiter::Cache(_)))
+    }
+
+    /// Check that cache-backed storage is reachable
+    ///
+    /// Memory-backed limiters are always reachable.
+    pub async fn ping(&self) -> Result<(), RateLimitError> {
+        for limiter in [&self.global, &self.per_ip].into_iter().flatten() {
+            if let Limiter::Cache(c) = limiter {
+                c.ping().await?;
+            }
+        }
+        Ok(())
+    }
 }
 
-fn build_limiter(storage: &RateLimitStorage, rate_limit: &RequestRateLimit) -> Result<Limiter, RateLimitError> {
-    let window = parse_duration(&rate_limit.window)?;
+/// Build a limiter allowing `requests` per `window` on the given storage
+pub fn build_limiter(storage: &RateLimitStorage, requests: u32, window: &str) -> Result<Limiter, RateLimitError> {
+    let window = parse_duration(window)?;
 
     match storage {
-        RateLimitStorage::Memory => Ok(Limiter::Memory(MemoryLimiter::new(rate_limit.requests, window)?)),
+        RateLimitStorage::Memory => Ok(Limiter::Memory(MemoryLimiter::new(requests, window)?)),
         RateLimitStorage::Cache(cache_config) => Ok(Limiter::Cache(Box::new(CacheLimiter::new(
             cache_config.url.as_str(),
-            rate_limit.requests,
+            requests,
             window,
         )?))),
     }
 }
 
-async fn check_limiter(limiter: &Limiter, key: &str) -> Result<(), RateLimitError> {
+/// Count a request against a limiter under the given key
+pub async fn check_limiter(limiter: &Limiter, key: &str) -> Result<(), RateLimitError> {
     match limiter {
         Limiter::Memory(m) => m.check(key),
         Limiter::Cache(r) => r.check(key).await,
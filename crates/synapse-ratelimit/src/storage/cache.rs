@@ -63,4 +63,20 @@ impl CacheLimiter {
 
         Ok(())
     }
+
+    /// Check that the backing store is reachable
+    pub async fn ping(&self) -> Result<(), RateLimitError> {
+        let mut conn = self
+            .client
+            .get_multiplexed_async_connection()
+            .await
+            .map_err(|e| RateLimitError::Cache(format!("failed to get connection: {e}")))?;
+
+        let _: String = redis::cmd("PING")
+            .query_async(&mut conn)
+            .await
+            .map_err(|e| RateLimitError::Cache(format!("PING failed: {e}")))?;
+
+        Ok(())
+    }
 }
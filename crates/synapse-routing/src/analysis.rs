@@ -6,13 +6,15 @@
 use std::sync::LazyLock;
 
 use regex::Regex;
+use serde::{Deserialize, Serialize};
 use tiktoken_rs::o200k_base;
 
 /// Token threshold above which a request is considered long-context
 const LONG_CONTEXT_THRESHOLD: usize = 30_000;
 
 /// Broad task classification for a query
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum TaskType {
     /// Code generation, debugging, implementation
     Code,
@@ -28,16 +30,42 @@ pub enum TaskType {
     General,
 }
 
+impl TaskType {
+    /// Stable `snake_case` name, matching the serialized form
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Code => "code",
+            Self::Math => "math",
+            Self::Creative => "creative",
+            Self::SimpleQa => "simple_qa",
+            Self::Analysis => "analysis",
+            Self::General => "general",
+        }
+    }
+}
+
 /// Complexity level for routing decisions
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum Complexity {
     Low,
     Medium,
     High,
 }
 
+impl Complexity {
+    /// Stable `snake_case` name, matching the serialized form
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Low => "low",
+            Self::Medium => "medium",
+            Self::High => "high",
+        }
+    }
+}
+
 /// Capabilities the request requires from a model
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, Serialize)]
 pub struct RequiredCapabilities {
     /// Request includes tool definitions
     pub tool_calling: bool,
@@ -45,10 +73,13 @@ pub struct RequiredCapabilities {
     pub vision: bool,
     /// Estimated input tokens exceed long-context threshold
     pub long_context: bool,
+    /// Tokens the context window must hold: the estimated prompt plus any
+    /// requested output tokens
+    pub context_tokens: usize,
 }
 
 /// Profile of a query for routing decisions
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct QueryProfile {
     /// Estimated input token count
     pub estimated_input_tokens: usize,
@@ -64,6 +95,24 @@ pub struct QueryProfile {
     pub message_count: usize,
     /// Whether a system prompt is present
     pub has_system_prompt: bool,
+    /// Embedding of the latest user turn, when a strategy needs one
+    ///
+    /// Filled in by the caller after analysis; heuristic analysis leaves
+    /// it empty.
+    #[serde(skip)]
+    pub embedding: Option<Vec<f32>>,
+    /// Client that sent the query, filled in by the caller when known
+    #[serde(skip)]
+    pub client: Option<RoutingClient>,
+}
+
+/// Identity of the client a query was sent by
+#[derive(Debug, Clone, Serialize)]
+pub struct RoutingClient {
+    /// Client identifier
+    pub id: String,
+    /// Group the client belongs to
+    pub group: Option<String>,
 }
 
 /// Structured input for query analysis
@@ -99,6 +148,7 @@ pub fn analyze_query_structured(input: &AnalysisInput) -> QueryProfile {
         tool_calling: input.has_tools || input.tool_call_turns > 0,
         vision: input.has_images,
         long_context: needs_long_context,
+        context_tokens: estimated_input_tokens,
     };
 
     let complexity = assess_complexity(estimated_input_tokens, task_type, input);
@@ -111,9 +161,23 @@ pub fn analyze_query_structured(input: &AnalysisInput) -> QueryProfile {
         required_capabilities,
         message_count: input.message_count,
         has_system_prompt: input.has_system_prompt,
+        embedding: None,
+        client: None,
     }
 }
 
+/// Estimate the prompt tokens of raw message JSON values
+pub fn estimate_prompt_tokens(messages: &[serde_json::Value]) -> usize {
+    estimate_tokens(&extract_text(messages))
+}
+
+/// Classify the task type of the latest user message
+///
+/// Cheaper than a full analysis: no tokens are counted.
+pub fn classify_task_type(messages: &[serde_json::Value]) -> TaskType {
+    classify_task(&extract_last_user_message(messages))
+}
+
 /// Analyze a query for routing decisions
 ///
 /// Backward-compatible wrapper around `analyze_query_structured`
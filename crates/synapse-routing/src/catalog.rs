@@ -0,0 +1,201 @@
+//! External model catalog
+//!
+//! Parses models.dev-style catalogs mapping provider IDs to models with
+//! pricing, context limits, modalities, and capability flags. Catalogs may
+//! be JSON or TOML; fields this crate does not use are ignored, so a
+//! models.dev `api.json` export can be used as-is.
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use serde::Deserialize;
+
+use crate::error::RoutingError;
+
+/// Catalog contents keyed by catalog provider ID
+#[derive(Debug, Default, Deserialize)]
+#[serde(transparent)]
+pub struct ModelCatalog {
+    providers: HashMap<String, CatalogProvider>,
+}
+
+/// Models offered by one catalog provider
+#[derive(Debug, Default, Deserialize)]
+pub struct CatalogProvider {
+    /// Models keyed by model ID
+    #[serde(default)]
+    pub models: HashMap<String, CatalogModel>,
+}
+
+/// Catalog entry for a single model
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct CatalogModel {
+    /// Whether the model supports tool calling
+    #[serde(default)]
+    pub tool_call: bool,
+    /// Whether the model accepts file attachments
+    #[serde(default)]
+    pub attachment: bool,
+    /// Input and output modalities
+    #[serde(default)]
+    pub modalities: CatalogModalities,
+    /// Pricing, when the model is not free
+    #[serde(default)]
+    pub cost: Option<CatalogCost>,
+    /// Token limits
+    #[serde(default)]
+    pub limit: CatalogLimit,
+}
+
+/// Modalities a model accepts and produces (e.g. "text", "image", "audio")
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct CatalogModalities {
+    /// Input modalities
+    #[serde(default)]
+    pub input: Vec<String>,
+    /// Output modalities
+    #[serde(default)]
+    pub output: Vec<String>,
+}
+
+/// Model pricing in USD per million tokens
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct CatalogCost {
+    /// Cost per million input tokens
+    #[serde(default)]
+    pub input: f64,
+    /// Cost per million output tokens
+    #[serde(default)]
+    pub output: f64,
+}
+
+/// Model token limits
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct CatalogLimit {
+    /// Context window in tokens
+    #[serde(default)]
+    pub context: u64,
+    /// Maximum output tokens
+    #[serde(default)]
+    pub output: u64,
+}
+
+impl CatalogModel {
+    /// Whether the model accepts image input
+    pub fn vision(&self) -> bool {
+        self.modalities.input.iter().any(|m| m == "image")
+    }
+
+    /// Context window in tokens, saturated to the profile's range
+    pub fn context_window(&self) -> u32 {
+        u32::try_from(self.limit.context).unwrap_or(u32::MAX)
+    }
+}
+
+impl ModelCatalog {
+    /// Parse a JSON catalog
+    pub fn from_json(contents: &str) -> Result<Self, RoutingError> {
+        serde_json::from_str(contents).map_err(|e| RoutingError::InvalidCatalog(e.to_string()))
+    }
+
+    /// Parse a TOML catalog
+    pub fn from_toml(contents: &str) -> Result<Self, RoutingError> {
+        toml::from_str(contents).map_err(|e| RoutingError::InvalidCatalog(e.to_string()))
+    }
+
+    /// Look up a model by catalog provider ID and model ID
+    pub fn model(&self, provider: &str, model: &str) -> Option<&CatalogModel> {
+        self.providers.get(provider)?.models.get(model)
+    }
+
+    /// Total number of models across providers
+    pub fn len(&self) -> usize {
+        self.providers.values().map(|p| p.models.len()).sum()
+    }
+
+    /// Whether the catalog lists no models
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Load a catalog file, parsed as TOML for a `.toml` extension and as JSON
+/// otherwise
+///
+/// # Errors
+///
+/// Returns an error if the file cannot be read or parsed
+pub fn load_catalog(path: &Path) -> Result<ModelCatalog, RoutingError> {
+    let contents = std::fs::read_to_string(path)
+        .map_err(|e| RoutingError::InvalidCatalog(format!("failed to read {}: {e}", path.display())))?;
+
+    if path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("toml")) {
+        ModelCatalog::from_toml(&contents)
+    } else {
+        ModelCatalog::from_json(&contents)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_models_dev_json() {
+        let catalog = ModelCatalog::from_json(
+            r#"{
+                "anthropic": {
+                    "id": "anthropic",
+                    "name": "Anthropic",
+                    "models": {
+                        "claude-sonnet-4-20250514": {
+                            "id": "claude-sonnet-4-20250514",
+                            "name": "Claude Sonnet 4",
+                            "attachment": true,
+                            "reasoning": true,
+                            "tool_call": true,
+                            "modalities": {"input": ["text", "image"], "output": ["text"]},
+                            "cost": {"input": 3, "output": 15, "cache_read": 0.3},
+                            "limit": {"context": 200000, "output": 64000}
+                        }
+                    }
+                }
+            }"#,
+        )
+        .unwrap();
+
+        let model = catalog.model("anthropic", "claude-sonnet-4-20250514").unwrap();
+        assert!(model.tool_call);
+        assert!(model.vision());
+        assert_eq!(model.context_window(), 200_000);
+        assert!((model.cost.as_ref().unwrap().output - 15.0).abs() < f64::EPSILON);
+        assert_eq!(catalog.len(), 1);
+        assert!(catalog.model("openai", "claude-sonnet-4-20250514").is_none());
+    }
+
+    #[test]
+    fn parses_toml() {
+        let catalog = ModelCatalog::from_toml(
+            r#"
+            [openai.models.gpt-4o-mini]
+            tool_call = true
+            modalities = { input = ["text"], output = ["text"] }
+            cost = { input = 0.15, output = 0.6 }
+            limit = { context = 128000, output = 16384 }
+            "#,
+        )
+        .unwrap();
+
+        let model = catalog.model("openai", "gpt-4o-mini").unwrap();
+        assert!(!model.vision());
+        assert_eq!(model.context_window(), 128_000);
+    }
+
+    #[test]
+    fn rejects_malformed_catalogs() {
+        assert!(matches!(
+            ModelCatalog::from_json(r#"{"openai": {"models": []}}"#),
+            Err(RoutingError::InvalidCatalog(_))
+        ));
+    }
+}
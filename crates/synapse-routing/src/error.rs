@@ -21,6 +21,10 @@ pub enum RoutingError {
     #[error("all providers are currently down")]
     AllProvidersDown,
 
+    /// Model catalog could not be read or parsed
+    #[error("invalid model catalog: {0}")]
+    InvalidCatalog(String),
+
     /// Feature not available in this build
     #[error("feature not available: {feature}")]
     FeatureNotAvailable { feature: String },
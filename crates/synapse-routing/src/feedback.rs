@@ -1,15 +1,23 @@
 //! Runtime feedback tracking for model performance
 //!
-//! Records latency, error rates, and token usage per model.
-//! Sliding window for latency percentile computation. In-memory only.
-
+//! Records latency, error rates, and token usage per model, plus explicit
+//! user quality ratings per task type.
+//! Sliding window for latency percentile computation. State is held in
+//! memory; changes can be drained and merged state applied so replicas
+//! converge on shared statistics, and snapshots can be exported and
+//! restored (with age-based decay) so statistics survive restarts.
+
+use std::collections::HashMap;
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::time::Duration;
 
 use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+
+use crate::analysis::TaskType;
 
 /// Maximum samples to retain per model in the sliding window
-const MAX_SAMPLES: usize = 1000;
+pub const MAX_SAMPLES: usize = 1000;
 
 /// Feedback record for a single request
 #[derive(Debug, Clone)]
@@ -33,6 +41,12 @@ struct ModelSamples {
     latencies_ms: Vec<f64>,
     total_requests: AtomicU64,
     total_errors: AtomicU64,
+    /// Recent requests observed by other replicas
+    peer_requests: u64,
+    /// Recent failed requests observed by other replicas
+    peer_errors: u64,
+    /// Changes recorded since the last drain
+    pending: FeedbackDelta,
 }
 
 impl ModelSamples {
@@ -41,19 +55,99 @@ impl ModelSamples {
             latencies_ms: Vec::with_capacity(MAX_SAMPLES),
             total_requests: AtomicU64::new(0),
             total_errors: AtomicU64::new(0),
+            peer_requests: 0,
+            peer_errors: 0,
+            pending: FeedbackDelta::default(),
         }
     }
+
+    /// Local and peer requests and errors
+    fn counts(&self) -> (u64, u64) {
+        (
+            self.total_requests.load(Ordering::Relaxed) + self.peer_requests,
+            self.total_errors.load(Ordering::Relaxed) + self.peer_errors,
+        )
+    }
+}
+
+/// Feedback recorded locally for one model since the last drain
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FeedbackDelta {
+    /// Model key in "provider/model" format
+    pub key: String,
+    /// New successful-request latencies in milliseconds
+    pub latencies_ms: Vec<f64>,
+    /// New requests observed
+    pub requests: u64,
+    /// New failed requests observed
+    pub errors: u64,
+}
+
+/// Recent feedback for one model from the rest of the fleet
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SharedFeedback {
+    /// Most recent latencies in milliseconds across replicas, oldest first
+    pub latencies_ms: Vec<f64>,
+    /// Requests other replicas observed within the sync window
+    pub peer_requests: u64,
+    /// Failed requests other replicas observed within the sync window
+    pub peer_errors: u64,
+}
+
+/// Running total of user ratings
+#[derive(Debug, Clone, Copy, Default)]
+struct RatingTally {
+    sum: f64,
+    count: u64,
+}
+
+/// User ratings of a model, per task type of the rated request
+#[derive(Debug, Default)]
+struct ModelRatings {
+    tallies: HashMap<Option<TaskType>, RatingTally>,
+    /// Ratings recorded since the last drain
+    pending: HashMap<Option<TaskType>, RatingTally>,
+}
+
+/// User ratings of one model for one task type
+///
+/// Used for ratings recorded since the last drain, for totals merged
+/// across replicas, and in snapshots.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RatingTotal {
+    /// Model key in "provider/model" format
+    pub key: String,
+    /// Task type of the rated requests; `None` for ratings without one
+    pub task_type: Option<TaskType>,
+    /// Sum of the normalized ratings
+    pub sum: f64,
+    /// Number of ratings
+    pub count: u64,
+}
+
+/// Aggregated user ratings for a model
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RatingStats {
+    /// Mean rating, normalized to 0.0 (worst) to 1.0 (best)
+    pub mean: f64,
+    /// Number of ratings received
+    pub count: u64,
 }
 
 /// Track runtime performance feedback across all models
 pub struct FeedbackTracker {
     models: DashMap<String, ModelSamples>,
+    /// User ratings per model, bucketed by the task type of the rated request
+    ratings: DashMap<String, ModelRatings>,
 }
 
 impl FeedbackTracker {
     /// Create a new feedback tracker
     pub fn new() -> Self {
-        Self { models: DashMap::new() }
+        Self {
+            models: DashMap::new(),
+            ratings: DashMap::new(),
+        }
     }
 
     /// Record feedback for a completed request
@@ -62,6 +156,7 @@ impl FeedbackTracker {
         let mut entry = self.models.entry(key).or_insert_with(ModelSamples::new);
 
         entry.total_requests.fetch_add(1, Ordering::Relaxed);
+        entry.pending.requests += 1;
 
         if feedback.success {
             let latency_ms = feedback.latency.as_secs_f64() * 1000.0;
@@ -71,11 +166,53 @@ impl FeedbackTracker {
                 entry.latencies_ms.remove(0);
             }
             entry.latencies_ms.push(latency_ms);
+
+            if entry.pending.latencies_ms.len() >= MAX_SAMPLES {
+                entry.pending.latencies_ms.remove(0);
+            }
+            entry.pending.latencies_ms.push(latency_ms);
         } else {
             entry.total_errors.fetch_add(1, Ordering::Relaxed);
+            entry.pending.errors += 1;
         }
     }
 
+    /// Drain feedback recorded since the last call
+    ///
+    /// Only models with new observations are returned.
+    pub fn drain_pending(&self) -> Vec<FeedbackDelta> {
+        self.models
+            .iter_mut()
+            .filter(|entry| entry.pending.requests > 0)
+            .map(|mut entry| {
+                let key = entry.key().clone();
+                let mut delta = std::mem::take(&mut entry.pending);
+                delta.key = key;
+                delta
+            })
+            .collect()
+    }
+
+    /// Keys of every model with recorded feedback, in "provider/model" format
+    pub fn model_keys(&self) -> Vec<String> {
+        self.models.iter().map(|entry| entry.key().clone()).collect()
+    }
+
+    /// Merge recent feedback from other replicas into a model's statistics
+    ///
+    /// Peer counts replace the previously applied peer counts and add to
+    /// the local ones. Fleet latencies replace the local sliding window,
+    /// keeping only the most recent samples; an empty list leaves it as is.
+    pub fn apply_shared(&self, key: &str, shared: SharedFeedback) {
+        let mut entry = self.models.entry(key.to_owned()).or_insert_with(ModelSamples::new);
+
+        if !shared.latencies_ms.is_empty() {
+            entry.latencies_ms = recent_latencies(shared.latencies_ms);
+        }
+        entry.peer_requests = shared.peer_requests;
+        entry.peer_errors = shared.peer_errors;
+    }
+
     /// Get latency percentiles for a model
     pub fn latency_stats(&self, provider: &str, model: &str) -> Option<LatencyStats> {
         let key = format!("{provider}/{model}");
@@ -108,8 +245,7 @@ impl FeedbackTracker {
             };
         };
 
-        let total = entry.total_requests.load(Ordering::Relaxed);
-        let errors = entry.total_errors.load(Ordering::Relaxed);
+        let (total, errors) = entry.counts();
 
         let latency_p50_ms = if entry.latencies_ms.is_empty() {
             None
@@ -137,8 +273,7 @@ impl FeedbackTracker {
         let key = format!("{provider}/{model}");
         let entry = self.models.get(&key)?;
 
-        let total = entry.total_requests.load(Ordering::Relaxed);
-        let errors = entry.total_errors.load(Ordering::Relaxed);
+        let (total, errors) = entry.counts();
         drop(entry);
 
         if total == 0 {
@@ -149,12 +284,242 @@ impl FeedbackTracker {
     }
 }
 
+impl FeedbackTracker {
+    /// Record a user rating for a response served by a model
+    ///
+    /// `score` is normalized to 0.0 (worst) to 1.0 (best) and clamped to
+    /// that range. Ratings without a task type still count toward the
+    /// model's overall rating.
+    pub fn record_rating(&self, provider: &str, model: &str, task_type: Option<TaskType>, score: f64) {
+        let key = format!("{provider}/{model}");
+        let score = score.clamp(0.0, 1.0);
+        let mut entry = self.ratings.entry(key).or_default();
+        let ratings = &mut *entry;
+        for tallies in [&mut ratings.tallies, &mut ratings.pending] {
+            let tally = tallies.entry(task_type).or_default();
+            tally.sum += score;
+            tally.count += 1;
+        }
+        drop(entry);
+    }
+
+    /// Drain user ratings recorded since the last call
+    pub fn drain_pending_ratings(&self) -> Vec<RatingTotal> {
+        self.ratings
+            .iter_mut()
+            .flat_map(|mut entry| {
+                let key = entry.key().clone();
+                std::mem::take(&mut entry.pending)
+                    .into_iter()
+                    .map(move |(task_type, tally)| RatingTotal {
+                        key: key.clone(),
+                        task_type,
+                        sum: tally.sum,
+                        count: tally.count,
+                    })
+            })
+            .collect()
+    }
+
+    /// Replace a model's ratings for one task type with a total merged
+    /// across replicas or restored from a snapshot
+    pub fn apply_shared_rating(&self, total: RatingTotal) {
+        let mut entry = self.ratings.entry(total.key).or_default();
+        entry.tallies.insert(
+            total.task_type,
+            RatingTally {
+                sum: total.sum,
+                count: total.count,
+            },
+        );
+    }
+
+    /// Keys of every model with user ratings, in "provider/model" format
+    pub fn rated_keys(&self) -> Vec<String> {
+        self.ratings.iter().map(|entry| entry.key().clone()).collect()
+    }
+
+    /// Replace all of a model's ratings with totals merged across replicas
+    ///
+    /// Task types without a total are left without ratings.
+    pub fn replace_shared_ratings(&self, key: &str, totals: impl IntoIterator<Item = RatingTotal>) {
+        let tallies = totals
+            .into_iter()
+            .map(|total| {
+                let tally = RatingTally {
+                    sum: total.sum,
+                    count: total.count,
+                };
+                (total.task_type, tally)
+            })
+            .collect();
+        self.ratings.entry(key.to_owned()).or_default().tallies = tallies;
+    }
+
+    /// Get aggregated user ratings for a model
+    ///
+    /// Prefers ratings recorded for the given task type, falling back to
+    /// all ratings of the model when that task type has none.
+    pub fn rating(&self, provider: &str, model: &str, task_type: Option<TaskType>) -> Option<RatingStats> {
+        let key = format!("{provider}/{model}");
+        let entry = self.ratings.get(&key)?;
+
+        let tally = match entry.tallies.get(&task_type) {
+            Some(tally) if task_type.is_some() && tally.count > 0 => *tally,
+            _ => entry
+                .tallies
+                .values()
+                .fold(RatingTally::default(), |acc, t| RatingTally {
+                    sum: acc.sum + t.sum,
+                    count: acc.count + t.count,
+                }),
+        };
+        drop(entry);
+
+        if tally.count == 0 {
+            return None;
+        }
+
+        Some(RatingStats {
+            mean: tally.sum / tally.count as f64,
+            count: tally.count,
+        })
+    }
+}
+
+impl FeedbackTracker {
+    /// Export the current statistics and user ratings of every model
+    pub fn export(&self, saved_at: u64) -> FeedbackSnapshot {
+        let models = self
+            .models
+            .iter()
+            .map(|entry| ModelSnapshot {
+                key: entry.key().clone(),
+                latencies_ms: entry.latencies_ms.clone(),
+                total_requests: entry.total_requests.load(Ordering::Relaxed),
+                total_errors: entry.total_errors.load(Ordering::Relaxed),
+            })
+            .collect();
+        let ratings = self
+            .ratings
+            .iter()
+            .flat_map(|entry| {
+                let key = entry.key().clone();
+                entry
+                    .tallies
+                    .iter()
+                    .map(|(task_type, tally)| RatingTotal {
+                        key: key.clone(),
+                        task_type: *task_type,
+                        sum: tally.sum,
+                        count: tally.count,
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+
+        FeedbackSnapshot {
+            saved_at,
+            models,
+            ratings,
+        }
+    }
+
+    /// Restore statistics from a snapshot, replacing any existing state
+    /// for the models it contains
+    pub fn restore(&self, snapshot: FeedbackSnapshot) {
+        for model in snapshot.models {
+            let mut entry = self.models.entry(model.key).or_insert_with(ModelSamples::new);
+            entry.latencies_ms = recent_latencies(model.latencies_ms);
+            entry.total_requests.store(model.total_requests, Ordering::Relaxed);
+            entry.total_errors.store(model.total_errors, Ordering::Relaxed);
+        }
+        for rating in snapshot.ratings {
+            self.apply_shared_rating(rating);
+        }
+    }
+}
+
 impl Default for FeedbackTracker {
     fn default() -> Self {
         Self::new()
     }
 }
 
+/// Persisted feedback statistics for all models
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct FeedbackSnapshot {
+    /// When the snapshot was taken (unix timestamp seconds)
+    pub saved_at: u64,
+    /// Per-model statistics
+    pub models: Vec<ModelSnapshot>,
+    /// User ratings per model and task type
+    #[serde(default)]
+    pub ratings: Vec<RatingTotal>,
+}
+
+/// Persisted feedback statistics for one model
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ModelSnapshot {
+    /// Model key in "provider/model" format
+    pub key: String,
+    /// Most recent latencies in milliseconds, oldest first
+    pub latencies_ms: Vec<f64>,
+    /// Total requests observed
+    pub total_requests: u64,
+    /// Total failed requests observed
+    pub total_errors: u64,
+}
+
+impl FeedbackSnapshot {
+    /// Age the snapshot so stale observations carry less weight
+    ///
+    /// Counts are scaled by `0.5^(age / half_life)` and only the same
+    /// fraction of the most recent latency samples is kept. Ratings keep
+    /// their mean but count for less. Snapshots older than `max_age` decay
+    /// to nothing.
+    #[must_use]
+    pub fn decayed(mut self, now: u64, half_life: Duration, max_age: Duration) -> Self {
+        let age = now.saturating_sub(self.saved_at);
+        if age > max_age.as_secs() {
+            self.models.clear();
+            self.ratings.clear();
+            return self;
+        }
+
+        let half_life = half_life.as_secs_f64();
+        let weight = if half_life > 0.0 {
+            0.5_f64.powf(age as f64 / half_life)
+        } else {
+            1.0
+        };
+
+        for model in &mut self.models {
+            model.total_requests = (model.total_requests as f64 * weight).round() as u64;
+            model.total_errors = (model.total_errors as f64 * weight).round() as u64;
+
+            let keep = (model.latencies_ms.len() as f64 * weight).ceil() as usize;
+            let drop_count = model.latencies_ms.len().saturating_sub(keep);
+            model.latencies_ms.drain(..drop_count);
+        }
+
+        self.models.retain(|m| m.total_requests > 0);
+
+        for rating in &mut self.ratings {
+            let count = (rating.count as f64 * weight).round() as u64;
+            rating.sum = if rating.count == 0 {
+                0.0
+            } else {
+                rating.sum / rating.count as f64 * count as f64
+            };
+            rating.count = count;
+        }
+        self.ratings.retain(|r| r.count > 0);
+
+        self
+    }
+}
+
 /// Snapshot of a model's observed performance
 #[derive(Debug, Clone)]
 pub struct ModelFeedback {
@@ -180,6 +545,14 @@ pub struct LatencyStats {
 }
 
 /// Compute a percentile from sorted values
+/// Keep only the most recent samples that fit the sliding window
+fn recent_latencies(mut latencies: Vec<f64>) -> Vec<f64> {
+    if latencies.len() > MAX_SAMPLES {
+        latencies.drain(..latencies.len() - MAX_SAMPLES);
+    }
+    latencies
+}
+
 fn percentile(sorted: &[f64], p: f64) -> f64 {
     if sorted.is_empty() {
         return 0.0;
@@ -231,4 +604,185 @@ mod tests {
         let rate = tracker.error_rate("test", "model-1").unwrap();
         assert!((rate - 0.25).abs() < 0.01);
     }
+
+    #[test]
+    fn drain_pending_and_apply_shared() {
+        let tracker = FeedbackTracker::new();
+
+        for success in [true, false] {
+            tracker.record(&RequestFeedback {
+                provider: "test".to_owned(),
+                model: "model-1".to_owned(),
+                latency: Duration::from_millis(10),
+                success,
+                input_tokens: None,
+                output_tokens: None,
+            });
+        }
+
+        let pending = tracker.drain_pending();
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].key, "test/model-1");
+        assert_eq!(pending[0].requests, 2);
+        assert_eq!(pending[0].errors, 1);
+        assert_eq!(pending[0].latencies_ms, vec![10.0]);
+        assert!(tracker.drain_pending().is_empty());
+
+        // Peer counts add to local statistics; fleet latencies replace the window
+        tracker.apply_shared(
+            "test/model-1",
+            SharedFeedback {
+                latencies_ms: vec![10.0, 30.0, 50.0],
+                peer_requests: 6,
+                peer_errors: 0,
+            },
+        );
+
+        let snap = tracker.snapshot("test", "model-1");
+        assert_eq!(snap.sample_count, 8);
+        assert!((snap.error_rate.unwrap() - 0.125).abs() < 0.01);
+        assert!((snap.latency_p50_ms.unwrap() - 30.0).abs() < f64::EPSILON);
+
+        // A later round replaces the peer counts rather than adding to them
+        tracker.apply_shared(
+            "test/model-1",
+            SharedFeedback {
+                latencies_ms: Vec::new(),
+                peer_requests: 0,
+                peer_errors: 0,
+            },
+        );
+
+        let snap = tracker.snapshot("test", "model-1");
+        assert_eq!(snap.sample_count, 2);
+        assert!((snap.error_rate.unwrap() - 0.5).abs() < 0.01);
+        assert!((snap.latency_p50_ms.unwrap() - 30.0).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn export_and_restore_round_trip() {
+        let tracker = FeedbackTracker::new();
+        for success in [true, true, false] {
+            tracker.record(&RequestFeedback {
+                provider: "test".to_owned(),
+                model: "model-1".to_owned(),
+                latency: Duration::from_millis(20),
+                success,
+                input_tokens: None,
+                output_tokens: None,
+            });
+        }
+
+        let snapshot = tracker.export(1_000);
+        let restored = FeedbackTracker::new();
+        restored.restore(snapshot);
+
+        let snap = restored.snapshot("test", "model-1");
+        assert_eq!(snap.sample_count, 3);
+        assert!((snap.latency_p50_ms.unwrap() - 20.0).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn ratings_prefer_matching_task_type() {
+        let tracker = FeedbackTracker::new();
+        assert!(tracker.rating("test", "model-1", Some(TaskType::Code)).is_none());
+
+        tracker.record_rating("test", "model-1", Some(TaskType::Code), 1.0);
+        tracker.record_rating("test", "model-1", Some(TaskType::Code), 0.5);
+        tracker.record_rating("test", "model-1", Some(TaskType::Creative), 0.0);
+
+        let code = tracker.rating("test", "model-1", Some(TaskType::Code)).unwrap();
+        assert_eq!(code.count, 2);
+        assert!((code.mean - 0.75).abs() < f64::EPSILON);
+
+        // Task types without ratings fall back to the model-wide mean
+        let math = tracker.rating("test", "model-1", Some(TaskType::Math)).unwrap();
+        assert_eq!(math.count, 3);
+        assert!((math.mean - 0.5).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn ratings_are_drained_and_restored() {
+        let tracker = FeedbackTracker::new();
+        tracker.record_rating("test", "model-1", Some(TaskType::Code), 1.0);
+
+        let pending = tracker.drain_pending_ratings();
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].count, 1);
+        assert!(tracker.drain_pending_ratings().is_empty());
+
+        // Merged totals replace local ratings
+        tracker.apply_shared_rating(RatingTotal {
+            key: "test/model-1".to_owned(),
+            task_type: Some(TaskType::Code),
+            sum: 1.0,
+            count: 4,
+        });
+        let restored = FeedbackTracker::new();
+        restored.restore(tracker.export(1_000));
+
+        let code = restored.rating("test", "model-1", Some(TaskType::Code)).unwrap();
+        assert_eq!(code.count, 4);
+        assert!((code.mean - 0.25).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn shared_ratings_replace_every_task_type() {
+        let tracker = FeedbackTracker::new();
+        tracker.record_rating("test", "model-1", Some(TaskType::Code), 1.0);
+        tracker.record_rating("test", "model-1", Some(TaskType::Creative), 0.0);
+        assert_eq!(tracker.rated_keys(), ["test/model-1"]);
+
+        tracker.replace_shared_ratings(
+            "test/model-1",
+            [RatingTotal {
+                key: "test/model-1".to_owned(),
+                task_type: Some(TaskType::Code),
+                sum: 1.5,
+                count: 3,
+            }],
+        );
+        let creative = tracker.rating("test", "model-1", Some(TaskType::Creative)).unwrap();
+        assert_eq!(creative.count, 3);
+        assert!((creative.mean - 0.5).abs() < f64::EPSILON);
+
+        // Ratings that aged out of the fleet window are gone locally too
+        tracker.replace_shared_ratings("test/model-1", []);
+        assert!(tracker.rating("test", "model-1", None).is_none());
+    }
+
+    #[test]
+    fn snapshot_decays_with_age() {
+        let snapshot = FeedbackSnapshot {
+            saved_at: 1_000,
+            models: vec![ModelSnapshot {
+                key: "test/model-1".to_owned(),
+                latencies_ms: vec![1.0, 2.0, 3.0, 4.0],
+                total_requests: 40,
+                total_errors: 8,
+            }],
+            ratings: vec![RatingTotal {
+                key: "test/model-1".to_owned(),
+                task_type: Some(TaskType::Code),
+                sum: 6.0,
+                count: 8,
+            }],
+        };
+
+        let hour = Duration::from_secs(3600);
+        let day = Duration::from_secs(86_400);
+
+        // One half-life halves counts and keeps the newest half of samples
+        let aged = snapshot.clone().decayed(1_000 + 3600, hour, day);
+        assert_eq!(aged.models[0].total_requests, 20);
+        assert_eq!(aged.models[0].total_errors, 4);
+        assert_eq!(aged.models[0].latencies_ms, vec![3.0, 4.0]);
+        assert_eq!(aged.ratings[0].count, 4);
+        assert!((aged.ratings[0].sum - 3.0).abs() < f64::EPSILON);
+
+        // Beyond the maximum age nothing survives
+        let expired = snapshot.decayed(1_000 + 86_401, hour, day);
+        assert!(expired.models.is_empty());
+        assert!(expired.ratings.is_empty());
+    }
 }
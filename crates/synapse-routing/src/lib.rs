@@ -4,6 +4,9 @@
 //! - **Threshold**: route by complexity (`HybridLLMRouter` pattern)
 //! - **Cost**: maximize quality within budget
 //! - **Cascade**: try cheap first, escalate on low confidence (`AutomixRouter` pattern)
+//! - **kNN**: pick the best model on the most similar labeled example prompts
+//! - **Classifier**: a cheap model classifies the query, then route by complexity
+//! - **Script**: user-defined Rhai scripts loaded from config
 
 #![allow(
     clippy::must_use_candidate,
@@ -14,21 +17,32 @@
 )]
 
 pub mod analysis;
+pub mod catalog;
 pub mod error;
 pub mod feedback;
 pub mod registry;
 pub mod scoring;
 pub mod strategy;
 
-pub use analysis::{AnalysisInput, QueryProfile, RequiredCapabilities, analyze_query, analyze_query_structured};
+pub use analysis::{
+    AnalysisInput, Complexity, QueryProfile, RequiredCapabilities, RoutingClient, TaskType, analyze_query,
+    analyze_query_structured, classify_task_type, estimate_prompt_tokens,
+};
+pub use catalog::{ModelCatalog, load_catalog};
 pub use error::RoutingError;
-pub use feedback::{FeedbackTracker, ModelFeedback, RequestFeedback};
-pub use registry::{ModelProfile, ModelRegistry};
+pub use feedback::{
+    FeedbackDelta, FeedbackSnapshot, FeedbackTracker, ModelFeedback, ModelSnapshot, RatingStats, RatingTotal,
+    RequestFeedback, SharedFeedback,
+};
+pub use registry::{CatalogSource, ModelProfile, ModelRegistry};
 pub use strategy::failover::FailoverState;
 pub use strategy::{Strategy, StrategyRegistry};
 
+use serde::Serialize;
+
 /// The reason a particular model was selected
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
 pub enum RoutingReason {
     /// Query classified as low complexity
     LowComplexity,
@@ -46,6 +60,31 @@ pub enum RoutingReason {
     ScoreOptimized,
     /// Selected by ONNX ML classifier
     OnnxClassified,
+    /// Best model on the most similar labeled example prompts
+    NearestNeighbors,
+    /// Selected by a configured routing script
+    Scripted,
+    /// Kept on the model chosen earlier in the same conversation
+    ConversationAffinity,
+}
+
+impl RoutingReason {
+    /// Stable `snake_case` name, matching the serialized form
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            Self::LowComplexity => "low_complexity",
+            Self::HighComplexity => "high_complexity",
+            Self::BestQuality => "best_quality",
+            Self::CostConstrained => "cost_constrained",
+            Self::CascadeInitial => "cascade_initial",
+            Self::CascadeEscalated => "cascade_escalated",
+            Self::ScoreOptimized => "score_optimized",
+            Self::OnnxClassified => "onnx_classified",
+            Self::NearestNeighbors => "nearest_neighbors",
+            Self::Scripted => "scripted",
+            Self::ConversationAffinity => "conversation_affinity",
+        }
+    }
 }
 
 /// Result of a routing decision
@@ -61,6 +100,44 @@ pub struct RoutingDecision {
     pub alternatives: Vec<(String, String)>,
 }
 
+/// A candidate model considered for a routing decision
+#[derive(Debug, Clone, Serialize)]
+pub struct CandidateScore {
+    /// Provider name
+    pub provider: String,
+    /// Model identifier
+    pub model: String,
+    /// Quality after user ratings and error feedback
+    pub quality: f64,
+    /// Estimated request cost in USD, assuming output matches input length
+    pub estimated_cost: f64,
+    /// Observed median latency in milliseconds
+    pub latency_p50_ms: Option<f64>,
+    /// Observed error rate (0.0 to 1.0)
+    pub error_rate: Option<f64>,
+    /// Score assigned by the strategy (higher is better)
+    pub score: f64,
+}
+
+/// Full trace of a routing decision, for dry runs and debugging
+#[derive(Debug, Clone, Serialize)]
+pub struct RoutingExplanation {
+    /// Strategy that made the decision
+    pub strategy: String,
+    /// Result of query analysis
+    pub profile: QueryProfile,
+    /// Models that satisfied the required capabilities
+    pub candidates: Vec<CandidateScore>,
+    /// Selected provider name
+    pub provider: String,
+    /// Selected model identifier
+    pub model: String,
+    /// Why this model was selected
+    pub reason: RoutingReason,
+    /// Alternatives for failover, in "provider/model" format
+    pub alternatives: Vec<String>,
+}
+
 /// Route a request using the configured strategy
 pub fn route_request(
     messages: &[serde_json::Value],
@@ -110,7 +187,17 @@ pub fn route_with_strategy_registry(
     feedback: Option<&FeedbackTracker>,
 ) -> Result<RoutingDecision, RoutingError> {
     let profile = analyze_query_structured(input);
+    route_profile(&profile, registry, config, strategy_registry, feedback)
+}
 
+/// Route an already analyzed query
+pub fn route_profile(
+    profile: &QueryProfile,
+    registry: &ModelRegistry,
+    config: &synapse_config::RoutingConfig,
+    strategy_registry: &StrategyRegistry,
+    feedback: Option<&FeedbackTracker>,
+) -> Result<RoutingDecision, RoutingError> {
     tracing::debug!(
         task_type = ?profile.task_type,
         complexity = ?profile.complexity,
@@ -119,8 +206,87 @@ pub fn route_with_strategy_registry(
         "query analyzed for routing"
     );
 
-    // Filter models by required capabilities
-    let filtered = registry.filtered(&profile.required_capabilities);
+    let (filtered, strategy, strategy_name) = prepare(profile, registry, config, strategy_registry, feedback)?;
+    let decision = strategy.route(profile, &filtered, feedback)?;
+
+    tracing::info!(
+        provider = %decision.provider,
+        model = %decision.model,
+        reason = ?decision.reason,
+        alternatives = decision.alternatives.len(),
+        strategy = strategy_name,
+        "routing decision made"
+    );
+
+    Ok(decision)
+}
+
+/// Route an already analyzed query and report how every candidate scored
+///
+/// Runs the same steps as [`route_profile`] without side effects, so it is
+/// safe for dry runs.
+pub fn explain_profile(
+    profile: QueryProfile,
+    registry: &ModelRegistry,
+    config: &synapse_config::RoutingConfig,
+    strategy_registry: &StrategyRegistry,
+    feedback: Option<&FeedbackTracker>,
+) -> Result<RoutingExplanation, RoutingError> {
+    let (filtered, strategy, strategy_name) = prepare(&profile, registry, config, strategy_registry, feedback)?;
+    let decision = strategy.route(&profile, &filtered, feedback)?;
+    let scores = strategy.scores(&profile, &filtered, feedback);
+
+    let tokens = profile.estimated_input_tokens;
+    let candidates = filtered
+        .profiles()
+        .iter()
+        .zip(scores)
+        .map(|(p, score)| {
+            let observed = feedback.map(|f| f.snapshot(&p.provider, &p.model));
+            CandidateScore {
+                provider: p.provider.clone(),
+                model: p.model.clone(),
+                quality: scoring::effective_quality(p, feedback),
+                estimated_cost: p.estimate_cost(tokens, tokens),
+                latency_p50_ms: observed
+                    .as_ref()
+                    .and_then(|o| o.latency_p50_ms)
+                    .or(p.observed_latency_p50_ms),
+                error_rate: observed.and_then(|o| o.error_rate),
+                score,
+            }
+        })
+        .collect();
+
+    Ok(RoutingExplanation {
+        strategy: strategy_name.to_owned(),
+        profile,
+        candidates,
+        provider: decision.provider,
+        model: decision.model,
+        reason: decision.reason,
+        alternatives: decision
+            .alternatives
+            .into_iter()
+            .map(|(provider, model)| format!("{provider}/{model}"))
+            .collect(),
+    })
+}
+
+/// Filter candidates and look up the configured strategy
+fn prepare<'a>(
+    profile: &QueryProfile,
+    registry: &ModelRegistry,
+    config: &'a synapse_config::RoutingConfig,
+    strategy_registry: &'a StrategyRegistry,
+    feedback: Option<&FeedbackTracker>,
+) -> Result<(ModelRegistry, &'a dyn Strategy, &'a str), RoutingError> {
+    // Filter models by required capabilities and fold in user ratings
+    let filtered = registry.filtered(&profile.required_capabilities).with_user_ratings(
+        profile.task_type,
+        feedback,
+        config.user_feedback.prior_weight,
+    );
 
     if filtered.profiles().is_empty() {
         return Err(RoutingError::NoModelAvailable {
@@ -135,16 +301,67 @@ pub fn route_with_strategy_registry(
             class: format!("unknown strategy: {strategy_name}"),
         })?;
 
-    let decision = strategy.route(&profile, &filtered, feedback)?;
+    Ok((filtered, strategy, strategy_name))
+}
 
-    tracing::info!(
-        provider = %decision.provider,
-        model = %decision.model,
-        reason = ?decision.reason,
-        alternatives = decision.alternatives.len(),
-        strategy = strategy_name,
-        "routing decision made"
-    );
+#[cfg(test)]
+mod tests {
+    use synapse_config::{ModelCapabilities, ModelProfileConfig, RoutingConfig};
 
-    Ok(decision)
+    use super::*;
+
+    fn test_registry() -> ModelRegistry {
+        ModelRegistry::from_config(&[
+            ModelProfileConfig {
+                provider: "anthropic".to_owned(),
+                model: "claude-sonnet".to_owned(),
+                context_window: 200_000,
+                input_per_mtok: 3.0,
+                output_per_mtok: 15.0,
+                quality: 0.92,
+                capabilities: ModelCapabilities::default(),
+            },
+            ModelProfileConfig {
+                provider: "openai".to_owned(),
+                model: "gpt-4o-mini".to_owned(),
+                context_window: 128_000,
+                input_per_mtok: 0.15,
+                output_per_mtok: 0.60,
+                quality: 0.78,
+                capabilities: ModelCapabilities::default(),
+            },
+        ])
+    }
+
+    #[test]
+    fn explain_matches_routing_decision() {
+        let messages = vec![serde_json::json!({"role": "user", "content": "What is the capital of France?"})];
+        let input = AnalysisInput {
+            messages: &messages,
+            has_tools: false,
+            has_images: false,
+            message_count: 1,
+            has_system_prompt: false,
+            tool_call_turns: 0,
+            is_multi_turn: false,
+        };
+        let config = RoutingConfig::default();
+        let strategies = StrategyRegistry::from_config(&config);
+        let registry = test_registry();
+
+        let decision = route_with_strategy_registry(&input, &registry, &config, &strategies, None).unwrap();
+        let explanation =
+            explain_profile(analyze_query_structured(&input), &registry, &config, &strategies, None).unwrap();
+
+        assert_eq!(explanation.strategy, "threshold");
+        assert_eq!(explanation.model, decision.model);
+        assert_eq!(explanation.reason, decision.reason);
+        assert_eq!(explanation.candidates.len(), 2);
+        assert!(
+            explanation
+                .candidates
+                .iter()
+                .any(|c| c.model == "claude-sonnet" && (c.score - 0.92).abs() < f64::EPSILON)
+        );
+    }
 }
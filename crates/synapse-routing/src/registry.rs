@@ -3,9 +3,12 @@
 //! Config-driven profiles analogous to `LLMRouter`'s `llm_data` JSON
 //! but sourced from TOML configuration.
 
-use synapse_config::ModelProfileConfig;
+use synapse_config::{ModelCapabilities, ModelProfileConfig};
 
-use crate::analysis::RequiredCapabilities;
+use crate::analysis::{RequiredCapabilities, TaskType};
+use crate::catalog::{CatalogModel, ModelCatalog};
+use crate::feedback::FeedbackTracker;
+use crate::scoring;
 
 /// Runtime model profile with observed metrics
 #[derive(Debug, Clone)]
@@ -38,6 +41,13 @@ impl ModelProfile {
         format!("{}/{}", self.provider, self.model)
     }
 
+    /// Whether the context window holds the given number of tokens
+    ///
+    /// Models without a configured context window are assumed to fit.
+    pub const fn fits_context(&self, tokens: usize) -> bool {
+        self.context_window == 0 || tokens <= self.context_window as usize
+    }
+
     /// Estimate the cost of a request with the given token counts
     pub fn estimate_cost(&self, input_tokens: usize, output_tokens: usize) -> f64 {
         let input_cost = (input_tokens as f64 / 1_000_000.0) * self.input_per_mtok;
@@ -46,10 +56,23 @@ impl ModelProfile {
     }
 }
 
+/// A configured provider's models to look up in a model catalog
+#[derive(Debug, Clone, Copy)]
+pub struct CatalogSource<'a> {
+    /// Configured provider name
+    pub provider: &'a str,
+    /// Catalog provider ID describing this provider's models
+    pub catalog_id: &'a str,
+    /// Models discovered for the provider
+    pub models: &'a [String],
+}
+
 /// Registry of all available model profiles
 #[derive(Debug)]
 pub struct ModelRegistry {
     profiles: Vec<ModelProfile>,
+    /// Position of each profile in the unfiltered registry, the ONNX class
+    classes: Vec<usize>,
 }
 
 impl ModelRegistry {
@@ -64,14 +87,74 @@ impl ModelRegistry {
                 input_per_mtok: c.input_per_mtok,
                 output_per_mtok: c.output_per_mtok,
                 quality: c.quality,
-                tool_calling: c.capabilities.tool_calling,
-                vision: c.capabilities.vision,
-                long_context: c.capabilities.long_context,
+                tool_calling: c.capabilities.tool_calling.unwrap_or(false),
+                vision: c.capabilities.vision.unwrap_or(false),
+                long_context: c.capabilities.long_context.unwrap_or(false),
                 observed_latency_p50_ms: None,
             })
-            .collect();
+            .collect::<Vec<_>>();
+
+        Self {
+            classes: (0..profiles.len()).collect(),
+            profiles,
+        }
+    }
+
+    /// Build a registry from hand-written profiles and a model catalog
+    ///
+    /// Hand-written profiles take precedence; their unset context window,
+    /// pricing, and capability flags are filled in from the catalog. Every
+    /// discovered model found in the catalog without a hand-written profile
+    /// gets one with `default_quality`.
+    pub fn from_catalog(
+        configs: &[ModelProfileConfig],
+        catalog: &ModelCatalog,
+        sources: &[CatalogSource<'_>],
+        default_quality: f64,
+    ) -> Self {
+        let catalog_id = |provider: &str| {
+            sources
+                .iter()
+                .find(|source| source.provider == provider)
+                .map_or(provider, |source| source.catalog_id)
+                .to_owned()
+        };
 
-        Self { profiles }
+        let mut registry = Self::from_config(configs);
+        for (profile, config) in registry.profiles.iter_mut().zip(configs) {
+            if let Some(entry) = catalog.model(&catalog_id(&profile.provider), &profile.model) {
+                fill_from_catalog(profile, &config.capabilities, entry);
+            }
+        }
+
+        for source in sources {
+            for model in source.models {
+                if registry.find(source.provider, model).is_some() {
+                    continue;
+                }
+                let Some(entry) = catalog.model(source.catalog_id, model) else {
+                    continue;
+                };
+
+                let mut profile = ModelProfile {
+                    provider: source.provider.to_owned(),
+                    model: model.clone(),
+                    context_window: 0,
+                    input_per_mtok: 0.0,
+                    output_per_mtok: 0.0,
+                    quality: default_quality,
+                    tool_calling: false,
+                    vision: false,
+                    long_context: false,
+                    observed_latency_p50_ms: None,
+                };
+                fill_from_catalog(&mut profile, &ModelCapabilities::default(), entry);
+                registry.classes.push(registry.profiles.len());
+                registry.profiles.push(profile);
+            }
+        }
+
+        registry
     }
 
     /// Get all profiles
@@ -86,6 +169,24 @@ impl ModelRegistry {
             .find(|p| p.provider == provider && p.model == model)
     }
 
+    /// Profile scored by an ONNX class, if it is still in this registry
+    ///
+    /// Classes are positions in the unfiltered registry, so they keep
+    /// naming the same model after capability filtering.
+    pub fn class_profile(&self, class: usize) -> Option<&ModelProfile> {
+        let position = self.classes.iter().position(|&c| c == class)?;
+        self.profiles.get(position)
+    }
+
+    /// ONNX class of a provider/model pair, the label in training data
+    pub fn class_of(&self, provider: &str, model: &str) -> Option<usize> {
+        let position = self
+            .profiles
+            .iter()
+            .position(|p| p.provider == provider && p.model == model)?;
+        self.classes.get(position).copied()
+    }
+
     /// Get profiles sorted by quality (highest first)
     pub fn by_quality(&self) -> Vec<&ModelProfile> {
         let mut sorted: Vec<&ModelProfile> = self.profiles.iter().collect();
@@ -117,18 +218,43 @@ impl ModelRegistry {
     /// Return a new registry containing only models that satisfy the required capabilities
     #[must_use]
     pub fn filtered(&self, required: &RequiredCapabilities) -> Self {
-        let profiles = self
+        let (profiles, classes) = self
             .profiles
             .iter()
-            .filter(|p| {
+            .zip(&self.classes)
+            .filter(|(p, _)| {
                 (!required.tool_calling || p.tool_calling)
                     && (!required.vision || p.vision)
                     && (!required.long_context || p.long_context)
+                    && p.fits_context(required.context_tokens)
             })
-            .cloned()
-            .collect();
+            .map(|(p, &class)| (p.clone(), class))
+            .unzip();
 
-        Self { profiles }
+        Self { profiles, classes }
+    }
+
+    /// Return the registry with user ratings blended into quality scores
+    ///
+    /// Ratings for the given task type take precedence over a model's
+    /// overall ratings, so strategies learn per-task preferences.
+    #[must_use]
+    pub fn with_user_ratings(
+        mut self,
+        task_type: TaskType,
+        feedback: Option<&FeedbackTracker>,
+        prior_weight: f64,
+    ) -> Self {
+        let Some(tracker) = feedback else {
+            return self;
+        };
+
+        for profile in &mut self.profiles {
+            let rating = tracker.rating(&profile.provider, &profile.model, Some(task_type));
+            profile.quality = scoring::rated_quality(profile.quality, rating, prior_weight);
+        }
+
+        self
     }
 
     /// Update observed latency for a model
@@ -143,6 +269,31 @@ impl ModelRegistry {
     }
 }
 
+/// Catalog context window from which a model counts as long-context
+const LONG_CONTEXT_WINDOW: u32 = 100_000;
+
+/// Fill a profile's unset fields from its catalog entry
+///
+/// Capabilities set explicitly in `configured` are kept, even when the
+/// catalog disagrees.
+fn fill_from_catalog(profile: &mut ModelProfile, configured: &ModelCapabilities, entry: &CatalogModel) {
+    if profile.context_window == 0 {
+        profile.context_window = entry.context_window();
+    }
+    if profile.input_per_mtok == 0.0
+        && profile.output_per_mtok == 0.0
+        && let Some(ref cost) = entry.cost
+    {
+        profile.input_per_mtok = cost.input;
+        profile.output_per_mtok = cost.output;
+    }
+    profile.tool_calling = configured.tool_calling.unwrap_or(entry.tool_call);
+    profile.vision = configured.vision.unwrap_or_else(|| entry.vision());
+    profile.long_context = configured
+        .long_context
+        .unwrap_or(profile.context_window >= LONG_CONTEXT_WINDOW);
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -158,9 +309,9 @@ mod tests {
                 output_per_mtok: 15.0,
                 quality: 0.92,
                 capabilities: ModelCapabilities {
-                    tool_calling: true,
-                    vision: true,
-                    long_context: true,
+                    tool_calling: Some(true),
+                    vision: Some(true),
+                    long_context: Some(true),
                 },
             },
             ModelProfileConfig {
@@ -171,9 +322,9 @@ mod tests {
                 output_per_mtok: 0.60,
                 quality: 0.78,
                 capabilities: ModelCapabilities {
-                    tool_calling: true,
-                    vision: true,
-                    long_context: false,
+                    tool_calling: Some(true),
+                    vision: Some(true),
+                    long_context: Some(false),
                 },
             },
         ]
@@ -214,6 +365,18 @@ mod tests {
         assert_eq!(filtered.profiles()[0].model, "claude-sonnet-4-20250514");
     }
 
+    #[test]
+    fn filtered_by_context_window() {
+        let registry = ModelRegistry::from_config(&test_profiles());
+        let caps = RequiredCapabilities {
+            context_tokens: 150_000,
+            ..RequiredCapabilities::default()
+        };
+        let filtered = registry.filtered(&caps);
+        assert_eq!(filtered.profiles().len(), 1);
+        assert_eq!(filtered.profiles()[0].model, "claude-sonnet-4-20250514");
+    }
+
     #[test]
     fn filtered_by_vision() {
         let registry = ModelRegistry::from_config(&test_profiles());
@@ -226,6 +389,20 @@ mod tests {
         assert_eq!(filtered.profiles().len(), 2);
     }
 
+    #[test]
+    fn filtering_keeps_classes() {
+        let registry = ModelRegistry::from_config(&test_profiles());
+        let caps = RequiredCapabilities {
+            long_context: true,
+            ..RequiredCapabilities::default()
+        };
+        let filtered = registry.filtered(&caps);
+        assert_eq!(registry.class_of("openai", "gpt-4o-mini"), Some(1));
+        assert_eq!(filtered.class_of("anthropic", "claude-sonnet-4-20250514"), Some(0));
+        assert!(filtered.class_profile(1).is_none());
+        assert_eq!(filtered.class_profile(0).unwrap().model, "claude-sonnet-4-20250514");
+    }
+
     #[test]
     fn filtered_no_requirements_keeps_all() {
         let registry = ModelRegistry::from_config(&test_profiles());
@@ -245,9 +422,9 @@ mod tests {
             output_per_mtok: 0.0,
             quality: 0.5,
             capabilities: ModelCapabilities {
-                tool_calling: false,
-                vision: false,
-                long_context: false,
+                tool_calling: Some(false),
+                vision: Some(false),
+                long_context: Some(false),
             },
         }];
         let registry = ModelRegistry::from_config(&profiles);
@@ -258,4 +435,73 @@ mod tests {
         let filtered = registry.filtered(&caps);
         assert!(filtered.profiles().is_empty());
     }
+
+    #[test]
+    fn catalog_fills_profiles_and_adds_discovered_models() {
+        let catalog = ModelCatalog::from_json(
+            r#"{
+                "openai": {"models": {
+                    "gpt-4o-mini": {"limit": {"context": 64000}, "cost": {"input": 9, "output": 9}, "modalities": {"input": ["text", "image"]}},
+                    "gpt-4.1": {"tool_call": true, "cost": {"input": 2, "output": 8}, "limit": {"context": 1047576}}
+                }},
+                "local-catalog": {"models": {
+                    "tiny": {"modalities": {"input": ["text", "image"]}, "limit": {"context": 8192}}
+                }}
+            }"#,
+        )
+        .unwrap();
+        let mut profiles = test_profiles();
+        profiles[1].context_window = 0;
+        profiles[1].capabilities.vision = Some(false);
+
+        let openai_models = vec!["gpt-4o-mini".to_owned(), "gpt-4.1".to_owned(), "whisper-1".to_owned()];
+        let local_models = vec!["tiny".to_owned()];
+        let sources = [
+            CatalogSource {
+                provider: "openai",
+                catalog_id: "openai",
+                models: &openai_models,
+            },
+            CatalogSource {
+                provider: "local",
+                catalog_id: "local-catalog",
+                models: &local_models,
+            },
+        ];
+        let registry = ModelRegistry::from_catalog(&profiles, &catalog, &sources, 0.4);
+
+        // Hand-written pricing wins, the unset context window is filled in
+        let mini = registry.find("openai", "gpt-4o-mini").unwrap();
+        assert_eq!(mini.context_window, 64_000);
+        assert!((mini.input_per_mtok - 0.15).abs() < f64::EPSILON);
+
+        // Explicit capabilities win over the catalog in both directions
+        assert!(mini.tool_calling);
+        assert!(!mini.vision);
+
+        let gpt = registry.find("openai", "gpt-4.1").unwrap();
+        assert!(gpt.tool_calling);
+        assert!(gpt.long_context);
+        assert!((gpt.output_per_mtok - 8.0).abs() < f64::EPSILON);
+        assert!((gpt.quality - 0.4).abs() < f64::EPSILON);
+
+        let tiny = registry.find("local", "tiny").unwrap();
+        assert!(tiny.vision);
+        assert!(!tiny.long_context);
+        assert!(registry.find("openai", "whisper-1").is_none());
+        assert_eq!(registry.profiles().len(), 4);
+    }
+
+    #[test]
+    fn user_ratings_reorder_quality() {
+        let registry = ModelRegistry::from_config(&test_profiles());
+        let tracker = FeedbackTracker::new();
+        for _ in 0..40 {
+            tracker.record_rating("anthropic", "claude-sonnet-4-20250514", Some(TaskType::Code), 0.0);
+            tracker.record_rating("openai", "gpt-4o-mini", Some(TaskType::Code), 1.0);
+        }
+
+        let rated = registry.with_user_ratings(TaskType::Code, Some(&tracker), 10.0);
+        assert_eq!(rated.best_quality().unwrap().model, "gpt-4o-mini");
+    }
 }
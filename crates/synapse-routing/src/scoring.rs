@@ -1,9 +1,11 @@
 //! Shared feedback-adjusted scoring
 //!
 //! Applies observed runtime performance data to model quality scores.
-//! Conservative by design — feedback can only demote, never promote.
+//! Conservative by design — error feedback can only demote, never promote.
+//! Explicit user ratings are the exception: they blend into the seeded
+//! quality score and can move it in either direction.
 
-use crate::feedback::FeedbackTracker;
+use crate::feedback::{FeedbackTracker, RatingStats};
 use crate::registry::ModelProfile;
 
 /// Minimum observations before feedback adjusts scores
@@ -45,6 +47,21 @@ pub fn effective_quality(profile: &ModelProfile, feedback: Option<&FeedbackTrack
     error_rate.mul_add(-ERROR_PENALTY_FACTOR, base).clamp(0.0, 1.0)
 }
 
+/// Blend user ratings into a seeded quality score
+///
+/// Treats the seeded score as `prior_weight` virtual ratings, so a handful
+/// of ratings nudges quality while a sustained signal dominates it.
+pub fn rated_quality(base: f64, rating: Option<RatingStats>, prior_weight: f64) -> f64 {
+    let Some(rating) = rating else {
+        return base;
+    };
+
+    let prior_weight = prior_weight.max(0.0);
+    let count = rating.count as f64;
+    let blended = base.mul_add(prior_weight, rating.mean * count) / (prior_weight + count);
+    blended.clamp(0.0, 1.0)
+}
+
 #[cfg(test)]
 mod tests {
     use std::time::Duration;
@@ -116,6 +133,19 @@ mod tests {
         assert!((q - 0.75).abs() < 0.01);
     }
 
+    #[test]
+    fn ratings_blend_with_prior() {
+        assert!((rated_quality(0.8, None, 10.0) - 0.8).abs() < f64::EPSILON);
+
+        // Ten perfect ratings against a prior weight of ten split the difference
+        let rating = RatingStats { mean: 1.0, count: 10 };
+        assert!((rated_quality(0.8, Some(rating), 10.0) - 0.9).abs() < 1e-9);
+
+        // Poor ratings can demote below the seeded score
+        let rating = RatingStats { mean: 0.0, count: 30 };
+        assert!((rated_quality(0.8, Some(rating), 10.0) - 0.2).abs() < 1e-9);
+    }
+
     #[test]
     fn low_error_rate_no_penalty() {
         let profile = test_profile();
@@ -1,11 +1,14 @@
 //! Cascade routing strategy
 //!
 //! Adapted from `LLMRouter`'s `AutomixRouter`. Tries a cheap model first,
-//! then evaluates response confidence heuristically. If confidence is
-//! below threshold, escalates to a stronger model.
+//! then evaluates response confidence. If confidence is below threshold,
+//! escalates to a stronger model.
 //!
-//! Unlike `AutoMix`'s LLM-based self-verification, this uses simple
-//! heuristic confidence signals (response length, hedging language).
+//! Confidence comes from simple heuristic signals (response length,
+//! hedging language) unless a verifier is configured, in which case a
+//! model grades the answer as in `AutoMix`'s self-verification. The caller
+//! runs the verifier with `VERIFIER_PROMPT` and `verification_input`, then
+//! reads the grade with `parse_grade`.
 
 use synapse_config::CascadeConfig;
 
@@ -94,6 +97,30 @@ fn estimate_confidence(response_text: &str, query_tokens: usize) -> f64 {
     score.clamp(0.0, 1.0)
 }
 
+/// System prompt instructing the verifier model to grade an answer
+pub const VERIFIER_PROMPT: &str = "You verify answers written by another AI assistant. Judge whether the \
+answer correctly and completely responds to the question. Do not answer the question yourself. Reply with a \
+single JSON object and nothing else, matching this schema:\n\
+{\"grade\": number}\n\
+where grade is from 0.0 (wrong or unhelpful) to 1.0 (certainly correct and complete).";
+
+/// User turn presenting the question and the answer to grade
+pub fn verification_input(question: &str, answer: &str) -> String {
+    format!("Question:\n{question}\n\nAnswer:\n{answer}")
+}
+
+/// Parse the verifier's grade, clamped to 0.0 to 1.0
+///
+/// Tolerates surrounding prose or code fences by reading the outermost
+/// JSON object. Returns `None` if no numeric grade is found.
+pub fn parse_grade(answer: &str) -> Option<f64> {
+    let start = answer.find('{')?;
+    let end = answer.rfind('}')?;
+    let value: serde_json::Value = serde_json::from_str(answer.get(start..=end)?).ok()?;
+    let grade = value.get("grade")?.as_f64()?;
+    grade.is_finite().then(|| grade.clamp(0.0, 1.0))
+}
+
 fn resolve_initial(registry: &ModelRegistry, config: &CascadeConfig) -> Result<(String, String), RoutingError> {
     if let Some(ref configured) = config.initial_model {
         return split_model(configured, "cascade initial");
@@ -152,4 +179,13 @@ mod tests {
     fn short_response_to_complex_query_escalates() {
         assert!(should_escalate("Maybe.", 1000, 0.5));
     }
+
+    #[test]
+    fn parses_verifier_grades() {
+        assert_eq!(parse_grade(r#"{"grade": 0.8}"#), Some(0.8));
+        assert_eq!(parse_grade("```json\n{\"grade\": 1}\n```"), Some(1.0));
+        assert_eq!(parse_grade(r#"{"grade": 3.5}"#), Some(1.0));
+        assert_eq!(parse_grade(r#"{"grade": "high"}"#), None);
+        assert_eq!(parse_grade("The answer looks right."), None);
+    }
 }
@@ -0,0 +1,110 @@
+//! LLM-classifier routing strategy
+//!
+//! A small, cheap model labels the latest user turn with a task type and
+//! complexity using a fixed JSON schema prompt. The caller runs the
+//! classifier and applies the result to the `QueryProfile`; this strategy
+//! then maps complexity onto registry models the same way the threshold
+//! strategy does. When classification times out or fails, the heuristic
+//! profile is routed unchanged.
+
+use serde::Deserialize;
+use synapse_config::ThresholdConfig;
+
+use crate::RoutingDecision;
+use crate::analysis::{Complexity, QueryProfile, TaskType};
+use crate::error::RoutingError;
+use crate::feedback::FeedbackTracker;
+use crate::registry::ModelRegistry;
+use crate::strategy::{Strategy, threshold};
+
+/// System prompt instructing the classifier model to answer in JSON
+pub const CLASSIFIER_PROMPT: &str = "You classify requests sent to an AI assistant so they can be routed to a \
+suitable model. Do not answer the request. Reply with a single JSON object and nothing else, matching this \
+schema:\n\
+{\"task_type\": \"code\" | \"math\" | \"creative\" | \"simple_qa\" | \"analysis\" | \"general\", \
+\"complexity\": \"low\" | \"medium\" | \"high\"}\n\
+Use \"low\" for requests a small model answers well, \"high\" for multi-step reasoning, \
+large implementations, or expert knowledge, and \"medium\" otherwise.";
+
+/// Task type and complexity assigned by the classifier model
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub struct Classification {
+    /// Classified task type
+    pub task_type: TaskType,
+    /// Classified complexity
+    pub complexity: Complexity,
+}
+
+impl Classification {
+    /// Parse the classifier's answer
+    ///
+    /// Tolerates surrounding prose or code fences by reading the outermost
+    /// JSON object. Returns `None` if no valid classification is found.
+    pub fn parse(answer: &str) -> Option<Self> {
+        let start = answer.find('{')?;
+        let end = answer.rfind('}')?;
+        serde_json::from_str(answer.get(start..=end)?).ok()
+    }
+
+    /// Replace the heuristic classification of a profile
+    pub const fn apply(self, profile: &mut QueryProfile) {
+        profile.task_type = self.task_type;
+        profile.complexity = self.complexity;
+    }
+}
+
+/// Routes a classified profile by complexity
+#[derive(Debug)]
+pub struct ClassifierStrategy {
+    threshold: ThresholdConfig,
+}
+
+impl ClassifierStrategy {
+    /// Map complexity onto models using the threshold configuration
+    pub const fn new(threshold: ThresholdConfig) -> Self {
+        Self { threshold }
+    }
+}
+
+#[allow(clippy::unnecessary_literal_bound)]
+impl Strategy for ClassifierStrategy {
+    fn route(
+        &self,
+        profile: &QueryProfile,
+        registry: &ModelRegistry,
+        feedback: Option<&FeedbackTracker>,
+    ) -> Result<RoutingDecision, RoutingError> {
+        threshold::route(profile, registry, &self.threshold, feedback)
+    }
+
+    fn name(&self) -> &'static str {
+        "classifier"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_plain_json() {
+        let classification = Classification::parse(r#"{"task_type": "code", "complexity": "high"}"#).unwrap();
+        assert_eq!(classification.task_type, TaskType::Code);
+        assert_eq!(classification.complexity, Complexity::High);
+    }
+
+    #[test]
+    fn parses_fenced_json() {
+        let answer = "```json\n{\"task_type\": \"simple_qa\", \"complexity\": \"low\"}\n```";
+        let classification = Classification::parse(answer).unwrap();
+        assert_eq!(classification.task_type, TaskType::SimpleQa);
+        assert_eq!(classification.complexity, Complexity::Low);
+    }
+
+    #[test]
+    fn rejects_invalid_answers() {
+        assert!(Classification::parse("This looks like a coding question.").is_none());
+        assert!(Classification::parse(r#"{"task_type": "poetry", "complexity": "low"}"#).is_none());
+        assert!(Classification::parse(r#"{"task_type": "code"}"#).is_none());
+    }
+}
@@ -134,6 +134,8 @@ mod tests {
             required_capabilities: RequiredCapabilities::default(),
             message_count: 1,
             has_system_prompt: false,
+            embedding: None,
+            client: None,
         };
         let config = CostConfig {
             max_cost_per_request: Some(0.001),
@@ -153,6 +155,8 @@ mod tests {
             required_capabilities: RequiredCapabilities::default(),
             message_count: 1,
             has_system_prompt: false,
+            embedding: None,
+            client: None,
         };
         let config = CostConfig {
             max_cost_per_request: None,
@@ -0,0 +1,379 @@
+//! Embedding-similarity (kNN) routing strategy
+//!
+//! Compares the embedded latest user turn against a labeled set of example
+//! prompts and selects the model that scored best on the nearest examples,
+//! weighted by similarity. Labels come from offline evaluation or our own
+//! prompt history, so routing follows observed quality rather than the
+//! keyword heuristics in `analysis`.
+//!
+//! The caller embeds the query and places the vector in
+//! `QueryProfile::embedding`; without it the strategy falls back to the
+//! best quality model.
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use serde::Deserialize;
+use synapse_config::KnnConfig;
+
+use crate::analysis::QueryProfile;
+use crate::error::RoutingError;
+use crate::feedback::FeedbackTracker;
+use crate::registry::{ModelProfile, ModelRegistry};
+use crate::scoring::effective_quality;
+use crate::strategy::Strategy;
+use crate::{RoutingDecision, RoutingReason};
+
+/// A labeled example prompt, one per line of the examples file
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LabeledExample {
+    /// Example prompt text
+    pub prompt: String,
+    /// Precomputed embedding of the prompt
+    #[serde(default)]
+    pub embedding: Option<Vec<f32>>,
+    /// Observed quality per "provider/model" (0.0 to 1.0)
+    pub scores: HashMap<String, f64>,
+}
+
+/// Load labeled examples from a JSONL file
+///
+/// # Errors
+///
+/// Returns an error if the file cannot be read or a line is not a valid example
+pub fn load_examples(path: &Path) -> Result<Vec<LabeledExample>, RoutingError> {
+    let contents = std::fs::read_to_string(path)
+        .map_err(|e| RoutingError::AnalysisFailed(format!("failed to read knn examples {}: {e}", path.display())))?;
+
+    contents
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(index, line)| {
+            serde_json::from_str(line)
+                .map_err(|e| RoutingError::AnalysisFailed(format!("invalid knn example on line {}: {e}", index + 1)))
+        })
+        .collect()
+}
+
+/// Example with a unit-length embedding, ready for cosine similarity
+#[derive(Debug)]
+struct IndexedExample {
+    embedding: Vec<f32>,
+    scores: HashMap<String, f64>,
+}
+
+/// Embedding-similarity routing strategy
+#[derive(Debug)]
+pub struct KnnStrategy {
+    examples: Vec<IndexedExample>,
+    neighbors: usize,
+    min_similarity: f64,
+}
+
+impl KnnStrategy {
+    /// Index embedded examples
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if there are no examples, an example has no
+    /// embedding, or embeddings differ in dimension
+    pub fn new(examples: Vec<LabeledExample>, config: &KnnConfig) -> Result<Self, RoutingError> {
+        let dimension = examples
+            .first()
+            .and_then(|e| e.embedding.as_ref())
+            .map(Vec::len)
+            .ok_or_else(|| {
+                RoutingError::AnalysisFailed("knn routing needs at least one embedded example".to_owned())
+            })?;
+
+        let examples = examples
+            .into_iter()
+            .enumerate()
+            .map(|(index, example)| {
+                let embedding = example.embedding.ok_or_else(|| {
+                    RoutingError::AnalysisFailed(format!("knn example {} has no embedding", index + 1))
+                })?;
+                if embedding.len() != dimension {
+                    return Err(RoutingError::AnalysisFailed(format!(
+                        "knn example {} has dimension {}, expected {dimension}",
+                        index + 1,
+                        embedding.len()
+                    )));
+                }
+
+                Ok(IndexedExample {
+                    embedding: normalize(embedding),
+                    scores: example.scores,
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        tracing::info!(examples = examples.len(), dimension, "knn routing index built");
+
+        Ok(Self {
+            examples,
+            neighbors: config.neighbors,
+            min_similarity: config.min_similarity,
+        })
+    }
+
+    /// Similarity-weighted score of each registry model on the nearest examples
+    ///
+    /// Returns `None` for models no neighbor has a score for, and `None`
+    /// overall when the query embedding does not match the index.
+    fn neighbor_scores(&self, embedding: &[f32], registry: &ModelRegistry) -> Option<Vec<Option<f64>>> {
+        if self.examples.first().map(|e| e.embedding.len()) != Some(embedding.len()) {
+            tracing::warn!(
+                dimension = embedding.len(),
+                "query embedding does not match knn index dimension"
+            );
+            return None;
+        }
+
+        let query = normalize(embedding.to_vec());
+        let mut nearest: Vec<(f64, &IndexedExample)> = self
+            .examples
+            .iter()
+            .map(|example| (f64::from(dot(&query, &example.embedding)), example))
+            .filter(|(similarity, _)| *similarity >= self.min_similarity)
+            .collect();
+        nearest.sort_by(|a, b| b.0.total_cmp(&a.0));
+        nearest.truncate(self.neighbors);
+
+        let scores = registry
+            .profiles()
+            .iter()
+            .map(|p| {
+                let id = p.id();
+                let (weighted, weight) = nearest
+                    .iter()
+                    .filter_map(|(similarity, example)| {
+                        example.scores.get(&id).map(|score| (score, similarity.max(0.0)))
+                    })
+                    .fold((0.0, 0.0), |(sum, total), (score, weight)| {
+                        (sum + score * weight, total + weight)
+                    });
+                (weight > 0.0).then(|| weighted / weight)
+            })
+            .collect();
+
+        Some(scores)
+    }
+}
+
+impl Strategy for KnnStrategy {
+    fn route(
+        &self,
+        profile: &QueryProfile,
+        registry: &ModelRegistry,
+        _feedback: Option<&FeedbackTracker>,
+    ) -> Result<RoutingDecision, RoutingError> {
+        let scores = profile
+            .embedding
+            .as_deref()
+            .and_then(|embedding| self.neighbor_scores(embedding, registry));
+
+        let tokens = profile.estimated_input_tokens;
+        let mut ranked: Vec<(&ModelProfile, f64)> = registry
+            .profiles()
+            .iter()
+            .zip(scores.unwrap_or_default())
+            .filter_map(|(p, score)| score.map(|score| (p, score)))
+            .collect();
+        // Highest score first; the cheaper model wins a tie
+        ranked.sort_by(|(a, a_score), (b, b_score)| {
+            b_score.total_cmp(a_score).then_with(|| {
+                a.estimate_cost(tokens, tokens)
+                    .total_cmp(&b.estimate_cost(tokens, tokens))
+            })
+        });
+
+        let (selected, reason) = if let Some((best, score)) = ranked.first() {
+            tracing::debug!(model = %best.id(), score, "knn neighbors selected model");
+            (*best, RoutingReason::NearestNeighbors)
+        } else {
+            tracing::debug!("no knn neighbors for query, falling back to best quality");
+            (
+                registry.best_quality().ok_or(RoutingError::NoProfiles)?,
+                RoutingReason::BestQuality,
+            )
+        };
+
+        // Alternatives in ranked order, then models without neighbor scores
+        let alternatives = ranked
+            .iter()
+            .map(|(p, _)| *p)
+            .chain(registry.profiles().iter())
+            .filter(|p| p.provider != selected.provider || p.model != selected.model)
+            .fold(Vec::new(), |mut alternatives, p| {
+                let pair = (p.provider.clone(), p.model.clone());
+                if !alternatives.contains(&pair) {
+                    alternatives.push(pair);
+                }
+                alternatives
+            });
+
+        Ok(RoutingDecision {
+            provider: selected.provider.clone(),
+            model: selected.model.clone(),
+            reason,
+            alternatives,
+        })
+    }
+
+    fn scores(&self, profile: &QueryProfile, registry: &ModelRegistry, feedback: Option<&FeedbackTracker>) -> Vec<f64> {
+        let scores = profile
+            .embedding
+            .as_deref()
+            .and_then(|embedding| self.neighbor_scores(embedding, registry));
+
+        scores.map_or_else(
+            || {
+                registry
+                    .profiles()
+                    .iter()
+                    .map(|p| effective_quality(p, feedback))
+                    .collect()
+            },
+            |scores| scores.into_iter().map(|score| score.unwrap_or(0.0)).collect(),
+        )
+    }
+
+    fn name(&self) -> &'static str {
+        "knn"
+    }
+}
+
+/// Scale a vector to unit length
+fn normalize(mut vector: Vec<f32>) -> Vec<f32> {
+    let norm = dot(&vector, &vector).sqrt();
+    if norm > 0.0 {
+        for value in &mut vector {
+            *value /= norm;
+        }
+    }
+    vector
+}
+
+fn dot(a: &[f32], b: &[f32]) -> f32 {
+    a.iter().zip(b).map(|(x, y)| x * y).sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use synapse_config::{ModelCapabilities, ModelProfileConfig};
+
+    use super::*;
+    use crate::analysis::{Complexity, RequiredCapabilities, TaskType};
+
+    fn test_registry() -> ModelRegistry {
+        ModelRegistry::from_config(&[
+            ModelProfileConfig {
+                provider: "anthropic".to_owned(),
+                model: "claude-sonnet".to_owned(),
+                context_window: 200_000,
+                input_per_mtok: 3.0,
+                output_per_mtok: 15.0,
+                quality: 0.92,
+                capabilities: ModelCapabilities::default(),
+            },
+            ModelProfileConfig {
+                provider: "openai".to_owned(),
+                model: "gpt-4o-mini".to_owned(),
+                context_window: 128_000,
+                input_per_mtok: 0.15,
+                output_per_mtok: 0.60,
+                quality: 0.78,
+                capabilities: ModelCapabilities::default(),
+            },
+        ])
+    }
+
+    fn example(embedding: [f32; 2], sonnet: f64, mini: f64) -> LabeledExample {
+        LabeledExample {
+            prompt: String::new(),
+            embedding: Some(embedding.to_vec()),
+            scores: HashMap::from([
+                ("anthropic/claude-sonnet".to_owned(), sonnet),
+                ("openai/gpt-4o-mini".to_owned(), mini),
+            ]),
+        }
+    }
+
+    fn strategy() -> KnnStrategy {
+        // Code prompts cluster on the first axis, chit-chat on the second
+        let examples = vec![
+            example([1.0, 0.0], 0.9, 0.4),
+            example([0.9, 0.1], 0.95, 0.5),
+            example([0.0, 1.0], 0.8, 0.8),
+            example([0.1, 0.9], 0.85, 0.8),
+        ];
+        let config = KnnConfig {
+            neighbors: 2,
+            ..KnnConfig::default()
+        };
+        KnnStrategy::new(examples, &config).unwrap()
+    }
+
+    fn profile(embedding: Option<Vec<f32>>) -> QueryProfile {
+        QueryProfile {
+            estimated_input_tokens: 50,
+            task_type: TaskType::SimpleQa,
+            complexity: Complexity::Low,
+            requires_tool_use: false,
+            required_capabilities: RequiredCapabilities::default(),
+            message_count: 1,
+            has_system_prompt: false,
+            embedding,
+            client: None,
+        }
+    }
+
+    #[test]
+    fn picks_best_model_on_nearest_examples() {
+        let decision = strategy()
+            .route(&profile(Some(vec![0.95, 0.05])), &test_registry(), None)
+            .unwrap();
+        assert_eq!(decision.model, "claude-sonnet");
+        assert_eq!(decision.reason, RoutingReason::NearestNeighbors);
+        assert_eq!(
+            decision.alternatives,
+            vec![("openai".to_owned(), "gpt-4o-mini".to_owned())]
+        );
+    }
+
+    #[test]
+    fn cheaper_model_wins_ties() {
+        let strategy = KnnStrategy::new(vec![example([0.0, 1.0], 0.8, 0.8)], &KnnConfig::default()).unwrap();
+        let decision = strategy
+            .route(&profile(Some(vec![0.0, 1.0])), &test_registry(), None)
+            .unwrap();
+        assert_eq!(decision.model, "gpt-4o-mini");
+    }
+
+    #[test]
+    fn falls_back_without_embedding() {
+        let registry = test_registry();
+        let decision = strategy().route(&profile(None), &registry, None).unwrap();
+        assert_eq!(decision.model, "claude-sonnet");
+        assert_eq!(decision.reason, RoutingReason::BestQuality);
+
+        let mismatched = strategy()
+            .route(&profile(Some(vec![1.0, 0.0, 0.0])), &registry, None)
+            .unwrap();
+        assert_eq!(mismatched.reason, RoutingReason::BestQuality);
+    }
+
+    #[test]
+    fn rejects_mixed_dimensions() {
+        let mut examples = vec![example([1.0, 0.0], 0.9, 0.4)];
+        examples.push(LabeledExample {
+            prompt: String::new(),
+            embedding: Some(vec![1.0]),
+            scores: HashMap::new(),
+        });
+        assert!(KnnStrategy::new(examples, &KnnConfig::default()).is_err());
+    }
+}
@@ -9,12 +9,16 @@ use crate::analysis::QueryProfile;
 use crate::error::RoutingError;
 use crate::feedback::FeedbackTracker;
 use crate::registry::ModelRegistry;
+use crate::scoring::effective_quality;
 
 pub mod cascade;
+pub mod classifier;
 pub mod cost;
 pub mod failover;
+pub mod knn;
 pub mod onnx;
 pub mod score;
+pub mod script;
 pub mod threshold;
 
 /// Trait for routing strategy implementations
@@ -28,6 +32,19 @@ pub trait Strategy: Send + Sync {
         feedback: Option<&FeedbackTracker>,
     ) -> Result<RoutingDecision, RoutingError>;
 
+    /// Score every candidate model, in registry order, to explain a decision
+    ///
+    /// Defaults to feedback-adjusted quality, which the ranking strategies
+    /// compare. Strategies optimizing another objective should override it.
+    fn scores(&self, profile: &QueryProfile, registry: &ModelRegistry, feedback: Option<&FeedbackTracker>) -> Vec<f64> {
+        let _ = profile;
+        registry
+            .profiles()
+            .iter()
+            .map(|p| effective_quality(p, feedback))
+            .collect()
+    }
+
     /// Human-readable strategy name
     fn name(&self) -> &'static str;
 }
@@ -111,6 +128,15 @@ impl Strategy for ScoreStrategy {
         score::route(profile, registry, &self.config, feedback)
     }
 
+    fn scores(
+        &self,
+        _profile: &QueryProfile,
+        registry: &ModelRegistry,
+        feedback: Option<&FeedbackTracker>,
+    ) -> Vec<f64> {
+        score::scores(registry, &self.config, feedback)
+    }
+
     fn name(&self) -> &'static str {
         "score"
     }
@@ -151,6 +177,11 @@ impl StrategyRegistry {
             }),
         );
 
+        strategies.insert(
+            "classifier".to_owned(),
+            Box::new(classifier::ClassifierStrategy::new(config.threshold.clone())),
+        );
+
         Self { strategies }
     }
 
@@ -171,6 +202,8 @@ impl StrategyRegistry {
             synapse_config::RoutingStrategy::Cost => "cost",
             synapse_config::RoutingStrategy::Cascade => "cascade",
             synapse_config::RoutingStrategy::Score => "score",
+            synapse_config::RoutingStrategy::Knn => "knn",
+            synapse_config::RoutingStrategy::Classifier => "classifier",
             synapse_config::RoutingStrategy::Custom(name) => name.as_str(),
         }
     }
@@ -222,6 +255,8 @@ mod tests {
             required_capabilities: RequiredCapabilities::default(),
             message_count: 1,
             has_system_prompt: false,
+            embedding: None,
+            client: None,
         }
     }
 
@@ -14,8 +14,19 @@ use crate::registry::ModelRegistry;
 use crate::strategy::Strategy;
 
 /// Number of input features extracted from a `QueryProfile`
-#[cfg(feature = "onnx")]
-const NUM_FEATURES: usize = 8;
+pub const NUM_FEATURES: usize = 8;
+
+/// Column names of the features, in order, as used in training data
+pub const FEATURE_NAMES: [&str; NUM_FEATURES] = [
+    "input_tokens",
+    "task_type",
+    "complexity",
+    "tool_use",
+    "vision",
+    "long_context",
+    "message_count",
+    "system_prompt",
+];
 
 /// ONNX ML-based routing strategy
 ///
@@ -61,6 +72,57 @@ impl OnnxStrategy {
             feature: "onnx".to_owned(),
         })
     }
+
+    /// Number of classes the model scores
+    ///
+    /// Found by running the model on an all-zero feature vector. A model
+    /// fits a registry when this matches the registry's profile count.
+    ///
+    /// # Errors
+    ///
+    /// Returns `RoutingError::AnalysisFailed` if inference fails
+    #[cfg(feature = "onnx")]
+    pub fn class_count(&self) -> Result<usize, RoutingError> {
+        self.probabilities(&[0.0; NUM_FEATURES]).map(|p| p.len())
+    }
+
+    /// Number of classes the model scores
+    ///
+    /// # Errors
+    ///
+    /// Returns `RoutingError::FeatureNotAvailable` when the `onnx` feature
+    /// is not enabled
+    #[cfg(not(feature = "onnx"))]
+    pub fn class_count(&self) -> Result<usize, RoutingError> {
+        Err(RoutingError::FeatureNotAvailable {
+            feature: "onnx".to_owned(),
+        })
+    }
+
+    /// Run inference on a feature vector and return the class probabilities
+    #[cfg(feature = "onnx")]
+    fn probabilities(&self, features: &[f32; NUM_FEATURES]) -> Result<Vec<f32>, RoutingError> {
+        let input_array = ndarray::Array2::from_shape_vec((1, NUM_FEATURES), features.to_vec())
+            .map_err(|e| RoutingError::AnalysisFailed(format!("failed to build input tensor: {e}")))?;
+
+        let outputs = self
+            .session
+            .run(
+                ort::inputs![input_array]
+                    .map_err(|e| RoutingError::AnalysisFailed(format!("failed to prepare ONNX inputs: {e}")))?,
+            )
+            .map_err(|e| RoutingError::AnalysisFailed(format!("ONNX inference failed: {e}")))?;
+
+        // Extract output probability tensor
+        let output_tensor = outputs
+            .first()
+            .ok_or_else(|| RoutingError::AnalysisFailed("ONNX model returned no outputs".to_owned()))?
+            .1
+            .try_extract_tensor::<f32>()
+            .map_err(|e| RoutingError::AnalysisFailed(format!("failed to extract output tensor: {e}")))?;
+
+        Ok(output_tensor.iter().copied().collect())
+    }
 }
 
 impl Strategy for OnnxStrategy {
@@ -78,31 +140,9 @@ impl Strategy for OnnxStrategy {
                 return Err(RoutingError::NoProfiles);
             }
 
-            // Extract features from the query profile
-            let features = profile_to_features(_profile);
-            let input_array = ndarray::Array2::from_shape_vec((1, NUM_FEATURES), features)
-                .map_err(|e| RoutingError::AnalysisFailed(format!("failed to build input tensor: {e}")))?;
-
-            // Run inference
-            let outputs = self
-                .session
-                .run(
-                    ort::inputs![input_array]
-                        .map_err(|e| RoutingError::AnalysisFailed(format!("failed to prepare ONNX inputs: {e}")))?,
-                )
-                .map_err(|e| RoutingError::AnalysisFailed(format!("ONNX inference failed: {e}")))?;
-
-            // Extract output probability tensor
-            let output_tensor = outputs
-                .first()
-                .ok_or_else(|| RoutingError::AnalysisFailed("ONNX model returned no outputs".to_owned()))?
-                .1
-                .try_extract_tensor::<f32>()
-                .map_err(|e| RoutingError::AnalysisFailed(format!("failed to extract output tensor: {e}")))?;
-
-            let probabilities: Vec<f32> = output_tensor.iter().copied().collect();
-
-            match select_model_from_probabilities(&probabilities, profiles, _profile) {
+            let probabilities = self.probabilities(&features(_profile))?;
+
+            match select_model_from_probabilities(&probabilities, registry, _profile) {
                 Some(selected) => {
                     let alternatives = profiles
                         .iter()
@@ -160,7 +200,6 @@ impl Strategy for OnnxStrategy {
     }
 }
 
-#[cfg(feature = "onnx")]
 /// Convert a `QueryProfile` into a fixed-size feature vector for ONNX inference
 ///
 /// Produces `NUM_FEATURES` (8) f32 values:
@@ -172,8 +211,9 @@ impl Strategy for OnnxStrategy {
 /// 5. `long_context` capability (0.0 or 1.0)
 /// 6. `message_count / 50.0` — normalized message count
 /// 7. `has_system_prompt` (0.0 or 1.0)
-#[cfg(feature = "onnx")]
-fn profile_to_features(profile: &QueryProfile) -> Vec<f32> {
+///
+/// Training data for the model must use the same encoding.
+pub fn features(profile: &QueryProfile) -> [f32; NUM_FEATURES] {
     use crate::analysis::{Complexity, TaskType};
 
     let task_ordinal = match profile.task_type {
@@ -191,7 +231,7 @@ fn profile_to_features(profile: &QueryProfile) -> Vec<f32> {
         Complexity::High => 2.0,
     };
 
-    vec![
+    [
         profile.estimated_input_tokens as f32 / 100_000.0,
         task_ordinal,
         complexity_ordinal,
@@ -209,18 +249,19 @@ fn profile_to_features(profile: &QueryProfile) -> Vec<f32> {
 
 /// Select a model from registry profiles using ONNX output probabilities
 ///
-/// Performs argmax over the probability vector, mapping the winning index
-/// to a registry profile. If the output dimension does not match the number
-/// of profiles, or the selected model lacks required capabilities, falls back
-/// to the highest-probability model that satisfies capability requirements.
+/// Performs argmax over the probability vector, mapping the winning class
+/// to its registry profile. Classes index the unfiltered registry, so a
+/// class whose model was filtered out, or that the registry does not have,
+/// is skipped, as is a model lacking required capabilities; the
+/// highest-probability remaining model is selected instead.
 /// Returns `None` when no valid selection can be made
 #[cfg(feature = "onnx")]
 fn select_model_from_probabilities(
     probabilities: &[f32],
-    profiles: &[crate::registry::ModelProfile],
+    registry: &ModelRegistry,
     query: &QueryProfile,
 ) -> Option<crate::registry::ModelProfile> {
-    if probabilities.is_empty() || profiles.is_empty() {
+    if probabilities.is_empty() || registry.profiles().is_empty() {
         return None;
     }
 
@@ -229,12 +270,10 @@ fn select_model_from_probabilities(
     ranked.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
 
     // Walk ranked list and return the first profile that satisfies capabilities
-    for (idx, _prob) in &ranked {
-        if *idx >= profiles.len() {
+    for (class, _prob) in &ranked {
+        let Some(candidate) = registry.class_profile(*class) else {
             continue;
-        }
-
-        let candidate = &profiles[*idx];
+        };
 
         if satisfies_capabilities(candidate, query) {
             return Some(candidate.clone());
@@ -263,3 +302,32 @@ fn satisfies_capabilities(profile: &crate::registry::ModelProfile, query: &Query
 
     true
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::{Complexity, RequiredCapabilities, TaskType};
+
+    #[test]
+    fn features_encode_the_profile() {
+        let profile = QueryProfile {
+            estimated_input_tokens: 50_000,
+            task_type: TaskType::Code,
+            complexity: Complexity::High,
+            requires_tool_use: true,
+            required_capabilities: RequiredCapabilities {
+                long_context: true,
+                ..RequiredCapabilities::default()
+            },
+            message_count: 5,
+            has_system_prompt: true,
+            embedding: None,
+            client: None,
+        };
+
+        let expected = [0.5, 4.0, 2.0, 1.0, 0.0, 1.0, 0.1, 1.0];
+        for (actual, expected) in features(&profile).iter().zip(expected) {
+            assert!((actual - expected).abs() < f32::EPSILON, "{actual} != {expected}");
+        }
+    }
+}
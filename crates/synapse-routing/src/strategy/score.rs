@@ -26,6 +26,31 @@ pub fn route(
         return Err(RoutingError::NoProfiles);
     }
 
+    let mut scored: Vec<_> = profiles.iter().zip(scores(registry, config, feedback)).collect();
+
+    // Sort by score descending
+    scored.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+
+    let (selected, _score) = scored.first().ok_or(RoutingError::NoProfiles)?;
+
+    let alternatives = scored
+        .iter()
+        .skip(1)
+        .map(|(p, _)| (p.provider.clone(), p.model.clone()))
+        .collect();
+
+    Ok(RoutingDecision {
+        provider: selected.provider.clone(),
+        model: selected.model.clone(),
+        reason: RoutingReason::ScoreOptimized,
+        alternatives,
+    })
+}
+
+/// Compute the weighted score of every model, in registry order
+pub fn scores(registry: &ModelRegistry, config: &ScoreConfig, feedback: Option<&FeedbackTracker>) -> Vec<f64> {
+    let profiles = registry.profiles();
+
     // Compute normalization bounds
     let max_cost = profiles
         .iter()
@@ -40,8 +65,7 @@ pub fn route(
     let max_cost = if max_cost <= 0.0 { 1.0 } else { max_cost };
     let max_latency = if max_latency <= 0.0 { 1.0 } else { max_latency };
 
-    // Score each model
-    let mut scored: Vec<_> = profiles
+    profiles
         .iter()
         .map(|p| {
             let cost = p.input_per_mtok + p.output_per_mtok;
@@ -66,28 +90,9 @@ pub fn route(
                 }
             });
 
-            let final_score = config.error_penalty.mul_add(-error_rate, 1.0) * raw;
-            (p, final_score)
+            config.error_penalty.mul_add(-error_rate, 1.0) * raw
         })
-        .collect();
-
-    // Sort by score descending
-    scored.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
-
-    let (selected, _score) = scored.first().ok_or(RoutingError::NoProfiles)?;
-
-    let alternatives = scored
-        .iter()
-        .skip(1)
-        .map(|(p, _)| (p.provider.clone(), p.model.clone()))
-        .collect();
-
-    Ok(RoutingDecision {
-        provider: selected.provider.clone(),
-        model: selected.model.clone(),
-        reason: RoutingReason::ScoreOptimized,
-        alternatives,
-    })
+        .collect()
 }
 
 /// Resolve latency for a model from feedback, profile, or default
@@ -142,6 +147,8 @@ mod tests {
             required_capabilities: RequiredCapabilities::default(),
             message_count: 1,
             has_system_prompt: false,
+            embedding: None,
+            client: None,
         }
     }
 
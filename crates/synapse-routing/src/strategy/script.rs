@@ -0,0 +1,404 @@
+//! Scripted routing strategies
+//!
+//! Lets users of the prebuilt binary define custom strategies as Rhai
+//! scripts referenced from config. A script defines
+//! `fn route(query, models, client)` where `query` is the analyzed
+//! `QueryProfile`, `models` the capability-filtered candidates with their
+//! observed feedback, and `client` the caller's `id` and `group` (or `()`
+//! when anonymous). It returns a "provider/model" string or a map with
+//! `model` and optional `alternatives`.
+//!
+//! Scripts run sandboxed: module imports and `eval` are disabled, output
+//! goes to tracing, and every call is bounded by an operation budget, a
+//! wall-clock limit, and size limits on strings, arrays, and maps. A script
+//! that fails, times out, or picks an unknown model falls back to the best
+//! quality candidate.
+
+use std::cell::Cell;
+use std::time::{Duration, Instant};
+
+use rhai::module_resolvers::DummyModuleResolver;
+use rhai::{AST, Dynamic, Engine, Scope};
+use serde::{Deserialize, Serialize};
+use synapse_config::ScriptStrategyConfig;
+
+use crate::analysis::QueryProfile;
+use crate::error::RoutingError;
+use crate::feedback::FeedbackTracker;
+use crate::registry::{ModelProfile, ModelRegistry};
+use crate::scoring::effective_quality;
+use crate::strategy::Strategy;
+use crate::{RoutingDecision, RoutingReason};
+
+/// Maximum nesting of script function calls
+const MAX_CALL_LEVELS: usize = 32;
+
+/// Maximum length of a script string
+const MAX_STRING_SIZE: usize = 64 * 1024;
+
+/// Maximum number of elements in a script array or map
+const MAX_COLLECTION_SIZE: usize = 10_000;
+
+thread_local! {
+    /// Deadline of the script call running on this thread
+    ///
+    /// Routing is synchronous, so a call never yields its thread before
+    /// the deadline is cleared.
+    static DEADLINE: Cell<Option<Instant>> = const { Cell::new(None) };
+}
+
+/// Candidate model as seen by a script
+#[derive(Debug, Serialize)]
+struct ScriptModel<'a> {
+    id: String,
+    provider: &'a str,
+    model: &'a str,
+    context_window: u32,
+    input_per_mtok: f64,
+    output_per_mtok: f64,
+    quality: f64,
+    /// Quality adjusted by error and user rating feedback
+    effective_quality: f64,
+    tool_calling: bool,
+    vision: bool,
+    long_context: bool,
+    latency_p50_ms: Option<f64>,
+    error_rate: Option<f64>,
+    sample_count: usize,
+}
+
+impl<'a> ScriptModel<'a> {
+    fn new(profile: &'a ModelProfile, feedback: Option<&FeedbackTracker>) -> Self {
+        let observed = feedback.map(|f| f.snapshot(&profile.provider, &profile.model));
+
+        Self {
+            id: profile.id(),
+            provider: &profile.provider,
+            model: &profile.model,
+            context_window: profile.context_window,
+            input_per_mtok: profile.input_per_mtok,
+            output_per_mtok: profile.output_per_mtok,
+            quality: profile.quality,
+            effective_quality: effective_quality(profile, feedback),
+            tool_calling: profile.tool_calling,
+            vision: profile.vision,
+            long_context: profile.long_context,
+            latency_p50_ms: observed
+                .as_ref()
+                .and_then(|o| o.latency_p50_ms)
+                .or(profile.observed_latency_p50_ms),
+            error_rate: observed.as_ref().and_then(|o| o.error_rate),
+            sample_count: observed.map_or(0, |o| o.sample_count),
+        }
+    }
+}
+
+/// Value returned by a script's `route` function
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum ScriptDecision {
+    Model(String),
+    Detailed {
+        model: String,
+        #[serde(default)]
+        alternatives: Vec<String>,
+    },
+}
+
+/// Routing strategy backed by a Rhai script
+pub struct ScriptStrategy {
+    name: String,
+    engine: Engine,
+    ast: AST,
+    timeout: Duration,
+}
+
+impl ScriptStrategy {
+    /// Compile a script and check that it defines `route`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the script cannot be read or compiled, or does
+    /// not define `fn route(query, models, client)`
+    pub fn load(config: &ScriptStrategyConfig) -> Result<Self, RoutingError> {
+        let engine = sandboxed_engine(&config.name, config.max_operations);
+        let ast = engine.compile_file(config.path.clone()).map_err(|e| {
+            RoutingError::AnalysisFailed(format!(
+                "failed to compile routing script {}: {e}",
+                config.path.display()
+            ))
+        })?;
+
+        if !ast.iter_functions().any(|f| f.name == "route" && f.params.len() == 3) {
+            return Err(RoutingError::AnalysisFailed(format!(
+                "routing script {} must define fn route(query, models, client)",
+                config.path.display()
+            )));
+        }
+
+        tracing::info!(name = %config.name, path = %config.path.display(), "loaded routing script");
+
+        Ok(Self {
+            name: config.name.clone(),
+            engine,
+            ast,
+            timeout: Duration::from_millis(config.timeout_ms),
+        })
+    }
+
+    /// Run the script's `route` function under the call deadline
+    fn call(
+        &self,
+        profile: &QueryProfile,
+        registry: &ModelRegistry,
+        feedback: Option<&FeedbackTracker>,
+    ) -> Result<ScriptDecision, String> {
+        let models: Vec<ScriptModel> = registry
+            .profiles()
+            .iter()
+            .map(|p| ScriptModel::new(p, feedback))
+            .collect();
+
+        let query = rhai::serde::to_dynamic(profile).map_err(|e| e.to_string())?;
+        let models = rhai::serde::to_dynamic(models).map_err(|e| e.to_string())?;
+        let client = profile
+            .client
+            .as_ref()
+            .map_or(Ok(Dynamic::UNIT), rhai::serde::to_dynamic)
+            .map_err(|e| e.to_string())?;
+
+        DEADLINE.set(Some(Instant::now() + self.timeout));
+        let result = self
+            .engine
+            .call_fn::<Dynamic>(&mut Scope::new(), &self.ast, "route", (query, models, client));
+        DEADLINE.set(None);
+
+        let value = result.map_err(|e| e.to_string())?;
+        rhai::serde::from_dynamic(&value).map_err(|e| format!("invalid return value: {e}"))
+    }
+}
+
+impl std::fmt::Debug for ScriptStrategy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ScriptStrategy")
+            .field("name", &self.name)
+            .field("timeout", &self.timeout)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Strategy for ScriptStrategy {
+    fn route(
+        &self,
+        profile: &QueryProfile,
+        registry: &ModelRegistry,
+        feedback: Option<&FeedbackTracker>,
+    ) -> Result<RoutingDecision, RoutingError> {
+        let selected = match self.call(profile, registry, feedback) {
+            Ok(decision) => {
+                let (model, alternatives) = match decision {
+                    ScriptDecision::Model(model) => (model, Vec::new()),
+                    ScriptDecision::Detailed { model, alternatives } => (model, alternatives),
+                };
+                let selected = find(registry, &model).map(|selected| (selected, alternatives));
+                if selected.is_none() {
+                    tracing::warn!(script = %self.name, model, "routing script selected an unknown model");
+                }
+                selected
+            }
+            Err(e) => {
+                tracing::warn!(script = %self.name, error = %e, "routing script failed");
+                None
+            }
+        };
+
+        let Some((selected, alternatives)) = selected else {
+            let best = registry.best_quality().ok_or(RoutingError::NoProfiles)?;
+            return Ok(RoutingDecision {
+                provider: best.provider.clone(),
+                model: best.model.clone(),
+                reason: RoutingReason::BestQuality,
+                alternatives: registry
+                    .profiles()
+                    .iter()
+                    .filter(|p| p.provider != best.provider || p.model != best.model)
+                    .map(|p| (p.provider.clone(), p.model.clone()))
+                    .collect(),
+            });
+        };
+
+        Ok(RoutingDecision {
+            provider: selected.provider.clone(),
+            model: selected.model.clone(),
+            reason: RoutingReason::Scripted,
+            alternatives: alternatives
+                .iter()
+                .filter_map(|id| find(registry, id))
+                .filter(|p| p.provider != selected.provider || p.model != selected.model)
+                .map(|p| (p.provider.clone(), p.model.clone()))
+                .collect(),
+        })
+    }
+
+    fn name(&self) -> &'static str {
+        "script"
+    }
+}
+
+/// Look up a "provider/model" id among the candidates
+fn find<'a>(registry: &'a ModelRegistry, id: &str) -> Option<&'a ModelProfile> {
+    let (provider, model) = id.split_once('/')?;
+    registry.find(provider, model)
+}
+
+/// Engine without file access, `eval`, or unbounded resource use
+fn sandboxed_engine(name: &str, max_operations: u64) -> Engine {
+    let mut engine = Engine::new();
+    engine
+        .set_module_resolver(DummyModuleResolver::new())
+        .set_max_operations(max_operations)
+        .set_max_call_levels(MAX_CALL_LEVELS)
+        .set_max_string_size(MAX_STRING_SIZE)
+        .set_max_array_size(MAX_COLLECTION_SIZE)
+        .set_max_map_size(MAX_COLLECTION_SIZE)
+        .disable_symbol("eval");
+
+    engine.on_progress(|_| {
+        DEADLINE
+            .get()
+            .filter(|deadline| Instant::now() > *deadline)
+            .map(|_| Dynamic::from("routing script exceeded its time limit"))
+    });
+
+    let print_name = name.to_owned();
+    engine.on_print(move |text| tracing::info!(script = %print_name, "{text}"));
+    let debug_name = name.to_owned();
+    engine.on_debug(move |text, _, position| tracing::debug!(script = %debug_name, %position, "{text}"));
+
+    engine
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use synapse_config::{ModelCapabilities, ModelProfileConfig};
+
+    use super::*;
+    use crate::analysis::{Complexity, RequiredCapabilities, RoutingClient, TaskType};
+
+    fn test_registry() -> ModelRegistry {
+        ModelRegistry::from_config(&[
+            ModelProfileConfig {
+                provider: "anthropic".to_owned(),
+                model: "claude-sonnet".to_owned(),
+                context_window: 200_000,
+                input_per_mtok: 3.0,
+                output_per_mtok: 15.0,
+                quality: 0.92,
+                capabilities: ModelCapabilities::default(),
+            },
+            ModelProfileConfig {
+                provider: "openai".to_owned(),
+                model: "gpt-4o-mini".to_owned(),
+                context_window: 128_000,
+                input_per_mtok: 0.15,
+                output_per_mtok: 0.60,
+                quality: 0.78,
+                capabilities: ModelCapabilities::default(),
+            },
+        ])
+    }
+
+    fn profile(group: Option<&str>) -> QueryProfile {
+        QueryProfile {
+            estimated_input_tokens: 50,
+            task_type: TaskType::Code,
+            complexity: Complexity::Low,
+            requires_tool_use: false,
+            required_capabilities: RequiredCapabilities::default(),
+            message_count: 1,
+            has_system_prompt: false,
+            embedding: None,
+            client: group.map(|group| RoutingClient {
+                id: "client-1".to_owned(),
+                group: Some(group.to_owned()),
+            }),
+        }
+    }
+
+    fn script(source: &str) -> ScriptStrategy {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        file.write_all(source.as_bytes()).unwrap();
+
+        ScriptStrategy::load(&ScriptStrategyConfig {
+            name: "test".to_owned(),
+            path: file.path().to_path_buf(),
+            timeout_ms: 50,
+            max_operations: 100_000,
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn routes_on_profile_and_client() {
+        let strategy = script(
+            r#"
+            fn route(query, models, client) {
+                if client != () && client.group == "pro" && query.task_type == "code" {
+                    return #{ model: "anthropic/claude-sonnet", alternatives: ["openai/gpt-4o-mini"] };
+                }
+                let cheapest = models[0];
+                for m in models {
+                    if m.input_per_mtok < cheapest.input_per_mtok { cheapest = m; }
+                }
+                cheapest.id
+            }
+            "#,
+        );
+        let registry = test_registry();
+
+        let pro = strategy.route(&profile(Some("pro")), &registry, None).unwrap();
+        assert_eq!(pro.model, "claude-sonnet");
+        assert_eq!(pro.reason, RoutingReason::Scripted);
+        assert_eq!(pro.alternatives, vec![("openai".to_owned(), "gpt-4o-mini".to_owned())]);
+
+        let anonymous = strategy.route(&profile(None), &registry, None).unwrap();
+        assert_eq!(anonymous.model, "gpt-4o-mini");
+    }
+
+    #[test]
+    fn falls_back_on_unknown_model_or_error() {
+        let registry = test_registry();
+
+        let unknown = script(r#"fn route(query, models, client) { "openai/gpt-9" }"#);
+        let decision = unknown.route(&profile(None), &registry, None).unwrap();
+        assert_eq!(decision.model, "claude-sonnet");
+        assert_eq!(decision.reason, RoutingReason::BestQuality);
+
+        let failing = script(r#"fn route(query, models, client) { throw "nope"; }"#);
+        let decision = failing.route(&profile(None), &registry, None).unwrap();
+        assert_eq!(decision.reason, RoutingReason::BestQuality);
+    }
+
+    #[test]
+    fn endless_scripts_are_stopped() {
+        let strategy = script("fn route(query, models, client) { loop {} }");
+        let decision = strategy.route(&profile(None), &test_registry(), None).unwrap();
+        assert_eq!(decision.reason, RoutingReason::BestQuality);
+    }
+
+    #[test]
+    fn rejects_scripts_without_route() {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        file.write_all(b"fn pick(models) { models[0].id }").unwrap();
+
+        let result = ScriptStrategy::load(&ScriptStrategyConfig {
+            name: "test".to_owned(),
+            path: file.path().to_path_buf(),
+            timeout_ms: 50,
+            max_operations: 100_000,
+        });
+        assert!(result.is_err());
+    }
+}
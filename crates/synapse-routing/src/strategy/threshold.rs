@@ -19,6 +19,7 @@ const fn model_satisfies_caps(profile: &ModelProfile, required: &RequiredCapabil
     (!required.tool_calling || profile.tool_calling)
         && (!required.vision || profile.vision)
         && (!required.long_context || profile.long_context)
+        && profile.fits_context(required.context_tokens)
 }
 
 /// Route a query using the threshold strategy
@@ -138,6 +139,8 @@ mod tests {
             required_capabilities: RequiredCapabilities::default(),
             message_count: 1,
             has_system_prompt: false,
+            embedding: None,
+            client: None,
         };
         let config = ThresholdConfig::default();
         let decision = route(&profile, &registry, &config, None).unwrap();
@@ -156,6 +159,8 @@ mod tests {
             required_capabilities: RequiredCapabilities::default(),
             message_count: 1,
             has_system_prompt: false,
+            embedding: None,
+            client: None,
         };
         let config = ThresholdConfig::default();
         let decision = route(&profile, &registry, &config, None).unwrap();
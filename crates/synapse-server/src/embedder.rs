@@ -0,0 +1,64 @@
+//! Embedder backed by the embeddings subsystem, for kNN routing and MCP
+//! tool search
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use synapse_core::RequestContext;
+use synapse_embeddings::{EmbedInput, EmbeddingRequest};
+use synapse_llm::{LlmError, QueryEmbedder};
+use synapse_mcp::McpError;
+use synapse_mcp::semantic::ToolEmbedder;
+
+/// Embeds routing queries and MCP tools with a configured embeddings
+/// provider
+pub struct EmbeddingsQueryEmbedder {
+    server: Arc<synapse_embeddings::Server>,
+    model: String,
+}
+
+impl EmbeddingsQueryEmbedder {
+    pub const fn new(server: Arc<synapse_embeddings::Server>, model: String) -> Self {
+        Self { server, model }
+    }
+
+    /// Embed each input in order
+    async fn embed_inputs(&self, inputs: Vec<String>) -> Result<Vec<Vec<f32>>, String> {
+        let request = EmbeddingRequest {
+            input: EmbedInput::Multiple(inputs),
+            model: self.model.clone(),
+            encoding_format: "float".to_owned(),
+            dimensions: None,
+            user: None,
+        };
+
+        // Gateway traffic, not billed to the caller
+        let response = self
+            .server
+            .embed(&request, &RequestContext::empty())
+            .await
+            .map_err(|e| e.to_string())?;
+
+        let mut data = response.data;
+        data.sort_by_key(|d| d.index);
+        Ok(data.into_iter().map(|d| d.embedding).collect())
+    }
+}
+
+#[async_trait]
+impl QueryEmbedder for EmbeddingsQueryEmbedder {
+    async fn embed(&self, inputs: Vec<String>) -> Result<Vec<Vec<f32>>, LlmError> {
+        self.embed_inputs(inputs)
+            .await
+            .map_err(|e| LlmError::Upstream(format!("query embedding failed: {e}")))
+    }
+}
+
+#[async_trait]
+impl ToolEmbedder for EmbeddingsQueryEmbedder {
+    async fn embed(&self, inputs: Vec<String>) -> Result<Vec<Vec<f32>>, McpError> {
+        self.embed_inputs(inputs)
+            .await
+            .map_err(|e| McpError::Transport(format!("tool embedding failed: {e}")))
+    }
+}
@@ -1,7 +1,229 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum::Json;
+use axum::extract::State;
 use axum::response::IntoResponse;
 use http::StatusCode;
+use serde::Serialize;
+use synapse_llm::LlmState;
+use synapse_llm::health::CircuitState;
+use synapse_mcp::McpState;
+use synapse_mcp::downstream::manager::ServerStatus;
+use synapse_ratelimit::RequestLimiter;
 
 /// Health check handler
 pub async fn health_handler() -> impl IntoResponse {
     (StatusCode::OK, "ok")
 }
+
+/// Subsystem handles inspected by the readiness endpoint
+#[derive(Clone)]
+pub struct ReadinessState {
+    pub llm: LlmState,
+    pub mcp: Arc<McpState>,
+    pub cache: Option<synapse_cache::ResponseCache>,
+    pub rate_limiter: Option<Arc<RequestLimiter>>,
+    pub billing: Option<synapse_billing::AetherClient>,
+    pub probe_timeout: Duration,
+    /// Whether MCP servers all being down makes the gateway not ready
+    pub mcp_required: bool,
+}
+
+/// Status of a single subsystem or of the gateway as a whole
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum Status {
+    /// Fully operational
+    Ok,
+    /// Serving requests with reduced capability
+    Degraded,
+    /// Unable to serve requests
+    Down,
+}
+
+#[derive(Debug, Serialize)]
+struct ReadinessResponse {
+    status: Status,
+    checks: Checks,
+}
+
+#[derive(Debug, Serialize)]
+struct Checks {
+    llm: LlmCheck,
+    mcp: McpCheck,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    cache: Option<ProbeCheck>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    rate_limit: Option<ProbeCheck>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    billing: Option<BillingCheck>,
+}
+
+#[derive(Debug, Serialize)]
+struct LlmCheck {
+    status: Status,
+    providers: Vec<ProviderCheck>,
+}
+
+#[derive(Debug, Serialize)]
+struct ProviderCheck {
+    name: String,
+    circuit: CircuitState,
+}
+
+#[derive(Debug, Serialize)]
+struct McpCheck {
+    status: Status,
+    servers: Vec<ServerStatus>,
+}
+
+#[derive(Debug, Serialize)]
+struct ProbeCheck {
+    status: Status,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct BillingCheck {
+    status: Status,
+    circuit: CircuitState,
+}
+
+/// Readiness handler reporting per-subsystem status
+///
+/// Responds `503` when any subsystem is down so orchestrators stop routing
+/// traffic to this instance; degraded subsystems still report ready.
+pub async fn readiness_handler(State(state): State<ReadinessState>) -> impl IntoResponse {
+    let llm = llm_check(&state.llm);
+    let mcp = mcp_check(&state.mcp, state.mcp_required);
+
+    // Optional backends: an unreachable response cache only disables
+    // caching, whereas rate limiting rejects requests without its store
+    let cache = match state.cache {
+        Some(ref cache) => {
+            let result = probe(state.probe_timeout, cache.ping()).await;
+            Some(probe_check(result, Status::Degraded))
+        }
+        None => None,
+    };
+    let rate_limit = match state.rate_limiter {
+        Some(ref limiter) if limiter.uses_cache() => {
+            let result = probe(state.probe_timeout, limiter.ping()).await;
+            Some(probe_check(result, Status::Down))
+        }
+        _ => None,
+    };
+
+    // Credit checks fail open, so an open billing circuit only degrades
+    let billing = state.billing.as_ref().map(|client| {
+        if client.is_circuit_open() {
+            BillingCheck {
+                status: Status::Degraded,
+                circuit: CircuitState::Open,
+            }
+        } else if client.is_circuit_half_open() {
+            BillingCheck {
+                status: Status::Degraded,
+                circuit: CircuitState::HalfOpen,
+            }
+        } else {
+            BillingCheck {
+                status: Status::Ok,
+                circuit: CircuitState::Closed,
+            }
+        }
+    });
+
+    let status = [
+        Some(llm.status),
+        Some(mcp.status),
+        cache.as_ref().map(|c| c.status),
+        rate_limit.as_ref().map(|c| c.status),
+        billing.as_ref().map(|c| c.status),
+    ]
+    .into_iter()
+    .flatten()
+    .max()
+    .unwrap_or(Status::Ok);
+
+    let code = if status == Status::Down {
+        StatusCode::SERVICE_UNAVAILABLE
+    } else {
+        StatusCode::OK
+    };
+
+    let body = ReadinessResponse {
+        status,
+        checks: Checks {
+            llm,
+            mcp,
+            cache,
+            rate_limit,
+            billing,
+        },
+    };
+
+    (code, Json(body))
+}
+
+fn llm_check(llm: &LlmState) -> LlmCheck {
+    let providers: Vec<ProviderCheck> = llm
+        .provider_health()
+        .into_iter()
+        .map(|(name, circuit)| ProviderCheck { name, circuit })
+        .collect();
+
+    let open = providers.iter().filter(|p| p.circuit == CircuitState::Open).count();
+    let status = if open == 0 {
+        Status::Ok
+    } else if open == providers.len() {
+        Status::Down
+    } else {
+        Status::Degraded
+    };
+
+    LlmCheck { status, providers }
+}
+
+/// MCP tools are optional for LLM traffic, so losing every server only
+/// degrades the gateway unless MCP is marked as required
+fn mcp_check(mcp: &McpState, required: bool) -> McpCheck {
+    let servers = mcp.current().downstream.server_statuses();
+
+    let connected = servers.iter().filter(|s| s.connected).count();
+    let status = if connected == servers.len() {
+        Status::Ok
+    } else if connected == 0 && required {
+        Status::Down
+    } else {
+        Status::Degraded
+    };
+
+    McpCheck { status, servers }
+}
+
+async fn probe<E: std::fmt::Display>(
+    timeout: Duration,
+    fut: impl Future<Output = Result<(), E>>,
+) -> Result<(), String> {
+    match tokio::time::timeout(timeout, fut).await {
+        Ok(Ok(())) => Ok(()),
+        Ok(Err(e)) => Err(e.to_string()),
+        Err(_) => Err(format!("probe timed out after {}ms", timeout.as_millis())),
+    }
+}
+
+fn probe_check(result: Result<(), String>, failure: Status) -> ProbeCheck {
+    match result {
+        Ok(()) => ProbeCheck {
+            status: Status::Ok,
+            error: None,
+        },
+        Err(error) => ProbeCheck {
+            status: failure,
+            error: Some(error),
+        },
+    }
+}
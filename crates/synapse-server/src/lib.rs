@@ -3,6 +3,7 @@ mod billing_identity;
 mod client_id;
 mod cors;
 mod csrf;
+mod embedder;
 mod entitlement;
 mod entitlement_cache;
 mod guardrails;
@@ -10,6 +11,7 @@ mod health;
 mod invalidate;
 mod rate_limit;
 mod request_context;
+mod tools;
 mod webhook;
 
 use std::net::SocketAddr;
@@ -25,6 +27,12 @@ use tower_http::trace::TraceLayer;
 pub struct Server {
     router: Router,
     listen_address: SocketAddr,
+    /// Dedicated health listener, when configured
+    health: Option<(SocketAddr, Router)>,
+    /// MCP subsystem, whose endpoint sessions are closed on shutdown
+    mcp: Arc<McpState>,
+    /// LLM subsystem, whose background tasks stop on shutdown
+    llm: LlmState,
 }
 
 impl Server {
@@ -51,8 +59,16 @@ impl Server {
         let mut tts_state = tts::build_server(&config)?;
         let mut embeddings_state = synapse_embeddings::build_server(&config)?;
         let mut imagegen_state = synapse_imagegen::build_server(&config)?;
+        let knn_embedding_model = (config.llm.routing.enabled
+            && matches!(config.llm.routing.strategy, synapse_config::RoutingStrategy::Knn))
+        .then(|| config.llm.routing.knn.embedding_model.clone())
+        .flatten();
         let mut llm_state = LlmState::from_config(config.llm).await?;
 
+        // Handles retained for the readiness endpoint
+        let mut readiness_billing = None;
+        let mut readiness_cache = None;
+
         // Configure billing for all modalities when enabled
         if let Some(ref billing_config) = config.billing
             && billing_config.enabled
@@ -91,6 +107,7 @@ impl Server {
                 billing_config.app_id.clone(),
                 billing_config.service_api_key.clone(),
             )?;
+            readiness_billing = Some(credit_client.clone());
             llm_state.set_billing_client(credit_client);
 
             // Wire billing into STT
@@ -200,25 +217,92 @@ impl Server {
                 Some(cache_config.key_prefix.clone()),
             )
             .map_err(|e| anyhow::anyhow!("failed to initialize response cache: {e}"))?;
+            readiness_cache = Some(cache.clone());
             llm_state.set_response_cache(cache);
             tracing::info!(ttl_seconds = cache_config.ttl_seconds, "response cache enabled");
         }
 
-        let mcp_state = Arc::new(McpState::new(&config.mcp).await?);
+        // Similarity routing embeds queries with the embeddings subsystem, once billing
+        // no longer needs exclusive access to its state
+        if let Some(model) = knn_embedding_model {
+            let embedder = embedder::EmbeddingsQueryEmbedder::new(Arc::clone(&embeddings_state), model);
+            llm_state
+                .enable_knn_routing(Arc::new(embedder))
+                .await
+                .map_err(|e| anyhow::anyhow!("failed to enable knn routing: {e}"))?;
+            tracing::info!("knn routing enabled");
+        }
+
+        let rate_limit_storage = config
+            .server
+            .rate_limit
+            .as_ref()
+            .map(|rate_limit| rate_limit.storage.clone())
+            .unwrap_or_default();
+        let mut mcp_state = McpState::new(&config.mcp, &rate_limit_storage).await?;
+
+        // Semantic tool search embeds tools with the embeddings subsystem
+        if let Some(ref model) = config.mcp.search.embedding_model {
+            let embedder = embedder::EmbeddingsQueryEmbedder::new(Arc::clone(&embeddings_state), model.clone());
+            mcp_state
+                .enable_semantic_search(Arc::new(embedder))
+                .await
+                .map_err(|e| anyhow::anyhow!("failed to enable semantic tool search: {e}"))?;
+            tracing::info!("semantic MCP tool search enabled");
+        }
+
+        let mcp_state = Arc::new(mcp_state);
+        mcp_state.supervise();
+
+        // Chat completions may ask the gateway to run MCP tool calls
+        if config.mcp.tool_loop.enabled {
+            let executor = tools::McpToolExecutor::new(Arc::clone(&mcp_state));
+            llm_state.enable_tool_loop(Arc::new(executor), config.mcp.tool_loop.max_iterations);
+        }
+
+        let request_limiter = config
+            .server
+            .rate_limit
+            .as_ref()
+            .map(synapse_ratelimit::create_request_limiter)
+            .transpose()?
+            .map(Arc::new);
 
         // Build base router with feature routes
         let mut app = Router::new();
 
-        // Health check
+        // Health checks (liveness and readiness)
+        let mut health = None;
         if config.server.health.enabled {
-            app = app.route(&config.server.health.path, axum::routing::get(health::health_handler));
+            let health_config = &config.server.health;
+            let readiness = health::ReadinessState {
+                llm: llm_state.clone(),
+                mcp: Arc::clone(&mcp_state),
+                cache: readiness_cache,
+                rate_limiter: request_limiter.clone(),
+                billing: readiness_billing,
+                probe_timeout: std::time::Duration::from_millis(health_config.probe_timeout_ms),
+                mcp_required: health_config.mcp_required,
+            };
+            let health_router = Router::new()
+                .route(&health_config.path, axum::routing::get(health::health_handler))
+                .route(
+                    &health_config.readiness_path,
+                    axum::routing::get(health::readiness_handler).with_state(readiness),
+                );
+
+            // A dedicated listener keeps orchestrator probes clear of auth and rate limiting
+            match health_config.listen_address {
+                Some(addr) => health = Some((addr, health_router)),
+                None => app = app.merge(health_router),
+            }
         }
 
         // LLM routes
-        app = app.merge(synapse_llm::llm_router(llm_state));
+        app = app.merge(synapse_llm::llm_router(llm_state.clone()));
 
         // MCP routes
-        app = app.merge(synapse_mcp::mcp_router(mcp_state));
+        app = app.merge(synapse_mcp::mcp_router(Arc::clone(&mcp_state)));
 
         // STT routes
         app = app.merge(stt::endpoint_router().with_state(stt_state));
@@ -282,8 +366,7 @@ impl Server {
         }
 
         // Rate limiting
-        if let Some(ref rl_config) = config.server.rate_limit {
-            let limiter = Arc::new(synapse_ratelimit::create_request_limiter(rl_config)?);
+        if let Some(limiter) = request_limiter {
             app = app.layer(axum::middleware::from_fn(move |req, next| {
                 let limiter = Arc::clone(&limiter);
                 async move { rate_limit::rate_limit_middleware_arc(limiter, req, next).await }
@@ -392,6 +475,9 @@ impl Server {
         Ok(Self {
             router: app,
             listen_address,
+            health,
+            mcp: mcp_state,
+            llm: llm_state,
         })
     }
 
@@ -401,13 +487,27 @@ impl Server {
         self.listen_address
     }
 
+    /// Get the dedicated health listen address, if configured
+    #[must_use]
+    pub fn health_listen_address(&self) -> Option<SocketAddr> {
+        self.health.as_ref().map(|(addr, _)| *addr)
+    }
+
     /// Consume the server and return the inner router
     ///
-    /// Useful for testing when the caller manages the listener
+    /// Useful for testing when the caller manages the listener. Health
+    /// routes on a dedicated listener are not included; use
+    /// [`Server::into_parts`] to obtain them.
     pub fn into_router(self) -> Router {
         self.router
     }
 
+    /// Consume the server and return the main router and the dedicated
+    /// health router, if one is configured
+    pub fn into_parts(self) -> (Router, Option<Router>) {
+        (self.router, self.health.map(|(_, router)| router))
+    }
+
     /// Start serving requests
     ///
     /// Blocks until the cancellation token is triggered.
@@ -420,10 +520,30 @@ impl Server {
         let local_addr = listener.local_addr()?;
         tracing::info!(%local_addr, "server listening");
 
+        if let Some((health_address, health_router)) = self.health {
+            let health_listener = tokio::net::TcpListener::bind(health_address).await?;
+            let health_addr = health_listener.local_addr()?;
+            tracing::info!(%health_addr, "health listener started");
+
+            let health_shutdown = shutdown.clone();
+            tokio::spawn(async move {
+                if let Err(e) = axum::serve(health_listener, health_router)
+                    .with_graceful_shutdown(async move { health_shutdown.cancelled().await })
+                    .await
+                {
+                    tracing::error!(error = %e, "health listener failed");
+                }
+            });
+        }
+
+        let mcp = self.mcp;
+        let llm = self.llm;
         axum::serve(listener, self.router)
             .with_graceful_shutdown(async move {
                 shutdown.cancelled().await;
                 tracing::info!("graceful shutdown initiated");
+                mcp.close_sessions();
+                llm.stop_background_tasks();
             })
             .await?;
 
@@ -14,7 +14,7 @@ pub async fn rate_limit_middleware_arc(limiter: Arc<RequestLimiter>, request: Re
     }
 
     // Check per-IP rate limit
-    if let Some(ip) = extract_client_ip(&request)
+    if let Some(ip) = synapse_core::client_ip(request.headers())
         && let Err(e) = limiter.check_ip(&ip).await
     {
         return rate_limit_response(&e);
@@ -23,25 +23,6 @@ pub async fn rate_limit_middleware_arc(limiter: Arc<RequestLimiter>, request: Re
     next.run(request).await
 }
 
-fn extract_client_ip(request: &Request) -> Option<String> {
-    // Try X-Forwarded-For first
-    if let Some(forwarded) = request.headers().get("x-forwarded-for")
-        && let Ok(val) = forwarded.to_str()
-        && let Some(first) = val.split(',').next()
-    {
-        return Some(first.trim().to_string());
-    }
-
-    // Try X-Real-IP
-    if let Some(real_ip) = request.headers().get("x-real-ip")
-        && let Ok(val) = real_ip.to_str()
-    {
-        return Some(val.trim().to_string());
-    }
-
-    None
-}
-
 fn rate_limit_response(error: &synapse_ratelimit::RateLimitError) -> Response {
     match error {
         synapse_ratelimit::RateLimitError::Exceeded { retry_after } => {
@@ -0,0 +1,82 @@
+//! MCP tools for the server-side tool loop of chat completions
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use synapse_core::{HttpError, RequestContext};
+use synapse_llm::types::{FunctionDefinition, ToolDefinition};
+use synapse_llm::{LlmError, ToolExecutor, ToolOutput};
+use synapse_mcp::McpState;
+
+/// Offers and runs the MCP tools a caller may use
+pub struct McpToolExecutor {
+    mcp: Arc<McpState>,
+}
+
+impl McpToolExecutor {
+    pub const fn new(mcp: Arc<McpState>) -> Self {
+        Self { mcp }
+    }
+}
+
+#[async_trait]
+impl ToolExecutor for McpToolExecutor {
+    async fn tools(&self, context: &RequestContext, selection: &[String]) -> Result<Vec<ToolDefinition>, LlmError> {
+        let snapshot = self.mcp.current();
+        let tools = snapshot
+            .caller_tools(context)
+            .filter(|tool| {
+                selection.is_empty()
+                    || selection
+                        .iter()
+                        .any(|selected| *selected == tool.qualified_name || *selected == tool.server_name)
+            })
+            .map(|tool| ToolDefinition {
+                tool_type: "function".to_owned(),
+                function: FunctionDefinition {
+                    name: tool.qualified_name.clone(),
+                    description: Some(tool.description.clone()),
+                    parameters: Some(tool.input_schema.clone()),
+                },
+            })
+            .collect();
+
+        Ok(tools)
+    }
+
+    async fn call(&self, context: &RequestContext, name: &str, arguments: &str) -> Result<ToolOutput, LlmError> {
+        let arguments = if arguments.trim().is_empty() {
+            None
+        } else {
+            match serde_json::from_str(arguments) {
+                Ok(arguments) => Some(arguments),
+                Err(e) => {
+                    return Ok(ToolOutput {
+                        content: format!("tool arguments must be a JSON object: {e}"),
+                        is_error: true,
+                    });
+                }
+            }
+        };
+
+        // Denials and downstream failures are outputs the model can read
+        let output = match self.mcp.call_tool(context, name, arguments).await {
+            Ok(result) => ToolOutput {
+                content: result
+                    .content
+                    .iter()
+                    .filter_map(|content| content.raw.as_text())
+                    .map(|text| text.text.as_str())
+                    .collect::<Vec<_>>()
+                    .join("\n"),
+                is_error: result.is_error.unwrap_or(false),
+            },
+            Err(e) => ToolOutput {
+                content: e.client_message(),
+                is_error: true,
+            },
+        };
+
+        Ok(output)
+    }
+}
@@ -56,6 +56,13 @@ impl Drop for TelemetryGuard {
     }
 }
 
+/// Set an attribute on the OpenTelemetry span of the current tracing span
+pub fn set_span_attribute(key: &'static str, value: impl Into<opentelemetry::Value>) {
+    use tracing_opentelemetry::OpenTelemetrySpanExt as _;
+
+    tracing::Span::current().set_attribute(key, value);
+}
+
 /// Initialize telemetry from configuration
 ///
 /// Sets up the `tracing-subscriber` with optional OTLP export for traces,
@@ -1,8 +1,10 @@
 //! Metric name constants and recording helpers
 
+use std::sync::LazyLock;
 use std::time::Instant;
 
-use opentelemetry::metrics::Histogram;
+use opentelemetry::KeyValue;
+use opentelemetry::metrics::{Counter, Histogram};
 
 /// Record a duration measurement on a histogram
 pub fn record_duration(histogram: &Histogram<f64>, start: Instant, attributes: &[opentelemetry::KeyValue]) {
@@ -10,6 +12,24 @@ pub fn record_duration(histogram: &Histogram<f64>, start: Instant, attributes: &
     histogram.record(duration, attributes);
 }
 
+/// Count a request assigned to an experiment arm
+pub fn record_experiment_request(experiment: &str, arm: &str) {
+    static COUNTER: LazyLock<Counter<u64>> = LazyLock::new(|| {
+        opentelemetry::global::meter("synapse")
+            .u64_counter(LLM_EXPERIMENT_REQUEST_COUNT)
+            .with_description("Requests assigned to each experiment arm")
+            .build()
+    });
+
+    COUNTER.add(
+        1,
+        &[
+            KeyValue::new(EXPERIMENT_NAME, experiment.to_owned()),
+            KeyValue::new(EXPERIMENT_ARM, arm.to_owned()),
+        ],
+    );
+}
+
 // HTTP metric names
 pub const HTTP_REQUEST_DURATION: &str = "http.server.request.duration";
 pub const HTTP_REQUEST_COUNT: &str = "http.server.request.count";
@@ -20,6 +40,11 @@ pub const LLM_REQUEST_COUNT: &str = "llm.request.count";
 pub const LLM_TOKEN_USAGE: &str = "llm.token.usage";
 pub const LLM_STREAMING_DURATION: &str = "llm.streaming.duration";
 pub const LLM_TIME_TO_FIRST_TOKEN: &str = "llm.time_to_first_token";
+pub const LLM_EXPERIMENT_REQUEST_COUNT: &str = "llm.experiment.request.count";
+
+// Span and metric attribute names
+pub const EXPERIMENT_NAME: &str = "llm.experiment.name";
+pub const EXPERIMENT_ARM: &str = "llm.experiment.arm";
 
 // MCP metric names
 pub const MCP_TOOL_CALL_DURATION: &str = "mcp.tool_call.duration";
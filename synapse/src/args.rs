@@ -1,6 +1,6 @@
 use std::path::PathBuf;
 
-use clap::Parser;
+use clap::{Parser, Subcommand};
 
 /// Synapse AI Router
 #[derive(Debug, Parser)]
@@ -13,4 +13,19 @@ pub struct Args {
     /// Override the listen address
     #[arg(long, env = "SYNAPSE_LISTEN")]
     pub listen: Option<std::net::SocketAddr>,
+
+    /// Run a maintenance command instead of the server
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+/// Maintenance commands
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Check that a trained ONNX routing model scores one class per
+    /// configured model profile
+    ValidateOnnx {
+        /// Path to the `.onnx` model file
+        model: PathBuf,
+    },
 }
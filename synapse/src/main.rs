@@ -2,9 +2,12 @@
 
 mod args;
 
-use args::Args;
+use std::path::Path;
+
+use args::{Args, Command};
 use clap::Parser;
 use synapse_config::Config;
+use synapse_routing::strategy::onnx::OnnxStrategy;
 use synapse_server::Server;
 use tokio_util::sync::CancellationToken;
 
@@ -15,6 +18,10 @@ async fn main() -> anyhow::Result<()> {
     // Load configuration
     let config = Config::load(&args.config)?;
 
+    if let Some(Command::ValidateOnnx { model }) = args.command {
+        return validate_onnx(&config, &model).await;
+    }
+
     // Initialize telemetry
     let _telemetry_guard = synapse_telemetry::init(config.telemetry.as_ref(), "info")?;
 
@@ -42,6 +49,36 @@ async fn main() -> anyhow::Result<()> {
     Ok(())
 }
 
+/// Compare an ONNX routing model's class count with the model registry
+///
+/// Class labels index the registry's profiles, so a model trained against
+/// a different set of profiles would route to the wrong models. The
+/// registry is built as the gateway builds it, including catalog models
+/// the providers currently list.
+async fn validate_onnx(config: &Config, model: &Path) -> anyhow::Result<()> {
+    let registry = synapse_llm::catalog::runtime_registry(&config.llm).await?;
+    let strategy = OnnxStrategy::load(&model.to_string_lossy())?;
+    let classes = strategy.class_count()?;
+    let profiles = registry.profiles().len();
+
+    if classes != profiles {
+        anyhow::bail!(
+            "{} scores {classes} classes but the registry has {profiles} model profiles",
+            model.display()
+        );
+    }
+
+    println!(
+        "{}: {classes} classes match the registry's model profiles",
+        model.display()
+    );
+    for (index, profile) in registry.profiles().iter().enumerate() {
+        println!("  {index}: {}", profile.id());
+    }
+
+    Ok(())
+}
+
 /// Wait for a shutdown signal (`SIGINT` or `SIGTERM`)
 async fn shutdown_signal() {
     let ctrl_c = async {